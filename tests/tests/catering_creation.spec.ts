@@ -10,7 +10,11 @@ test.use({
 test('test', async ({ page }) => {
 	const url = "http://localhost:3000"
 
-	await page.goto(`${url}`);
+	await page.goto(`${url}/login`);
+	await page.getByTestId('login-input').fill(process.env.WRANGLER_LOGIN ?? 'admin');
+	await page.getByTestId('password-input').fill(process.env.WRANGLER_PASSWORD ?? 'admin');
+	await page.getByTestId('login-submit').click();
+	await page.waitForURL(`${url}/attendance`);
 	await page.getByTestId('add-catering').click();
 	await page.getByTestId('catering-save').click();
 	await expect(page.getByTestId('snackbar-root')).toContainText('Podano nieprawidłowy czas lub datę');
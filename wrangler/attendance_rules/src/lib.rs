@@ -0,0 +1,298 @@
+//! Rules deciding which days and meals of a catering can be changed and by whom,
+//! shared by the message daemon and the web app.
+
+#[cfg(feature = "sqlx")]
+pub mod store;
+pub mod tests;
+
+use std::fmt::Display;
+
+use chrono::{Datelike, Days, NaiveDate, NaiveDateTime, NaiveTime};
+use uuid::Uuid;
+
+/// Who is changing the attendance
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Actor {
+    /// Staff of the kitchen or the school, may change any served day and override anything
+    Staff,
+    /// Guardian writing a message or using the portal, bound by the cut-off and staff decisions
+    Guardian,
+}
+
+impl Actor {
+    /// Whether days past their cut-off can still be changed
+    pub fn ignores_deadline(&self) -> bool {
+        match self {
+            Actor::Staff => true,
+            Actor::Guardian => false,
+        }
+    }
+
+    /// Whether attendance set by staff (`attendance_override`) can be replaced
+    pub fn replaces_overrides(&self) -> bool {
+        match self {
+            Actor::Staff => true,
+            Actor::Guardian => false,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Rejection {
+    InvalidRange,
+    OutsideCatering,
+    PastDeadline,
+    NoMeals,
+}
+
+impl Display for Rejection {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            Rejection::InvalidRange => "Invalid time range",
+            Rejection::OutsideCatering => "Selected days are outside of the catering",
+            Rejection::PastDeadline => "The deadline for changing these days has passed",
+            Rejection::NoMeals => "None of the selected meals is served by the catering",
+        })
+    }
+}
+
+impl std::error::Error for Rejection {}
+
+/// Decodes `caterings.dow`, bit 0 stands for monday
+pub fn days_of_week(mask: i16) -> [bool; 7] {
+    std::array::from_fn(|i| (mask >> i) & 1 == 1)
+}
+
+/// First day whose cut-off at `grace_period` on that day hasn't passed at `now`
+pub fn first_open_day(grace_period: NaiveTime, now: NaiveDateTime) -> Option<NaiveDate> {
+    (now - grace_period.signed_duration_since(NaiveTime::default()))
+        .date()
+        .checked_add_days(Days::new(1))
+}
+
+/// Guardians can change a meal until `time` on the day `days_before` days before it's served
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Cutoff {
+    pub days_before: u32,
+    pub time: NaiveTime,
+}
+
+impl Cutoff {
+    pub fn new(days_before: u32, time: NaiveTime) -> Self {
+        Cutoff { days_before, time }
+    }
+
+    /// The meal served on `day` can't be changed by guardians after this moment
+    pub fn deadline(&self, day: NaiveDate) -> NaiveDateTime {
+        (day - Days::new(self.days_before as u64)).and_time(self.time)
+    }
+
+    /// First day whose meal can still be changed at `now`
+    pub fn first_open_day(&self, now: NaiveDateTime) -> Option<NaiveDate> {
+        first_open_day(self.time, now)?.checked_add_days(Days::new(self.days_before as u64))
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Meal {
+    pub id: Uuid,
+    pub cutoff: Cutoff,
+}
+
+/// Schedule of a catering as far as attendance changes are concerned
+#[derive(Debug, Clone)]
+pub struct Catering {
+    pub days_of_week: [bool; 7],
+    pub since: NaiveDate,
+    pub until: NaiveDate,
+    pub meals: Vec<Meal>,
+    /// Inclusive ranges of days without meals
+    pub closures: Vec<(NaiveDate, NaiveDate)>,
+}
+
+impl Catering {
+    /// Catering serving `meals` every day between `since` and `until`, without closures
+    pub fn new(since: NaiveDate, until: NaiveDate, meals: Vec<Meal>) -> Self {
+        Catering {
+            days_of_week: [true; 7],
+            since,
+            until,
+            meals,
+            closures: vec![],
+        }
+    }
+
+    pub fn meal(&self, meal_id: Uuid) -> Option<&Meal> {
+        self.meals.iter().find(|meal| meal.id == meal_id)
+    }
+
+    /// Moment after which guardians can't change `meal` served on `day`
+    pub fn deadline(&self, meal_id: Uuid, day: NaiveDate) -> Option<NaiveDateTime> {
+        self.meal(meal_id).map(|meal| meal.cutoff.deadline(day))
+    }
+
+    /// First day of the catering on which `actor` can still change `meal`
+    pub fn first_open_day_of(
+        &self,
+        actor: Actor,
+        now: NaiveDateTime,
+        meal_id: Uuid,
+    ) -> Option<NaiveDate> {
+        let meal = self.meal(meal_id)?;
+        if actor.ignores_deadline() {
+            return Some(self.since);
+        }
+        meal.cutoff
+            .first_open_day(now)
+            .map(|day| day.max(self.since))
+    }
+
+    /// First day of the catering on which `actor` can still change any of the meals
+    pub fn first_open_day(&self, actor: Actor, now: NaiveDateTime) -> Option<NaiveDate> {
+        if actor.ignores_deadline() {
+            return Some(self.since);
+        }
+        self.meals
+            .iter()
+            .filter_map(|meal| self.first_open_day_of(actor, now, meal.id))
+            .min()
+    }
+
+    pub fn is_closed(&self, day: NaiveDate) -> bool {
+        self.closures
+            .iter()
+            .any(|(since, until)| *since <= day && day <= *until)
+    }
+
+    /// Whether meals are served on `day`, regardless of who asks
+    pub fn is_served(&self, day: NaiveDate) -> bool {
+        self.since <= day
+            && day <= self.until
+            && self.days_of_week[day.weekday().num_days_from_monday() as usize]
+            && !self.is_closed(day)
+    }
+
+    pub fn is_open(&self, actor: Actor, now: NaiveDateTime, meal_id: Uuid, day: NaiveDate) -> bool {
+        self.is_served(day)
+            && self
+                .deadline(meal_id, day)
+                .is_some_and(|deadline| actor.ignores_deadline() || now < deadline)
+    }
+
+    /// Narrows the requested range to the part of `meal` that `actor` can change,
+    /// days before the cut-off and outside of the catering are dropped
+    pub fn clamp(
+        &self,
+        actor: Actor,
+        now: NaiveDateTime,
+        meal_id: Uuid,
+        since: NaiveDate,
+        until: NaiveDate,
+    ) -> Result<(NaiveDate, NaiveDate), Rejection> {
+        if until < since {
+            return Err(Rejection::InvalidRange);
+        }
+        if until < self.since || since > self.until {
+            return Err(Rejection::OutsideCatering);
+        }
+        if self.meal(meal_id).is_none() {
+            return Err(Rejection::NoMeals);
+        }
+        let first = self
+            .first_open_day_of(actor, now, meal_id)
+            .ok_or(Rejection::InvalidRange)?;
+        if until < first {
+            return Err(Rejection::PastDeadline);
+        }
+        let since = since.max(first);
+        let until = until.min(self.until);
+        if since > until {
+            return Err(Rejection::PastDeadline);
+        }
+        Ok((since, until))
+    }
+
+    /// Served days of the range on which `actor` can change `meal`
+    pub fn open_days(
+        &self,
+        actor: Actor,
+        now: NaiveDateTime,
+        meal_id: Uuid,
+        since: NaiveDate,
+        until: NaiveDate,
+    ) -> Result<Vec<NaiveDate>, Rejection> {
+        let (since, until) = self.clamp(actor, now, meal_id, since, until)?;
+        Ok(self.served_days(since, until))
+    }
+
+    /// Open days of every meal of the selection, meals without any are left out.
+    /// Fails only when none of the meals can be changed in the range
+    pub fn open_meals(
+        &self,
+        actor: Actor,
+        now: NaiveDateTime,
+        meals: &[Uuid],
+        since: NaiveDate,
+        until: NaiveDate,
+    ) -> Result<Vec<(Uuid, Vec<NaiveDate>)>, Rejection> {
+        let mut open = vec![];
+        let mut rejection = None;
+        for meal_id in meals {
+            match self.open_days(actor, now, *meal_id, since, until) {
+                Ok(days) => open.push((*meal_id, days)),
+                Err(error) => rejection = rejection.or(Some(error)),
+            }
+        }
+        match rejection {
+            Some(rejection) if open.is_empty() => Err(rejection),
+            _ => Ok(open
+                .into_iter()
+                .filter(|(_, days)| !days.is_empty())
+                .collect()),
+        }
+    }
+
+    pub fn served_days(&self, since: NaiveDate, until: NaiveDate) -> Vec<NaiveDate> {
+        since
+            .iter_days()
+            .take_while(|day| *day <= until)
+            .filter(|day| self.is_served(*day))
+            .collect()
+    }
+
+    /// Keeps the selected days on which `actor` can change `meal`
+    pub fn filter_days(
+        &self,
+        actor: Actor,
+        now: NaiveDateTime,
+        meal_id: Uuid,
+        days: &[NaiveDate],
+    ) -> Vec<NaiveDate> {
+        days.iter()
+            .copied()
+            .filter(|day| self.is_open(actor, now, meal_id, *day))
+            .collect()
+    }
+
+    /// Meals of the selection served by the catering
+    pub fn served_meals(&self, meals: &[Uuid]) -> Vec<Uuid> {
+        self.meals
+            .iter()
+            .map(|meal| meal.id)
+            .filter(|meal_id| meals.contains(meal_id))
+            .collect()
+    }
+
+    /// Requested meals served by the catering, no meals stand for all of them
+    pub fn select_meals(&self, requested: &[Uuid]) -> Result<Vec<Uuid>, Rejection> {
+        if requested.is_empty() {
+            return Ok(self.meals.iter().map(|meal| meal.id).collect());
+        }
+        let meals = self.served_meals(requested);
+        if meals.is_empty() {
+            Err(Rejection::NoMeals)
+        } else {
+            Ok(meals)
+        }
+    }
+}
@@ -0,0 +1,92 @@
+use chrono::NaiveDate;
+use sqlx::{Connection, Error, Executor, Postgres};
+use uuid::Uuid;
+
+use crate::{Actor, Catering, Cutoff, Meal, days_of_week};
+
+/// Catering of the group or student `target`, with its meals and closures. For a student
+/// the catering is narrowed down to the days they are enrolled
+pub async fn load_catering<C>(target: Uuid, connection: &mut C) -> Result<Option<Catering>, Error>
+where
+    C: Connection<Database = Postgres>,
+    for<'a> &'a mut C: Executor<'a, Database = Postgres>,
+{
+    let Some(catering) = sqlx::query!(
+        "SELECT caterings.id, caterings.dow,
+        GREATEST(caterings.since, students.joined_on) AS \"since!\", LEAST(caterings.until, students.left_on) AS \"until!\" FROM caterings
+        INNER JOIN group_relations ON group_relations.parent = caterings.group_id
+        LEFT JOIN students ON students.id = group_relations.child
+        WHERE group_relations.child = $1",
+        target
+    )
+    .fetch_optional(&mut *connection)
+    .await?
+    else {
+        return Ok(None);
+    };
+
+    let meals = sqlx::query!(
+        "SELECT meal_id AS \"meal_id!\", cutoff_days, cutoff_time FROM catering_meals WHERE catering_id = $1 ORDER BY meal_order",
+        catering.id
+    )
+    .fetch_all(&mut *connection)
+    .await?
+    .into_iter()
+    .map(|row| Meal {
+        id: row.meal_id,
+        cutoff: Cutoff::new(row.cutoff_days.max(0) as u32, row.cutoff_time),
+    })
+    .collect();
+
+    let closures = sqlx::query!(
+        "SELECT since, until FROM closures WHERE catering_id IS NULL OR catering_id = $1",
+        catering.id
+    )
+    .fetch_all(&mut *connection)
+    .await?
+    .into_iter()
+    .map(|row| (row.since, row.until))
+    .collect();
+
+    Ok(Some(Catering {
+        days_of_week: days_of_week(catering.dow),
+        since: catering.since,
+        until: catering.until,
+        meals,
+        closures,
+    }))
+}
+
+/// Records `value` for every day and meal of `target`, the days and meals have to be checked
+/// against the catering beforehand. Guardians can't replace attendance set by staff,
+/// those days are skipped. Returns the number of recorded meals
+pub async fn record_attendance<C>(
+    cause_id: Uuid,
+    target: Uuid,
+    actor: Actor,
+    days: &[NaiveDate],
+    meals: &[Uuid],
+    value: bool,
+    connection: &mut C,
+) -> Result<u64, Error>
+where
+    C: Connection<Database = Postgres>,
+    for<'a> &'a mut C: Executor<'a, Database = Postgres>,
+{
+    Ok(sqlx::query!(
+        "INSERT INTO attendance (cause_id, target, day, meal_id, value) SELECT $1, $2, days.day, meals.id, $5 FROM UNNEST($3::date[]) AS days(day)
+        CROSS JOIN UNNEST($4::uuid[]) AS meals(id)
+        WHERE $6 OR NOT EXISTS (SELECT 1 FROM effective_attendance
+            INNER JOIN attendance_override ON attendance_override.id = effective_attendance.cause_id
+            WHERE effective_attendance.target = $2 AND effective_attendance.day = days.day AND effective_attendance.meal_id = meals.id)",
+        cause_id,
+        target,
+        days,
+        meals,
+        value,
+        actor.replaces_overrides()
+    )
+    .execute(&mut *connection)
+    .await?
+    .rows_affected())
+}
@@ -0,0 +1,443 @@
+#[cfg(test)]
+mod test {
+    use chrono::{NaiveDate, NaiveDateTime, NaiveTime};
+    use uuid::Uuid;
+
+    use crate::*;
+
+    fn date(y: i32, m: u32, d: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(y, m, d).unwrap()
+    }
+
+    fn time(value: &str) -> NaiveDateTime {
+        NaiveDateTime::parse_from_str(value, "%Y-%m-%d %H:%M:%S").unwrap()
+    }
+
+    fn cutoff(days_before: u32, hour: u32) -> Cutoff {
+        Cutoff::new(days_before, NaiveTime::from_hms_opt(hour, 0, 0).unwrap())
+    }
+
+    const LUNCH: Uuid = Uuid::from_u128(1);
+    const DINNER: Uuid = Uuid::from_u128(2);
+    const BREAKFAST: Uuid = Uuid::from_u128(3);
+
+    /// Monday to friday catering of january 2025 with a cut-off at 7:00
+    fn catering() -> Catering {
+        Catering {
+            days_of_week: days_of_week(0b0011111),
+            since: date(2025, 1, 1),
+            until: date(2025, 1, 31),
+            meals: vec![
+                Meal {
+                    id: LUNCH,
+                    cutoff: cutoff(0, 7),
+                },
+                Meal {
+                    id: DINNER,
+                    cutoff: cutoff(0, 7),
+                },
+            ],
+            closures: vec![(date(2025, 1, 6), date(2025, 1, 6))],
+        }
+    }
+
+    /// Catering whose breakfast has to be cancelled by 15:00 of the previous day
+    fn catering_with_breakfast() -> Catering {
+        let mut catering = catering();
+        catering.meals.insert(
+            0,
+            Meal {
+                id: BREAKFAST,
+                cutoff: cutoff(1, 15),
+            },
+        );
+        catering
+    }
+
+    #[test]
+    fn days_of_week_start_on_monday() {
+        assert_eq!(
+            days_of_week(0b0011111),
+            [true, true, true, true, true, false, false]
+        );
+        assert_eq!(
+            days_of_week(0b1000000),
+            [false, false, false, false, false, false, true]
+        );
+        assert_eq!(days_of_week(0), [false; 7]);
+    }
+
+    #[test]
+    fn first_open_day_depends_on_grace_period() {
+        let grace = NaiveTime::from_hms_opt(7, 0, 0).unwrap();
+
+        assert_eq!(
+            first_open_day(grace, time("2025-01-01 06:59:59")),
+            Some(date(2025, 1, 1))
+        );
+        assert_eq!(
+            first_open_day(grace, time("2025-01-01 07:00:00")),
+            Some(date(2025, 1, 2))
+        );
+        assert_eq!(
+            first_open_day(grace, time("2025-01-01 23:00:00")),
+            Some(date(2025, 1, 2))
+        );
+        assert_eq!(
+            first_open_day(NaiveTime::default(), time("2025-01-01 00:00:00")),
+            Some(date(2025, 1, 2))
+        );
+    }
+
+    #[test]
+    fn deadline_is_on_the_day_itself() {
+        assert_eq!(
+            catering().deadline(LUNCH, date(2025, 1, 2)),
+            Some(time("2025-01-02 07:00:00"))
+        );
+        assert_eq!(catering().deadline(BREAKFAST, date(2025, 1, 2)), None);
+    }
+
+    #[test]
+    fn first_open_day_is_never_before_catering_start() {
+        let catering = catering();
+
+        assert_eq!(
+            catering.first_open_day(Actor::Guardian, time("2024-12-01 12:00:00")),
+            Some(date(2025, 1, 1))
+        );
+        assert_eq!(
+            catering.first_open_day(Actor::Staff, time("2025-01-20 12:00:00")),
+            Some(date(2025, 1, 1))
+        );
+    }
+
+    #[test]
+    fn served_days_follow_schedule_range_and_closures() {
+        let catering = catering();
+
+        assert!(catering.is_served(date(2025, 1, 2)));
+        // saturday
+        assert!(!catering.is_served(date(2025, 1, 4)));
+        // closed monday
+        assert!(catering.is_closed(date(2025, 1, 6)));
+        assert!(!catering.is_served(date(2025, 1, 6)));
+        assert!(!catering.is_served(date(2024, 12, 31)));
+        assert!(!catering.is_served(date(2025, 2, 3)));
+    }
+
+    #[test]
+    fn guardian_is_bound_by_deadline() {
+        let catering = catering();
+        let now = time("2025-01-02 07:30:00");
+
+        assert!(!catering.is_open(Actor::Guardian, now, LUNCH, date(2025, 1, 2)));
+        assert!(catering.is_open(Actor::Guardian, now, LUNCH, date(2025, 1, 3)));
+        assert!(catering.is_open(Actor::Staff, now, LUNCH, date(2025, 1, 2)));
+        assert!(!catering.is_open(Actor::Staff, now, LUNCH, date(2025, 1, 4)));
+    }
+
+    #[test]
+    fn clamp_drops_days_past_deadline() {
+        let catering = catering();
+
+        assert_eq!(
+            catering.clamp(
+                Actor::Guardian,
+                time("2025-01-01 07:01:00"),
+                LUNCH,
+                date(2025, 1, 1),
+                date(2025, 1, 3)
+            ),
+            Ok((date(2025, 1, 2), date(2025, 1, 3)))
+        );
+        assert_eq!(
+            catering.clamp(
+                Actor::Guardian,
+                time("2025-01-01 06:59:00"),
+                LUNCH,
+                date(2025, 1, 1),
+                date(2025, 1, 3)
+            ),
+            Ok((date(2025, 1, 1), date(2025, 1, 3)))
+        );
+    }
+
+    #[test]
+    fn clamp_rejects_range_fully_past_deadline() {
+        assert_eq!(
+            catering().clamp(
+                Actor::Guardian,
+                time("2025-01-10 08:00:00"),
+                LUNCH,
+                date(2025, 1, 8),
+                date(2025, 1, 10)
+            ),
+            Err(Rejection::PastDeadline)
+        );
+    }
+
+    #[test]
+    fn clamp_lets_staff_change_the_past() {
+        assert_eq!(
+            catering().clamp(
+                Actor::Staff,
+                time("2025-01-10 08:00:00"),
+                LUNCH,
+                date(2025, 1, 8),
+                date(2025, 1, 10)
+            ),
+            Ok((date(2025, 1, 8), date(2025, 1, 10)))
+        );
+    }
+
+    #[test]
+    fn clamp_keeps_range_inside_catering() {
+        let catering = catering();
+        let now = time("2024-12-01 12:00:00");
+
+        assert_eq!(
+            catering.clamp(
+                Actor::Guardian,
+                now,
+                LUNCH,
+                date(2024, 12, 20),
+                date(2025, 1, 3)
+            ),
+            Ok((date(2025, 1, 1), date(2025, 1, 3)))
+        );
+        assert_eq!(
+            catering.clamp(
+                Actor::Guardian,
+                now,
+                LUNCH,
+                date(2025, 1, 30),
+                date(2025, 2, 10)
+            ),
+            Ok((date(2025, 1, 30), date(2025, 1, 31)))
+        );
+        assert_eq!(
+            catering.clamp(
+                Actor::Guardian,
+                now,
+                LUNCH,
+                date(2025, 2, 1),
+                date(2025, 2, 10)
+            ),
+            Err(Rejection::OutsideCatering)
+        );
+        assert_eq!(
+            catering.clamp(
+                Actor::Staff,
+                now,
+                LUNCH,
+                date(2024, 12, 1),
+                date(2024, 12, 10)
+            ),
+            Err(Rejection::OutsideCatering)
+        );
+    }
+
+    #[test]
+    fn clamp_rejects_reversed_range() {
+        assert_eq!(
+            catering().clamp(
+                Actor::Staff,
+                time("2025-01-01 00:00:00"),
+                LUNCH,
+                date(2025, 1, 3),
+                date(2025, 1, 2)
+            ),
+            Err(Rejection::InvalidRange)
+        );
+    }
+
+    #[test]
+    fn open_days_skip_weekends_and_closures() {
+        assert_eq!(
+            catering().open_days(
+                Actor::Guardian,
+                time("2025-01-02 08:00:00"),
+                LUNCH,
+                date(2025, 1, 1),
+                date(2025, 1, 8)
+            ),
+            Ok(vec![date(2025, 1, 3), date(2025, 1, 7), date(2025, 1, 8)])
+        );
+    }
+
+    #[test]
+    fn open_days_can_be_empty() {
+        assert_eq!(
+            catering().open_days(
+                Actor::Guardian,
+                time("2025-01-01 08:00:00"),
+                LUNCH,
+                date(2025, 1, 4),
+                date(2025, 1, 6)
+            ),
+            Ok(vec![])
+        );
+    }
+
+    #[test]
+    fn served_days_ignore_deadline() {
+        assert_eq!(
+            catering().served_days(date(2025, 1, 3), date(2025, 1, 7)),
+            vec![date(2025, 1, 3), date(2025, 1, 7)]
+        );
+    }
+
+    #[test]
+    fn filter_days_applies_policy_to_selection() {
+        let catering = catering();
+        let days = [
+            date(2025, 1, 2),
+            date(2025, 1, 4),
+            date(2025, 1, 6),
+            date(2025, 1, 7),
+        ];
+        let now = time("2025-01-03 12:00:00");
+
+        assert_eq!(
+            catering.filter_days(Actor::Guardian, now, LUNCH, &days),
+            vec![date(2025, 1, 7)]
+        );
+        assert_eq!(
+            catering.filter_days(Actor::Staff, now, LUNCH, &days),
+            vec![date(2025, 1, 2), date(2025, 1, 7)]
+        );
+    }
+
+    #[test]
+    fn select_meals_keeps_catering_meals() {
+        let catering = catering();
+
+        assert_eq!(catering.select_meals(&[]), Ok(vec![LUNCH, DINNER]));
+        assert_eq!(
+            catering.select_meals(&[DINNER, BREAKFAST]),
+            Ok(vec![DINNER])
+        );
+        assert_eq!(catering.select_meals(&[BREAKFAST]), Err(Rejection::NoMeals));
+    }
+
+    #[test]
+    fn only_staff_overrides() {
+        assert!(Actor::Staff.ignores_deadline());
+        assert!(Actor::Staff.replaces_overrides());
+        assert!(!Actor::Guardian.ignores_deadline());
+        assert!(!Actor::Guardian.replaces_overrides());
+    }
+
+    #[test]
+    fn new_catering_serves_every_day() {
+        let catering = Catering::new(date(2025, 1, 1), date(2025, 1, 31), vec![]);
+
+        assert!(catering.is_served(date(2025, 1, 4)));
+        assert!(catering.is_served(date(2025, 1, 5)));
+        assert!(catering.meals.is_empty());
+    }
+
+    #[test]
+    fn cutoff_can_fall_on_previous_days() {
+        let breakfast = cutoff(1, 15);
+
+        assert_eq!(
+            breakfast.deadline(date(2025, 1, 2)),
+            time("2025-01-01 15:00:00")
+        );
+        assert_eq!(
+            breakfast.first_open_day(time("2025-01-01 14:59:00")),
+            Some(date(2025, 1, 2))
+        );
+        assert_eq!(
+            breakfast.first_open_day(time("2025-01-01 15:00:00")),
+            Some(date(2025, 1, 3))
+        );
+        assert_eq!(
+            cutoff(2, 0).first_open_day(time("2025-01-01 00:00:00")),
+            Some(date(2025, 1, 4))
+        );
+    }
+
+    #[test]
+    fn meals_close_at_their_own_cutoff() {
+        let catering = catering_with_breakfast();
+        let now = time("2025-01-02 16:00:00");
+
+        assert!(!catering.is_open(Actor::Guardian, now, BREAKFAST, date(2025, 1, 3)));
+        assert!(catering.is_open(Actor::Guardian, now, LUNCH, date(2025, 1, 3)));
+        assert!(catering.is_open(Actor::Staff, now, BREAKFAST, date(2025, 1, 3)));
+        assert!(!catering.is_open(Actor::Guardian, now, Uuid::from_u128(4), date(2025, 1, 3)));
+    }
+
+    #[test]
+    fn first_open_day_of_catering_is_the_earliest_meal() {
+        let catering = catering_with_breakfast();
+        let now = time("2025-01-02 16:00:00");
+
+        assert_eq!(
+            catering.first_open_day_of(Actor::Guardian, now, BREAKFAST),
+            Some(date(2025, 1, 4))
+        );
+        assert_eq!(
+            catering.first_open_day_of(Actor::Guardian, now, LUNCH),
+            Some(date(2025, 1, 3))
+        );
+        assert_eq!(
+            catering.first_open_day(Actor::Guardian, now),
+            Some(date(2025, 1, 3))
+        );
+    }
+
+    #[test]
+    fn clamp_rejects_unknown_meal() {
+        assert_eq!(
+            catering().clamp(
+                Actor::Staff,
+                time("2025-01-01 00:00:00"),
+                BREAKFAST,
+                date(2025, 1, 2),
+                date(2025, 1, 3)
+            ),
+            Err(Rejection::NoMeals)
+        );
+    }
+
+    #[test]
+    fn open_meals_skip_meals_past_their_cutoff() {
+        let catering = catering_with_breakfast();
+        let now = time("2025-01-02 16:00:00");
+
+        assert_eq!(
+            catering.open_meals(
+                Actor::Guardian,
+                now,
+                &[BREAKFAST, LUNCH],
+                date(2025, 1, 3),
+                date(2025, 1, 3)
+            ),
+            Ok(vec![(LUNCH, vec![date(2025, 1, 3)])])
+        );
+        assert_eq!(
+            catering.open_meals(
+                Actor::Guardian,
+                now,
+                &[BREAKFAST],
+                date(2025, 1, 3),
+                date(2025, 1, 3)
+            ),
+            Err(Rejection::PastDeadline)
+        );
+        assert_eq!(
+            catering.open_meals(
+                Actor::Guardian,
+                now,
+                &[BREAKFAST, LUNCH],
+                date(2025, 1, 4),
+                date(2025, 1, 5)
+            ),
+            Ok(vec![])
+        );
+    }
+}
@@ -7,19 +7,38 @@ use leptos_router::{
 };
 
 use crate::{
-    components::{general_provider::GeneralProvider, searchbar::Searchbar, snackbar::Snackbar},
+    components::{
+        auth::{has_role, RequireLogin},
+        general_provider::GeneralProvider,
+        searchbar::Searchbar,
+        snackbar::Snackbar,
+    },
     pages::{
         attendance_dashboard::AttendanceDashboard,
         attendance_page::{AttendancePage, AttendanceVersion, GroupVersion},
+        audit_page::AuditPage,
+        billing_page::BillingPage,
+        broadcast_page::{BroadcastDetails, BroadcastPage},
+        closures_page::ClosuresPage,
         detail_page::DetailPage,
         faker_page::FakerPage,
         guardian_contact_details::GuardianContactDetails,
+        import_page::ImportPage,
+        jobs_page::JobsPage,
+        login_page::{LoginPage, LogoutPage},
         message_dashboard::MessageDashboard,
         message_page::MessagePage,
+        payments_page::PaymentsPage,
+        portal_page::{PortalLoginPage, PortalPage},
+        production_page::ProductionPage,
+        recycle_bin_page::RecycleBinPage,
+        templates_page::TemplatesPage,
         test_page::TestPage,
         unknown_contact_details::UnknownContactDetails,
+        users_page::UsersPage,
     },
 };
+use dto::user::Role;
 
 pub fn shell(options: LeptosOptions) -> impl IntoView {
     view! {
@@ -64,36 +83,114 @@ pub fn App() -> impl IntoView {
         <Router>
             <Snackbar>
                 <Routes fallback=|| "Nie ma takiej strony".into_view()>
+                    <Route path=path!("/login") view=LoginPage />
+                    <Route path=path!("/logout") view=LogoutPage />
+                    <Route path=path!("/portal") view=PortalPage />
+                    <Route path=path!("/portal/login") view=PortalLoginPage />
                     <ParentRoute
                         path=path!("/")
                         view=|| {
                             view! {
-                                <nav class="flex flex-row p-1 gap-1">
-                                    <div class="md:flex-1"></div>
-                                    <Searchbar />
-                                    <div class="flex flex-row flex-1 items-center justify-end gap-1">
-                                        <A href="/attendance">
-                                            <span class="bg-gray-900 hover:bg-gray-700 md:cursor-pointer rounded-md p-2">
-                                                Obecność
-                                            </span>
-                                        </A>
-                                        <A href="/messages">
-                                            <span class="bg-gray-900 hover:bg-gray-700 md:cursor-pointer rounded-md p-2">
-                                                Wiadomości
-                                            </span>
-                                        </A>
-                                    </div>
-                                </nav>
-                                <main class="flex flex-1 flex-row overflow-hidden">
-                                    <GeneralProvider />
-                                </main>
+                                <RequireLogin>
+                                    <nav class="flex flex-row p-1 gap-1 print:hidden">
+                                        <div class="md:flex-1"></div>
+                                        <Searchbar />
+                                        <div class="flex flex-row flex-1 items-center justify-end gap-1">
+                                            <A href="/attendance">
+                                                <span class="bg-gray-900 hover:bg-gray-700 md:cursor-pointer rounded-md p-2">
+                                                    Obecność
+                                                </span>
+                                            </A>
+                                            <A href="/production">
+                                                <span class="bg-gray-900 hover:bg-gray-700 md:cursor-pointer rounded-md p-2">
+                                                    Produkcja
+                                                </span>
+                                            </A>
+                                            <Show when=|| has_role(&[Role::Admin, Role::Teacher])>
+                                                <A href="/messages">
+                                                    <span class="bg-gray-900 hover:bg-gray-700 md:cursor-pointer rounded-md p-2">
+                                                        Wiadomości
+                                                    </span>
+                                                </A>
+                                            </Show>
+                                            <Show when=|| has_role(&[Role::Admin])>
+                                                <A href="/audit">
+                                                    <span class="bg-gray-900 hover:bg-gray-700 md:cursor-pointer rounded-md p-2">
+                                                        Historia
+                                                    </span>
+                                                </A>
+                                                <A href="/billing">
+                                                    <span class="bg-gray-900 hover:bg-gray-700 md:cursor-pointer rounded-md p-2">
+                                                        Rozliczenia
+                                                    </span>
+                                                </A>
+                                                <A href="/payments">
+                                                    <span class="bg-gray-900 hover:bg-gray-700 md:cursor-pointer rounded-md p-2">
+                                                        Płatności
+                                                    </span>
+                                                </A>
+                                                <A href="/closures">
+                                                    <span class="bg-gray-900 hover:bg-gray-700 md:cursor-pointer rounded-md p-2">
+                                                        Dni wolne
+                                                    </span>
+                                                </A>
+                                                <A href="/templates">
+                                                    <span class="bg-gray-900 hover:bg-gray-700 md:cursor-pointer rounded-md p-2">
+                                                        Szablony
+                                                    </span>
+                                                </A>
+                                                <A href="/import">
+                                                    <span class="bg-gray-900 hover:bg-gray-700 md:cursor-pointer rounded-md p-2">
+                                                        Import
+                                                    </span>
+                                                </A>
+                                                <A href="/users">
+                                                    <span class="bg-gray-900 hover:bg-gray-700 md:cursor-pointer rounded-md p-2">
+                                                        Użytkownicy
+                                                    </span>
+                                                </A>
+                                                <A href="/jobs">
+                                                    <span class="bg-gray-900 hover:bg-gray-700 md:cursor-pointer rounded-md p-2">
+                                                        Zadania
+                                                    </span>
+                                                </A>
+                                                <A href="/recycle-bin">
+                                                    <span class="bg-gray-900 hover:bg-gray-700 md:cursor-pointer rounded-md p-2">
+                                                        Kosz
+                                                    </span>
+                                                </A>
+                                            </Show>
+                                            <A href="/logout">
+                                                <span class="bg-gray-900 hover:bg-gray-700 md:cursor-pointer rounded-md p-2">
+                                                    Wyloguj
+                                                </span>
+                                            </A>
+                                        </div>
+                                    </nav>
+                                    <main class="flex flex-1 flex-row overflow-hidden">
+                                        <GeneralProvider />
+                                    </main>
+                                </RequireLogin>
                             }
                         }
                     >
                         <Route path=path!("test") view=TestPage />
                         <Route path=path!("faker") view=FakerPage />
+                        <Route path=path!("users") view=UsersPage />
+                        <Route path=path!("jobs") view=JobsPage />
+                        <Route path=path!("recycle-bin") view=RecycleBinPage />
+                        <Route path=path!("import") view=ImportPage />
+                        <Route path=path!("billing") view=BillingPage />
+                        <Route path=path!("payments") view=PaymentsPage />
+                        <Route path=path!("closures") view=ClosuresPage />
+                        <Route path=path!("templates") view=TemplatesPage />
+                        <Route path=path!("production") view=ProductionPage />
+                        <Route path=path!("audit") view=AuditPage />
+                        <Route path=path!("audit/:id") view=AuditPage />
                         <ParentRoute path=path!("messages") view=MessagePage>
                             <Route path=path!("/") view=MessageDashboard />
+                            <Route path=path!("/broadcasts") view=BroadcastPage />
+                            <Route path=path!("/broadcasts/:id") view=BroadcastDetails />
                             <Route
                                 path=path!("/guardian/:id")
                                 view=|| {
@@ -1,4 +1,6 @@
+pub mod auth;
 pub mod calendar;
+pub mod download;
 pub mod dropdown;
 pub mod general_provider;
 pub mod loader;
@@ -7,4 +9,5 @@ pub mod modal;
 pub mod modals;
 pub mod searchbar;
 pub mod snackbar;
+pub mod template_picker;
 pub mod tree;
@@ -0,0 +1,49 @@
+use dto::user::{Role, UserDto};
+use leptos::{context::Provider, either::Either, prelude::*};
+use leptos_router::components::Redirect;
+
+use crate::{components::loader::Loader, services::auth::get_current_user};
+
+#[derive(Clone, Debug)]
+pub struct CurrentUser(pub UserDto);
+
+pub fn use_current_user() -> Option<UserDto> {
+    use_context::<CurrentUser>().map(|user| user.0)
+}
+
+pub fn has_role(roles: &[Role]) -> bool {
+    use_current_user()
+        .map(|user| roles.contains(&user.role))
+        .unwrap_or(false)
+}
+
+#[component]
+pub fn RequireLogin(children: ChildrenFn) -> impl IntoView {
+    let user = Resource::new(|| (), |_| async move { get_current_user().await });
+    let children = StoredValue::new(children);
+
+    view! {
+        <Loader>
+            {move || Suspend::new(async move {
+                let user = user.await?;
+                Ok::<
+                    _,
+                    ServerFnError,
+                >(
+                    match user {
+                        Some(user) => {
+                            Either::Left(
+                                view! {
+                                    <Provider value=CurrentUser(user)>
+                                        {children.read_value()()}
+                                    </Provider>
+                                },
+                            )
+                        }
+                        None => Either::Right(view! { <Redirect path="/login" /> }),
+                    },
+                )
+            })}
+        </Loader>
+    }
+}
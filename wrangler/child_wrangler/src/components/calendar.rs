@@ -1,20 +1,16 @@
 use std::iter;
 
-use chrono::{Datelike, Days, Months, NaiveDate, Utc, Weekday};
+use chrono::{Datelike, Days, Months, NaiveDate, NaiveDateTime, Utc, Weekday};
 use dto::attendance::{
     EffectiveAttendance, EffectiveMonthAttendance, GetEffectiveMonthAttendance,
     GetMonthAttendanceDto, MonthAttendanceDto,
 };
-use leptos::wasm_bindgen::JsCast;
 use leptos::{either::Either, prelude::*};
 
 use leptos_router::hooks::use_params;
 use uuid::Uuid;
-use wasm_bindgen_futures::JsFuture;
-use web_sys::{
-    js_sys::Array, wasm_bindgen::JsValue, Blob, FileSystemFileHandle, FileSystemWritableFileStream,
-};
 
+use crate::components::download::save_text_to_file;
 use crate::components::modals::meal_history_modal::MealHistoryModal;
 use crate::icons::history::HistoryIcon;
 use crate::icons::list_icon::ListIcon;
@@ -142,7 +138,12 @@ pub fn Calendar() -> impl IntoView {
             if let Ok(summary) =
                 get_monthly_summary(target().unwrap(), year() as i32, month()).await
             {
-                save_to_file(&summary).await;
+                save_text_to_file(
+                    &summary,
+                    "Zapisano obecność",
+                    "Nie udało się zapisać obecności",
+                )
+                .await;
             } else {
                 snackbar.error("Nie udało się pobrac danych o obecności", "");
             }
@@ -256,41 +257,11 @@ pub fn Calendar() -> impl IntoView {
     }
 }
 
-async fn save_to_file(summary: &str) {
-    let snackbar = use_snackbar();
-
-    let array = Array::new();
-    array.push(&JsValue::from_str(summary));
-
-    if let Ok(blob) = Blob::new_with_str_sequence(&array) {
-        wasm_bindgen_futures::spawn_local(async move {
-            match async move {
-                let promise = web_sys::window().map(|window| window.show_save_file_picker());
-                if let Some(Ok(promise)) = promise {
-                    let handle = JsFuture::from(promise)
-                        .await
-                        .and_then(|handle| handle.dyn_into::<FileSystemFileHandle>())?;
-                    let writable = JsFuture::from(handle.create_writable())
-                        .await
-                        .and_then(|writable| writable.dyn_into::<FileSystemWritableFileStream>())?;
-                    JsFuture::from(writable.write_with_blob(&blob)?).await?;
-                    JsFuture::from(writable.close()).await?;
-                }
-                Ok::<_, JsValue>(())
-            }
-            .await
-            {
-                Ok(_) => snackbar.success("Zapisano obecność"),
-                Err(_) => snackbar.error("Nie udało się zapisać obecności", ""),
-            }
-        });
-    }
-}
-
 pub enum CalendarDay {
     OtherMonth,
     OtherDow,
-    Day(Vec<(Uuid, String, u32, EffectiveAttendance)>),
+    Closed(String),
+    Day(Vec<(Uuid, String, u32, EffectiveAttendance, NaiveDateTime)>),
 }
 
 #[component]
@@ -373,11 +344,13 @@ pub fn InnerCalendar(
         }
     };
 
+    let closures = attendance.closures.clone();
     let is_active = move |day: NaiveDate| {
         let att_start = attendance.start;
         let att_end = attendance.end;
         if att_start <= day
             && day <= att_end
+            && !closures.contains_key(&day)
             && dow
                 .get(day.weekday().num_days_from_monday() as usize)
                 .map(|b| *b)
@@ -422,6 +395,8 @@ pub fn InnerCalendar(
             || day > attendance.end
         {
             (day, CalendarDay::OtherDow)
+        } else if let Some(reason) = attendance.closures.get(&day) {
+            (day, CalendarDay::Closed(reason.clone()))
         } else {
             let meals = attendance
                 .meals
@@ -440,6 +415,7 @@ pub fn InnerCalendar(
                             .get(&day)
                             .and_then(|a| a.get(&m.id))
                             .unwrap_or(&EffectiveAttendance::Present),
+                        m.cutoff.deadline(day),
                     )
                 })
                 .collect::<Vec<_>>();
@@ -492,14 +468,30 @@ pub fn InnerCalendar(
                                         ),
                                     )
                                 }
+                                CalendarDay::Closed(reason) => {
+                                    Either::Right(
+                                        Either::Left(
+                                            view! {
+                                                <h3 class="text-center justify-start text-gray-600">
+                                                    {format!("{}", date.format("%e %B"))}
+                                                </h3>
+                                                <span class="text-center text-yellow-600 italic">
+                                                    {reason}
+                                                </span>
+                                            },
+                                        ),
+                                    )
+                                }
                                 CalendarDay::Day(meals) => {
                                     Either::Right(
-                                        view! {
-                                            <h3 class="text-center justify-start">
-                                                {format!("{}", date.format("%e %B"))}
-                                            </h3>
-                                            <Day date is_student meals />
-                                        },
+                                        Either::Right(
+                                            view! {
+                                                <h3 class="text-center justify-start">
+                                                    {format!("{}", date.format("%e %B"))}
+                                                </h3>
+                                                <Day date is_student meals />
+                                            },
+                                        ),
                                     )
                                 }
                             }}
@@ -563,13 +555,19 @@ pub fn InnerCalendar(
 pub fn Day(
     date: NaiveDate,
     is_student: bool,
-    meals: Vec<(Uuid, String, u32, EffectiveAttendance)>,
+    meals: Vec<(Uuid, String, u32, EffectiveAttendance, NaiveDateTime)>,
 ) -> impl IntoView {
     view! {
         <div class="flex-1 grid gap-x-4 items-center" class:day=!is_student>
             {meals
                 .into_iter()
-                .map(|(meal_id, meal_name, attendance, status)| {
+                .map(|(meal_id, meal_name, attendance, status, deadline)| {
+                    // Guardians can change the meal until its cut-off, which may fall on an earlier day
+                    let deadline = if deadline.date() == date {
+                        format!("do {}", deadline.format("%H:%M"))
+                    } else {
+                        format!("do {}", deadline.format("%d.%m %H:%M"))
+                    };
                     view! {
                         <div
                             data-testid=format!(
@@ -584,8 +582,10 @@ pub fn Day(
                             class:text-red-600=status == EffectiveAttendance::Absent
                             class:text-yellow-600=status == EffectiveAttendance::Cancelled
                             class:text-gray-600=status == EffectiveAttendance::Blocked
+                            title=format!("Odwołanie {}", deadline)
                         >
                             {meal_name.clone()}
+                            <span class="block text-xs text-gray-500">{deadline.clone()}</span>
                         </div>
                         {if !is_student {
                             Either::Left(
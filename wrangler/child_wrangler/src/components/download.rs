@@ -0,0 +1,49 @@
+use leptos::wasm_bindgen::JsCast;
+use wasm_bindgen_futures::JsFuture;
+use web_sys::{
+    js_sys::{Array, Uint8Array},
+    wasm_bindgen::JsValue,
+    Blob, FileSystemFileHandle, FileSystemWritableFileStream,
+};
+
+use crate::components::snackbar::{use_snackbar, SnackbarContext};
+
+pub async fn save_text_to_file(content: &str, success: &'static str, failure: &'static str) {
+    let array = Array::new();
+    array.push(&JsValue::from_str(content));
+    save_to_file(Blob::new_with_str_sequence(&array), success, failure);
+}
+
+pub async fn save_bytes_to_file(content: &[u8], success: &'static str, failure: &'static str) {
+    let array = Array::new();
+    array.push(&Uint8Array::from(content));
+    save_to_file(Blob::new_with_u8_array_sequence(&array), success, failure);
+}
+
+fn save_to_file(blob: Result<Blob, JsValue>, success: &'static str, failure: &'static str) {
+    let snackbar = use_snackbar();
+
+    if let Ok(blob) = blob {
+        wasm_bindgen_futures::spawn_local(async move {
+            match async move {
+                let promise = web_sys::window().map(|window| window.show_save_file_picker());
+                if let Some(Ok(promise)) = promise {
+                    let handle = JsFuture::from(promise)
+                        .await
+                        .and_then(|handle| handle.dyn_into::<FileSystemFileHandle>())?;
+                    let writable = JsFuture::from(handle.create_writable())
+                        .await
+                        .and_then(|writable| writable.dyn_into::<FileSystemWritableFileStream>())?;
+                    JsFuture::from(writable.write_with_blob(&blob)?).await?;
+                    JsFuture::from(writable.close()).await?;
+                }
+                Ok::<_, JsValue>(())
+            }
+            .await
+            {
+                Ok(_) => snackbar.success(success),
+                Err(_) => snackbar.error(failure, ""),
+            }
+        });
+    }
+}
@@ -1,7 +1,7 @@
 use std::collections::{BTreeMap, HashMap};
 
 use chrono::NaiveDateTime;
-use dto::messages::{Message, PendingMessage, ReceivedMessage, SentMessage};
+use dto::messages::{DeliveryStatus, Message, PendingMessage, ReceivedMessage, SentMessage};
 use leptos::{either::Either, logging::log, prelude::*};
 use uuid::Uuid;
 
@@ -10,8 +10,9 @@ use crate::{
         modal::Modal,
         modals::message_details::MessageDetailsModal,
         snackbar::{use_snackbar, SnackbarContext},
+        template_picker::TemplatePicker,
     },
-    services::messages::{get_messages, send_message},
+    services::messages::{get_messages, requeue_message, send_message},
 };
 
 #[component]
@@ -56,6 +57,7 @@ pub fn Messages(phone: String) -> impl IntoView {
                                 <InnerMessages messages />
                             </div>
                             <div class="flex flex-row gap-2">
+                                <TemplatePicker set_content=set_msg />
                                 <input
                                     class="input flex-1"
                                     autocomplete="off"
@@ -168,13 +170,49 @@ pub fn InnerMessages(messages: Vec<Message>) -> impl IntoView {
 
 #[component]
 pub fn PendingMessageView(message: PendingMessage) -> impl IntoView {
+    let snackbar = use_snackbar();
+    let msg_id = message.metadata.id;
+    let retry = Action::new(move |_: &()| async move {
+        match requeue_message(msg_id).await {
+            Ok(_) => snackbar.success("Wiadomość zostanie wysłana ponownie"),
+            Err(e) => snackbar.error("Nie udało się ponowić wysyłki wiadomości", e),
+        }
+    });
+
+    let failed = message.status == DeliveryStatus::Failed;
+
     view! {
         <div class="flex flex-col gap-1 w-fit self-end">
-            <div class="card row row-col p-2 w-fit">
+            <div
+                class="card row row-col p-2 w-fit"
+                class=("outline", failed)
+                class=("outline-red-500/50", failed)
+                class=("bg-red-500/15", failed)
+            >
                 <span>{format!("{}", message.data.content)}</span>
             </div>
-            <small class="self-end gray">
-                {format!("Zakolejkowano: {}", message.metadata.inserted.format("%H:%M:%S"))}
+            <small class="self-end gray flex flex-row gap-2">
+                <span>
+                    {format!("Zakolejkowano: {}", message.metadata.inserted.format("%H:%M:%S"))}
+                </span>
+                <span>{message.status.label()}</span>
+                {(message.attempts > 1)
+                    .then(|| view! { <span>{format!("Próby: {}", message.attempts)}</span> })}
+                {message.last_error.map(|error| view! { <span class="text-red-500">{error}</span> })}
+                {failed
+                    .then(|| {
+                        view! {
+                            <button
+                                class="btn"
+                                disabled=move || retry.pending()()
+                                on:click=move |_| {
+                                    retry.dispatch(());
+                                }
+                            >
+                                Ponów
+                            </button>
+                        }
+                    })}
             </small>
         </div>
     }
@@ -192,6 +230,7 @@ pub fn SentMessageView(message: SentMessage) -> impl IntoView {
                     {format!("Zakolejkowano: {}", message.metadata.inserted.format("%H:%M:%S"))}
                 </span>
                 <span>{format!("Wysłano: {}", message.sent.format("%H:%M:%S"))}</span>
+                <span>{message.status.label()}</span>
             </small>
         </div>
     }
@@ -4,9 +4,12 @@ pub mod add_guardian_from_phone;
 pub mod add_student;
 pub mod delete_group;
 pub mod delete_student;
+pub mod edit_catering;
+pub mod guardian_ledger;
 pub mod meal_count_modal;
 pub mod meal_edit_modal;
 pub mod meal_history_modal;
 pub mod message_details;
 pub mod modify_group;
 pub mod modify_guardian_phone;
+pub mod transfer_student;
@@ -1,7 +1,8 @@
 use std::iter;
 
 use chrono::{NaiveDate, NaiveTime, Weekday};
-use dto::catering::{CreateCateringDto, MealDto};
+use dto::attendance::CateringMealDto;
+use dto::catering::{CateringMealSettingsDto, CreateCateringDto, MealCutoffDto, MealDto};
 use leptos::{either::Either, logging::log, prelude::*};
 use uuid::Uuid;
 
@@ -10,6 +11,7 @@ use crate::{
         dropdown::Dropdown,
         general_provider::GroupVersion,
         modal::Modal,
+        modals::edit_catering::MealCutoffInput,
         snackbar::{use_snackbar, SnackbarContext},
     },
     icons::close::CloseIcon,
@@ -47,15 +49,25 @@ fn InnerCateringModal(
     let update_groups = expect_context::<GroupVersion>();
     let on_cancel = move |_| on_close(None);
 
-    let (selected_meals, set_selected_meals) = signal(vec![]);
+    let (selected_meals, set_selected_meals) = signal(Vec::<CateringMealDto>::new());
     let (name, set_name) = signal(String::new());
     let (start, set_start) = signal(String::new());
     let (end, set_end) = signal(String::new());
     let (grace, set_grace) = signal(String::new());
 
+    // New meals close together with the catering unless changed
+    let default_cutoff = move || MealCutoffDto {
+        days_before: 0,
+        time: NaiveTime::parse_from_str(&grace.get_untracked(), "%H:%M").unwrap_or_default(),
+    };
+
     let on_meal_select = move |meal: Result<MealDto, String>| match meal {
         Ok(meal) => {
-            set_selected_meals.write().push(meal);
+            set_selected_meals.write().push(CateringMealDto {
+                id: meal.id,
+                name: meal.name,
+                cutoff: default_cutoff(),
+            });
             None
         }
         Err(name) => {
@@ -65,9 +77,10 @@ fn InnerCateringModal(
                 .any(|m| m.name.to_lowercase() == name.to_lowercase())
                 && !name.is_empty()
             {
-                set_selected_meals.write().push(MealDto {
+                set_selected_meals.write().push(CateringMealDto {
                     id: Uuid::new_v4(),
                     name: String::from(name),
+                    cutoff: default_cutoff(),
                 });
                 Some(String::from(name))
             } else {
@@ -89,6 +102,11 @@ fn InnerCateringModal(
     );
 
     let on_remove = move |id| set_selected_meals.write().retain(|x| x.id != id);
+    let on_cutoff = move |id, cutoff| {
+        if let Some(meal) = set_selected_meals.write().iter_mut().find(|m| m.id == id) {
+            meal.cutoff = cutoff;
+        }
+    };
 
     let create_catering = Action::new(move |dto: &CreateCateringDto| {
         let dto = dto.clone();
@@ -116,7 +134,13 @@ fn InnerCateringModal(
                 since,
                 until,
                 grace_period,
-                meals: selected_meals().iter().map(|m| m.name.clone()).collect(),
+                meals: selected_meals()
+                    .into_iter()
+                    .map(|m| CateringMealSettingsDto {
+                        name: m.name,
+                        cutoff: m.cutoff,
+                    })
+                    .collect(),
                 dow: dow()
                     .into_iter()
                     .map(|(_, enabled)| enabled)
@@ -143,7 +167,7 @@ fn InnerCateringModal(
     };
 
     view! {
-        <div class="gap-2 flex flex-col" style:width="25em">
+        <div class="gap-2 flex flex-col" style:width="32em">
             <h2 class="text-center text-lg">Dodaj catering</h2>
             <div class="flex flex-col">
                 <label for="name">Nazwa</label>
@@ -187,9 +211,13 @@ fn InnerCateringModal(
                         } else {
                             Either::Right(view! {})
                         }
-                    }} <For each=selected_meals key=|meal: &MealDto| meal.id let:meal>
+                    }} <For each=selected_meals key=|meal: &CateringMealDto| meal.id let:meal>
                         <div class="rounded-md outline outline-stone-300/25 flex-1 flex align-center p-1">
                             <span class="p-1 flex-1 align-self-center">{meal.name}</span>
+                            <MealCutoffInput
+                                cutoff=meal.cutoff
+                                on_change=move |cutoff| on_cutoff(meal.id, cutoff)
+                            />
                             <button
                                 class="p-1 md:hover:bg-gray-700 md:active:bg-gray-600 md:cursor-pointer red rounded-md"
                                 on:click=move |_| on_remove(meal.id)
@@ -220,6 +248,9 @@ fn InnerCateringModal(
                     type="time"
                     placeholder="Koniec"
                 />
+                <span class="text-sm text-gray-400">
+                    Posiłek można odwołać najpóźniej w tym czasie, termin każdego posiłku ustawia się przy nim
+                </span>
             </div>
 
             <label>Dni obowiązywania</label>
@@ -1,3 +1,4 @@
+use chrono::NaiveDate;
 use dto::{
     catering::AllergyDto, details::StudentDetailsDto, guardian::GuardianDto,
     student::CreateStudentDto,
@@ -84,6 +85,20 @@ fn InnerAddStudentModal(
             .map(|i| i.guardians.clone())
             .unwrap_or(vec![]),
     );
+    let (joined, set_joined) = signal(
+        initial
+            .as_ref()
+            .and_then(|i| i.joined)
+            .map(|d| d.to_string())
+            .unwrap_or_default(),
+    );
+    let (left, set_left) = signal(
+        initial
+            .as_ref()
+            .and_then(|i| i.left)
+            .map(|d| d.to_string())
+            .unwrap_or_default(),
+    );
 
     let available_guardians = move || {
         let g2 = selected_guardians().clone();
@@ -141,6 +156,9 @@ fn InnerAddStudentModal(
                     surname: insert_dto.surname,
                     guardians,
                     allergies,
+                    joined: insert_dto.joined,
+                    left: insert_dto.left,
+                    placements: vec![],
                 };
                 match update_student(dto).await {
                     Ok(_) => {
@@ -164,6 +182,15 @@ fn InnerAddStudentModal(
     });
 
     let on_save = move |_| {
+        // Empty date means the enrolment follows the catering
+        let parse = |date: String| match date.trim() {
+            "" => Ok(None),
+            date => NaiveDate::parse_from_str(date, "%Y-%m-%d").map(Some),
+        };
+        let (Ok(joined), Ok(left)) = (parse(joined()), parse(left())) else {
+            snackbar.error("Podano nieprawidłową datę", "");
+            return;
+        };
         let dto = CreateStudentDto {
             name: name(),
             group_id: group,
@@ -173,6 +200,8 @@ fn InnerAddStudentModal(
                 .into_iter()
                 .map(|a| a.fullname)
                 .collect(),
+            joined,
+            left,
         };
         save_student.dispatch(dto);
     };
@@ -193,6 +222,20 @@ fn InnerAddStudentModal(
                 </div>
             </div>
 
+            <div class="flex flex-row gap-2">
+                <div class="flex flex-col flex-1">
+                    <label for="joined">Początek nauki</label>
+                    <input bind:value=(joined, set_joined) id="joined" class="input" type="date" />
+                </div>
+                <div class="flex flex-col flex-1">
+                    <label for="left">Koniec nauki</label>
+                    <input bind:value=(left, set_left) id="left" class="input" type="date" />
+                </div>
+            </div>
+            <span class="text-sm text-gray-400">
+                Bez dat uczeń je posiłki przez cały czas trwania cateringu
+            </span>
+
             <label>Alergie</label>
             <ul
                 class="gap-1 flex flex-col p-1 rounded-md outline outline-dashed"
@@ -0,0 +1,363 @@
+use chrono::{NaiveDate, NaiveTime, Weekday};
+use dto::attendance::CateringMealDto;
+use dto::catering::{
+    CateringDetailsDto, CateringMealSettingsDto, CateringUpdateImpactDto, MealCutoffDto, MealDto,
+    UpdateCateringDto,
+};
+use leptos::{either::Either, prelude::*};
+use uuid::Uuid;
+
+use crate::{
+    components::{
+        dropdown::Dropdown,
+        loader::Loader,
+        snackbar::{use_snackbar, SnackbarContext},
+    },
+    icons::close::CloseIcon,
+    services::{
+        catering::{get_catering_details, preview_catering_update, update_catering},
+        student::get_meals,
+    },
+};
+
+#[component]
+pub fn EditCateringModal(
+    group_id: Uuid,
+    on_close: impl Fn(bool) + Send + Sync + Copy + 'static,
+) -> impl IntoView {
+    let data = Resource::new(
+        || (),
+        move |_| async move {
+            Ok::<_, ServerFnError>((get_catering_details(group_id).await?, get_meals().await?))
+        },
+    );
+
+    view! {
+        <Loader>
+            {move || Suspend::new(async move {
+                let (catering, meals) = data.await?;
+                Ok::<_, ServerFnError>(view! { <InnerEditCateringModal catering meals on_close /> })
+            })}
+        </Loader>
+    }
+}
+
+#[component]
+fn InnerEditCateringModal(
+    catering: CateringDetailsDto,
+    meals: Vec<MealDto>,
+    on_close: impl Fn(bool) + Send + Sync + Copy + 'static,
+) -> impl IntoView {
+    let snackbar = use_snackbar();
+    let catering_id = catering.id;
+
+    let (selected_meals, set_selected_meals) = signal(catering.meals);
+    let (start, set_start) = signal(catering.since.to_string());
+    let (end, set_end) = signal(catering.until.to_string());
+    let (grace, set_grace) = signal(catering.grace_period.format("%H:%M").to_string());
+    let (dow, set_dow) = signal(catering.dow);
+    // Preview of a change that would remove recorded cancellations, waiting for confirmation
+    let (pending, set_pending) = signal(None::<(UpdateCateringDto, CateringUpdateImpactDto)>);
+    Effect::new(move |_| {
+        start.track();
+        end.track();
+        grace.track();
+        dow.track();
+        selected_meals.track();
+        set_pending(None);
+    });
+
+    // New meals close together with the catering unless changed
+    let default_cutoff = move || MealCutoffDto {
+        days_before: 0,
+        time: NaiveTime::parse_from_str(&grace.get_untracked(), "%H:%M").unwrap_or_default(),
+    };
+
+    let on_meal_select = move |meal: Result<MealDto, String>| match meal {
+        Ok(meal) => {
+            set_selected_meals.write().push(CateringMealDto {
+                id: meal.id,
+                name: meal.name,
+                cutoff: default_cutoff(),
+            });
+            None
+        }
+        Err(name) => {
+            let name = name.trim();
+            if !selected_meals()
+                .iter()
+                .any(|m| m.name.to_lowercase() == name.to_lowercase())
+                && !name.is_empty()
+            {
+                set_selected_meals.write().push(CateringMealDto {
+                    id: Uuid::new_v4(),
+                    name: String::from(name),
+                    cutoff: default_cutoff(),
+                });
+                Some(String::from(name))
+            } else {
+                None
+            }
+        }
+    };
+
+    let on_remove = move |id| set_selected_meals.write().retain(|x| x.id != id);
+    let on_cutoff = move |id, cutoff| {
+        if let Some(meal) = set_selected_meals.write().iter_mut().find(|m| m.id == id) {
+            meal.cutoff = cutoff;
+        }
+    };
+    let on_move_up = move |id| {
+        let mut meals = set_selected_meals.write();
+        if let Some(i) = meals.iter().position(|m| m.id == id).filter(|i| *i > 0) {
+            meals.swap(i - 1, i);
+        }
+    };
+
+    let save_action = Action::new(move |(dto, confirmed): &(UpdateCateringDto, bool)| {
+        let (dto, confirmed) = (dto.clone(), *confirmed);
+        async move {
+            if !confirmed {
+                match preview_catering_update(dto.clone()).await {
+                    Ok(impact) if impact.cancellations > 0 => {
+                        set_pending(Some((dto, impact)));
+                        return;
+                    }
+                    Ok(_) => {}
+                    Err(e) => {
+                        snackbar.error("Nie udało się zmienić cateringu", e);
+                        return;
+                    }
+                }
+            }
+            match update_catering(dto, confirmed).await {
+                Ok(impact) => {
+                    snackbar.success(&format!(
+                        "Zmieniono catering od {}, dodano {} i usunięto {} posiłków",
+                        impact.effective, impact.added, impact.removed
+                    ));
+                    on_close(true);
+                }
+                Err(e) => snackbar.error("Nie udało się zmienić cateringu", e),
+            }
+        }
+    });
+
+    let on_save = move |_| match (
+        NaiveDate::parse_from_str(&start(), "%Y-%m-%d"),
+        NaiveDate::parse_from_str(&end(), "%Y-%m-%d"),
+        NaiveTime::parse_from_str(&grace(), "%H:%M"),
+    ) {
+        (Ok(since), Ok(until), Ok(grace_period)) => {
+            let dto = UpdateCateringDto {
+                id: catering_id,
+                since,
+                until,
+                grace_period,
+                meals: selected_meals()
+                    .into_iter()
+                    .map(|m| CateringMealSettingsDto {
+                        name: m.name,
+                        cutoff: m.cutoff,
+                    })
+                    .collect(),
+                dow: dow(),
+            };
+            save_action.dispatch((dto, false));
+        }
+        _ => {
+            snackbar.error("Podano nieprawidłowy czas lub datę", "");
+        }
+    };
+
+    let on_confirm = move |_| {
+        if let Some((dto, _)) = pending.get_untracked() {
+            set_pending(None);
+            save_action.dispatch((dto, true));
+        }
+    };
+
+    let available_meals = move || {
+        let selected_meals = selected_meals();
+        meals
+            .clone()
+            .into_iter()
+            .filter(|m| {
+                !selected_meals
+                    .iter()
+                    .any(|b| b.name.to_lowercase() == m.name.to_lowercase())
+            })
+            .collect::<Vec<_>>()
+    };
+
+    view! {
+        <div class="gap-2 flex flex-col" style:width="32em">
+            <h2 class="text-center text-lg">{format!("Edytuj catering {}", catering.name)}</h2>
+            <span>Zmiany obowiązują od najbliższego dnia, którego można jeszcze odwołać</span>
+
+            <div class="flex flex-row gap-2">
+                <div class="flex flex-col flex-1">
+                    <label for="start">Początek</label>
+                    <input bind:value=(start, set_start) id="start" class="input" type="date" />
+                </div>
+                <div class="flex flex-col flex-1">
+                    <label for="end">Koniec</label>
+                    <input bind:value=(end, set_end) id="end" class="input" type="date" />
+                </div>
+            </div>
+
+            <label for="meals">Posiłki</label>
+            <div class="p-1 rounded-md outline outline-dashed outline-gray/25">
+                <div class="flex flex-col gap-2">
+                    {move || {
+                        if selected_meals().is_empty() {
+                            Either::Left(view! { "Nie wybrano posiłków" })
+                        } else {
+                            Either::Right(view! {})
+                        }
+                    }} <For each=selected_meals key=|meal: &CateringMealDto| meal.id let:meal>
+                        <div class="rounded-md outline outline-stone-300/25 flex-1 flex align-center p-1">
+                            <span class="p-1 flex-1 align-self-center">{meal.name}</span>
+                            <MealCutoffInput
+                                cutoff=meal.cutoff
+                                on_change=move |cutoff| on_cutoff(meal.id, cutoff)
+                            />
+                            <button
+                                class="p-1 md:hover:bg-gray-700 md:active:bg-gray-600 md:cursor-pointer rounded-md"
+                                on:click=move |_| on_move_up(meal.id)
+                            >
+                                "↑"
+                            </button>
+                            <button
+                                class="p-1 md:hover:bg-gray-700 md:active:bg-gray-600 md:cursor-pointer red rounded-md"
+                                on:click=move |_| on_remove(meal.id)
+                            >
+                                <CloseIcon />
+                            </button>
+                        </div>
+                    </For>
+                </div>
+            </div>
+
+            <Dropdown
+                name="meals"
+                options=available_meals
+                key=|i| i.id
+                on_select=on_meal_select
+                item_view=|item| view! { <div class="p-1">{item.name}</div> }
+                filter=|needle, hay| hay.name.to_lowercase().contains(&needle.to_lowercase())
+            />
+
+            <div class="flex flex-col">
+                <label for="cancellation">Czas na odmowę</label>
+                <input bind:value=(grace, set_grace) id="cancellation" class="input" type="time" />
+                <span class="text-sm text-gray-400">
+                    Posiłek można odwołać najpóźniej w tym czasie, termin każdego posiłku ustawia się przy nim
+                </span>
+            </div>
+
+            <label>Dni obowiązywania</label>
+            <div class="flex flex-row gap-2">
+                {move || {
+                    dow()
+                        .iter()
+                        .enumerate()
+                        .map(|(i, enabled)| {
+                            let weekday = Weekday::try_from(i as u8).unwrap_or(Weekday::Mon);
+                            view! {
+                                <button
+                                    class:outline-2=*enabled
+                                    class="p-1 rounded-md outline-green-900 md:cursor-pointer md:hover:bg-gray-700 md:active:bg-gray-600 flex-1"
+                                    on:click=move |_| set_dow.write()[i] = !dow()[i]
+                                >
+                                    {format!("{}", weekday)}
+                                </button>
+                            }
+                        })
+                        .collect::<Vec<_>>()
+                }}
+            </div>
+
+            {move || {
+                pending()
+                    .map(|(_, impact)| {
+                        view! {
+                            <span class="red">
+                                {format!(
+                                    "Zmiana usunie {} zapisanych odwołań posiłków od {}. Zapisać mimo to?",
+                                    impact.cancellations,
+                                    impact.effective,
+                                )}
+                            </span>
+                        }
+                    })
+            }}
+
+            <div class="flex flex-row justify-end gap-2">
+                <button
+                    class="btn cancel"
+                    on:click=move |_| on_close(false)
+                    disabled=save_action.pending()
+                >
+                    Anuluj
+                </button>
+                <Show
+                    when=move || pending.with(Option::is_some)
+                    fallback=move || {
+                        view! {
+                            <button
+                                class="btn save"
+                                on:click=on_save
+                                disabled=save_action.pending()
+                            >
+                                Zapisz
+                            </button>
+                        }
+                    }
+                >
+                    <button class="btn save" on:click=on_confirm disabled=save_action.pending()>
+                        Zapisz mimo to
+                    </button>
+                </Show>
+            </div>
+        </div>
+    }
+}
+
+/// Cut-off of a catering meal, guardians can change it until the time on the day that many days before it
+#[component]
+pub fn MealCutoffInput(
+    cutoff: MealCutoffDto,
+    on_change: impl Fn(MealCutoffDto) + Send + Sync + Copy + 'static,
+) -> impl IntoView {
+    let (cutoff, set_cutoff) = signal(cutoff);
+
+    view! {
+        <div class="flex flex-row items-center gap-1 text-sm" title="Termin odwołania posiłku">
+            <input
+                class="input w-12"
+                type="number"
+                min="0"
+                prop:value=cutoff.get_untracked().days_before.to_string()
+                on:change=move |ev| {
+                    if let Ok(days_before) = event_target_value(&ev).parse::<u32>() {
+                        set_cutoff.write().days_before = days_before;
+                        on_change(cutoff.get_untracked());
+                    }
+                }
+            />
+            <span>dni wcześniej do</span>
+            <input
+                class="input"
+                type="time"
+                prop:value=cutoff.get_untracked().time.format("%H:%M").to_string()
+                on:change=move |ev| {
+                    if let Ok(time) = NaiveTime::parse_from_str(&event_target_value(&ev), "%H:%M") {
+                        set_cutoff.write().time = time;
+                        on_change(cutoff.get_untracked());
+                    }
+                }
+            />
+        </div>
+    }
+}
@@ -0,0 +1,220 @@
+use chrono::Utc;
+use dto::billing::{format_amount, parse_amount};
+use dto::ledger::{CreateLedgerEntryDto, GuardianLedgerDto, LedgerEntryDto, LedgerEntryKind};
+use dto::user::Role;
+use leptos::prelude::*;
+use uuid::Uuid;
+
+use crate::{
+    components::{
+        auth::has_role,
+        loader::Loader,
+        snackbar::{use_snackbar, SnackbarContext},
+    },
+    services::ledger::{add_ledger_entry, get_guardian_ledger, remove_ledger_entry},
+};
+
+fn balance_label(balance: i64) -> String {
+    if balance < 0 {
+        format!("Zaległość: {}", format_amount(-balance))
+    } else if balance > 0 {
+        format!("Nadpłata: {}", format_amount(balance))
+    } else {
+        String::from("Rozliczony")
+    }
+}
+
+/// Balance of the guardian, shown next to the contact so staff know about arrears while messaging
+#[component]
+pub fn GuardianBalance(guardian_id: Uuid, version: ReadSignal<i32>) -> impl IntoView {
+    let ledger = Resource::new(version, move |_| async move {
+        get_guardian_ledger(guardian_id).await
+    });
+
+    view! {
+        <Suspense>
+            {move || Suspend::new(async move {
+                ledger
+                    .await
+                    .ok()
+                    .map(|ledger| {
+                        view! {
+                            <span class:red=ledger.balance < 0 class:green={ledger.balance > 0}>
+                                {balance_label(ledger.balance)}
+                            </span>
+                        }
+                    })
+            })}
+        </Suspense>
+    }
+}
+
+#[component]
+pub fn GuardianLedgerModal(
+    guardian_id: Uuid,
+    version: ReadSignal<i32>,
+    set_version: WriteSignal<i32>,
+    on_close: impl Fn() + Send + Sync + Copy + 'static,
+) -> impl IntoView {
+    let ledger = Resource::new(version, move |_| async move {
+        get_guardian_ledger(guardian_id).await
+    });
+
+    view! {
+        <h2 class="text-lg text-center">Rozliczenia</h2>
+        <div class="flex flex-col gap-1 overflow-auto max-h-96">
+            <Loader>
+                {move || Suspend::new(async move {
+                    let ledger = ledger.await?;
+                    Ok::<_, ServerFnError>(view! { <LedgerEntries ledger set_version /> })
+                })}
+            </Loader>
+        </div>
+        <Show when=|| has_role(&[Role::Admin])>
+            <AddLedgerEntryForm guardian_id set_version />
+        </Show>
+        <div class="flex flex-row justify-end gap-2">
+            <Show when=|| has_role(&[Role::Admin])>
+                <a class="btn" href="/payments">
+                    Wszystkie zaległości
+                </a>
+            </Show>
+            <button class="btn cancel" on:click=move |_| on_close()>
+                Zamknij
+            </button>
+        </div>
+    }
+}
+
+#[component]
+fn LedgerEntries(ledger: GuardianLedgerDto, set_version: WriteSignal<i32>) -> impl IntoView {
+    view! {
+        <span class:red=ledger.balance < 0 class:green={ledger.balance > 0}>
+            {balance_label(ledger.balance)}
+        </span>
+        {ledger.entries.is_empty().then(|| view! { <span>Brak wpisów</span> })}
+        {ledger
+            .entries
+            .into_iter()
+            .map(|entry| view! { <LedgerEntryRow entry set_version /> })
+            .collect::<Vec<_>>()}
+    }
+}
+
+#[component]
+fn LedgerEntryRow(entry: LedgerEntryDto, set_version: WriteSignal<i32>) -> impl IntoView {
+    let snackbar = use_snackbar();
+    let id = entry.id;
+
+    let remove_action = Action::new(move |_: &()| async move {
+        match remove_ledger_entry(id).await {
+            Ok(_) => {
+                snackbar.success("Usunięto wpis");
+                *set_version.write() += 1;
+            }
+            Err(e) => snackbar.error("Nie udało się usunąć wpisu", e),
+        }
+    });
+
+    view! {
+        <div class="flex flex-row flex-wrap gap-2 items-center">
+            <span>{entry.booked.to_string()}</span>
+            <span class="min-w-20">{entry.kind.label()}</span>
+            <span class="flex-1 min-w-32">
+                {entry.description} {entry.from_bank.then_some(" (przelew)")}
+            </span>
+            <span class:red=entry.amount < 0 class:green={entry.amount > 0}>
+                {format_amount(entry.amount)}
+            </span>
+            <Show when=|| has_role(&[Role::Admin])>
+                <input
+                    type="button"
+                    class="btn"
+                    value="Usuń"
+                    disabled=remove_action.pending()
+                    on:click=move |_| {
+                        remove_action.dispatch(());
+                    }
+                />
+            </Show>
+        </div>
+    }
+}
+
+#[component]
+fn AddLedgerEntryForm(guardian_id: Uuid, set_version: WriteSignal<i32>) -> impl IntoView {
+    let snackbar = use_snackbar();
+    let (kind, set_kind) = signal(LedgerEntryKind::Payment);
+    let (amount, set_amount) = signal(String::new());
+    let (booked, set_booked) = signal(Utc::now().date_naive().to_string());
+    let (description, set_description) = signal(String::from("Wpłata gotówkowa"));
+
+    let add_action = Action::new(move |_: &()| {
+        let value = amount();
+        let (negative, value) = match value.trim().strip_prefix('-') {
+            Some(value) => (true, String::from(value)),
+            None => (false, value),
+        };
+        let dto = parse_amount(&value)
+            .zip(booked().parse().ok())
+            .map(|(value, booked)| CreateLedgerEntryDto {
+                guardian_id,
+                kind: kind(),
+                amount: if negative { -value } else { value },
+                booked,
+                description: description(),
+            });
+        async move {
+            let Some(dto) = dto else {
+                snackbar.error("Podaj kwotę i datę", "");
+                return;
+            };
+            match add_ledger_entry(dto).await {
+                Ok(_) => {
+                    snackbar.success("Dodano wpis");
+                    set_amount(String::new());
+                    *set_version.write() += 1;
+                }
+                Err(e) => snackbar.error("Nie udało się dodać wpisu", e),
+            }
+        }
+    });
+
+    view! {
+        <form
+            class="flex flex-row flex-wrap gap-2 items-center"
+            on:submit=move |e| {
+                e.prevent_default();
+                add_action.dispatch(());
+            }
+        >
+            <select
+                class="input"
+                on:change=move |e| {
+                    if let Ok(kind) = event_target_value(&e).parse() {
+                        set_kind(kind);
+                    }
+                }
+            >
+                {[LedgerEntryKind::Payment, LedgerEntryKind::Adjustment]
+                    .into_iter()
+                    .map(|k| {
+                        view! {
+                            <option value=k.as_str() selected=move || kind() == k>
+                                {k.label()}
+                            </option>
+                        }
+                    })
+                    .collect::<Vec<_>>()}
+            </select>
+            <input class="input w-24" placeholder="Kwota" bind:value=(amount, set_amount) />
+            <input class="input" type="date" bind:value=(booked, set_booked) />
+            <input
+                class="input flex-1"
+                placeholder="Opis"
+                bind:value=(description, set_description)
+            />
+            <input type="submit" class="btn save" value="Dodaj" disabled=add_action.pending() />
+        </form>
+    }
+}
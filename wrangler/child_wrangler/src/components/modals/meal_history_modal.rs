@@ -176,10 +176,49 @@ pub fn MealHistoryModalInner(
                                         ),
                                     )
                                 }
+                                dto::attendance::AttendanceItemDto::Portal(guardian) => {
+                                    Either::Right(
+                                        Either::Left(
+                                            view! {
+                                                <div class="flex flex-col gap-2">
+                                                    <h2 class="flex gap-2">
+                                                        <span class="text-lg">Zmiana w portalu rodzica</span>
+                                                        <span class="rounded-md flex align-center pl-2 pr-2 bg-violet-500/50 outline outline-violet-500 flex gap-2">
+                                                            {guardian}
+                                                        </span>
+                                                    </h2>
+                                                    <ul class="flex flex-row gap-2">
+                                                        {att
+                                                            .meals
+                                                            .iter()
+                                                            .map(|meal| {
+                                                                view! {
+                                                                    <li
+                                                                        class="w-fit p-1 outline-2 rounded-md gap-2"
+                                                                        class:outline-red-800=!meal.1
+                                                                        class:bg-red-600=!meal.1
+                                                                        class:outline-green-800=meal.1
+                                                                        class:bg-green-600=meal.1
+                                                                    >
+                                                                        {meals
+                                                                            .get(&meal.0)
+                                                                            .map(|meal| format!("{}", meal.name))
+                                                                            .unwrap_or(format!("Nieprawidłówy posiłek"))}
+                                                                    </li>
+                                                                }
+                                                            })
+                                                            .collect::<Vec<_>>()}
+                                                    </ul>
+                                                </div>
+                                            },
+                                        ),
+                                    )
+                                }
                                 dto::attendance::AttendanceItemDto::Init => {
                                     Either::Right(
-
-                                        view! { <h2 class="text-lg">Dodano catering</h2> },
+                                        Either::Right(
+                                            view! { <h2 class="text-lg">Dodano catering</h2> },
+                                        ),
                                     )
                                 }
                             }}
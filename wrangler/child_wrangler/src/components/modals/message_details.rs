@@ -3,8 +3,8 @@ use std::collections::HashMap;
 use dto::{
     catering::MealDto,
     messages::{
-        AttendanceCancellation, CancellationRequest, CancellationResult, MessageProcessing,
-        RequestError, Student, Token,
+        AttendanceCancellation, CancellationRequest, CancellationResult, Clarification,
+        MessageProcessing, RequestError, Student, Token,
     },
     student::StudentDto,
 };
@@ -97,7 +97,8 @@ fn MessageDetailsModalInner(
                                             ),
                                         )
                                     }
-                                    MessageProcessing::Cancellation(request) => {
+                                    MessageProcessing::Cancellation(request)
+                                    | MessageProcessing::Restoration(request) => {
                                         Either::Left(
                                             Either::Right(
                                                 Either::Left(
@@ -112,7 +113,8 @@ fn MessageDetailsModalInner(
                                             ),
                                         )
                                     }
-                                    MessageProcessing::StudentCancellation(cancellation) => {
+                                    MessageProcessing::StudentCancellation(cancellation)
+                                    | MessageProcessing::StudentRestoration(cancellation) => {
                                         Either::Left(
                                             Either::Right(
                                                 Either::Right(
@@ -129,10 +131,22 @@ fn MessageDetailsModalInner(
                                     }
                                     MessageProcessing::RequestError(error) => {
                                         Either::Right(
-                                            Either::Left(view! { <ComponentError error /> }),
+                                            Either::Left(
+                                                Either::Left(view! { <ComponentError error /> }),
+                                            ),
+                                        )
+                                    }
+                                    MessageProcessing::Clarification(clarification) => {
+                                        Either::Right(
+                                            Either::Left(
+                                                Either::Right(
+                                                    view! { <ClarificationInfo clarification /> },
+                                                ),
+                                            ),
                                         )
                                     }
-                                    MessageProcessing::CancellationResult(result) => {
+                                    MessageProcessing::CancellationResult(result)
+                                    | MessageProcessing::RestorationResult(result) => {
                                         Either::Right(
                                             Either::Right(view! { <CancellationResultView result /> }),
                                         )
@@ -236,6 +250,20 @@ pub fn TokenInfo(
                                 ),
                             )
                         }
+                        Token::DateRange(since, until) => {
+                            Either::Left(
+                                Either::Left(
+                                    Either::Right(
+                                        view! {
+                                            <div class="flex p-1 pl-2 pr-2  gap-1 rounded-full outline-violet-500/50 outline bg-violet-500/15">
+                                                <CalendarIcon />
+                                                {format!("{} - {}", since, until)}
+                                            </div>
+                                        },
+                                    ),
+                                ),
+                            )
+                        }
                         Token::Meal(uuid) => {
                             Either::Left(
                                 Either::Right(
@@ -266,7 +294,21 @@ pub fn TokenInfo(
                         }
                         Token::Ambiguous(i) => {
                             Either::Right(
-                                view! { <div class="pill outline-red">{format!("{}", i)}</div> },
+                                Either::Left(
+                                    view! { <div class="pill outline-red">{format!("{}", i)}</div> },
+                                ),
+                            )
+                        }
+                        Token::Restore => {
+                            Either::Right(
+                                Either::Right(
+                                    view! {
+                                        <div class="flex p-1 pl-2 pr-2 gap-1 rounded-full outline-green-500/50 outline bg-green-500/15">
+                                            <RefreshIcon />
+                                            Przywrócenie
+                                        </div>
+                                    },
+                                ),
                             )
                         }
                     })
@@ -276,6 +318,23 @@ pub fn TokenInfo(
     }
 }
 
+#[component]
+pub fn ClarificationInfo(clarification: Clarification) -> impl IntoView {
+    view! {
+        <div class="flex flex-col gap-2 bg-yellow-500/15 outline outline-yellow-500/50 rounded-md p-2">
+            <h2 class="text-center">Oczekuje na doprecyzowanie</h2>
+            <ol class="flex flex-col gap-1">
+                {clarification
+                    .options
+                    .into_iter()
+                    .enumerate()
+                    .map(|(i, option)| view! { <li>{format!("{} - {}", i + 1, option.label)}</li> })
+                    .collect::<Vec<_>>()}
+            </ol>
+        </div>
+    }
+}
+
 #[component]
 pub fn ComponentError(error: RequestError) -> impl IntoView {
     view! {
@@ -0,0 +1,118 @@
+use chrono::{NaiveDate, Utc};
+use dto::{group::GroupDto, student::TransferStudentDto};
+use leptos::prelude::*;
+use uuid::Uuid;
+
+use crate::{
+    components::{
+        dropdown::Dropdown,
+        loader::Loader,
+        snackbar::{use_snackbar, SnackbarContext},
+    },
+    services::{group::get_groups, student::transfer_student},
+};
+
+#[component]
+pub fn TransferStudentModal(
+    student_id: Uuid,
+    on_close: impl Fn(bool) + Send + Sync + Copy + 'static,
+) -> impl IntoView {
+    let groups = Resource::new(|| (), |_| async move { get_groups().await });
+
+    view! {
+        <Loader>
+            {move || Suspend::new(async move {
+                let groups = groups.await?;
+                Ok::<_, ServerFnError>(view! { <InnerTransferStudentModal student_id groups on_close /> })
+            })}
+        </Loader>
+    }
+}
+
+#[component]
+fn InnerTransferStudentModal(
+    student_id: Uuid,
+    groups: Vec<GroupDto>,
+    on_close: impl Fn(bool) + Send + Sync + Copy + 'static,
+) -> impl IntoView {
+    let snackbar = use_snackbar();
+
+    let (group, set_group) = signal(None::<GroupDto>);
+    let (effective, set_effective) = signal(Utc::now().date_naive().to_string());
+
+    // Students can only be placed in groups without subgroups, caterings have no parent
+    let leaf_groups = groups
+        .iter()
+        .filter(|g| g.parent.is_some() && !groups.iter().any(|c| c.parent == Some(g.id)))
+        .cloned()
+        .collect::<Vec<_>>();
+
+    let on_select = move |selected: Result<GroupDto, String>| {
+        let selected = selected.ok();
+        let name = selected.as_ref().map(|g| g.name.clone());
+        set_group(selected);
+        name
+    };
+
+    let transfer = Action::new(move |dto: &TransferStudentDto| {
+        let dto = dto.clone();
+        async move {
+            match transfer_student(dto).await {
+                Ok(_) => {
+                    snackbar.success("Zapisano przeniesienie ucznia");
+                    on_close(true);
+                }
+                Err(e) => snackbar.error("Nie udało się przenieść ucznia", e),
+            }
+        }
+    });
+
+    let on_save = move |_| match (
+        group(),
+        NaiveDate::parse_from_str(&effective(), "%Y-%m-%d"),
+    ) {
+        (Some(group), Ok(effective)) => {
+            transfer.dispatch(TransferStudentDto {
+                student_id,
+                group_id: group.id,
+                effective,
+            });
+        }
+        (None, _) => snackbar.error("Nie wybrano grupy", ""),
+        (_, Err(_)) => snackbar.error("Podano nieprawidłową datę", ""),
+    };
+
+    view! {
+        <div class="gap-2 flex flex-col">
+            <h2 class="text-center text-lg">Przenieś ucznia</h2>
+            <label for="group">Nowa grupa</label>
+            <Dropdown
+                name="group"
+                options=move || leaf_groups.clone()
+                key=|g| g.id
+                on_select
+                item_view=|item| view! { <div class="p-1">{item.name}</div> }
+                filter=|needle, hay| hay.name.to_lowercase().contains(&needle.to_lowercase())
+            />
+            <div class="flex flex-col">
+                <label for="effective">Od dnia</label>
+                <input bind:value=(effective, set_effective) id="effective" class="input" type="date" />
+                <span class="text-sm text-gray-400">
+                    Wcześniejsze dni pozostają w zestawieniach poprzedniej grupy
+                </span>
+            </div>
+            <div class="flex flex-row gap-2 justify-end">
+                <button
+                    class="btn cancel"
+                    on:click=move |_| on_close(false)
+                    disabled=transfer.pending()
+                >
+                    Anuluj
+                </button>
+                <button class="btn save" on:click=on_save disabled=transfer.pending()>
+                    Przenieś
+                </button>
+            </div>
+        </div>
+    }
+}
@@ -0,0 +1,41 @@
+use leptos::prelude::*;
+
+use crate::services::template::get_templates;
+
+/// Select of staff templates, choosing one replaces the message content with it
+#[component]
+pub fn TemplatePicker(set_content: WriteSignal<String>) -> impl IntoView {
+    let templates = Resource::new(|| (), |_| async move { get_templates().await });
+
+    view! {
+        <select
+            class="input"
+            on:change=move |e| {
+                let content = event_target_value(&e);
+                if !content.is_empty() {
+                    set_content(content);
+                }
+            }
+        >
+            <option value="" selected>
+                Szablon
+            </option>
+            <Suspense>
+                {move || Suspend::new(async move {
+                    templates
+                        .await
+                        .map(|templates| {
+                            templates
+                                .into_iter()
+                                .filter(|template| template.system.is_none())
+                                .map(|template| {
+                                    view! { <option value=template.content>{template.name}</option> }
+                                })
+                                .collect::<Vec<_>>()
+                        })
+                        .unwrap_or_default()
+                })}
+            </Suspense>
+        </select>
+    }
+}
@@ -31,6 +31,14 @@ async fn main() {
         .await
         .expect("Failed to execute migration");
 
+    if let Ok(password) = std::env::var("ADMIN_PASSWORD") {
+        child_wrangler::services::auth::create_initial_admin(&pool, &password)
+            .await
+            .expect("Failed to create initial admin account");
+    }
+
+    tokio::spawn(child_wrangler::services::scheduler::run_scheduler(pool.clone()));
+
     let app = Router::new()
         .leptos_routes_with_context(
             &leptos_options,
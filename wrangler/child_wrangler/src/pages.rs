@@ -1,9 +1,22 @@
 pub mod attendance_dashboard;
 pub mod attendance_page;
+pub mod audit_page;
+pub mod billing_page;
+pub mod broadcast_page;
+pub mod closures_page;
 pub mod detail_page;
 pub mod faker_page;
 pub mod guardian_contact_details;
+pub mod import_page;
+pub mod jobs_page;
+pub mod login_page;
 pub mod message_dashboard;
 pub mod message_page;
+pub mod payments_page;
+pub mod portal_page;
+pub mod production_page;
+pub mod recycle_bin_page;
+pub mod templates_page;
 pub mod test_page;
 pub mod unknown_contact_details;
+pub mod users_page;
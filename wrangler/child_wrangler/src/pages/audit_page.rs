@@ -0,0 +1,214 @@
+use dto::{
+    audit::{AuditChangeDto, AuditEntity, AuditEntryDto},
+    group::SearchTerm,
+};
+use leptos::{either::Either, prelude::*};
+use leptos_router::{
+    hooks::{use_navigate, use_params},
+    params::Params,
+};
+use uuid::Uuid;
+
+use crate::{
+    components::{dropdown::Dropdown, loader::Loader},
+    services::{audit::get_audit_log, group::get_search_terms, student::get_guardians},
+};
+
+#[derive(Params, PartialEq)]
+pub struct AuditParams {
+    pub id: Option<Uuid>,
+}
+
+fn field_label(field: &str) -> &str {
+    match field {
+        "name" => "Nazwa",
+        "surname" => "Nazwisko",
+        "fullname" => "Imię i nazwisko",
+        "phone" => "Telefon",
+        "removed" => "Usunięty",
+        "group" | "parent" => "Grupa",
+        "allergies" => "Alergie",
+        "guardians" => "Rodzice",
+        "students" => "Uczniowie",
+        "grace_period" => "Czas na odwołanie",
+        "dow" => "Dni tygodnia",
+        "since" => "Od",
+        "until" => "Do",
+        "meals" => "Posiłki",
+        "cutoffs" => "Odwołania posiłków",
+        "joined" => "Początek nauki",
+        "left" => "Koniec nauki",
+        "placements" => "Historia grup",
+        "prices" => "Ceny",
+        "balance" => "Saldo",
+        "entries" => "Wpisy",
+        "catering" => "Catering",
+        "reason" => "Powód",
+        "job" => "Zadanie",
+        "schedule" => "Harmonogram",
+        "enabled" => "Włączone",
+        "content" => "Treść",
+        "recipients" => "Odbiorcy",
+        "outgoing" => "Wychodząca",
+        "sent" => "Wysłano",
+        "processed" => "Przetworzona",
+        "login" => "Login",
+        "role" => "Rola",
+        "disabled" => "Zablokowany",
+        other => other,
+    }
+}
+
+#[component]
+pub fn AuditPage() -> impl IntoView {
+    let params = use_params::<AuditParams>();
+    let id = move || params.read().as_ref().ok().and_then(|params| params.id);
+
+    let entries = Resource::new(id, |id| async move { get_audit_log(id).await });
+    let terms = Resource::new(
+        || (),
+        |_| async move {
+            let terms = get_search_terms().await?;
+            let guardians = get_guardians().await?;
+            Ok::<_, ServerFnError>(
+                terms
+                    .into_iter()
+                    .chain(guardians.into_iter().map(|guardian| SearchTerm {
+                        id: guardian.id,
+                        name: guardian.fullname,
+                        parent_name: Some(String::from(AuditEntity::Guardian.label())),
+                    }))
+                    .collect::<Vec<_>>(),
+            )
+        },
+    );
+
+    view! {
+        <div class="flex-1 flex flex-col gap-2 overflow-hidden">
+            <div class="card p-2 flex flex-row gap-2 items-center">
+                <h2 class="h2">Historia zmian</h2>
+                <Loader>
+                    {move || Suspend::new(async move {
+                        let terms = terms.await?;
+                        Ok::<_, ServerFnError>(view! { <AuditFilter terms /> })
+                    })}
+                </Loader>
+                <a class="btn" href="/audit">
+                    Wszystkie
+                </a>
+            </div>
+            <div class="flex-1 overflow-auto">
+                <Loader>
+                    {move || Suspend::new(async move {
+                        let entries = entries.await?;
+                        Ok::<_, ServerFnError>(view! { <AuditTable entries /> })
+                    })}
+                </Loader>
+            </div>
+        </div>
+    }
+}
+
+#[component]
+fn AuditFilter(terms: Vec<SearchTerm>) -> impl IntoView {
+    let on_select = move |item: Result<SearchTerm, String>| {
+        if let Ok(item) = item {
+            let navigate = use_navigate();
+            navigate(&format!("/audit/{}", item.id), Default::default());
+        }
+        Some(String::new())
+    };
+
+    view! {
+        <Dropdown
+            name="audit_search"
+            options=move || terms.clone()
+            key=|t| t.id
+            filter=|h, n| n.name.to_lowercase().contains(&h.to_lowercase())
+            on_select
+            item_view=|item| {
+                view! {
+                    <div class="flex flex-row justify-center">
+                        {item.parent_name.map(|name| view! { <div class="p-1">{name}</div> })}
+                        <div class="p-1">{item.name}</div>
+                    </div>
+                }
+            }
+        />
+    }
+}
+
+#[component]
+fn AuditTable(entries: Vec<AuditEntryDto>) -> impl IntoView {
+    if entries.is_empty() {
+        return Either::Left(view! { <div class="card p-2">Brak zmian</div> });
+    }
+
+    Either::Right(view! {
+        <table class="card w-full text-left">
+            <thead>
+                <tr>
+                    <th class="p-1">Data</th>
+                    <th class="p-1">Użytkownik</th>
+                    <th class="p-1">Operacja</th>
+                    <th class="p-1">Obiekt</th>
+                    <th class="p-1">Zmiany</th>
+                </tr>
+            </thead>
+            <tbody>
+                {entries
+                    .into_iter()
+                    .map(|entry| {
+                        view! {
+                            <tr class="border-t border-white/15 align-top">
+                                <td class="p-1 whitespace-nowrap">
+                                    {entry.created.format("%Y-%m-%d %H:%M:%S").to_string()}
+                                </td>
+                                <td class="p-1">
+                                    {entry.actor.unwrap_or(String::from("System"))}
+                                </td>
+                                <td class="p-1">{entry.action.label()}</td>
+                                <td class="p-1">
+                                    <a class="md:hover:text-gray-300" href=format!("/audit/{}", entry.entity_id)>
+                                        {format!(
+                                            "{}: {}",
+                                            entry.entity.label(),
+                                            entry.entity_name.unwrap_or(entry.entity_id.to_string()),
+                                        )}
+                                    </a>
+                                </td>
+                                <td class="p-1">
+                                    <AuditChanges changes=entry.changes />
+                                </td>
+                            </tr>
+                        }
+                    })
+                    .collect::<Vec<_>>()}
+            </tbody>
+        </table>
+    })
+}
+
+#[component]
+fn AuditChanges(changes: Vec<AuditChangeDto>) -> impl IntoView {
+    view! {
+        <ul>
+            {changes
+                .into_iter()
+                .map(|change| {
+                    view! {
+                        <li>
+                            <span class="text-gray-400">{field_label(&change.field).to_string()}</span>
+                            " "
+                            <span class="text-red-400 line-through">
+                                {change.before.unwrap_or_default()}
+                            </span>
+                            " → "
+                            <span class="text-green-400">{change.after.unwrap_or_default()}</span>
+                        </li>
+                    }
+                })
+                .collect::<Vec<_>>()}
+        </ul>
+    }
+}
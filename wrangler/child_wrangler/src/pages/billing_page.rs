@@ -0,0 +1,401 @@
+use chrono::{Datelike, NaiveDate, Utc};
+use dto::{
+    billing::{
+        format_amount, parse_amount, CreateMealPriceDto, GuardianStatementDto, MealPriceDto,
+    },
+    catering::{CateringDto, MealDto},
+};
+use leptos::prelude::*;
+use uuid::Uuid;
+
+use crate::{
+    components::{
+        download::{save_bytes_to_file, save_text_to_file},
+        loader::Loader,
+        snackbar::{use_snackbar, SnackbarContext},
+    },
+    services::{
+        billing::{
+            add_meal_price, get_meal_prices, get_statements, get_statements_csv,
+            get_statements_pdf, notify_statements, remove_meal_price,
+        },
+        catering::{get_catering_meals, get_caterings},
+    },
+};
+
+fn parse_month(month: &str) -> Option<(i32, u32)> {
+    let date = NaiveDate::parse_from_str(&format!("{}-01", month), "%Y-%m-%d").ok()?;
+    Some((date.year(), date.month()))
+}
+
+#[component]
+pub fn BillingPage() -> impl IntoView {
+    let now = Utc::now();
+    let (month, set_month) = signal(format!("{}-{:02}", now.year(), now.month()));
+    let selected = Memo::new(move |_| parse_month(&month()));
+
+    let caterings = Resource::new(|| (), |_| async move { get_caterings().await });
+
+    view! {
+        <div class="flex-1 flex flex-col gap-2 overflow-auto">
+            <Loader>
+                {move || Suspend::new(async move {
+                    let caterings = caterings.await?;
+                    Ok::<_, ServerFnError>(view! { <MealPrices caterings /> })
+                })}
+            </Loader>
+            <div class="card p-2 flex flex-row flex-wrap gap-2 items-center">
+                <h2 class="h2 flex-1">Rozliczenia</h2>
+                <input class="input" type="month" bind:value=(month, set_month) />
+            </div>
+            {move || selected().map(|(year, month)| view! { <Statements year month /> })}
+        </div>
+    }
+}
+
+#[component]
+fn MealPrices(caterings: Vec<CateringDto>) -> impl IntoView {
+    let (catering, set_catering) = signal(caterings.first().map(|catering| catering.id));
+    let (version, set_version) = signal(0);
+
+    let prices = Resource::new(
+        move || (catering(), version()),
+        |(catering, _)| async move {
+            match catering {
+                Some(catering) => Ok(Some((
+                    get_meal_prices(catering).await?,
+                    get_catering_meals(catering).await?,
+                ))),
+                None => Ok::<_, ServerFnError>(None),
+            }
+        },
+    );
+
+    view! {
+        <div class="card p-2 flex flex-col gap-1">
+            <div class="flex flex-row flex-wrap gap-2 items-center">
+                <h2 class="h2 flex-1">Cennik posiłków</h2>
+                <select
+                    class="input"
+                    on:change=move |e| set_catering(event_target_value(&e).parse().ok())
+                >
+                    {caterings
+                        .into_iter()
+                        .map(|c| {
+                            view! {
+                                <option value=c.id.to_string() selected=move || catering() == Some(c.id)>
+                                    {c.name}
+                                </option>
+                            }
+                        })
+                        .collect::<Vec<_>>()}
+                </select>
+            </div>
+            <Loader>
+                {move || Suspend::new(async move {
+                    let prices = prices.await?;
+                    Ok::<
+                        _,
+                        ServerFnError,
+                    >(
+                        prices
+                            .zip(catering.get_untracked())
+                            .map(|((prices, meals), catering_id)| {
+                                view! {
+                                    {prices
+                                        .into_iter()
+                                        .map(|price| view! { <MealPriceRow price set_version /> })
+                                        .collect::<Vec<_>>()}
+                                    <AddMealPriceForm catering_id meals set_version />
+                                }
+                            }),
+                    )
+                })}
+            </Loader>
+        </div>
+    }
+}
+
+#[component]
+fn MealPriceRow(price: MealPriceDto, set_version: WriteSignal<i32>) -> impl IntoView {
+    let snackbar = use_snackbar();
+    let id = price.id;
+
+    let remove_action = Action::new(move |_: &()| async move {
+        match remove_meal_price(id).await {
+            Ok(_) => {
+                snackbar.success("Usunięto cenę");
+                *set_version.write() += 1;
+            }
+            Err(e) => snackbar.error("Nie udało się usunąć ceny", e),
+        }
+    });
+
+    view! {
+        <div class="flex flex-row flex-wrap gap-2 items-center">
+            <span class="flex-1 min-w-32">{price.meal}</span>
+            <span>{format_amount(price.price as i64)}</span>
+            <span>
+                {format!(
+                    "od {} do {}",
+                    price.since,
+                    price.until.map(|until| until.to_string()).unwrap_or(String::from("odwołania")),
+                )}
+            </span>
+            <input
+                type="button"
+                class="btn"
+                value="Usuń"
+                disabled=remove_action.pending()
+                on:click=move |_| {
+                    remove_action.dispatch(());
+                }
+            />
+        </div>
+    }
+}
+
+#[component]
+fn AddMealPriceForm(
+    catering_id: Uuid,
+    meals: Vec<MealDto>,
+    set_version: WriteSignal<i32>,
+) -> impl IntoView {
+    let snackbar = use_snackbar();
+    let (meal, set_meal) = signal(meals.first().map(|meal| meal.id));
+    let (price, set_price) = signal(String::new());
+    let (since, set_since) = signal(String::new());
+    let (until, set_until) = signal(String::new());
+
+    let add_action = Action::new(move |_: &()| {
+        let dto = (|| {
+            Some(CreateMealPriceDto {
+                catering_id,
+                meal_id: meal()?,
+                price: parse_amount(&price())?.try_into().ok()?,
+                since: since().parse().ok()?,
+                until: until().parse().ok(),
+            })
+        })();
+        async move {
+            let Some(dto) = dto else {
+                snackbar.error("Podaj posiłek, cenę i datę początkową", "");
+                return;
+            };
+            match add_meal_price(dto).await {
+                Ok(_) => {
+                    snackbar.success("Dodano cenę");
+                    set_price(String::new());
+                    *set_version.write() += 1;
+                }
+                Err(e) => snackbar.error("Nie udało się dodać ceny", e),
+            }
+        }
+    });
+
+    view! {
+        <form
+            class="flex flex-row flex-wrap gap-2 items-center"
+            on:submit=move |e| {
+                e.prevent_default();
+                add_action.dispatch(());
+            }
+        >
+            <select class="input" on:change=move |e| set_meal(event_target_value(&e).parse().ok())>
+                {meals
+                    .into_iter()
+                    .map(|m| {
+                        view! {
+                            <option value=m.id.to_string() selected=move || meal() == Some(m.id)>
+                                {m.name}
+                            </option>
+                        }
+                    })
+                    .collect::<Vec<_>>()}
+            </select>
+            <input class="input" placeholder="Cena (zł)" bind:value=(price, set_price) />
+            <label class="flex flex-row gap-1 items-center">
+                Od <input class="input" type="date" bind:value=(since, set_since) />
+            </label>
+            <label class="flex flex-row gap-1 items-center">
+                Do <input class="input" type="date" bind:value=(until, set_until) />
+            </label>
+            <input type="submit" class="btn save" value="Dodaj cenę" disabled=add_action.pending() />
+        </form>
+    }
+}
+
+#[component]
+fn Statements(year: i32, month: u32) -> impl IntoView {
+    let snackbar = use_snackbar();
+    let statements = Resource::new(|| (), move |_| async move { get_statements(year, month).await });
+
+    let csv_action = Action::new(move |_: &()| async move {
+        match get_statements_csv(year, month).await {
+            Ok(csv) => {
+                save_text_to_file(&csv, "Zapisano rozliczenia", "Nie udało się zapisać rozliczeń")
+                    .await
+            }
+            Err(e) => snackbar.error("Nie udało się pobrać rozliczeń", e),
+        }
+    });
+
+    let pdf_action = Action::new(move |guardian: &Option<Uuid>| {
+        let guardian = *guardian;
+        async move {
+            match get_statements_pdf(year, month, guardian).await {
+                Ok(pdf) => {
+                    save_bytes_to_file(&pdf, "Zapisano rozliczenia", "Nie udało się zapisać rozliczeń")
+                        .await
+                }
+                Err(e) => snackbar.error("Nie udało się pobrać rozliczeń", e),
+            }
+        }
+    });
+
+    let notify_action = Action::new(move |guardians: &Vec<Uuid>| {
+        let guardians = guardians.clone();
+        async move {
+            match notify_statements(year, month, guardians).await {
+                Ok(sent) => snackbar.success(&format!("Wysłano {} wiadomości", sent)),
+                Err(e) => snackbar.error("Nie udało się wysłać wiadomości", e),
+            }
+        }
+    });
+
+    view! {
+        <Loader>
+            {move || Suspend::new(async move {
+                let statements = statements.await?;
+                let due = statements
+                    .iter()
+                    .filter(|statement| statement.due > 0)
+                    .map(|statement| statement.guardian_id)
+                    .collect::<Vec<_>>();
+                let nothing_due = due.is_empty();
+                let total: i64 = statements.iter().map(|statement| statement.due).sum();
+                Ok::<
+                    _,
+                    ServerFnError,
+                >(
+                    view! {
+                        <div class="card p-2 flex flex-row flex-wrap gap-2 items-center">
+                            <span class="flex-1">
+                                {format!("Do zapłaty łącznie: {}", format_amount(total))}
+                            </span>
+                            <input
+                                type="button"
+                                class="btn"
+                                value="Eksport CSV"
+                                disabled=csv_action.pending()
+                                on:click=move |_| {
+                                    csv_action.dispatch(());
+                                }
+                            />
+                            <input
+                                type="button"
+                                class="btn"
+                                value="Eksport PDF"
+                                disabled=pdf_action.pending()
+                                on:click=move |_| {
+                                    pdf_action.dispatch(None);
+                                }
+                            />
+                            <input
+                                type="button"
+                                class="btn save"
+                                value="Wyślij SMS do wszystkich"
+                                disabled=move || nothing_due || notify_action.pending()()
+                                on:click=move |_| {
+                                    notify_action.dispatch(due.clone());
+                                }
+                            />
+                        </div>
+                        {statements
+                            .into_iter()
+                            .map(|statement| {
+                                view! { <StatementCard statement pdf_action notify_action /> }
+                            })
+                            .collect::<Vec<_>>()}
+                    },
+                )
+            })}
+        </Loader>
+    }
+}
+
+#[component]
+fn StatementCard(
+    statement: GuardianStatementDto,
+    pdf_action: Action<Option<Uuid>, ()>,
+    notify_action: Action<Vec<Uuid>, ()>,
+) -> impl IntoView {
+    let guardian_id = statement.guardian_id;
+    let can_notify = statement.phone.is_some() && statement.due > 0;
+    let missing_prices = statement.has_missing_prices();
+
+    view! {
+        <div class="card p-2 flex flex-col gap-1">
+            <div class="flex flex-row flex-wrap gap-2 items-center">
+                <h3 class="flex-1">
+                    <a href=format!("/messages/guardian/{}", guardian_id)>{statement.guardian}</a>
+                </h3>
+                <span>{format!("Do zapłaty: {}", format_amount(statement.due))}</span>
+                <input
+                    type="button"
+                    class="btn"
+                    value="PDF"
+                    disabled=pdf_action.pending()
+                    on:click=move |_| {
+                        pdf_action.dispatch(Some(guardian_id));
+                    }
+                />
+                <input
+                    type="button"
+                    class="btn"
+                    value="SMS"
+                    disabled=move || !can_notify || notify_action.pending()()
+                    on:click=move |_| {
+                        notify_action.dispatch(vec![guardian_id]);
+                    }
+                />
+            </div>
+            {missing_prices
+                .then(|| {
+                    view! {
+                        <span class="red">"Brak ceny części posiłków, nie zostały one policzone"</span>
+                    }
+                })}
+            {statement
+                .lines
+                .into_iter()
+                .map(|line| {
+                    view! {
+                        <div class="flex flex-row flex-wrap gap-2 pl-4">
+                            <span class="flex-1 min-w-32 flex flex-col">
+                                <span>{line.student}</span>
+                                {(!line.other_guardians.is_empty())
+                                    .then(|| {
+                                        view! {
+                                            <span class="text-sm text-gray-400">
+                                                {format!("Również: {}", line.other_guardians.join(", "))}
+                                            </span>
+                                        }
+                                    })}
+                            </span>
+                            <span class="min-w-24">{line.meal}</span>
+                            <span class="min-w-24">
+                                {line
+                                    .unit_price
+                                    .map(|price| format_amount(price as i64))
+                                    .unwrap_or(String::from("brak ceny"))}
+                            </span>
+                            <span>{format!("{} zamówione, {} odwołane", line.ordered, line.cancelled)}</span>
+                            <span class="min-w-24">{format_amount(line.charged - line.refunded)}</span>
+                        </div>
+                    }
+                })
+                .collect::<Vec<_>>()}
+        </div>
+    }
+}
@@ -0,0 +1,353 @@
+use std::{collections::HashMap, time::Duration};
+
+use dto::{
+    broadcast::{BroadcastDeliveryDto, BroadcastDto, BroadcastRecipientDto, CreateBroadcastDto},
+    group::GroupDto,
+    messages::DeliveryStatus,
+    user::Role,
+};
+use leptos::prelude::*;
+use leptos_router::{
+    components::A,
+    hooks::{use_navigate, use_params},
+    params::Params,
+};
+use uuid::Uuid;
+
+use crate::{
+    components::{
+        auth::has_role,
+        general_provider::GroupResource,
+        loader::Loader,
+        snackbar::{use_snackbar, SnackbarContext},
+        template_picker::TemplatePicker,
+    },
+    services::broadcast::{get_broadcast, get_broadcasts, preview_broadcast, send_broadcast},
+};
+
+const DELIVERY_REFRESH_INTERVAL: Duration = Duration::from_secs(10);
+
+#[derive(Params, PartialEq)]
+pub struct BroadcastParams {
+    pub id: Uuid,
+}
+
+/// Groups in tree order, indented by depth. Top level groups are the caterings
+fn group_options(groups: &HashMap<Uuid, GroupDto>) -> Vec<(Uuid, String)> {
+    let mut children: HashMap<Option<Uuid>, Vec<&GroupDto>> = HashMap::new();
+    for group in groups.values() {
+        children.entry(group.parent).or_default().push(group);
+    }
+    for siblings in children.values_mut() {
+        siblings.sort_by(|a, b| b.name.cmp(&a.name));
+    }
+
+    let mut result = vec![];
+    let mut stack = children
+        .get(&None)
+        .map(|roots| roots.iter().map(|group| (*group, 0)).collect::<Vec<_>>())
+        .unwrap_or_default();
+    while let Some((group, depth)) = stack.pop() {
+        result.push((
+            group.id,
+            format!("{}{}", "\u{2003}".repeat(depth), group.name),
+        ));
+        if let Some(siblings) = children.get(&Some(group.id)) {
+            stack.extend(siblings.iter().map(|child| (*child, depth + 1)));
+        }
+    }
+    result
+}
+
+#[component]
+pub fn BroadcastPage() -> impl IntoView {
+    let broadcasts = Resource::new(|| (), |_| async move { get_broadcasts().await });
+
+    view! {
+        <div class="flex-1 flex flex-col gap-2 overflow-auto">
+            <Show when=|| has_role(&[Role::Admin])>
+                <BroadcastForm />
+            </Show>
+            <div class="card p-2 flex flex-col gap-1">
+                <h2 class="h2">Wysłane wiadomości zbiorcze</h2>
+                <Loader>
+                    {move || Suspend::new(async move {
+                        let broadcasts = broadcasts.await?;
+                        Ok::<
+                            _,
+                            ServerFnError,
+                        >(
+                            view! {
+                                {broadcasts
+                                    .is_empty()
+                                    .then(|| view! { <span>Brak wiadomości zbiorczych</span> })}
+                                {broadcasts
+                                    .into_iter()
+                                    .map(|broadcast| view! { <BroadcastRow broadcast /> })
+                                    .collect::<Vec<_>>()}
+                            },
+                        )
+                    })}
+                </Loader>
+            </div>
+        </div>
+    }
+}
+
+#[component]
+fn BroadcastForm() -> impl IntoView {
+    let snackbar = use_snackbar();
+    let groups = expect_context::<GroupResource>().0;
+
+    let (group, set_group) = signal(None::<Uuid>);
+    let (content, set_content) = signal(String::new());
+
+    let recipients = Resource::new(group, |group| async move {
+        match group {
+            Some(group) => preview_broadcast(group).await,
+            None => Ok(vec![]),
+        }
+    });
+
+    let send_action = Action::new(move |dto: &CreateBroadcastDto| {
+        let dto = dto.clone();
+        async move {
+            match send_broadcast(dto).await {
+                Ok(id) => {
+                    snackbar.success("Wiadomość została dodana do kolejki");
+                    let navigate = use_navigate();
+                    navigate(&format!("/messages/broadcasts/{}", id), Default::default());
+                }
+                Err(e) => snackbar.error("Nie udało się wysłać wiadomości", e),
+            }
+        }
+    });
+
+    view! {
+        <div class="card p-2 flex flex-col gap-2">
+            <h2 class="h2">Nowa wiadomość zbiorcza</h2>
+            <select class="input" on:change=move |e| set_group(event_target_value(&e).parse().ok())>
+                <option value="" selected=move || group().is_none()>
+                    Wybierz grupę lub catering
+                </option>
+                <Suspense>
+                    {move || Suspend::new(async move {
+                        groups
+                            .await
+                            .map(|groups| {
+                                group_options(&groups)
+                                    .into_iter()
+                                    .map(|(id, name)| {
+                                        view! {
+                                            <option
+                                                value=id.to_string()
+                                                selected=move || group() == Some(id)
+                                            >
+                                                {name}
+                                            </option>
+                                        }
+                                    })
+                                    .collect::<Vec<_>>()
+                            })
+                            .unwrap_or_default()
+                    })}
+                </Suspense>
+            </select>
+            <TemplatePicker set_content />
+            <textarea
+                class="input"
+                rows="3"
+                placeholder="Treść wiadomości"
+                prop:value=content
+                on:input=move |e| set_content(event_target_value(&e))
+            ></textarea>
+            <span>{move || format!("Znaków: {}", content().chars().count())}</span>
+            <Loader>
+                {move || Suspend::new(async move {
+                    let recipients = recipients.await?;
+                    let reachable = recipients
+                        .iter()
+                        .filter(|recipient| recipient.phone.is_some())
+                        .count();
+                    Ok::<
+                        _,
+                        ServerFnError,
+                    >(
+                        view! {
+                            <div class="flex flex-row flex-wrap gap-2 items-center">
+                                <span class="flex-1">
+                                    {format!(
+                                        "Odbiorcy: {} z {} rodziców ma numer telefonu",
+                                        reachable,
+                                        recipients.len(),
+                                    )}
+                                </span>
+                                <input
+                                    type="button"
+                                    class="btn save"
+                                    value=format!("Wyślij do {} rodziców", reachable)
+                                    disabled=move || {
+                                        reachable == 0 || content().trim().is_empty()
+                                            || send_action.pending()()
+                                    }
+                                    on:click=move |_| {
+                                        if let Some(group_id) = group() {
+                                            send_action
+                                                .dispatch(CreateBroadcastDto {
+                                                    group_id,
+                                                    content: content(),
+                                                });
+                                        }
+                                    }
+                                />
+                            </div>
+                            <div class="flex flex-col gap-0.5 max-h-64 overflow-auto">
+                                {recipients
+                                    .into_iter()
+                                    .map(|recipient| view! { <RecipientRow recipient /> })
+                                    .collect::<Vec<_>>()}
+                            </div>
+                        },
+                    )
+                })}
+            </Loader>
+        </div>
+    }
+}
+
+#[component]
+fn RecipientRow(recipient: BroadcastRecipientDto) -> impl IntoView {
+    let missing_phone = recipient.phone.is_none();
+
+    view! {
+        <div class="flex flex-row flex-wrap gap-2 pl-4">
+            <span class="min-w-48">{recipient.fullname}</span>
+            <span class="min-w-32" class:red=missing_phone>
+                {recipient.phone.unwrap_or(String::from("Brak numeru"))}
+            </span>
+            <span class="flex-1 min-w-32">{recipient.students.join(", ")}</span>
+        </div>
+    }
+}
+
+fn progress(broadcast: &BroadcastDto) -> String {
+    format!(
+        "Dostarczono {}, wysłano {}, w kolejce {}, błędy {} z {}",
+        broadcast.delivered,
+        broadcast.sent,
+        broadcast.pending,
+        broadcast.failed,
+        broadcast.recipients
+    )
+}
+
+#[component]
+fn BroadcastRow(broadcast: BroadcastDto) -> impl IntoView {
+    let failing = broadcast.failed > 0;
+
+    view! {
+        <A href=format!("/messages/broadcasts/{}", broadcast.id)>
+            <div class="flex flex-row flex-wrap gap-2 rounded-md p-1 md:hover:bg-gray-700">
+                <span class="min-w-36">{broadcast.created.format("%Y-%m-%d %H:%M").to_string()}</span>
+                <span class="min-w-32">{broadcast.group.clone()}</span>
+                <span class="flex-1 min-w-48 truncate">{broadcast.content.clone()}</span>
+                <span class:red=failing>{progress(&broadcast)}</span>
+            </div>
+        </A>
+    }
+}
+
+/// Delivery state of every message of the broadcast, refreshed periodically
+#[component]
+pub fn BroadcastDetails() -> impl IntoView {
+    let params = use_params::<BroadcastParams>();
+    let id = move || params.read().as_ref().ok().map(|params| params.id);
+
+    let details = Resource::new(id, |id| async move {
+        match id {
+            Some(id) => get_broadcast(id).await.map(Some),
+            None => Ok(None),
+        }
+    });
+
+    Effect::new(move |_| {
+        if let Ok(handle) =
+            set_interval_with_handle(move || details.refetch(), DELIVERY_REFRESH_INTERVAL)
+        {
+            on_cleanup(move || handle.clear());
+        }
+    });
+
+    view! {
+        <div class="flex-1 flex flex-col gap-2 overflow-auto">
+            <Transition>
+                <ErrorBoundary fallback=|_| {
+                    view! { <span class="red">Nie udało się pobrać wiadomości zbiorczej</span> }
+                }>
+                    {move || Suspend::new(async move {
+                        let details = details.await?;
+                        Ok::<
+                            _,
+                            ServerFnError,
+                        >(
+                            details
+                                .map(|details| {
+                                    let broadcast = details.broadcast;
+                                    let failing = broadcast.failed > 0;
+                                    view! {
+                                        <div class="card p-2 flex flex-col gap-1">
+                                            <h2 class="h2">
+                                                {format!("Wiadomość zbiorcza: {}", broadcast.group)}
+                                            </h2>
+                                            <span>
+                                                {format!(
+                                                    "{}, {}",
+                                                    broadcast.created.format("%Y-%m-%d %H:%M"),
+                                                    broadcast.author,
+                                                )}
+                                            </span>
+                                            <span>{broadcast.content.clone()}</span>
+                                            <span class:red=failing>{progress(&broadcast)}</span>
+                                        </div>
+                                        <div class="card p-2 flex flex-col gap-0.5">
+                                            {details
+                                                .deliveries
+                                                .into_iter()
+                                                .map(|delivery| view! { <DeliveryRow delivery /> })
+                                                .collect::<Vec<_>>()}
+                                        </div>
+                                    }
+                                }),
+                        )
+                    })}
+                </ErrorBoundary>
+            </Transition>
+        </div>
+    }
+}
+
+#[component]
+fn DeliveryRow(delivery: BroadcastDeliveryDto) -> impl IntoView {
+    let failed = delivery.status == DeliveryStatus::Failed;
+    let delivered = delivery.status == DeliveryStatus::Delivered;
+
+    view! {
+        <div class="flex flex-row flex-wrap gap-2">
+            <A href=format!("/messages/guardian/{}", delivery.guardian_id)>
+                <span class="min-w-48 md:hover:underline">{delivery.fullname}</span>
+            </A>
+            <span class="min-w-32">{delivery.phone}</span>
+            <span class="min-w-28" class:red=failed class:green=delivered>
+                {delivery.status.label()}
+            </span>
+            <span class="min-w-36">
+                {delivery.sent.map(|sent| sent.format("%Y-%m-%d %H:%M").to_string())}
+            </span>
+            <span class="flex-1 min-w-32">
+                {delivery
+                    .last_error
+                    .map(|error| format!("{} (próby: {})", error, delivery.attempts))}
+            </span>
+        </div>
+    }
+}
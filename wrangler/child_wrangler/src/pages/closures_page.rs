@@ -0,0 +1,256 @@
+use chrono::{Datelike, NaiveDate, Utc};
+use dto::closure::{ClosureDto, CreateClosureDto};
+use leptos::prelude::*;
+use uuid::Uuid;
+
+use crate::{
+    components::{
+        loader::Loader,
+        snackbar::{use_snackbar, SnackbarContext},
+    },
+    services::{
+        catering::get_caterings,
+        closure::{add_closure, get_closures, import_public_holidays, remove_closure},
+    },
+};
+
+#[component]
+pub fn ClosuresPage() -> impl IntoView {
+    let (year, set_year) = signal(Utc::now().year().to_string());
+    let (version, set_version) = signal(0);
+
+    view! {
+        <div class="flex-1 flex flex-col gap-2 overflow-auto">
+            <div class="flex flex-row flex-wrap gap-2">
+                <AddClosureForm set_version />
+                <PublicHolidays year set_version />
+            </div>
+            <ClosureList year set_year version set_version />
+        </div>
+    }
+}
+
+/// Select of the closure scope, empty value stands for all caterings
+#[component]
+fn CateringScope(
+    scope: ReadSignal<Option<Uuid>>,
+    set_scope: WriteSignal<Option<Uuid>>,
+) -> impl IntoView {
+    let caterings = Resource::new(|| (), |_| async move { get_caterings().await });
+
+    view! {
+        <select class="input" on:change=move |e| set_scope(event_target_value(&e).parse().ok())>
+            <option value="" selected=move || scope().is_none()>
+                Wszystkie cateringi
+            </option>
+            <Suspense>
+                {move || Suspend::new(async move {
+                    caterings
+                        .await
+                        .map(|caterings| {
+                            caterings
+                                .into_iter()
+                                .map(|c| {
+                                    view! {
+                                        <option
+                                            value=c.id.to_string()
+                                            selected=move || scope() == Some(c.id)
+                                        >
+                                            {c.name}
+                                        </option>
+                                    }
+                                })
+                                .collect::<Vec<_>>()
+                        })
+                        .unwrap_or_default()
+                })}
+            </Suspense>
+        </select>
+    }
+}
+
+#[component]
+fn ClosureList(
+    year: ReadSignal<String>,
+    set_year: WriteSignal<String>,
+    version: ReadSignal<i32>,
+    set_version: WriteSignal<i32>,
+) -> impl IntoView {
+    let closures = Resource::new(
+        move || (year(), version()),
+        |(year, _)| async move {
+            match year.parse() {
+                Ok(year) => get_closures(year).await,
+                Err(_) => Ok(vec![]),
+            }
+        },
+    );
+
+    view! {
+        <div class="card p-2 flex flex-col gap-1">
+            <div class="flex flex-row gap-2 items-center">
+                <h2 class="h2 flex-1">Dni wolne</h2>
+                <input class="input w-24" type="number" bind:value=(year, set_year) />
+            </div>
+            <Loader>
+                {move || Suspend::new(async move {
+                    let closures = closures.await?;
+                    Ok::<
+                        _,
+                        ServerFnError,
+                    >(
+                        view! {
+                            {closures.is_empty().then(|| view! { <span>Brak dni wolnych</span> })}
+                            {closures
+                                .into_iter()
+                                .map(|closure| view! { <ClosureRow closure set_version /> })
+                                .collect::<Vec<_>>()}
+                        },
+                    )
+                })}
+            </Loader>
+        </div>
+    }
+}
+
+#[component]
+fn ClosureRow(closure: ClosureDto, set_version: WriteSignal<i32>) -> impl IntoView {
+    let snackbar = use_snackbar();
+    let id = closure.id;
+
+    let remove_action = Action::new(move |_: &()| async move {
+        match remove_closure(id).await {
+            Ok(_) => {
+                snackbar.success("Usunięto dzień wolny");
+                *set_version.write() += 1;
+            }
+            Err(e) => snackbar.error("Nie udało się usunąć dnia wolnego", e),
+        }
+    });
+
+    let dates = if closure.since == closure.until {
+        closure.since.to_string()
+    } else {
+        format!("{} - {}", closure.since, closure.until)
+    };
+
+    view! {
+        <div class="flex flex-row flex-wrap gap-2 items-center">
+            <span class="min-w-48">{dates}</span>
+            <span class="flex-1 min-w-32">{closure.reason}</span>
+            <span class="min-w-32">
+                {closure.catering.unwrap_or_else(|| String::from("Wszystkie cateringi"))}
+            </span>
+            <input
+                type="button"
+                class="btn"
+                value="Usuń"
+                disabled=remove_action.pending()
+                on:click=move |_| {
+                    remove_action.dispatch(());
+                }
+            />
+        </div>
+    }
+}
+
+#[component]
+fn AddClosureForm(set_version: WriteSignal<i32>) -> impl IntoView {
+    let snackbar = use_snackbar();
+    let (scope, set_scope) = signal(None::<Uuid>);
+    let (since, set_since) = signal(String::new());
+    let (until, set_until) = signal(String::new());
+    let (reason, set_reason) = signal(String::new());
+
+    let add_action = Action::new(move |_: &()| {
+        let since = NaiveDate::parse_from_str(&since(), "%Y-%m-%d").ok();
+        // Single day closures don't need the end date
+        let until = NaiveDate::parse_from_str(&until(), "%Y-%m-%d").ok().or(since);
+        let dto = since.zip(until).map(|(since, until)| CreateClosureDto {
+            catering_id: scope(),
+            since,
+            until,
+            reason: reason(),
+        });
+        async move {
+            let Some(dto) = dto else {
+                snackbar.error("Podaj datę", "");
+                return;
+            };
+            match add_closure(dto).await {
+                Ok(_) => {
+                    snackbar.success("Dodano dzień wolny");
+                    set_reason(String::new());
+                    *set_version.write() += 1;
+                }
+                Err(e) => snackbar.error("Nie udało się dodać dnia wolnego", e),
+            }
+        }
+    });
+
+    view! {
+        <form
+            class="card p-2 flex flex-col gap-1 flex-1"
+            on:submit=move |e| {
+                e.prevent_default();
+                add_action.dispatch(());
+            }
+        >
+            <h2 class="h2">Nowy dzień wolny</h2>
+            <span>Posiłki w dni wolne nie są wydawane ani liczone, odwołania SMS są pomijane</span>
+            <div class="flex flex-row flex-wrap gap-2 items-center">
+                <input class="input" type="date" bind:value=(since, set_since) />
+                <input class="input" type="date" bind:value=(until, set_until) />
+                <input class="input flex-1" placeholder="Powód" bind:value=(reason, set_reason) />
+                <CateringScope scope set_scope />
+                <input type="submit" class="btn save" value="Dodaj" disabled=add_action.pending() />
+            </div>
+        </form>
+    }
+}
+
+#[component]
+fn PublicHolidays(
+    year: ReadSignal<String>,
+    set_version: WriteSignal<i32>,
+) -> impl IntoView {
+    let snackbar = use_snackbar();
+    let (scope, set_scope) = signal(None::<Uuid>);
+
+    let import_action = Action::new(move |_: &()| {
+        let year = year().parse::<i32>().ok();
+        let catering_id = scope();
+        async move {
+            let Some(year) = year else {
+                snackbar.error("Podaj rok", "");
+                return;
+            };
+            match import_public_holidays(year, catering_id).await {
+                Ok(added) => {
+                    snackbar.success(&format!("Dodano {} świąt", added));
+                    *set_version.write() += 1;
+                }
+                Err(e) => snackbar.error("Nie udało się dodać świąt", e),
+            }
+        }
+    });
+
+    view! {
+        <div class="card p-2 flex flex-col gap-1 flex-1">
+            <h2 class="h2">Święta państwowe</h2>
+            <span>{move || format!("Dodaje dni ustawowo wolne od pracy w roku {}", year())}</span>
+            <div class="flex flex-row flex-wrap gap-2 items-center">
+                <CateringScope scope set_scope />
+                <input
+                    type="button"
+                    class="btn save"
+                    value="Dodaj święta"
+                    disabled=import_action.pending()
+                    on:click=move |_| {
+                        import_action.dispatch(());
+                    }
+                />
+            </div>
+        </div>
+    }
+}
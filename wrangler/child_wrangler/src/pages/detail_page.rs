@@ -1,6 +1,7 @@
 use dto::{
     details::{EntityDto, GroupDetailsDto, StudentDetailsDto},
     group::GroupDto,
+    user::Role,
 };
 use leptos::{either::Either, prelude::*};
 use leptos_router::hooks::use_params;
@@ -8,12 +9,14 @@ use uuid::Uuid;
 
 use crate::{
     components::{
+        auth::has_role,
         calendar::Calendar,
         loader::Loader,
         modal::Modal,
         modals::{
             add_group::AddGroupModal, add_student::AddStudentModal, delete_group::DeleteGroupModal,
-            delete_student::DeleteStudentModal, modify_group::ModifyGroupModal,
+            delete_student::DeleteStudentModal, edit_catering::EditCateringModal,
+            modify_group::ModifyGroupModal, transfer_student::TransferStudentModal,
         },
     },
     icons::{
@@ -132,6 +135,20 @@ pub fn Breadcrumb(trail: Vec<GroupDto>) -> impl IntoView {
                     </li>
                 </For>
             </ol>
+            <Show when=|| has_role(&[Role::Admin])>
+                <a
+                    class="ms-auto text-md text-gray-500 md:hover:text-gray-200"
+                    href=move || {
+                        params
+                            .read()
+                            .as_ref()
+                            .ok()
+                            .map(|params| format!("/audit/{}", params.target))
+                    }
+                >
+                    Historia zmian
+                </a>
+            </Show>
         </nav>
     }
 }
@@ -140,6 +157,7 @@ pub fn Breadcrumb(trail: Vec<GroupDto>) -> impl IntoView {
 pub fn Catering(catering: GroupDetailsDto, trail: Vec<GroupDto>) -> impl IntoView {
     let (add_group, set_add_group) = signal(false);
     let (edit_group, set_edit_group) = signal(false);
+    let (edit_catering, set_edit_catering) = signal(false);
     let GroupVersion(group_version, set_group_version) = use_context().unwrap();
 
     view! {
@@ -159,6 +177,14 @@ pub fn Catering(catering: GroupDetailsDto, trail: Vec<GroupDto>) -> impl IntoVie
                 >
                     <EditIcon />
                 </button>
+                <Show when=|| has_role(&[Role::Admin])>
+                    <button
+                        class="md:cursor-pointer md:hover:bg-gray-800 md:active:bg-gray-700 rounded-md p-1"
+                        on:click=move |_| set_edit_catering(true)
+                    >
+                        <MealIcon />
+                    </button>
+                </Show>
             </div>
         </div>
         <Modal is_open=add_group on_close=move || set_add_group(false)>
@@ -172,6 +198,17 @@ pub fn Catering(catering: GroupDetailsDto, trail: Vec<GroupDto>) -> impl IntoVie
                 parent=catering.id
             />
         </Modal>
+        <Modal is_open=edit_catering on_close=move || set_edit_catering(false)>
+            <EditCateringModal
+                group_id=catering.id
+                on_close=move |changed| {
+                    if changed {
+                        *set_group_version.write() += 1;
+                    }
+                    set_edit_catering(false);
+                }
+            />
+        </Modal>
         <Modal is_open=edit_group on_close=move || set_edit_group(false)>
             <ModifyGroupModal
                 group_name=catering.name.clone()
@@ -399,6 +436,7 @@ pub fn Student(student: StudentDetailsDto, trail: Vec<GroupDto>) -> impl IntoVie
     let GroupVersion(group_version, set_group_version) = use_context().unwrap();
     let (delete_student, set_delete_student) = signal(false);
     let (edit_student, set_edit_student) = signal(false);
+    let (transfer_student, set_transfer_student) = signal(false);
 
     let on_delete = move |deleted| {
         set_delete_student(false);
@@ -440,6 +478,9 @@ pub fn Student(student: StudentDetailsDto, trail: Vec<GroupDto>) -> impl IntoVie
                     .collect::<Vec<_>>()}
             </div>
             <div class="justify-end flex flex-row gap-1">
+                <button class="btn" on:click=move |_| set_transfer_student(true)>
+                    Przenieś
+                </button>
                 <button class="btn" on:click=move |_| set_edit_student(true)>
                     <EditIcon />
                 </button>
@@ -448,10 +489,46 @@ pub fn Student(student: StudentDetailsDto, trail: Vec<GroupDto>) -> impl IntoVie
                 </button>
             </div>
         </div>
+        <div class="flex flex-row flex-wrap gap-2 text-sm text-gray-400">
+            <span>
+                {format!(
+                    "Nauka od {} do {}",
+                    student.joined.map(|d| d.to_string()).unwrap_or(String::from("początku cateringu")),
+                    student.left.map(|d| d.to_string()).unwrap_or(String::from("końca cateringu")),
+                )}
+            </span>
+            {student
+                .placements
+                .iter()
+                .map(|placement| {
+                    view! {
+                        <span class="rounded-full outline outline-gray-600 pr-2 pl-2">
+                            {format!(
+                                "{}: {} – {}",
+                                placement.group_name,
+                                placement.since.map(|d| d.to_string()).unwrap_or_default(),
+                                placement.until.map(|d| d.to_string()).unwrap_or_default(),
+                            )}
+                        </span>
+                    }
+                })
+                .collect::<Vec<_>>()}
+        </div>
 
         <Modal is_open=delete_student on_close=move || set_delete_student(false)>
             <DeleteStudentModal student_id=student.id on_close=on_delete />
         </Modal>
+        <Modal is_open=transfer_student on_close=move || set_transfer_student(false)>
+            <TransferStudentModal
+                student_id=student.id
+                on_close=move |transferred| {
+                    if transferred {
+                        *set_group_version.write() += 1;
+                    }
+                    set_transfer_student(false)
+                }
+            />
+        </Modal>
         <Modal is_open=edit_student on_close=move || set_edit_student(false)>
             <AddStudentModal
                 group=Uuid::nil()
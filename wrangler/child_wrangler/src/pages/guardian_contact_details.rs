@@ -1,13 +1,16 @@
 use chrono::{Datelike, Utc};
 use dto::guardian::GuardianDetailDto;
+use dto::user::Role;
 use leptos::either::Either;
 use leptos::prelude::*;
 use leptos_router::hooks::use_params;
 use leptos_router::params::Params;
 use uuid::Uuid;
 
+use crate::components::auth::has_role;
 use crate::components::messages::Messages;
 use crate::components::modal::Modal;
+use crate::components::modals::guardian_ledger::{GuardianBalance, GuardianLedgerModal};
 use crate::components::modals::modify_guardian_phone::ModifyGuardianModal;
 use crate::icons::edit::EditIcon;
 use crate::icons::person::PersonIcon;
@@ -53,6 +56,9 @@ pub fn GuardianContactDetails() -> impl IntoView {
 #[component]
 pub fn InnerGuardianContactDetails(details: GuardianDetailDto) -> impl IntoView {
     let (edit_guardian, set_edit_guardian) = signal(false);
+    let (show_ledger, set_show_ledger) = signal(false);
+    let (ledger_version, set_ledger_version) = signal(0);
+    let guardian_id = details.id;
     let now = Utc::now();
 
     view! {
@@ -96,6 +102,18 @@ pub fn InnerGuardianContactDetails(details: GuardianDetailDto) -> impl IntoView
                         }
                     })
                     .collect::<Vec<_>>()}
+                <button
+                    class="btn"
+                    title="Rozliczenia"
+                    on:click=move |_| set_show_ledger(true)
+                >
+                    <GuardianBalance guardian_id version=ledger_version />
+                </button>
+                <Show when=|| has_role(&[Role::Admin])>
+                    <a class="btn" href=format!("/audit/{}", details.id)>
+                        Historia zmian
+                    </a>
+                </Show>
                 <button class="btn justify-self-end" on:click=move |_| set_edit_guardian(true)>
                     <EditIcon />
                 </button>
@@ -108,5 +126,13 @@ pub fn InnerGuardianContactDetails(details: GuardianDetailDto) -> impl IntoView
                 on_close=move |_| set_edit_guardian(false)
             />
         </Modal>
+        <Modal is_open=show_ledger on_close=move || set_show_ledger(false)>
+            <GuardianLedgerModal
+                guardian_id
+                version=ledger_version
+                set_version=set_ledger_version
+                on_close=move || set_show_ledger(false)
+            />
+        </Modal>
     }
 }
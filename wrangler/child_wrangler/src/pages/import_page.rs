@@ -0,0 +1,209 @@
+use dto::import::{ImportFileDto, ImportPreviewDto, ImportPreviewRowDto, ImportRowStatus};
+use leptos::{either::Either, prelude::*};
+use wasm_bindgen_futures::JsFuture;
+use web_sys::{js_sys::Uint8Array, HtmlInputElement};
+
+use crate::{
+    components::snackbar::{use_snackbar, SnackbarContext},
+    pages::attendance_page::GroupVersion,
+    services::import::{apply_import, preview_import},
+};
+
+pub async fn read_file(input: HtmlInputElement) -> Option<ImportFileDto> {
+    let file = input.files()?.get(0)?;
+    let buffer = JsFuture::from(file.array_buffer()).await.ok()?;
+    Some(ImportFileDto {
+        filename: file.name(),
+        content: Uint8Array::new(&buffer).to_vec(),
+    })
+}
+
+#[component]
+pub fn ImportPage() -> impl IntoView {
+    let snackbar = use_snackbar();
+    let GroupVersion(_, set_group_version) = use_context().unwrap();
+    let input = NodeRef::<leptos::html::Input>::new();
+    let (file, set_file) = signal(None::<ImportFileDto>);
+    let (preview, set_preview) = signal(None::<ImportPreviewDto>);
+
+    let on_file_change = move |_| {
+        set_file(None);
+        set_preview(None);
+        let Some(input) = input.get_untracked() else {
+            return;
+        };
+        wasm_bindgen_futures::spawn_local(async move {
+            match read_file(input).await {
+                Some(selected) => set_file(Some(selected)),
+                None => snackbar.error("Nie udało się odczytać pliku", ""),
+            }
+        });
+    };
+
+    let preview_action = Action::new(move |_: &()| async move {
+        let Some(selected) = file.get_untracked() else {
+            return;
+        };
+        match preview_import(selected).await {
+            Ok(result) => set_preview(Some(result)),
+            Err(e) => snackbar.error("Nie udało się przygotować importu", e),
+        }
+    });
+
+    let apply_action = Action::new(move |_: &()| async move {
+        let Some(selected) = file.get_untracked() else {
+            return;
+        };
+        match apply_import(selected).await {
+            Ok(result) => {
+                snackbar.success(&format!(
+                    "Zaimportowano: {} nowych uczniów, {} zmienionych, {} nowych grup, {} pominiętych",
+                    result.created_students,
+                    result.updated_students,
+                    result.created_groups,
+                    result.skipped
+                ));
+                set_file(None);
+                set_preview(None);
+                *set_group_version.write() += 1;
+            }
+            Err(e) => snackbar.error("Nie udało się zaimportować danych", e),
+        }
+    });
+
+    let can_apply = move || {
+        preview.with(|preview| {
+            preview
+                .as_ref()
+                .is_some_and(|preview| preview.errors.is_empty())
+        }) && !apply_action.pending()()
+    };
+
+    view! {
+        <div class="flex-1 flex flex-col gap-2 overflow-auto">
+            <div class="card p-2 flex flex-col gap-1">
+                <h2 class="h2">Import uczniów</h2>
+                <span>
+                    "Plik CSV lub XLSX z kolumnami: grupa (np. Szkoła/Klasa 1A), imię, nazwisko, alergie, opiekunowie, telefony"
+                </span>
+                <div class="flex flex-row gap-2 items-center">
+                    <input
+                        class="input flex-1"
+                        type="file"
+                        accept=".csv,.xlsx,.xls,.ods"
+                        node_ref=input
+                        on:change=on_file_change
+                    />
+                    <input
+                        type="button"
+                        class="btn"
+                        value="Podgląd"
+                        disabled=move || file.with(Option::is_none) || preview_action.pending()()
+                        on:click=move |_| {
+                            preview_action.dispatch(());
+                        }
+                    />
+                    <input
+                        type="button"
+                        class="btn save"
+                        value="Importuj"
+                        disabled=move || !can_apply()
+                        on:click=move |_| {
+                            apply_action.dispatch(());
+                        }
+                    />
+                </div>
+            </div>
+            {move || preview().map(|preview| view! { <ImportPreview preview /> })}
+        </div>
+    }
+}
+
+#[component]
+fn ImportPreview(preview: ImportPreviewDto) -> impl IntoView {
+    view! {
+        {(!preview.errors.is_empty())
+            .then(|| {
+                view! {
+                    <div class="error rounded-3 padded flex flex-col">
+                        {preview
+                            .errors
+                            .into_iter()
+                            .map(|error| {
+                                view! { <span>{format!("Wiersz {}: {}", error.line, error.message)}</span> }
+                            })
+                            .collect_view()}
+                    </div>
+                }
+            })}
+        {(!preview.new_groups.is_empty())
+            .then(|| {
+                view! {
+                    <div class="card p-2 flex flex-col">
+                        <h3>Nowe grupy</h3>
+                        {preview.new_groups.into_iter().map(|group| view! { <span>{group}</span> }).collect_view()}
+                    </div>
+                }
+            })}
+        {(!preview.new_guardians.is_empty())
+            .then(|| {
+                view! {
+                    <div class="card p-2 flex flex-col">
+                        <h3>Nowi opiekunowie</h3>
+                        <span>{preview.new_guardians.join(", ")}</span>
+                    </div>
+                }
+            })}
+        <div class="card p-2 flex flex-col gap-1">
+            <h3>Uczniowie</h3>
+            {preview.rows.into_iter().map(|row| view! { <ImportPreviewRow row /> }).collect_view()}
+        </div>
+    }
+}
+
+#[component]
+fn ImportPreviewRow(row: ImportPreviewRowDto) -> impl IntoView {
+    let ImportPreviewRowDto { row, status } = row;
+    let label = status.label();
+    let guardians = row
+        .guardians
+        .iter()
+        .map(|g| match &g.phone {
+            Some(phone) => format!("{} ({})", g.fullname, phone),
+            None => g.fullname.clone(),
+        })
+        .collect::<Vec<_>>()
+        .join(", ");
+
+    view! {
+        <div class="flex flex-col">
+            <div class="flex flex-row flex-wrap gap-2">
+                <span>{row.line}</span>
+                <span
+                    class:green=matches!(status, ImportRowStatus::New)
+                    class:red=matches!(status, ImportRowStatus::Duplicate(_))
+                >
+                    {label}
+                </span>
+                <span>{row.group_path.join("/")}</span>
+                <span>{format!("{} {}", row.name, row.surname)}</span>
+                <span>{row.allergies.join(", ")}</span>
+                <span>{guardians}</span>
+            </div>
+            {match status {
+                ImportRowStatus::Changed(_, changes) => {
+                    Either::Left(
+                        changes
+                            .into_iter()
+                            .map(|change| view! { <span class="pl-4">{change}</span> })
+                            .collect_view(),
+                    )
+                }
+                ImportRowStatus::Duplicate(reason) => {
+                    Either::Right(Some(view! { <span class="pl-4">{reason}</span> }))
+                }
+                _ => Either::Right(None),
+            }}
+        </div>
+    }
+}
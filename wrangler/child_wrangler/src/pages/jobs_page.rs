@@ -0,0 +1,143 @@
+use dto::jobs::{JobDto, JobRunDto, UpdateJobDto};
+use leptos::prelude::*;
+
+use crate::{
+    components::{
+        loader::Loader,
+        snackbar::{use_snackbar, SnackbarContext},
+    },
+    services::scheduler::{get_jobs, run_job_now, update_job},
+};
+
+#[component]
+pub fn JobsPage() -> impl IntoView {
+    let (version, set_version) = signal(0);
+    let jobs = Resource::new(version, |_| async move { get_jobs().await });
+
+    view! {
+        <div class="flex-1 flex flex-col gap-2 overflow-auto">
+            <div class="card p-2 flex flex-col gap-1">
+                <h2 class="h2">Zadania</h2>
+                <span>
+                    "Harmonogram w formacie cron: minuta, godzina, dzień miesiąca, miesiąc, dzień tygodnia (np. 0 3 * * *)"
+                </span>
+            </div>
+            <Loader>
+                {move || Suspend::new(async move {
+                    let jobs = jobs.await?;
+                    Ok::<
+                        _,
+                        ServerFnError,
+                    >(
+                        jobs
+                            .into_iter()
+                            .map(|job| view! { <JobCard job set_version /> })
+                            .collect::<Vec<_>>(),
+                    )
+                })}
+            </Loader>
+        </div>
+    }
+}
+
+#[component]
+fn JobCard(job: JobDto, set_version: WriteSignal<i32>) -> impl IntoView {
+    let snackbar = use_snackbar();
+    let id = job.id;
+    let (schedule, set_schedule) = signal(job.schedule);
+    let (enabled, set_enabled) = signal(job.enabled);
+
+    let save_action = Action::new(move |_: &()| {
+        let dto = UpdateJobDto {
+            id,
+            schedule: schedule(),
+            enabled: enabled(),
+        };
+        async move {
+            match update_job(dto).await {
+                Ok(_) => {
+                    snackbar.success("Zapisano zmiany");
+                    *set_version.write() += 1;
+                }
+                Err(e) => snackbar.error("Nie udało się zapisać zmian", e),
+            }
+        }
+    });
+
+    let run_action = Action::new(move |_: &()| async move {
+        match run_job_now(id).await {
+            Ok(true) => {
+                snackbar.success("Zadanie zostało wykonane");
+                *set_version.write() += 1;
+            }
+            Ok(false) => snackbar.error("Zadanie jest już wykonywane", ""),
+            Err(e) => snackbar.error("Nie udało się uruchomić zadania", e),
+        }
+    });
+
+    let failing = job
+        .runs
+        .first()
+        .is_some_and(|run| run.success == Some(false));
+
+    view! {
+        <div class="card p-2 flex flex-col gap-1">
+            <div class="flex flex-row flex-wrap gap-2 items-center">
+                <h3 class="flex-1 min-w-48" class:red=failing>
+                    {job.job.label()}
+                </h3>
+                <input class="input" bind:value=(schedule, set_schedule) />
+                <label class="flex flex-row gap-1 items-center">
+                    <input type="checkbox" bind:checked=(enabled, set_enabled) />
+                    Włączone
+                </label>
+                <input
+                    type="button"
+                    class="btn save"
+                    value="Zapisz"
+                    disabled=save_action.pending()
+                    on:click=move |_| {
+                        save_action.dispatch(());
+                    }
+                />
+                <input
+                    type="button"
+                    class="btn"
+                    value="Uruchom teraz"
+                    disabled=move || !job.enabled || run_action.pending()()
+                    on:click=move |_| {
+                        run_action.dispatch(());
+                    }
+                />
+            </div>
+            <span>
+                {match (job.enabled, job.next_run) {
+                    (false, _) => String::from("Wyłączone"),
+                    (true, Some(next_run)) => {
+                        format!("Następne uruchomienie: {}", next_run.format("%Y-%m-%d %H:%M"))
+                    }
+                    (true, None) => String::from("Oczekuje na zaplanowanie"),
+                }}
+            </span>
+            {job.runs.is_empty().then(|| view! { <span>Zadanie nie było jeszcze uruchamiane</span> })}
+            {job.runs.into_iter().map(|run| view! { <JobRunRow run /> }).collect::<Vec<_>>()}
+        </div>
+    }
+}
+
+#[component]
+fn JobRunRow(run: JobRunDto) -> impl IntoView {
+    let status = match run.success {
+        None => "W trakcie",
+        Some(true) => "Zakończone",
+        Some(false) => "Błąd",
+    };
+
+    view! {
+        <div class="flex flex-row flex-wrap gap-2 pl-4" class:red=run.success == Some(false)>
+            <span class="min-w-48">{run.started.format("%Y-%m-%d %H:%M:%S").to_string()}</span>
+            <span class="min-w-24">{status}</span>
+            <span class="flex-1 min-w-32">{run.output}</span>
+        </div>
+    }
+}
@@ -0,0 +1,88 @@
+use leptos::prelude::*;
+use leptos_router::hooks::use_navigate;
+
+use crate::{
+    components::snackbar::{use_snackbar, SnackbarContext},
+    services::auth::{login, logout},
+};
+
+#[component]
+pub fn LoginPage() -> impl IntoView {
+    let snackbar = use_snackbar();
+    let navigate = use_navigate();
+    let (username, set_username) = signal(String::new());
+    let (password, set_password) = signal(String::new());
+
+    let login_action = Action::new(move |_: &()| {
+        let navigate = navigate.clone();
+        async move {
+            match login(username.get_untracked(), password.get_untracked()).await {
+                Ok(_) => navigate("/attendance", Default::default()),
+                Err(e) => {
+                    set_password(String::new());
+                    snackbar.error("Nie udało się zalogować", e);
+                }
+            }
+        }
+    });
+
+    view! {
+        <div class="flex flex-1 items-center justify-center">
+            <form
+                class="card flex flex-col gap-2 p-4 w-full max-w-sm"
+                on:submit=move |e| {
+                    e.prevent_default();
+                    login_action.dispatch(());
+                }
+            >
+                <h1 class="text-xl">Logowanie</h1>
+                <label for="login">Login</label>
+                <input
+                    id="login"
+                    class="input"
+                    autocomplete="username"
+                    data-testid="login-input"
+                    bind:value=(username, set_username)
+                />
+                <label for="password">Hasło</label>
+                <input
+                    id="password"
+                    type="password"
+                    class="input"
+                    autocomplete="current-password"
+                    data-testid="password-input"
+                    bind:value=(password, set_password)
+                />
+                <input
+                    type="submit"
+                    class="btn save"
+                    data-testid="login-submit"
+                    value="Zaloguj"
+                    disabled=login_action.pending()
+                />
+            </form>
+        </div>
+    }
+}
+
+#[component]
+pub fn LogoutPage() -> impl IntoView {
+    let snackbar = use_snackbar();
+    let navigate = use_navigate();
+
+    let logout_action = Action::new(move |_: &()| {
+        let navigate = navigate.clone();
+        async move {
+            if let Err(e) = logout().await {
+                snackbar.error("Nie udało się wylogować", e);
+            }
+            navigate("/login", Default::default());
+        }
+    });
+
+    Effect::new(move || {
+        logout_action.dispatch(());
+    });
+
+    view! { <div>Wylogowywanie...</div> }
+}
@@ -1,15 +1,18 @@
-use chrono::{Duration, TimeDelta, Utc};
-use dto::messages::{Message, PhoneStatusDto};
+use std::time::Duration;
+
+use chrono::TimeDelta;
+use dto::messages::{phone_state_label, ConnectorState, ConnectorStatusDto, PhoneStatusDto};
 use leptos::{either::Either, prelude::*};
 
 use crate::{
     components::{loader::Loader, messages::InnerMessages},
-    services::messages::{get_latest_messages, get_phone_status},
+    services::messages::{get_connector_status, get_latest_messages, get_phone_status},
 };
 
+const PHONE_REFRESH_INTERVAL: Duration = Duration::from_secs(30);
+
 #[component]
 pub fn MessageDashboard() -> impl IntoView {
-    let phone = Resource::new(|| (), move |_| async move { get_phone_status().await });
     let messages = Resource::new(
         || (),
         move |_| async move { get_latest_messages(TimeDelta::days(10)).await },
@@ -17,54 +20,163 @@ pub fn MessageDashboard() -> impl IntoView {
 
     view! {
         <div class="overflow-hidden flex-1 flex flex-col gap-2">
+            <PhoneStatusPanel />
             <Loader>
                 {move || Suspend::new(async move {
-                    let phone = phone.await?;
                     let messages = messages.await?;
-                    Ok::<_, ServerFnError>(view! { <MessageDashboardInner phone messages /> })
+                    Ok::<_, ServerFnError>(view! { <InnerMessages messages /> })
                 })}
             </Loader>
         </div>
     }
 }
 
+/// Health of every modem line, refreshed periodically
 #[component]
-pub fn MessageDashboardInner(
-    phone: Option<PhoneStatusDto>,
-    messages: Vec<Message>,
-) -> impl IntoView {
+pub fn PhoneStatusPanel() -> impl IntoView {
+    let phones = Resource::new(
+        || (),
+        move |_| async move {
+            Ok::<_, ServerFnError>((get_connector_status().await?, get_phone_status().await?))
+        },
+    );
+
+    Effect::new(move |_| {
+        if let Ok(handle) = set_interval_with_handle(move || phones.refetch(), PHONE_REFRESH_INTERVAL)
+        {
+            on_cleanup(move || handle.clear());
+        }
+    });
+
     view! {
         <div class="card">
-            <h2 class="h2">Phone status</h2>
-            {phone
-                .map(|phone| {
-                    let active = Utc::now().naive_utc().signed_duration_since(phone.last_updated)
-                        < Duration::minutes(5);
-                    let signal_color_g = (255.0 * phone.signal as f32 / 100.0) as i32;
-                    let signal_color_r = (255.0 - signal_color_g as f32) as i32;
-                    Either::Left(
+            <h2 class="h2">Status modemów</h2>
+            <Transition>
+                <ErrorBoundary fallback=|_| {
+                    view! { <span class="red">Nie udało się pobrać statusu modemów</span> }
+                }>
+                    {move || Suspend::new(async move {
+                        let (connector, phones) = phones.await?;
+                        let online = connector.online;
+                        Ok::<
+                            _,
+                            ServerFnError,
+                        >(
+                            view! {
+                                <ConnectorStatusView connector />
+                                {if phones.is_empty() {
+                                    Either::Left(
+                                        online.then(|| view! { <span>Nie wykryto modemu</span> }),
+                                    )
+                                } else {
+                                Either::Right(
+                                    phones
+                                        .into_iter()
+                                        .map(|phone| view! { <PhoneStatusView phone /> })
+                                        .collect_view(),
+                                    )
+                                }}
+                            },
+                        )
+                    })}
+                </ErrorBoundary>
+            </Transition>
+        </div>
+    }
+}
 
-                        view! {
-                            <div class="horizontal gap">
-                                <span>Ostatni kontakt</span>
-                                <span class:green=active class:red=!active>
-                                    {format!("{}", phone.last_updated)}
-                                </span>
-                                <span>Sygnał</span>
-                                <span style:color=format!(
-                                    "rgb({},{},0)",
-                                    signal_color_r,
-                                    signal_color_g,
-                                )>{format!("{}", phone.signal)}</span>
-                                <span>/</span>
-                                <span>100</span>
-                            </div>
-                        },
-                    )
-                })
-                .unwrap_or(Either::Right(view! { <span>Nie wykryto modemu</span> }))}
+#[component]
+pub fn ConnectorStatusView(connector: ConnectorStatusDto) -> impl IntoView {
+    let heartbeat = connector
+        .heartbeat
+        .map(|heartbeat| format!("{}", heartbeat.format("%Y-%m-%d %H:%M:%S")))
+        .unwrap_or(String::from("nigdy"));
+
+    if connector.online {
+        let running = connector.state == Some(ConnectorState::Running);
+        Either::Left(view! {
+            <div class="horizontal gap padded">
+                <span>Konektor SMS</span>
+                <span class:green=running class:red=!running>
+                    {connector.state.map(|state| state.label())}
+                </span>
+                {connector
+                    .since
+                    .map(|since| format!("od {}", since.format("%Y-%m-%d %H:%M")))}
+                {connector.detail}
+            </div>
+        })
+    } else {
+        Either::Right(view! {
+            <div class="error rounded-3 padded">
+                {format!("Konektor SMS nie działa, ostatni kontakt: {}", heartbeat)}
+            </div>
+        })
+    }
+}
+
+#[component]
+pub fn PhoneStatusView(phone: PhoneStatusDto) -> impl IntoView {
+    let healthy = phone.alert.is_none() && !phone.locked && phone.state >= 8;
+    let signal_color_g = (255.0 * phone.signal as f32 / 100.0) as i32;
+    let signal_color_r = (255.0 - signal_color_g as f32) as i32;
+
+    view! {
+        <div class="flex flex-col gap-1 padded">
+            {phone
+                .alert
+                .map(|alert| {
+                    view! { <div class="error rounded-3 padded">{alert}</div> }
+                })}
+            <div class="horizontal gap">
+                <span class="font-bold">{phone.number}</span>
+                <span class:green=healthy class:red=!healthy>
+                    {if phone.locked { "Zablokowany" } else { phone_state_label(phone.state) }}
+                </span>
+                <span>Od</span>
+                <span>{format!("{}", phone.state_since.format("%Y-%m-%d %H:%M"))}</span>
+                <span>Ostatni kontakt</span>
+                <span>{format!("{}", phone.last_updated.format("%Y-%m-%d %H:%M:%S"))}</span>
+                <span>Sygnał</span>
+                <span style:color=format!(
+                    "rgb({},{},0)",
+                    signal_color_r,
+                    signal_color_g,
+                )>{format!("{}", phone.signal)}</span>
+                <span>/</span>
+                <span>100</span>
+                <span>Wysłane</span>
+                <span>{phone.total_sent}</span>
+                <span>Odebrane</span>
+                <span>{phone.total_received}</span>
+            </div>
+            <details>
+                <summary>Historia stanu</summary>
+                <ul>
+                    {phone
+                        .history
+                        .into_iter()
+                        .map(|change| {
+                            view! {
+                                <li class="horizontal gap">
+                                    <span>
+                                        {format!("{}", change.recorded.format("%Y-%m-%d %H:%M:%S"))}
+                                    </span>
+                                    <span>
+                                        {if change.locked {
+                                            "Zablokowany"
+                                        } else {
+                                            phone_state_label(change.state.unwrap_or(0))
+                                        }}
+                                    </span>
+                                    <span>{change.signal.map(|signal| format!("{}/100", signal))}</span>
+                                </li>
+                            }
+                        })
+                        .collect_view()}
+                </ul>
+            </details>
         </div>
-        <InnerMessages messages />
     }
 }
 
@@ -50,6 +50,12 @@ pub fn InnerMessagePage(contacts: Vec<ContactDto>) -> impl IntoView {
     view! {
         <div class="flex-1 flex flex-row gap-2 p-0.5">
             <div class="flex flex-col gap-2 p-0.5">
+                <a
+                    class="bg-gray-800 rounded-md md:cursor-pointer md:hover:bg-gray-700 md:active:bg-gray-600 p-2 md:w-72"
+                    href="/messages/broadcasts"
+                >
+                    Wiadomości zbiorcze
+                </a>
                 <div class="flex-1 overflow-auto">
                     <ul class="flex-1 flex flex-col md:w-72 gap-0.5">
                         {move || {
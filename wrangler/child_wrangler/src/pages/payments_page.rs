@@ -0,0 +1,293 @@
+use chrono::{Datelike, NaiveDate, Utc};
+use dto::{
+    billing::format_amount,
+    guardian::GuardianDto,
+    import::ImportFileDto,
+    ledger::{ArrearsDto, BankTransferDto},
+};
+use leptos::prelude::*;
+use uuid::Uuid;
+
+use crate::{
+    components::{
+        dropdown::Dropdown,
+        loader::Loader,
+        snackbar::{use_snackbar, SnackbarContext},
+    },
+    pages::import_page::read_file,
+    services::{
+        ledger::{
+            assign_transfer, get_arrears, get_unmatched_transfers, ignore_transfer,
+            import_bank_statement, post_monthly_charges,
+        },
+        student::get_guardians,
+    },
+};
+
+#[component]
+pub fn PaymentsPage() -> impl IntoView {
+    let (version, set_version) = signal(0);
+
+    view! {
+        <div class="flex-1 flex flex-col gap-2 overflow-auto">
+            <div class="flex flex-row flex-wrap gap-2">
+                <BankStatementImport set_version />
+                <MonthlyCharges set_version />
+            </div>
+            <UnmatchedTransfers version set_version />
+            <Arrears version />
+        </div>
+    }
+}
+
+#[component]
+fn BankStatementImport(set_version: WriteSignal<i32>) -> impl IntoView {
+    let snackbar = use_snackbar();
+    let input = NodeRef::<leptos::html::Input>::new();
+    let (file, set_file) = signal(None::<ImportFileDto>);
+
+    let on_file_change = move |_| {
+        set_file(None);
+        let Some(input) = input.get_untracked() else {
+            return;
+        };
+        wasm_bindgen_futures::spawn_local(async move {
+            match read_file(input).await {
+                Some(selected) => set_file(Some(selected)),
+                None => snackbar.error("Nie udało się odczytać pliku", ""),
+            }
+        });
+    };
+
+    let import_action = Action::new(move |_: &()| async move {
+        let Some(selected) = file.get_untracked() else {
+            return;
+        };
+        match import_bank_statement(selected).await {
+            Ok(result) => {
+                snackbar.success(&format!(
+                    "Zaimportowano {} przelewów, dopasowano {}, pominięto {} wcześniej zaimportowanych i {} wychodzących",
+                    result.imported, result.matched, result.duplicates, result.skipped
+                ));
+                *set_version.write() += 1;
+            }
+            Err(e) => snackbar.error("Nie udało się zaimportować wyciągu", e),
+        }
+    });
+
+    view! {
+        <div class="card p-2 flex flex-col gap-1 flex-1">
+            <h2 class="h2">Wyciąg bankowy</h2>
+            <span>Plik CSV lub MT940, wpłaty są dopasowywane do rodziców po tytule i kwocie</span>
+            <div class="flex flex-row gap-2 items-center">
+                <input
+                    class="input flex-1"
+                    type="file"
+                    accept=".csv,.sta,.mt940,.940,.txt"
+                    node_ref=input
+                    on:change=on_file_change
+                />
+                <input
+                    type="button"
+                    class="btn save"
+                    value="Importuj"
+                    disabled=move || file.with(Option::is_none) || import_action.pending()()
+                    on:click=move |_| {
+                        import_action.dispatch(());
+                    }
+                />
+            </div>
+        </div>
+    }
+}
+
+#[component]
+fn MonthlyCharges(set_version: WriteSignal<i32>) -> impl IntoView {
+    let snackbar = use_snackbar();
+    let now = Utc::now();
+    let (month, set_month) = signal(format!("{}-{:02}", now.year(), now.month()));
+
+    let post_action = Action::new(move |_: &()| {
+        let selected = NaiveDate::parse_from_str(&format!("{}-01", month()), "%Y-%m-%d").ok();
+        async move {
+            let Some(selected) = selected else {
+                snackbar.error("Wybierz miesiąc", "");
+                return;
+            };
+            match post_monthly_charges(selected.year(), selected.month()).await {
+                Ok(changed) => {
+                    snackbar.success(&format!("Naliczono {} opłat", changed));
+                    *set_version.write() += 1;
+                }
+                Err(e) => snackbar.error("Nie udało się naliczyć opłat", e),
+            }
+        }
+    });
+
+    view! {
+        <div class="card p-2 flex flex-col gap-1 flex-1">
+            <h2 class="h2">Naliczanie opłat</h2>
+            <span>Opłata każdego rodzica to kwota do zapłaty z miesięcznego rozliczenia</span>
+            <div class="flex flex-row gap-2 items-center">
+                <input class="input flex-1" type="month" bind:value=(month, set_month) />
+                <input
+                    type="button"
+                    class="btn save"
+                    value="Nalicz opłaty"
+                    disabled=post_action.pending()
+                    on:click=move |_| {
+                        post_action.dispatch(());
+                    }
+                />
+            </div>
+        </div>
+    }
+}
+
+#[component]
+fn UnmatchedTransfers(version: ReadSignal<i32>, set_version: WriteSignal<i32>) -> impl IntoView {
+    let transfers = Resource::new(version, |_| async move {
+        Ok::<_, ServerFnError>((get_unmatched_transfers().await?, get_guardians().await?))
+    });
+
+    view! {
+        <div class="card p-2 flex flex-col gap-1">
+            <h2 class="h2">Nieprzypisane przelewy</h2>
+            <Loader>
+                {move || Suspend::new(async move {
+                    let (transfers, guardians) = transfers.await?;
+                    Ok::<
+                        _,
+                        ServerFnError,
+                    >(
+                        view! {
+                            {transfers.is_empty().then(|| view! { <span>Brak przelewów</span> })}
+                            {transfers
+                                .into_iter()
+                                .map(|transfer| {
+                                    let guardians = guardians.clone();
+                                    view! { <UnmatchedTransfer transfer guardians set_version /> }
+                                })
+                                .collect::<Vec<_>>()}
+                        },
+                    )
+                })}
+            </Loader>
+        </div>
+    }
+}
+
+#[component]
+fn UnmatchedTransfer(
+    transfer: BankTransferDto,
+    guardians: Vec<GuardianDto>,
+    set_version: WriteSignal<i32>,
+) -> impl IntoView {
+    let snackbar = use_snackbar();
+    let transfer_id = transfer.id;
+
+    let assign_action = Action::new(move |guardian_id: &Uuid| {
+        let guardian_id = *guardian_id;
+        async move {
+            match assign_transfer(transfer_id, guardian_id).await {
+                Ok(_) => {
+                    snackbar.success("Przypisano przelew");
+                    *set_version.write() += 1;
+                }
+                Err(e) => snackbar.error("Nie udało się przypisać przelewu", e),
+            }
+        }
+    });
+
+    let ignore_action = Action::new(move |_: &()| async move {
+        match ignore_transfer(transfer_id).await {
+            Ok(_) => *set_version.write() += 1,
+            Err(e) => snackbar.error("Nie udało się pominąć przelewu", e),
+        }
+    });
+
+    let on_select = move |guardian: Result<GuardianDto, String>| {
+        if let Ok(guardian) = guardian {
+            assign_action.dispatch(guardian.id);
+        }
+        Some(String::new())
+    };
+
+    view! {
+        <div class="flex flex-row flex-wrap gap-2 items-center">
+            <span>{transfer.booked.to_string()}</span>
+            <span class="min-w-24">{format_amount(transfer.amount)}</span>
+            <span class="flex-1 min-w-48">
+                {transfer.title}
+                {transfer.counterparty.map(|counterparty| format!(" ({})", counterparty))}
+            </span>
+            <div class="min-w-48">
+                <Dropdown
+                    name="transfer_guardian"
+                    options=move || guardians.clone()
+                    key=|g| g.id
+                    filter=|h, g| g.fullname.to_lowercase().contains(&h.to_lowercase())
+                    on_select
+                    item_view=|g| view! { <div class="p-1">{g.fullname}</div> }
+                />
+            </div>
+            <input
+                type="button"
+                class="btn"
+                value="Pomiń"
+                disabled=ignore_action.pending()
+                on:click=move |_| {
+                    ignore_action.dispatch(());
+                }
+            />
+        </div>
+    }
+}
+
+#[component]
+fn Arrears(version: ReadSignal<i32>) -> impl IntoView {
+    let arrears = Resource::new(version, |_| async move { get_arrears().await });
+
+    view! {
+        <div class="card p-2 flex flex-col gap-1">
+            <h2 class="h2">Zaległości</h2>
+            <Loader>
+                {move || Suspend::new(async move {
+                    let arrears = arrears.await?;
+                    let total: i64 = arrears.iter().map(|arrear| -arrear.balance).sum();
+                    Ok::<
+                        _,
+                        ServerFnError,
+                    >(
+                        view! {
+                            <span>{format!("Łącznie: {}", format_amount(total))}</span>
+                            {arrears
+                                .into_iter()
+                                .map(|arrear| view! { <ArrearsRow arrear /> })
+                                .collect::<Vec<_>>()}
+                        },
+                    )
+                })}
+            </Loader>
+        </div>
+    }
+}
+
+#[component]
+fn ArrearsRow(arrear: ArrearsDto) -> impl IntoView {
+    view! {
+        <div class="flex flex-row flex-wrap gap-2 items-center">
+            <a class="flex-1 min-w-48" href=format!("/messages/guardian/{}", arrear.guardian_id)>
+                {arrear.fullname}
+            </a>
+            <span>{arrear.phone.unwrap_or_default()}</span>
+            <span class="red min-w-24">{format_amount(-arrear.balance)}</span>
+            <span class="min-w-32">
+                {arrear
+                    .overdue_since
+                    .map(|since| format!("od {}", since))
+                    .unwrap_or_default()}
+            </span>
+        </div>
+    }
+}
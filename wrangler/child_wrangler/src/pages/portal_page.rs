@@ -0,0 +1,406 @@
+use std::iter;
+
+use chrono::{Datelike, Days, Months, NaiveDate, Utc};
+use dto::{
+    attendance::EffectiveAttendance,
+    portal::{PortalAttendanceDto, PortalMonthDto, PortalStudentDto},
+};
+use leptos::{either::Either, prelude::*};
+use leptos_router::{components::Redirect, hooks::use_navigate};
+use uuid::Uuid;
+
+use crate::{
+    components::{
+        loader::Loader,
+        snackbar::{use_snackbar, SnackbarContext},
+    },
+    services::portal::{
+        get_portal_guardian, get_portal_month, portal_login, portal_logout, request_portal_code,
+        update_portal_attendance,
+    },
+};
+
+const WEEKDAYS: [&str; 7] = ["pon.", "wt.", "śr.", "czw.", "pt.", "sob.", "niedz."];
+
+#[component]
+pub fn PortalLoginPage() -> impl IntoView {
+    let snackbar = use_snackbar();
+    let navigate = use_navigate();
+    let (phone, set_phone) = signal(String::new());
+    let (code, set_code) = signal(String::new());
+    let (code_sent, set_code_sent) = signal(false);
+
+    let code_action = Action::new(move |_: &()| async move {
+        match request_portal_code(phone.get_untracked()).await {
+            Ok(_) => {
+                set_code_sent(true);
+                snackbar.success("Jeśli numer jest w naszej bazie, wysłaliśmy na niego kod");
+            }
+            Err(e) => snackbar.error("Nie udało się wysłać kodu", e),
+        }
+    });
+
+    let login_action = Action::new(move |_: &()| {
+        let navigate = navigate.clone();
+        async move {
+            match portal_login(phone.get_untracked(), code.get_untracked()).await {
+                Ok(_) => navigate("/portal", Default::default()),
+                Err(e) => {
+                    set_code(String::new());
+                    snackbar.error("Nie udało się zalogować", e);
+                }
+            }
+        }
+    });
+
+    view! {
+        <div class="flex flex-1 items-center justify-center">
+            <form
+                class="card flex flex-col gap-2 p-4 w-full max-w-sm"
+                on:submit=move |e| {
+                    e.prevent_default();
+                    if code_sent() {
+                        login_action.dispatch(());
+                    } else {
+                        code_action.dispatch(());
+                    }
+                }
+            >
+                <h1 class="text-xl">Portal rodzica</h1>
+                <label for="phone">Numer telefonu</label>
+                <input
+                    id="phone"
+                    type="tel"
+                    class="input"
+                    autocomplete="tel"
+                    bind:value=(phone, set_phone)
+                />
+                <Show when=code_sent>
+                    <label for="code">Kod z SMS</label>
+                    <input
+                        id="code"
+                        class="input"
+                        inputmode="numeric"
+                        autocomplete="one-time-code"
+                        bind:value=(code, set_code)
+                    />
+                </Show>
+                <input
+                    type="submit"
+                    class="btn save"
+                    value=move || if code_sent() { "Zaloguj" } else { "Wyślij kod" }
+                    disabled=move || code_action.pending()() || login_action.pending()()
+                />
+                <Show when=code_sent>
+                    <input
+                        type="button"
+                        class="btn"
+                        value="Wyślij kod ponownie"
+                        disabled=code_action.pending()
+                        on:click=move |_| {
+                            code_action.dispatch(());
+                        }
+                    />
+                </Show>
+            </form>
+        </div>
+    }
+}
+
+#[component]
+pub fn PortalPage() -> impl IntoView {
+    let guardian = Resource::new(|| (), |_| async move { get_portal_guardian().await });
+
+    view! {
+        <Loader>
+            {move || Suspend::new(async move {
+                Ok::<
+                    _,
+                    ServerFnError,
+                >(
+                    match guardian.await? {
+                        Some(guardian) => {
+                            Either::Left(
+                                view! {
+                                    <div class="flex flex-col flex-1 gap-2 w-full max-w-xl mx-auto overflow-auto">
+                                        <div class="card p-2 flex flex-row items-center gap-2">
+                                            <h1 class="text-lg flex-1">{guardian.fullname}</h1>
+                                            <PortalLogout />
+                                        </div>
+                                        <PortalStudents students=guardian.students />
+                                    </div>
+                                },
+                            )
+                        }
+                        None => Either::Right(view! { <Redirect path="/portal/login" /> }),
+                    },
+                )
+            })}
+        </Loader>
+    }
+}
+
+#[component]
+fn PortalLogout() -> impl IntoView {
+    let snackbar = use_snackbar();
+    let navigate = use_navigate();
+
+    let logout_action = Action::new(move |_: &()| {
+        let navigate = navigate.clone();
+        async move {
+            if let Err(e) = portal_logout().await {
+                snackbar.error("Nie udało się wylogować", e);
+            }
+            navigate("/portal/login", Default::default());
+        }
+    });
+
+    view! {
+        <input
+            type="button"
+            class="btn"
+            value="Wyloguj"
+            on:click=move |_| {
+                logout_action.dispatch(());
+            }
+        />
+    }
+}
+
+#[component]
+fn PortalStudents(students: Vec<PortalStudentDto>) -> impl IntoView {
+    let (student, set_student) = signal(students.first().map(|student| student.id));
+    let today = Utc::now().date_naive();
+    let (month, set_month) = signal(today.with_day(1).unwrap_or(today));
+
+    let change_month = move |forward: bool| {
+        set_month.update(|month| {
+            let next = if forward {
+                month.checked_add_months(Months::new(1))
+            } else {
+                month.checked_sub_months(Months::new(1))
+            };
+            if let Some(next) = next {
+                *month = next;
+            }
+        })
+    };
+
+    view! {
+        {if students.is_empty() {
+            Either::Left(
+                view! {
+                    <div class="card p-2">Brak przypisanych dzieci, skontaktuj się z placówką</div>
+                },
+            )
+        } else {
+            Either::Right(
+                view! {
+                    <div class="flex flex-row flex-wrap gap-1">
+                        {students
+                            .into_iter()
+                            .map(|s| {
+                                view! {
+                                    <input
+                                        type="button"
+                                        class="btn flex-1"
+                                        class:save=move || student() == Some(s.id)
+                                        value=format!("{} {}", s.name, s.surname)
+                                        on:click=move |_| set_student(Some(s.id))
+                                    />
+                                }
+                            })
+                            .collect::<Vec<_>>()}
+                    </div>
+                    <div class="card p-2 flex flex-row items-center gap-2">
+                        <input type="button" class="btn" value="<" on:click=move |_| change_month(false) />
+                        <h2 class="flex-1 text-center">{move || month().format("%m.%Y").to_string()}</h2>
+                        <input type="button" class="btn" value=">" on:click=move |_| change_month(true) />
+                    </div>
+                    {move || {
+                        student()
+                            .map(|student| {
+                                view! { <PortalCalendar student month=month() /> }
+                            })
+                    }}
+                },
+            )
+        }}
+    }
+}
+
+#[component]
+fn PortalCalendar(student: Uuid, month: NaiveDate) -> impl IntoView {
+    let (version, set_version) = signal(0);
+    let calendar = Resource::new(version, move |_| async move {
+        get_portal_month(student, month.year(), month.month()).await
+    });
+
+    view! {
+        <Loader>
+            {move || Suspend::new(async move {
+                let calendar = calendar.await?;
+                let days = iter::successors(Some(month), |day| day.checked_add_days(Days::new(1)))
+                    .take_while(|day| day.month() == month.month())
+                    .filter(|day| calendar.is_served(*day) || calendar.closures.contains_key(day))
+                    .collect::<Vec<_>>();
+                let editable_since = calendar.editable_since;
+                let calendar = StoredValue::new(calendar);
+                Ok::<
+                    _,
+                    ServerFnError,
+                >(
+                    view! {
+                        <div class="flex flex-col gap-1">
+                            <span class="text-sm text-gray-400">
+                                {format!(
+                                    "Zmiany są możliwe od {}, każdy posiłek do terminu podanego pod nim. Kolory: zielony - posiłek wydawany, żółty - odwołany, czerwony i szary - zmienione przez placówkę",
+                                    editable_since.format("%d.%m.%Y"),
+                                )}
+                            </span>
+                            {days
+                                .into_iter()
+                                .map(|day| view! { <PortalDay student day calendar set_version /> })
+                                .collect::<Vec<_>>()}
+                        </div>
+                    },
+                )
+            })}
+        </Loader>
+    }
+}
+
+#[component]
+fn PortalDay(
+    student: Uuid,
+    day: NaiveDate,
+    calendar: StoredValue<PortalMonthDto>,
+    set_version: WriteSignal<i32>,
+) -> impl IntoView {
+    let snackbar = use_snackbar();
+    let closure = calendar.with_value(|calendar| calendar.closures.get(&day).cloned());
+    let meals = calendar.with_value(|calendar| {
+        calendar
+            .meals
+            .iter()
+            .map(|meal| {
+                (
+                    meal.id,
+                    meal.name.clone(),
+                    calendar.status(day, meal.id),
+                    meal.cutoff.deadline(day),
+                    calendar.is_editable(day, meal.id),
+                )
+            })
+            .collect::<Vec<_>>()
+    });
+    let editable = meals.iter().any(|(_, _, _, _, editable)| *editable);
+    // Staff overrides can't be changed by the guardian
+    let changeable = meals
+        .iter()
+        .filter(|(_, _, status, _, editable)| {
+            *editable
+                && matches!(
+                    status,
+                    EffectiveAttendance::Present | EffectiveAttendance::Cancelled
+                )
+        })
+        .map(|(id, _, _, _, _)| *id)
+        .collect::<Vec<_>>();
+    let any_present = meals.iter().any(|(id, _, status, _, _)| {
+        changeable.contains(id) && *status == EffectiveAttendance::Present
+    });
+    let day_toggle = editable && closure.is_none() && !changeable.is_empty();
+
+    let update_action = Action::new(move |(meals, present): &(Vec<Uuid>, bool)| {
+        let dto = PortalAttendanceDto {
+            student_id: student,
+            since: day,
+            until: day,
+            meals: meals.clone(),
+            present: *present,
+        };
+        async move {
+            match update_portal_attendance(dto).await {
+                Ok(_) => *set_version.write() += 1,
+                Err(e) => snackbar.error("Nie udało się zmienić obecności", e),
+            }
+        }
+    });
+
+    view! {
+        <div class="card p-2 flex flex-col gap-1" class:opacity-60=!editable>
+            <div class="flex flex-row items-center gap-2">
+                <h3 class="flex-1">
+                    {format!(
+                        "{} {}",
+                        WEEKDAYS[day.weekday().num_days_from_monday() as usize],
+                        day.format("%d.%m"),
+                    )}
+                </h3>
+                {day_toggle
+                    .then(|| {
+                        view! {
+                            <input
+                                type="button"
+                                class="btn"
+                                value=if any_present { "Odwołaj dzień" } else { "Przywróć dzień" }
+                                disabled=update_action.pending()
+                                on:click=move |_| {
+                                    update_action.dispatch((changeable.clone(), !any_present));
+                                }
+                            />
+                        }
+                    })}
+            </div>
+            {match closure.clone() {
+                Some(reason) => {
+                    Either::Left(view! { <span class="text-yellow-600 italic">{reason}</span> })
+                }
+                None => {
+                    Either::Right(
+                        view! {
+                            <div class="flex flex-row flex-wrap gap-1">
+                                {meals
+                                    .into_iter()
+                                    .map(|(id, name, status, deadline, editable)| {
+                                        let present = status == EffectiveAttendance::Present;
+                                        let locked = !editable
+                                            || !matches!(
+                                                status,
+                                                EffectiveAttendance::Present
+                                                | EffectiveAttendance::Cancelled
+                                            );
+                                        view! {
+                                            <div class="flex flex-col flex-1 items-center">
+                                                <input
+                                                    type="button"
+                                                    class="btn w-full"
+                                                    class:text-green-600=status == EffectiveAttendance::Present
+                                                    class:text-red-600=status == EffectiveAttendance::Absent
+                                                    class:text-yellow-600=status == EffectiveAttendance::Cancelled
+                                                    class:text-gray-600=status == EffectiveAttendance::Blocked
+                                                    value=name
+                                                    disabled=move || locked || update_action.pending()()
+                                                    on:click=move |_| {
+                                                        update_action.dispatch((vec![id], !present));
+                                                    }
+                                                />
+                                                <span class="text-xs text-gray-400">
+                                                    {format!(
+                                                        "Zmiany do {}",
+                                                        deadline.format("%d.%m %H:%M"),
+                                                    )}
+                                                </span>
+                                            </div>
+                                        }
+                                    })
+                                    .collect::<Vec<_>>()}
+                            </div>
+                        },
+                    )
+                }
+            }}
+        </div>
+    }
+}
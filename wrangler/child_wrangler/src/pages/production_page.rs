@@ -0,0 +1,316 @@
+use chrono::{Datelike, NaiveDate, Utc};
+use dto::production::{CateringProductionDto, CateringSnapshotDto, MealProductionDto, PortionsDto};
+use leptos::prelude::*;
+
+use crate::{
+    components::{
+        download::{save_bytes_to_file, save_text_to_file},
+        loader::Loader,
+        snackbar::{use_snackbar, SnackbarContext},
+    },
+    services::{
+        production::{get_production_report, get_production_report_csv, get_production_report_pdf},
+        snapshot::{get_late_changes_csv, get_snapshots},
+    },
+};
+
+#[component]
+pub fn ProductionPage() -> impl IntoView {
+    let snackbar = use_snackbar();
+    let (day, set_day) = signal(Utc::now().date_naive().to_string());
+    let selected = Memo::new(move |_| NaiveDate::parse_from_str(&day(), "%Y-%m-%d").ok());
+
+    let report = Resource::new(selected, |day| async move {
+        match day {
+            Some(day) => get_production_report(day).await.map(Some),
+            None => Ok(None),
+        }
+    });
+    let snapshots = Resource::new(selected, |day| async move {
+        match day {
+            Some(day) => get_snapshots(day).await,
+            None => Ok(vec![]),
+        }
+    });
+
+    let csv_action = Action::new(move |day: &NaiveDate| {
+        let day = *day;
+        async move {
+            match get_production_report_csv(day).await {
+                Ok(csv) => {
+                    save_text_to_file(&csv, "Zapisano zestawienie", "Nie udało się zapisać zestawienia")
+                        .await
+                }
+                Err(e) => snackbar.error("Nie udało się pobrać zestawienia", e),
+            }
+        }
+    });
+
+    let pdf_action = Action::new(move |day: &NaiveDate| {
+        let day = *day;
+        async move {
+            match get_production_report_pdf(day).await {
+                Ok(pdf) => {
+                    save_bytes_to_file(&pdf, "Zapisano zestawienie", "Nie udało się zapisać zestawienia")
+                        .await
+                }
+                Err(e) => snackbar.error("Nie udało się pobrać zestawienia", e),
+            }
+        }
+    });
+
+    let late_changes_action = Action::new(move |day: &NaiveDate| {
+        let day = *day;
+        async move {
+            match get_late_changes_csv(day.year(), day.month()).await {
+                Ok(csv) => {
+                    save_text_to_file(&csv, "Zapisano zmiany", "Nie udało się zapisać zmian").await
+                }
+                Err(e) => snackbar.error("Nie udało się pobrać zmian", e),
+            }
+        }
+    });
+
+    view! {
+        <div class="flex-1 flex flex-col gap-2 overflow-auto">
+            <div class="card p-2 flex flex-row flex-wrap gap-2 items-center print:hidden">
+                <h2 class="h2 flex-1">Zestawienie produkcji</h2>
+                <input class="input" type="date" bind:value=(day, set_day) />
+                <input
+                    type="button"
+                    class="btn"
+                    value="Drukuj"
+                    on:click=move |_| {
+                        let _ = window().print();
+                    }
+                />
+                <input
+                    type="button"
+                    class="btn"
+                    value="Eksport CSV"
+                    disabled=move || selected().is_none() || csv_action.pending()()
+                    on:click=move |_| {
+                        if let Some(day) = selected() {
+                            csv_action.dispatch(day);
+                        }
+                    }
+                />
+                <input
+                    type="button"
+                    class="btn"
+                    value="Eksport PDF"
+                    disabled=move || selected().is_none() || pdf_action.pending()()
+                    on:click=move |_| {
+                        if let Some(day) = selected() {
+                            pdf_action.dispatch(day);
+                        }
+                    }
+                />
+                <input
+                    type="button"
+                    class="btn"
+                    value="Zmiany po zamknięciu w miesiącu"
+                    disabled=move || selected().is_none() || late_changes_action.pending()()
+                    on:click=move |_| {
+                        if let Some(day) = selected() {
+                            late_changes_action.dispatch(day);
+                        }
+                    }
+                />
+            </div>
+            <Loader>
+                {move || Suspend::new(async move {
+                    let report = report.await?;
+                    Ok::<
+                        _,
+                        ServerFnError,
+                    >(
+                        report
+                            .map(|report| {
+                                let day = report.day;
+                                view! {
+                                    {report
+                                        .caterings
+                                        .is_empty()
+                                        .then(|| view! { <span>W tym dniu posiłki nie są wydawane</span> })}
+                                    {report
+                                        .caterings
+                                        .into_iter()
+                                        .map(|catering| view! { <CateringProduction day catering /> })
+                                        .collect::<Vec<_>>()}
+                                }
+                            }),
+                    )
+                })}
+            </Loader>
+            <Loader>
+                {move || Suspend::new(async move {
+                    let snapshots = snapshots.await?;
+                    Ok::<
+                        _,
+                        ServerFnError,
+                    >(
+                        snapshots
+                            .into_iter()
+                            .map(|snapshot| view! { <SnapshotComparison snapshot /> })
+                            .collect::<Vec<_>>(),
+                    )
+                })}
+            </Loader>
+        </div>
+    }
+}
+
+#[component]
+fn CateringProduction(day: NaiveDate, catering: CateringProductionDto) -> impl IntoView {
+    let cutoff = catering.cutoff.format("%H:%M").to_string();
+
+    view! {
+        <div class="card p-2 flex flex-col gap-2 break-after-page">
+            <div class="flex flex-row flex-wrap gap-2 items-center">
+                <h2 class="h2 flex-1">{format!("{} - {}", catering.catering, day)}</h2>
+                {if catering.locked {
+                    view! { <span>{format!("Stan na {}", cutoff)}</span> }.into_any()
+                } else {
+                    view! {
+                        <span class="red">
+                            {format!("Wstępne zestawienie, odwołania przyjmowane do {}", cutoff)}
+                        </span>
+                    }
+                        .into_any()
+                }}
+            </div>
+            {catering.meals.is_empty().then(|| view! { <span>Brak zamówionych posiłków</span> })}
+            {catering
+                .meals
+                .into_iter()
+                .map(|meal| view! { <MealProduction meal /> })
+                .collect::<Vec<_>>()}
+        </div>
+    }
+}
+
+/// Table of a single meal, one row per group and a column per allergy combination ordered that day
+#[component]
+fn MealProduction(meal: MealProductionDto) -> impl IntoView {
+    let diets = meal.total.diets.keys().cloned().collect::<Vec<_>>();
+
+    let row = move |group: String, portions: PortionsDto, class: &'static str| {
+        view! {
+            <tr class=class>
+                <td class="p-2 border border-gray-300/25 text-left">{group}</td>
+                <td class="p-2 border border-gray-300/25">{portions.regular}</td>
+                {diets
+                    .iter()
+                    .map(|diet| {
+                        view! {
+                            <td class="p-2 border border-gray-300/25">
+                                {portions.diets.get(diet).copied().unwrap_or(0)}
+                            </td>
+                        }
+                    })
+                    .collect::<Vec<_>>()}
+                <td class="p-2 border border-gray-300/25">{portions.total()}</td>
+            </tr>
+        }
+    };
+
+    view! {
+        <div class="flex flex-col gap-1">
+            <h3>{meal.meal}</h3>
+            <table class="table-auto text-center border border-gray-300 border-collapse w-full">
+                <thead class="bg-gray-700 font-normal">
+                    <tr>
+                        <th class="font-normal p-2 border border-gray-300/25 text-left">Grupa</th>
+                        <th class="font-normal p-2 border border-gray-300/25">Zwykłe</th>
+                        {meal
+                            .total
+                            .diets
+                            .keys()
+                            .map(|diet| {
+                                view! {
+                                    <th class="font-normal p-2 border border-gray-300/25">
+                                        {diet.clone()}
+                                    </th>
+                                }
+                            })
+                            .collect::<Vec<_>>()}
+                        <th class="font-normal p-2 border border-gray-300/25">Razem</th>
+                    </tr>
+                </thead>
+                <tbody class="bg-gray-800 text-gray-400">
+                    {meal
+                        .groups
+                        .into_iter()
+                        .map(|group| row.clone()(group.group, group.portions, "text-sm"))
+                        .collect::<Vec<_>>()}
+                    {row(String::from("Razem"), meal.total, "font-bold")}
+                </tbody>
+            </table>
+        </div>
+    }
+}
+
+/// Counts frozen at the cut-off next to the current ones, with the changes made afterwards
+#[component]
+fn SnapshotComparison(snapshot: CateringSnapshotDto) -> impl IntoView {
+    view! {
+        <div class="card p-2 flex flex-col gap-1 print:hidden">
+            <div class="flex flex-row flex-wrap gap-2 items-center">
+                <h2 class="h2 flex-1">{format!("Zmiany po zamknięciu - {}", snapshot.catering)}</h2>
+                <span>
+                    {format!(
+                        "Zamknięto {}, zapisano {}",
+                        snapshot.cutoff.format("%H:%M"),
+                        snapshot.taken.format("%H:%M"),
+                    )}
+                </span>
+            </div>
+            {snapshot
+                .meals
+                .into_iter()
+                .map(|meal| {
+                    let changed = meal.snapshot != meal.actual;
+                    view! {
+                        <div class="flex flex-row flex-wrap gap-2" class:red=changed>
+                            <span class="flex-1 min-w-32">{meal.meal}</span>
+                            <span>
+                                {format!("{} przy zamknięciu, {} obecnie", meal.snapshot, meal.actual)}
+                            </span>
+                        </div>
+                    }
+                })
+                .collect::<Vec<_>>()}
+            {snapshot.changes.is_empty().then(|| view! { <span>Brak zmian po zamknięciu</span> })}
+            {snapshot
+                .changes
+                .into_iter()
+                .map(|change| {
+                    view! {
+                        <div class="flex flex-row flex-wrap gap-2 pl-4">
+                            <a
+                                class="flex-1 min-w-32"
+                                href=format!(
+                                    "/attendance/{}/{}/{}",
+                                    change.student_id,
+                                    snapshot.cutoff.year(),
+                                    snapshot.cutoff.month(),
+                                )
+                            >
+                                {change.student}
+                            </a>
+                            <span class="min-w-24">{change.meal}</span>
+                            <span>
+                                {if change.actual {
+                                    "dopisany po zamknięciu"
+                                } else {
+                                    "odwołany po zamknięciu"
+                                }}
+                            </span>
+                        </div>
+                    }
+                })
+                .collect::<Vec<_>>()}
+        </div>
+    }
+}
@@ -0,0 +1,135 @@
+use dto::{audit::AuditEntity, recycle_bin::RemovedEntityDto};
+use leptos::prelude::*;
+
+use crate::{
+    components::{
+        general_provider::StudentVersion,
+        loader::Loader,
+        snackbar::{use_snackbar, SnackbarContext},
+    },
+    pages::attendance_page::GroupVersion,
+    services::recycle_bin::{get_removed_entities, purge_removed, restore_removed},
+};
+
+#[component]
+pub fn RecycleBinPage() -> impl IntoView {
+    let (version, set_version) = signal(0);
+    let entities = Resource::new(version, |_| async move { get_removed_entities().await });
+
+    view! {
+        <div class="flex-1 flex flex-col gap-2 overflow-auto">
+            <div class="card p-2 flex flex-col gap-1">
+                <h2 class="h2">Kosz</h2>
+                <span>
+                    Usunięte grupy i uczniowie wracają do grupy, z której zostali usunięci, razem ze wszystkim usuniętym wraz z nimi
+                </span>
+                <Loader>
+                    {move || Suspend::new(async move {
+                        let entities = entities.await?;
+                        Ok::<
+                            _,
+                            ServerFnError,
+                        >(
+                            view! {
+                                {entities.is_empty().then(|| view! { <span>Kosz jest pusty</span> })}
+                                {entities
+                                    .into_iter()
+                                    .map(|entity| view! { <RemovedEntityRow entity set_version /> })
+                                    .collect::<Vec<_>>()}
+                            },
+                        )
+                    })}
+                </Loader>
+            </div>
+        </div>
+    }
+}
+
+#[component]
+fn RemovedEntityRow(entity: RemovedEntityDto, set_version: WriteSignal<i32>) -> impl IntoView {
+    let snackbar = use_snackbar();
+    let GroupVersion(_, set_group_version) = expect_context();
+    let student_version = expect_context::<StudentVersion>().0;
+    let id = entity.id;
+    // Purging can't be undone, the first click only asks for confirmation
+    let (confirm_purge, set_confirm_purge) = signal(false);
+
+    let restore_action = Action::new(move |_: &()| async move {
+        match restore_removed(id).await {
+            Ok(_) => {
+                snackbar.success("Przywrócono z kosza");
+                *set_group_version.write() += 1;
+                *student_version.write() += 1;
+                *set_version.write() += 1;
+            }
+            Err(e) => snackbar.error("Nie udało się przywrócić", e),
+        }
+    });
+
+    let purge_action = Action::new(move |_: &()| async move {
+        match purge_removed(id).await {
+            Ok(_) => {
+                snackbar.success("Usunięto na stałe");
+                *set_version.write() += 1;
+            }
+            Err(e) => snackbar.error("Nie udało się usunąć na stałe", e),
+        }
+    });
+
+    let contents = match entity.entity {
+        AuditEntity::Student => String::from("Uczeń"),
+        _ => format!(
+            "Grupa, {} grup i {} uczniów",
+            entity.groups.saturating_sub(1),
+            entity.students
+        ),
+    };
+    let pending = move || restore_action.pending()() || purge_action.pending()();
+
+    view! {
+        <div class="flex flex-row flex-wrap gap-2 items-center">
+            <span class="min-w-48 flex flex-col">
+                <span>{entity.name}</span>
+                <span class="text-sm text-gray-400">
+                    {entity.parent_name.unwrap_or(String::from("Catering"))}
+                </span>
+            </span>
+            <span class="min-w-48">{contents}</span>
+            <span class="flex-1 min-w-48 flex flex-col">
+                <span>
+                    {format!(
+                        "Usunięto {} przez {}",
+                        entity.removed_at.format("%Y-%m-%d %H:%M"),
+                        entity.removed_by.unwrap_or(String::from("-")),
+                    )}
+                </span>
+                <span class="text-sm text-gray-400">
+                    {format!("Zostanie usunięte na stałe {}", entity.purge_at.format("%Y-%m-%d"))}
+                </span>
+            </span>
+            <input
+                type="button"
+                class="btn"
+                value="Przywróć"
+                disabled=pending
+                on:click=move |_| {
+                    restore_action.dispatch(());
+                }
+            />
+            <input
+                type="button"
+                class="btn"
+                class:red=confirm_purge
+                value=move || if confirm_purge() { "Na pewno usunąć?" } else { "Usuń na stałe" }
+                disabled=pending
+                on:click=move |_| {
+                    if confirm_purge() {
+                        purge_action.dispatch(());
+                    } else {
+                        set_confirm_purge(true);
+                    }
+                }
+            />
+        </div>
+    }
+}
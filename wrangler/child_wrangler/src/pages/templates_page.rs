@@ -0,0 +1,183 @@
+use dto::templates::{preview, SaveTemplateDto, TemplateDto};
+use leptos::{either::Either, prelude::*};
+
+use crate::{
+    components::{
+        loader::Loader,
+        snackbar::{use_snackbar, SnackbarContext},
+    },
+    services::template::{delete_template, get_templates, save_template},
+};
+
+#[component]
+pub fn TemplatesPage() -> impl IntoView {
+    let (version, set_version) = signal(0);
+    let templates = Resource::new(version, |_| async move { get_templates().await });
+
+    view! {
+        <div class="flex-1 flex flex-col gap-2 overflow-auto">
+            <Loader>
+                {move || Suspend::new(async move {
+                    let (system, custom): (Vec<_>, Vec<_>) = templates
+                        .await?
+                        .into_iter()
+                        .partition(|template| template.system.is_some());
+                    Ok::<
+                        _,
+                        ServerFnError,
+                    >(
+                        view! {
+                            <div class="card p-2 flex flex-col gap-1">
+                                <h2 class="h2">Szablony wiadomości</h2>
+                                <span>
+                                    "Szablony można wybrać przy pisaniu wiadomości, znaczniki są uzupełniane dla każdego odbiorcy"
+                                </span>
+                            </div>
+                            <TemplateEditor
+                                template=TemplateDto {
+                                    id: None,
+                                    name: String::new(),
+                                    content: String::new(),
+                                    system: None,
+                                }
+                                set_version
+                            />
+                            {custom
+                                .into_iter()
+                                .map(|template| view! { <TemplateEditor template set_version /> })
+                                .collect::<Vec<_>>()}
+                            <div class="card p-2 flex flex-col gap-1">
+                                <h2 class="h2">Odpowiedzi automatyczne</h2>
+                                <span>Odpowiedzi wysyłane rodzicom po przetworzeniu ich wiadomości</span>
+                            </div>
+                            {system
+                                .into_iter()
+                                .map(|template| view! { <TemplateEditor template set_version /> })
+                                .collect::<Vec<_>>()}
+                        },
+                    )
+                })}
+            </Loader>
+        </div>
+    }
+}
+
+#[component]
+fn TemplateEditor(template: TemplateDto, set_version: WriteSignal<i32>) -> impl IntoView {
+    let snackbar = use_snackbar();
+    let id = template.id;
+    let system = template.system;
+    let placeholders = template.placeholders();
+    let (name, set_name) = signal(template.name);
+    let (content, set_content) = signal(template.content);
+
+    let save_action = Action::new(move |_: &()| {
+        let dto = SaveTemplateDto {
+            id,
+            name: name(),
+            content: content(),
+        };
+        async move {
+            match save_template(dto).await {
+                Ok(_) => {
+                    snackbar.success("Zapisano szablon");
+                    *set_version.write() += 1;
+                }
+                Err(e) => snackbar.error("Nie udało się zapisać szablonu", e),
+            }
+        }
+    });
+
+    let delete_action = Action::new(move |_: &()| async move {
+        let Some(id) = id else {
+            return;
+        };
+        match delete_template(id).await {
+            Ok(_) => {
+                snackbar.success(if system.is_some() {
+                    "Przywrócono domyślną treść"
+                } else {
+                    "Usunięto szablon"
+                });
+                *set_version.write() += 1;
+            }
+            Err(e) => snackbar.error("Nie udało się usunąć szablonu", e),
+        }
+    });
+
+    view! {
+        <div class="card p-2 flex flex-col gap-1">
+            <div class="flex flex-row flex-wrap gap-2 items-center">
+                {match system {
+                    Some(system) => {
+                        Either::Left(view! { <h3 class="flex-1 min-w-48">{system.label()}</h3> })
+                    }
+                    None => {
+                        Either::Right(
+                            view! {
+                                <input
+                                    class="input flex-1 min-w-48"
+                                    placeholder="Nazwa nowego szablonu"
+                                    bind:value=(name, set_name)
+                                />
+                            },
+                        )
+                    }
+                }}
+                <input
+                    type="button"
+                    class="btn save"
+                    value="Zapisz"
+                    disabled=save_action.pending()
+                    on:click=move |_| {
+                        save_action.dispatch(());
+                    }
+                />
+                {id
+                    .map(|_| {
+                        view! {
+                            <input
+                                type="button"
+                                class="btn"
+                                value=if system.is_some() { "Przywróć domyślną" } else { "Usuń" }
+                                disabled=delete_action.pending()
+                                on:click=move |_| {
+                                    delete_action.dispatch(());
+                                }
+                            />
+                        }
+                    })}
+            </div>
+            <textarea
+                class="input"
+                rows="3"
+                prop:value=content
+                on:input=move |e| set_content(event_target_value(&e))
+            ></textarea>
+            <div class="flex flex-row flex-wrap gap-1 items-center">
+                <span>Znaczniki:</span>
+                {placeholders
+                    .iter()
+                    .map(|placeholder| {
+                        view! {
+                            <input
+                                type="button"
+                                class="btn"
+                                title=placeholder.label()
+                                value=format!("{{{}}}", placeholder.as_str())
+                                on:click=move |_| {
+                                    set_content
+                                        .write()
+                                        .push_str(&format!("{{{}}}", placeholder.as_str()));
+                                }
+                            />
+                        }
+                    })
+                    .collect::<Vec<_>>()}
+            </div>
+            <span class="whitespace-pre-wrap rounded-md p-2 bg-gray-800">
+                {move || preview(&content())}
+            </span>
+        </div>
+    }
+}
@@ -0,0 +1,174 @@
+use dto::user::{CreateUserDto, Role, UpdateUserDto, UserDto};
+use leptos::prelude::*;
+
+use crate::{
+    components::{
+        loader::Loader,
+        snackbar::{use_snackbar, SnackbarContext},
+    },
+    services::auth::{create_user, get_users, update_user},
+};
+
+#[component]
+pub fn UsersPage() -> impl IntoView {
+    let (version, set_version) = signal(0);
+    let users = Resource::new(version, |_| async move { get_users().await });
+
+    view! {
+        <div class="flex-1 flex flex-col gap-2 overflow-auto">
+            <Loader>
+                {move || Suspend::new(async move {
+                    let users = users.await?;
+                    Ok::<
+                        _,
+                        ServerFnError,
+                    >(
+                        view! {
+                            <div class="card p-2 flex flex-col gap-1">
+                                <h2 class="h2">Użytkownicy</h2>
+                                {users
+                                    .into_iter()
+                                    .map(|user| view! { <UserRow user set_version /> })
+                                    .collect::<Vec<_>>()}
+                            </div>
+                        },
+                    )
+                })}
+            </Loader>
+            <AddUserForm set_version />
+        </div>
+    }
+}
+
+#[component]
+fn RoleSelect(role: ReadSignal<Role>, set_role: WriteSignal<Role>) -> impl IntoView {
+    view! {
+        <select
+            class="input"
+            on:change=move |e| {
+                if let Ok(role) = event_target_value(&e).parse() {
+                    set_role(role);
+                }
+            }
+        >
+            {Role::ALL
+                .iter()
+                .map(|r| {
+                    view! {
+                        <option value=r.as_str() selected=move || role() == *r>
+                            {r.label()}
+                        </option>
+                    }
+                })
+                .collect::<Vec<_>>()}
+        </select>
+    }
+}
+
+#[component]
+fn UserRow(user: UserDto, set_version: WriteSignal<i32>) -> impl IntoView {
+    let snackbar = use_snackbar();
+    let (role, set_role) = signal(user.role);
+    let (disabled, set_disabled) = signal(user.disabled);
+    let (password, set_password) = signal(String::new());
+
+    let save_action = Action::new(move |_: &()| {
+        let dto = UpdateUserDto {
+            id: user.id,
+            role: role(),
+            disabled: disabled(),
+            password: Some(password()).filter(|p| !p.is_empty()),
+        };
+        async move {
+            match update_user(dto).await {
+                Ok(_) => {
+                    snackbar.success("Zapisano zmiany");
+                    set_password(String::new());
+                    *set_version.write() += 1;
+                }
+                Err(e) => snackbar.error("Nie udało się zapisać zmian", e),
+            }
+        }
+    });
+
+    view! {
+        <div class="flex flex-row flex-wrap gap-2 items-center">
+            <span class="flex-1 min-w-32">{user.login}</span>
+            <RoleSelect role set_role />
+            <label class="flex flex-row gap-1 items-center">
+                <input type="checkbox" bind:checked=(disabled, set_disabled) />
+                Zablokowany
+            </label>
+            <input
+                class="input"
+                type="password"
+                placeholder="Nowe hasło"
+                autocomplete="new-password"
+                bind:value=(password, set_password)
+            />
+            <input
+                type="button"
+                class="btn save"
+                value="Zapisz"
+                disabled=save_action.pending()
+                on:click=move |_| {
+                    save_action.dispatch(());
+                }
+            />
+        </div>
+    }
+}
+
+#[component]
+fn AddUserForm(set_version: WriteSignal<i32>) -> impl IntoView {
+    let snackbar = use_snackbar();
+    let (login, set_login) = signal(String::new());
+    let (password, set_password) = signal(String::new());
+    let (role, set_role) = signal(Role::Teacher);
+
+    let create_action = Action::new(move |_: &()| {
+        let dto = CreateUserDto {
+            login: login(),
+            password: password(),
+            role: role(),
+        };
+        async move {
+            match create_user(dto).await {
+                Ok(_) => {
+                    snackbar.success("Dodano użytkownika");
+                    set_login(String::new());
+                    set_password(String::new());
+                    *set_version.write() += 1;
+                }
+                Err(e) => snackbar.error("Nie udało się dodać użytkownika", e),
+            }
+        }
+    });
+
+    view! {
+        <form
+            class="card p-2 flex flex-row flex-wrap gap-2 items-center"
+            on:submit=move |e| {
+                e.prevent_default();
+                create_action.dispatch(());
+            }
+        >
+            <h2 class="h2 w-full">Nowy użytkownik</h2>
+            <input class="input" placeholder="Login" bind:value=(login, set_login) />
+            <input
+                class="input"
+                type="password"
+                placeholder="Hasło"
+                autocomplete="new-password"
+                bind:value=(password, set_password)
+            />
+            <RoleSelect role set_role />
+            <input
+                type="submit"
+                class="btn save"
+                value="Dodaj"
+                disabled=create_action.pending()
+            />
+        </form>
+    }
+}
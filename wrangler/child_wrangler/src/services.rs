@@ -1,6 +1,20 @@
 pub mod attendance;
+pub mod audit;
+pub mod auth;
+pub mod bank_statement;
+pub mod billing;
+pub mod broadcast;
 pub mod catering;
+pub mod closure;
 pub mod group;
+pub mod import;
+pub mod ledger;
 pub mod messages;
+pub mod portal;
+pub mod production;
+pub mod recycle_bin;
+pub mod scheduler;
+pub mod snapshot;
 pub mod student;
+pub mod template;
 pub mod test;
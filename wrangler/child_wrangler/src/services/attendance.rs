@@ -7,7 +7,7 @@ use dto::{
         GetAttendanceHistoryDto, GetEffectiveMonthAttendance, GetMonthAttendanceDto, MealStatus,
         MonthAttendanceDto, MonthlyStudentAttendanceDto, UpdateAttendanceDto,
     },
-    catering::MealDto,
+    catering::{MealCutoffDto, MealDto},
     group::GroupDto,
 };
 use std::collections::{BTreeMap, HashMap};
@@ -21,8 +21,12 @@ pub async fn get_month_attendance(
 ) -> Result<MonthAttendanceDto, ServerFnError> {
     use chrono::Datelike;
     use sqlx::postgres::PgPool;
+    use crate::services::auth::authorize;
+    use crate::services::closure::closed_days;
+    use dto::user::Role;
 
     let pool: PgPool = use_context().ok_or(ServerFnError::new("Failed to retrieve db pool"))?;
+    authorize(&pool, Role::ALL).await?;
 
     let catering = sqlx::query!("SELECT * FROM caterings INNER JOIN group_relations ON group_relations.parent = caterings.group_id WHERE group_relations.child = $1", dto.target)
         .fetch_optional(&pool)
@@ -45,7 +49,9 @@ pub async fn get_month_attendance(
                         WHERE rooted_attendance.root = $1 AND rooted_attendance.day >= $2 AND rooted_attendance.day < $3 AND present = true
                         GROUP BY meal_id, day", dto.target, start, end).fetch_all(&pool).await?;
 
-    let meal_order = sqlx::query!("SELECT meals.id, meals.name FROM meals INNER JOIN catering_meals ON catering_meals.meal_id = meals.id WHERE catering_meals.catering_id = $1 ORDER BY meal_order", catering.id).fetch_all(&pool).await?;
+    let meal_order = sqlx::query!("SELECT meals.id, meals.name, catering_meals.cutoff_days, catering_meals.cutoff_time FROM meals INNER JOIN catering_meals ON catering_meals.meal_id = meals.id WHERE catering_meals.catering_id = $1 ORDER BY meal_order", catering.id).fetch_all(&pool).await?;
+
+    let closures = closed_days(&mut *pool.acquire().await?, catering.id, start, end).await?;
 
     let dow = (0..7)
         .map(|i| (catering.dow >> i) & 1 == 1)
@@ -79,57 +85,43 @@ pub async fn get_month_attendance(
             .map(|row| CateringMealDto {
                 id: row.id,
                 name: row.name,
+                cutoff: MealCutoffDto {
+                    days_before: row.cutoff_days.max(0) as u32,
+                    time: row.cutoff_time,
+                },
             })
             .collect(),
         days_of_week: dow,
         start: catering.since,
         end: catering.until,
         attendance: days,
+        closures,
     })
 }
 
-#[server]
-pub async fn get_effective_attendance(
-    dto: GetEffectiveMonthAttendance,
-) -> Result<EffectiveMonthAttendance, ServerFnError> {
-    use sqlx::postgres::PgPool;
-
-    let pool: PgPool = use_context().ok_or(ServerFnError::new("Failed to retrieve db pool"))?;
-    let start = NaiveDate::from_ymd_opt(dto.year, dto.month, 1);
-    let end = start.and_then(|date| date.checked_add_months(Months::new(1)));
-
-    let (Some(start), Some(end)) = (start, end) else {
-        log!(
-            "Failed to parse the dates: {:?} {:?} for request: {:?}",
-            start,
-            end,
-            dto
-        );
-        return Err(ServerFnError::new("Failed to parse provided date"));
-    };
-
-    let is_student = sqlx::query!(
-        "SELECT id FROM students WHERE students.id = $1 LIMIT 1",
-        dto.target
-    )
-    .fetch_optional(&pool)
-    .await?
-    .is_some();
-
+/// Effective attendance of every meal of `target` in `[start, end)`, resolved across its groups
+#[cfg(feature = "ssr")]
+pub async fn effective_attendance(
+    pool: &sqlx::PgPool,
+    target: Uuid,
+    start: NaiveDate,
+    end: NaiveDate,
+) -> Result<BTreeMap<NaiveDate, BTreeMap<Uuid, EffectiveAttendance>>, ServerFnError> {
     let attendance = sqlx::query!(
         "SELECT DISTINCT ON (day,meal_id) day, meal_id, value, target, effective_attendance.cause_id, 
     (attendance_override.id IS NOT NULL) AS is_override,
-    (messages.id IS NOT NULL) AS is_cancellation FROM effective_attendance 
+    (messages.id IS NOT NULL OR guardian_requests.id IS NOT NULL) AS is_cancellation FROM effective_attendance 
     INNER JOIN group_relations ON group_relations.parent = effective_attendance.target
     LEFT JOIN attendance_override ON attendance_override.id = effective_attendance.cause_id
     LEFT JOIN messages ON messages.id = effective_attendance.cause_id
+    LEFT JOIN guardian_requests ON guardian_requests.id = effective_attendance.cause_id
     WHERE group_relations.child=$1 AND day >= $2 AND day < $3
     ORDER BY day, meal_id, value, level DESC",
-        dto.target,
+        target,
         start,
         end
     )
-    .fetch_all(&pool)
+    .fetch_all(pool)
     .await?;
 
     let mut entries = BTreeMap::new();
@@ -142,7 +134,7 @@ pub async fn get_effective_attendance(
                     EffectiveAttendance::Present
                 } else {
                     if entry.is_override.unwrap_or(false) {
-                        if Some(dto.target) == entry.target {
+                        if Some(target) == entry.target {
                             EffectiveAttendance::Absent
                         } else {
                             EffectiveAttendance::Blocked
@@ -158,6 +150,42 @@ pub async fn get_effective_attendance(
         }
     }
 
+    Ok(entries)
+}
+
+#[server]
+pub async fn get_effective_attendance(
+    dto: GetEffectiveMonthAttendance,
+) -> Result<EffectiveMonthAttendance, ServerFnError> {
+    use sqlx::postgres::PgPool;
+    use crate::services::auth::authorize;
+    use dto::user::Role;
+
+    let pool: PgPool = use_context().ok_or(ServerFnError::new("Failed to retrieve db pool"))?;
+    authorize(&pool, Role::ALL).await?;
+    let start = NaiveDate::from_ymd_opt(dto.year, dto.month, 1);
+    let end = start.and_then(|date| date.checked_add_months(Months::new(1)));
+
+    let (Some(start), Some(end)) = (start, end) else {
+        log!(
+            "Failed to parse the dates: {:?} {:?} for request: {:?}",
+            start,
+            end,
+            dto
+        );
+        return Err(ServerFnError::new("Failed to parse provided date"));
+    };
+
+    let is_student = sqlx::query!(
+        "SELECT id FROM students WHERE students.id = $1 LIMIT 1",
+        dto.target
+    )
+    .fetch_optional(&pool)
+    .await?
+    .is_some();
+
+    let entries = effective_attendance(&pool, dto.target, start, end).await?;
+
     Ok(EffectiveMonthAttendance {
         is_student,
         attendance: entries,
@@ -167,10 +195,30 @@ pub async fn get_effective_attendance(
 #[server]
 pub async fn update_attendance(dto: UpdateAttendanceDto) -> Result<(), ServerFnError> {
     use sqlx::postgres::PgPool;
+    use attendance_rules::store::{load_catering, record_attendance};
+    use attendance_rules::Actor;
+    use chrono::Local;
+    use crate::services::audit::{attendance_snapshot, record};
+    use crate::services::auth::authorize;
+    use dto::audit::{AuditAction, AuditEntity};
+    use dto::user::Role;
 
     let pool: PgPool = use_context().ok_or(ServerFnError::new("Failed to retrieve db pool"))?;
+    let actor = authorize(&pool, &[Role::Admin]).await?;
     let mut tr = pool.begin().await?;
 
+    let catering = load_catering(dto.target, &mut *tr)
+        .await?
+        .ok_or(ServerFnError::new("No catering found for given id"))?;
+    let days = dto
+        .days
+        .iter()
+        .copied()
+        .filter(|day| catering.is_served(*day))
+        .collect::<Vec<_>>();
+
+    let before = attendance_snapshot(&mut tr, dto.target, &days).await?;
+
     let override_id = sqlx::query!(
         "INSERT INTO attendance_override (note) VALUES ($1) RETURNING id",
         dto.note,
@@ -179,11 +227,33 @@ pub async fn update_attendance(dto: UpdateAttendanceDto) -> Result<(), ServerFnE
     .await?
     .id;
 
-    sqlx::query!("INSERT INTO attendance (cause_id,target,day,meal_id,value) SELECT $1,$2,day,meal_id, false FROM UNNEST($3::date[]) AS arg1(day) 
-                    CROSS JOIN UNNEST($4::uuid[]) AS arg2(meal_id)", override_id, dto.target, &dto.days, &dto.inactive_meals).execute(&mut *tr).await?;
+    let now = Local::now().naive_local();
+    for (meals, value) in [(&dto.inactive_meals, false), (&dto.active_meals, true)] {
+        for meal_id in catering.served_meals(meals) {
+            record_attendance(
+                override_id,
+                dto.target,
+                Actor::Staff,
+                &catering.filter_days(Actor::Staff, now, meal_id, &days),
+                &[meal_id],
+                value,
+                &mut *tr,
+            )
+            .await?;
+        }
+    }
 
-    sqlx::query!("INSERT INTO attendance (cause_id,target,day,meal_id,value) SELECT $1,$2,day,meal_id, true FROM UNNEST($3::date[]) AS arg1(day) 
-                    CROSS JOIN UNNEST($4::uuid[]) AS arg2(meal_id)", override_id, dto.target, &dto.days, &dto.active_meals).execute(&mut *tr).await?;
+    let after = attendance_snapshot(&mut tr, dto.target, &days).await?;
+    record(
+        &mut tr,
+        &actor,
+        AuditAction::Update,
+        AuditEntity::Attendance,
+        dto.target,
+        before,
+        after,
+    )
+    .await?;
 
     tr.commit().await?;
     Ok(())
@@ -194,16 +264,21 @@ pub async fn get_attendance_history(
     dto: GetAttendanceHistoryDto,
 ) -> Result<AttendanceHistoryDto, ServerFnError> {
     use sqlx::postgres::PgPool;
+    use crate::services::auth::authorize;
+    use dto::user::Role;
 
     let pool: PgPool = use_context().ok_or(ServerFnError::new("Failed to retrieve db pool"))?;
+    authorize(&pool, Role::ALL).await?;
 
     let history = sqlx::query!(
-        r#"SELECT attendance.target, attendance.cause_id, messages.phone AS "phone?", messages.content AS "content?", originated, ARRAY_AGG((meal_id, value)) AS "meals: Vec<(Uuid,bool)>", note, messages.id AS "msg_id?" FROM group_relations
+        r#"SELECT attendance.target, attendance.cause_id, messages.phone AS "phone?", messages.content AS "content?", originated, ARRAY_AGG((meal_id, value)) AS "meals: Vec<(Uuid,bool)>", note, messages.id AS "msg_id?", guardians.fullname AS "portal_guardian?" FROM group_relations
     INNER JOIN attendance ON attendance.target = group_relations.parent
     LEFT JOIN attendance_override ON attendance_override.id = attendance.cause_id
     LEFT JOIN messages ON messages.id = attendance.cause_id
+    LEFT JOIN guardian_requests ON guardian_requests.id = attendance.cause_id
+    LEFT JOIN guardians ON guardians.id = guardian_requests.guardian_id
     WHERE group_relations.child = $1 AND attendance.day = $2 
-    GROUP BY attendance.cause_id, attendance.originated, attendance_override.id, messages.id , target
+    GROUP BY attendance.cause_id, attendance.originated, attendance_override.id, messages.id, guardians.id, target
     ORDER BY originated"#,
         dto.target,
         dto.date
@@ -224,6 +299,12 @@ pub async fn get_attendance_history(
                         row.content.unwrap_or_default(),
                     ),
                 }
+            } else if let Some(guardian) = row.portal_guardian {
+                AttendanceHistoryItemDto {
+                    time: row.originated,
+                    meals: row.meals.unwrap_or_default(),
+                    item: AttendanceItemDto::Portal(guardian),
+                }
             } else if let Some(note) = row.note {
                 AttendanceHistoryItemDto {
                     time: row.originated,
@@ -240,10 +321,11 @@ pub async fn get_attendance_history(
         })
         .collect::<Vec<_>>();
 
-    let events = sqlx::query!(r#"SELECT note, messages.id AS "trigger_id?", target, level FROM group_relations
+    let events = sqlx::query!(r#"SELECT note, COALESCE(messages.id, guardian_requests.id) AS "trigger_id?", target, level FROM group_relations
     INNER JOIN effective_attendance ON effective_attendance.target = group_relations.parent
     LEFT JOIN attendance_override ON attendance_override.id = effective_attendance.cause_id
     LEFT JOIN messages ON messages.id = effective_attendance.cause_id
+    LEFT JOIN guardian_requests ON guardian_requests.id = effective_attendance.cause_id
     WHERE group_relations.child = $1 AND effective_attendance.day = $2 AND effective_attendance.meal_id = $3 AND ((level > 0 AND value = false) OR level = 0)
     ORDER BY level DESC LIMIT 1"#, dto.target, dto.date, Uuid::nil())
     .fetch_optional(&pool)
@@ -272,8 +354,11 @@ pub async fn get_attendance_breakdown(
     dto: GetAttendanceBreakdownDto,
 ) -> Result<AttendanceBreakdownDto, ServerFnError> {
     use sqlx::postgres::PgPool;
+    use crate::services::auth::authorize;
+    use dto::user::Role;
 
     let pool: PgPool = use_context().ok_or(ServerFnError::new("Failed to retrieve db pool"))?;
+    authorize(&pool, Role::ALL).await?;
 
     let meals = sqlx::query!(
         "SELECT meals.id, meals.name FROM meals
@@ -311,8 +396,10 @@ pub async fn get_attendance_breakdown(
 
         if groups.is_empty() {
             sqlx::query!("SELECT students.id, students.name, students.surname FROM students
-        INNER JOIN group_relations ON group_relations.child = students.id AND group_relations.level = 1
-        WHERE group_relations.parent = $1 AND NOT students.removed ORDER BY surname", dto.target).fetch_all(&pool).await?.into_iter().map(|row| GroupDto{
+        INNER JOIN student_placements ON student_placements.student_id = students.id
+        WHERE student_placements.group_id = $1 AND NOT students.removed
+        AND $2 BETWEEN COALESCE(student_placements.since, '-infinity') AND COALESCE(student_placements.until, 'infinity')
+        ORDER BY surname", dto.target, dto.date).fetch_all(&pool).await?.into_iter().map(|row| GroupDto{
                     id: row.id,
                     name: format!("{} {}", row.name, row.surname),
                     parent: None
@@ -322,10 +409,22 @@ pub async fn get_attendance_breakdown(
         }
     };
 
-    let attendance_rows = sqlx::query!("SELECT group_relations.child AS id, meal_id, COUNT(*) AS max_attendance, SUM(present::int) AS attendance FROM group_relations
-    INNER JOIN rooted_attendance ON rooted_attendance.root = group_relations.child
-    WHERE group_relations.parent = $1 AND group_relations.level = 1 AND rooted_attendance.day = $2
-    GROUP BY group_relations.child, rooted_attendance.meal_id
+    // Students count towards the group they were placed in on that day, not their current one
+    let attendance_rows = sqlx::query!("WITH placed AS (
+        SELECT group_relations.child AS id, student_placements.student_id FROM group_relations
+        INNER JOIN group_relations AS placement_relation ON placement_relation.parent = group_relations.child
+        INNER JOIN student_placements ON student_placements.group_id = placement_relation.child
+        WHERE group_relations.parent = $1 AND group_relations.level = 1
+        AND $2 BETWEEN COALESCE(student_placements.since, '-infinity') AND COALESCE(student_placements.until, 'infinity')
+        UNION ALL
+        SELECT student_placements.student_id, student_placements.student_id FROM student_placements
+        WHERE student_placements.group_id = $1
+        AND $2 BETWEEN COALESCE(student_placements.since, '-infinity') AND COALESCE(student_placements.until, 'infinity')
+    )
+    SELECT placed.id AS \"id!\", total_attendance.meal_id, COUNT(*) AS max_attendance, SUM(total_attendance.value::int) AS attendance FROM placed
+    INNER JOIN total_attendance ON total_attendance.student_id = placed.student_id
+    WHERE total_attendance.day = $2
+    GROUP BY placed.id, total_attendance.meal_id
     ", dto.target, dto.date).fetch_all(&pool)
     .await?;
 
@@ -352,7 +451,10 @@ pub async fn get_monthly_summary(
     month: u32,
 ) -> Result<String, ServerFnError> {
     use sqlx::postgres::PgPool;
+    use crate::services::auth::authorize;
+    use dto::user::Role;
     let pool: PgPool = use_context().ok_or(ServerFnError::new("Failed to retrieve db pool"))?;
+    authorize(&pool, Role::ALL).await?;
     use csv::WriterBuilder;
 
     let start = NaiveDate::from_ymd_opt(year, month, 1).ok_or(ServerFnError::new(
@@ -374,14 +476,18 @@ pub async fn get_monthly_summary(
     .await?
     .group_id;
 
+    // A student transferred during the month gets a row for every group they were in
     let attendance = sqlx::query!(
-        "WITH student_attendance AS (SELECT student_id, SUM(present::int) AS student_attendance FROM rooted_attendance 
-    WHERE root = $1 AND day >= $2 AND day < $3
-    GROUP BY student_id)
+        "WITH student_attendance AS (SELECT total_attendance.student_id, student_placements.group_id, SUM(total_attendance.value::int) AS student_attendance FROM total_attendance
+    INNER JOIN student_placements ON student_placements.student_id = total_attendance.student_id
+        AND total_attendance.day BETWEEN COALESCE(student_placements.since, '-infinity') AND COALESCE(student_placements.until, 'infinity')
+    INNER JOIN group_relations ON group_relations.child = student_placements.group_id AND group_relations.parent = $1
+    WHERE total_attendance.day >= $2 AND total_attendance.day < $3
+    GROUP BY total_attendance.student_id, student_placements.group_id)
     SELECT groups.name AS group_name, students.name, students.surname, students.id, student_attendance AS attendance FROM student_attendance
     INNER JOIN students ON student_attendance.student_id = students.id
-    INNER JOIN group_relations AS direct_relation ON direct_relation.level = 1 AND direct_relation.child = students.id
-    INNER JOIN groups ON groups.id = direct_relation.parent",catering_group_id, start, end)
+    INNER JOIN groups ON groups.id = student_attendance.group_id
+    ORDER BY students.surname, students.name, groups.name",catering_group_id, start, end)
         .fetch_all(&pool)
         .await?
     .into_iter()
@@ -410,14 +516,18 @@ pub async fn get_attendance_overview(
     catering_id: Uuid,
 ) -> Result<AttendanceOverviewDto, ServerFnError> {
     use sqlx::postgres::PgPool;
+    use crate::services::auth::authorize;
+    use dto::user::Role;
     let pool: PgPool = use_context().ok_or(ServerFnError::new("Failed to retrieve db pool"))?;
+    authorize(&pool, Role::ALL).await?;
 
-    let students = sqlx::query!("SELECT bool_and(value) AS value, meal_id,  (attendance_override.id IS NOT NULL) AS is_override, students.id AS id, (messages.id IS NOT NULL) AS is_cancellation, students.allergy_combination_id AS allergies_id FROM caterings
-    INNER JOIN group_relations ON group_relations.parent = caterings.group_id
-    INNER JOIN students ON students.id = group_relations.child
+    let students = sqlx::query!("SELECT bool_and(value) AS value, meal_id,  (attendance_override.id IS NOT NULL) AS is_override, students.id AS id, (messages.id IS NOT NULL OR guardian_requests.id IS NOT NULL) AS is_cancellation, students.allergy_combination_id AS allergies_id FROM caterings
+    INNER JOIN student_targets ON student_targets.target = caterings.group_id AND $1 BETWEEN student_targets.since AND student_targets.until
+    INNER JOIN students ON students.id = student_targets.student_id
     INNER JOIN total_attendance ON total_attendance.student_id = students.id
     LEFT JOIN attendance_override ON attendance_override.id = total_attendance.cause_id
     LEFT JOIN messages ON messages.id = total_attendance.cause_id
+    LEFT JOIN guardian_requests ON guardian_requests.id = total_attendance.cause_id
     WHERE total_attendance.day = $1 AND caterings.id = $2
     GROUP BY students.id, meal_id, is_override, is_cancellation
 ",  date, catering_id)
@@ -0,0 +1,351 @@
+use dto::audit::AuditEntryDto;
+#[cfg(feature = "ssr")]
+use dto::audit::{AuditAction, AuditChangeDto, AuditEntity};
+#[cfg(feature = "ssr")]
+use dto::user::UserDto;
+use leptos::prelude::*;
+#[cfg(feature = "ssr")]
+use serde_json::Value;
+use uuid::Uuid;
+
+#[cfg(feature = "ssr")]
+pub async fn snapshot(
+    conn: &mut sqlx::PgConnection,
+    entity: AuditEntity,
+    id: Uuid,
+) -> Result<Option<Value>, ServerFnError> {
+    let value = match entity {
+        AuditEntity::Student => sqlx::query_scalar!(
+            r#"SELECT jsonb_build_object(
+                'name', students.name,
+                'surname', students.surname,
+                'removed', students.removed,
+                'group', (SELECT groups.name FROM group_relations INNER JOIN groups ON groups.id = group_relations.parent WHERE group_relations.child = students.id AND group_relations.level = 1),
+                'allergies', (SELECT COALESCE(jsonb_agg(allergies.name ORDER BY allergies.name), '[]') FROM allergy_combinations INNER JOIN allergies ON allergies.id = allergy_combinations.allergy_id WHERE allergy_combinations.id = students.allergy_combination_id),
+                'guardians', (SELECT COALESCE(jsonb_agg(guardians.fullname ORDER BY guardians.fullname), '[]') FROM student_guardians INNER JOIN guardians ON guardians.id = student_guardians.guardian_id WHERE student_guardians.student_id = students.id),
+                'joined', students.joined_on,
+                'left', students.left_on,
+                'placements', (SELECT COALESCE(jsonb_agg(format('%s od %s do %s', COALESCE(groups.name, 'usunięta grupa'), COALESCE(student_placements.since::text, '-'), COALESCE(student_placements.until::text, '-')) ORDER BY student_placements.since NULLS FIRST), '[]') FROM student_placements LEFT JOIN groups ON groups.id = student_placements.group_id WHERE student_placements.student_id = students.id)
+            ) AS "value!" FROM students WHERE students.id = $1"#,
+            id
+        )
+        .fetch_optional(&mut *conn)
+        .await?,
+        AuditEntity::Guardian => sqlx::query_scalar!(
+            r#"SELECT jsonb_build_object(
+                'fullname', guardians.fullname,
+                'phone', guardians.phone,
+                'removed', guardians.removed,
+                'students', (SELECT COALESCE(jsonb_agg(students.name || ' ' || students.surname ORDER BY students.surname, students.name), '[]') FROM student_guardians INNER JOIN students ON students.id = student_guardians.student_id WHERE student_guardians.guardian_id = guardians.id)
+            ) AS "value!" FROM guardians WHERE guardians.id = $1"#,
+            id
+        )
+        .fetch_optional(&mut *conn)
+        .await?,
+        AuditEntity::Group => sqlx::query_scalar!(
+            r#"SELECT jsonb_build_object(
+                'name', groups.name,
+                'removed', groups.removed,
+                'parent', (SELECT parents.name FROM group_relations INNER JOIN groups AS parents ON parents.id = group_relations.parent WHERE group_relations.child = groups.id AND group_relations.level = 1)
+            ) AS "value!" FROM groups WHERE groups.id = $1"#,
+            id
+        )
+        .fetch_optional(&mut *conn)
+        .await?,
+        AuditEntity::Catering => sqlx::query_scalar!(
+            r#"SELECT jsonb_build_object(
+                'grace_period', caterings.grace_period,
+                'dow', caterings.dow,
+                'since', caterings.since,
+                'until', caterings.until,
+                'meals', (SELECT COALESCE(jsonb_agg(meals.name ORDER BY catering_meals.meal_order), '[]') FROM catering_meals INNER JOIN meals ON meals.id = catering_meals.meal_id WHERE catering_meals.catering_id = caterings.id),
+                'cutoffs', (SELECT COALESCE(jsonb_agg(format('%s do %s, %s dni wcześniej', meals.name, to_char(catering_meals.cutoff_time, 'HH24:MI'), catering_meals.cutoff_days) ORDER BY catering_meals.meal_order), '[]') FROM catering_meals INNER JOIN meals ON meals.id = catering_meals.meal_id WHERE catering_meals.catering_id = caterings.id),
+                'prices', (SELECT COALESCE(jsonb_agg(format('%s %s od %s do %s', meals.name, catering_meal_prices.price, catering_meal_prices.since, COALESCE(catering_meal_prices.until::text, '-')) ORDER BY meals.name, catering_meal_prices.since), '[]') FROM catering_meal_prices INNER JOIN meals ON meals.id = catering_meal_prices.meal_id WHERE catering_meal_prices.catering_id = caterings.id)
+            ) AS "value!" FROM caterings WHERE caterings.group_id = $1"#,
+            id
+        )
+        .fetch_optional(&mut *conn)
+        .await?,
+        AuditEntity::Message => sqlx::query_scalar!(
+            r#"SELECT jsonb_build_object(
+                'phone', phone,
+                'content', content,
+                'outgoing', outgoing,
+                'sent', sent,
+                'processed', processed,
+                'status', status,
+                'attempts', attempts
+            ) AS "value!" FROM messages WHERE id = $1"#,
+            id
+        )
+        .fetch_optional(&mut *conn)
+        .await?,
+        AuditEntity::User => sqlx::query_scalar!(
+            r#"SELECT jsonb_build_object(
+                'login', login,
+                'role', role,
+                'disabled', disabled
+            ) AS "value!" FROM users WHERE id = $1"#,
+            id
+        )
+        .fetch_optional(&mut *conn)
+        .await?,
+        AuditEntity::Ledger => sqlx::query_scalar!(
+            r#"SELECT jsonb_build_object(
+                'balance', COALESCE(SUM(amount), 0),
+                'entries', COUNT(*)
+            ) AS "value!" FROM ledger_entries WHERE guardian_id = $1"#,
+            id
+        )
+        .fetch_optional(&mut *conn)
+        .await?,
+        AuditEntity::Closure => sqlx::query_scalar!(
+            r#"SELECT jsonb_build_object(
+                'catering', (SELECT groups.name FROM caterings INNER JOIN groups ON groups.id = caterings.group_id WHERE caterings.id = closures.catering_id),
+                'since', closures.since,
+                'until', closures.until,
+                'reason', closures.reason
+            ) AS "value!" FROM closures WHERE closures.id = $1"#,
+            id
+        )
+        .fetch_optional(&mut *conn)
+        .await?,
+        AuditEntity::Job => sqlx::query_scalar!(
+            r#"SELECT jsonb_build_object(
+                'job', name,
+                'schedule', schedule,
+                'enabled', enabled
+            ) AS "value!" FROM scheduled_jobs WHERE id = $1"#,
+            id
+        )
+        .fetch_optional(&mut *conn)
+        .await?,
+        AuditEntity::Broadcast => sqlx::query_scalar!(
+            r#"SELECT jsonb_build_object(
+                'group', groups.name,
+                'content', broadcasts.content,
+                'recipients', (SELECT COUNT(*) FROM broadcast_recipients WHERE broadcast_recipients.broadcast_id = broadcasts.id)
+            ) AS "value!" FROM broadcasts LEFT JOIN groups ON groups.id = broadcasts.group_id WHERE broadcasts.id = $1"#,
+            id
+        )
+        .fetch_optional(&mut *conn)
+        .await?,
+        AuditEntity::Template => sqlx::query_scalar!(
+            r#"SELECT jsonb_build_object(
+                'name', name,
+                'content', content
+            ) AS "value!" FROM message_templates WHERE id = $1"#,
+            id
+        )
+        .fetch_optional(&mut *conn)
+        .await?,
+        AuditEntity::Attendance => {
+            return Err(ServerFnError::new(
+                "Attendance snapshots require a list of days",
+            ))
+        }
+    };
+    Ok(value)
+}
+
+#[cfg(feature = "ssr")]
+pub async fn node_entity(
+    conn: &mut sqlx::PgConnection,
+    id: Uuid,
+) -> Result<AuditEntity, ServerFnError> {
+    let is_student = sqlx::query!("SELECT id FROM students WHERE id = $1", id)
+        .fetch_optional(&mut *conn)
+        .await?
+        .is_some();
+    Ok(if is_student {
+        AuditEntity::Student
+    } else {
+        AuditEntity::Group
+    })
+}
+
+#[cfg(feature = "ssr")]
+pub async fn attendance_snapshot(
+    conn: &mut sqlx::PgConnection,
+    target: Uuid,
+    days: &[chrono::NaiveDate],
+) -> Result<Option<Value>, ServerFnError> {
+    let value = sqlx::query_scalar!(
+        r#"SELECT COALESCE(jsonb_object_agg(format('%s %s', effective_attendance.day, meals.name), effective_attendance.value), '{}') AS "value!"
+        FROM effective_attendance
+        INNER JOIN meals ON meals.id = effective_attendance.meal_id
+        WHERE effective_attendance.target = $1 AND effective_attendance.day = ANY($2)"#,
+        target,
+        days
+    )
+    .fetch_one(&mut *conn)
+    .await?;
+    Ok(Some(value))
+}
+
+#[cfg(feature = "ssr")]
+fn diff(before: Option<Value>, after: Option<Value>) -> (Option<Value>, Option<Value>) {
+    match (before, after) {
+        (Some(Value::Object(mut before)), Some(Value::Object(mut after))) => {
+            let unchanged = before
+                .iter()
+                .filter(|(key, value)| after.get(*key) == Some(value))
+                .map(|(key, _)| key.clone())
+                .collect::<Vec<_>>();
+            for key in unchanged {
+                before.remove(&key);
+                after.remove(&key);
+            }
+            (Some(Value::Object(before)), Some(Value::Object(after)))
+        }
+        other => other,
+    }
+}
+
+#[cfg(feature = "ssr")]
+pub async fn record(
+    conn: &mut sqlx::PgConnection,
+    actor: &UserDto,
+    action: AuditAction,
+    entity: AuditEntity,
+    entity_id: Uuid,
+    before: Option<Value>,
+    after: Option<Value>,
+) -> Result<(), ServerFnError> {
+    insert_record(conn, Some(actor.id), action, entity, entity_id, before, after).await
+}
+
+/// Records a change made by the server itself (e.g. a scheduled job), without a user
+#[cfg(feature = "ssr")]
+pub async fn record_system(
+    conn: &mut sqlx::PgConnection,
+    action: AuditAction,
+    entity: AuditEntity,
+    entity_id: Uuid,
+    before: Option<Value>,
+    after: Option<Value>,
+) -> Result<(), ServerFnError> {
+    insert_record(conn, None, action, entity, entity_id, before, after).await
+}
+
+#[cfg(feature = "ssr")]
+async fn insert_record(
+    conn: &mut sqlx::PgConnection,
+    user_id: Option<Uuid>,
+    action: AuditAction,
+    entity: AuditEntity,
+    entity_id: Uuid,
+    before: Option<Value>,
+    after: Option<Value>,
+) -> Result<(), ServerFnError> {
+    let (before, after) = diff(before, after);
+
+    sqlx::query!(
+        "INSERT INTO audit_log (user_id, action, entity_type, entity_id, before, after) VALUES ($1, $2, $3, $4, $5, $6)",
+        user_id,
+        action.as_str(),
+        entity.as_str(),
+        entity_id,
+        before,
+        after
+    )
+    .execute(&mut *conn)
+    .await?;
+    Ok(())
+}
+
+#[cfg(feature = "ssr")]
+fn format_value(value: &Value) -> Option<String> {
+    match value {
+        Value::Null => None,
+        Value::String(s) => Some(s.clone()),
+        Value::Array(values) => Some(
+            values
+                .iter()
+                .filter_map(format_value)
+                .collect::<Vec<_>>()
+                .join(", "),
+        ),
+        other => Some(other.to_string()),
+    }
+}
+
+#[cfg(feature = "ssr")]
+fn into_changes(before: Option<Value>, after: Option<Value>) -> Vec<AuditChangeDto> {
+    match (before, after) {
+        (before @ (Some(Value::Object(_)) | None), after @ (Some(Value::Object(_)) | None)) => {
+            let before = match before {
+                Some(Value::Object(map)) => map,
+                _ => Default::default(),
+            };
+            let after = match after {
+                Some(Value::Object(map)) => map,
+                _ => Default::default(),
+            };
+            let mut fields = before
+                .keys()
+                .chain(after.keys())
+                .cloned()
+                .collect::<Vec<_>>();
+            fields.sort();
+            fields.dedup();
+            fields
+                .into_iter()
+                .map(|field| AuditChangeDto {
+                    before: before.get(&field).and_then(format_value),
+                    after: after.get(&field).and_then(format_value),
+                    field,
+                })
+                .collect()
+        }
+        (before, after) => vec![AuditChangeDto {
+            field: String::new(),
+            before: before.as_ref().and_then(format_value),
+            after: after.as_ref().and_then(format_value),
+        }],
+    }
+}
+
+#[server]
+pub async fn get_audit_log(entity_id: Option<Uuid>) -> Result<Vec<AuditEntryDto>, ServerFnError> {
+    use crate::services::auth::authorize;
+    use dto::user::Role;
+    use sqlx::postgres::PgPool;
+
+    let pool: PgPool = use_context().ok_or(ServerFnError::new("Failed to retrieve db pool"))?;
+    authorize(&pool, &[Role::Admin]).await?;
+
+    let entries = sqlx::query!(
+        r#"SELECT audit_log.id, audit_log.created, users.login AS "actor?", audit_log.action, audit_log.entity_type, audit_log.entity_id,
+            audit_log.before, audit_log.after,
+            COALESCE(groups.name, students.name || ' ' || students.surname, guardians.fullname, target_users.login) AS entity_name
+        FROM audit_log
+        LEFT JOIN users ON users.id = audit_log.user_id
+        LEFT JOIN groups ON groups.id = audit_log.entity_id
+        LEFT JOIN students ON students.id = audit_log.entity_id
+        LEFT JOIN guardians ON guardians.id = audit_log.entity_id
+        LEFT JOIN users AS target_users ON target_users.id = audit_log.entity_id
+        WHERE $1::uuid IS NULL OR audit_log.entity_id = $1 OR audit_log.entity_id IN (SELECT child FROM group_relations WHERE parent = $1)
+        ORDER BY audit_log.created DESC
+        LIMIT 500"#,
+        entity_id
+    )
+    .fetch_all(&pool)
+    .await?
+    .into_iter()
+    .map(|row| {
+        Ok(AuditEntryDto {
+            id: row.id,
+            created: row.created,
+            actor: row.actor,
+            action: row.action.parse().map_err(ServerFnError::new)?,
+            entity: row.entity_type.parse().map_err(ServerFnError::new)?,
+            entity_id: row.entity_id,
+            entity_name: row.entity_name,
+            changes: into_changes(row.before, row.after),
+        })
+    })
+    .collect::<Result<Vec<_>, ServerFnError>>()?;
+
+    Ok(entries)
+}
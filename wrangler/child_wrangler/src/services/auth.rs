@@ -0,0 +1,348 @@
+#[cfg(feature = "ssr")]
+use dto::user::Role;
+use dto::user::{CreateUserDto, UpdateUserDto, UserDto};
+use leptos::prelude::*;
+use uuid::Uuid;
+
+#[cfg(feature = "ssr")]
+const SESSION_COOKIE: &str = "wrangler_session";
+#[cfg(feature = "ssr")]
+const SESSION_DAYS: i64 = 7;
+
+#[cfg(feature = "ssr")]
+pub(crate) fn hash_password(password: &str) -> Result<String, ServerFnError> {
+    use argon2::password_hash::{PasswordHasher, SaltString};
+    use argon2::Argon2;
+    use rand::RngCore;
+
+    let mut salt = [0u8; 16];
+    rand::rng().fill_bytes(&mut salt);
+    let salt =
+        SaltString::encode_b64(&salt).map_err(|_| ServerFnError::new("Failed to generate salt"))?;
+
+    Argon2::default()
+        .hash_password(password.as_bytes(), &salt)
+        .map(|hash| hash.to_string())
+        .map_err(|_| ServerFnError::new("Failed to hash password"))
+}
+
+#[cfg(feature = "ssr")]
+pub(crate) fn verify_password(password: &str, hash: &str) -> bool {
+    use argon2::password_hash::{PasswordHash, PasswordVerifier};
+    use argon2::Argon2;
+
+    PasswordHash::new(hash)
+        .map(|hash| {
+            Argon2::default()
+                .verify_password(password.as_bytes(), &hash)
+                .is_ok()
+        })
+        .unwrap_or(false)
+}
+
+#[cfg(feature = "ssr")]
+fn validate_password(password: &str) -> Result<(), ServerFnError> {
+    if password.chars().count() < 8 {
+        return Err(ServerFnError::new(
+            "Password must have at least 8 characters",
+        ));
+    }
+    Ok(())
+}
+
+#[cfg(feature = "ssr")]
+pub(crate) async fn read_cookie(cookie_name: &str) -> Option<String> {
+    use axum::http::{header::COOKIE, HeaderMap};
+
+    let headers: HeaderMap = leptos_axum::extract().await.ok()?;
+    headers
+        .get_all(COOKIE)
+        .iter()
+        .filter_map(|value| value.to_str().ok())
+        .flat_map(|value| value.split(';'))
+        .filter_map(|cookie| cookie.trim().split_once('='))
+        .find(|(name, _)| *name == cookie_name)
+        .map(|(_, value)| String::from(value))
+}
+
+#[cfg(feature = "ssr")]
+pub(crate) fn set_cookie(
+    cookie_name: &str,
+    token: &str,
+    max_age: i64,
+) -> Result<(), ServerFnError> {
+    use axum::http::{header::SET_COOKIE, HeaderValue};
+
+    let response: leptos_axum::ResponseOptions =
+        use_context().ok_or(ServerFnError::new("Failed to retrieve response options"))?;
+    let cookie = format!(
+        "{}={}; Path=/; HttpOnly; SameSite=Lax; Max-Age={}",
+        cookie_name, token, max_age
+    );
+    response.append_header(
+        SET_COOKIE,
+        HeaderValue::from_str(&cookie).map_err(|_| ServerFnError::new("Invalid session cookie"))?,
+    );
+    Ok(())
+}
+
+#[cfg(feature = "ssr")]
+async fn session_token() -> Option<String> {
+    read_cookie(SESSION_COOKIE).await
+}
+
+#[cfg(feature = "ssr")]
+fn set_session_cookie(token: &str, max_age: i64) -> Result<(), ServerFnError> {
+    set_cookie(SESSION_COOKIE, token, max_age)
+}
+
+#[cfg(feature = "ssr")]
+pub async fn current_user(pool: &sqlx::PgPool) -> Result<Option<UserDto>, ServerFnError> {
+    let Some(token) = session_token().await else {
+        return Ok(None);
+    };
+
+    let user = sqlx::query!(
+        "SELECT users.id, users.login, users.role, users.disabled FROM sessions
+        INNER JOIN users ON users.id = sessions.user_id
+        WHERE sessions.id = $1 AND sessions.expires > LOCALTIMESTAMP AND NOT users.disabled",
+        token
+    )
+    .fetch_optional(pool)
+    .await?;
+
+    user.map(|row| {
+        Ok(UserDto {
+            id: row.id,
+            login: row.login,
+            role: row.role.parse().map_err(ServerFnError::new)?,
+            disabled: row.disabled,
+        })
+    })
+    .transpose()
+}
+
+#[cfg(feature = "ssr")]
+pub async fn authorize(pool: &sqlx::PgPool, roles: &[Role]) -> Result<UserDto, ServerFnError> {
+    let user = current_user(pool)
+        .await?
+        .ok_or(ServerFnError::new("Not logged in"))?;
+
+    if !roles.contains(&user.role) {
+        return Err(ServerFnError::new("Insufficient permissions"));
+    }
+    Ok(user)
+}
+
+#[cfg(feature = "ssr")]
+pub async fn create_initial_admin(
+    pool: &sqlx::PgPool,
+    password: &str,
+) -> Result<(), ServerFnError> {
+    let admins = sqlx::query!("SELECT COUNT(*) AS \"count!\" FROM users WHERE role = 'admin'")
+        .fetch_one(pool)
+        .await?
+        .count;
+
+    if admins == 0 {
+        let hash = hash_password(password)?;
+        sqlx::query!(
+            "INSERT INTO users (login, password_hash, role) VALUES ('admin', $1, 'admin')",
+            hash
+        )
+        .execute(pool)
+        .await?;
+        log::info!("Created initial admin account");
+    }
+    Ok(())
+}
+
+#[server]
+pub async fn login(login: String, password: String) -> Result<UserDto, ServerFnError> {
+    use rand::distr::{Alphanumeric, SampleString};
+    use sqlx::postgres::PgPool;
+
+    let pool: PgPool = use_context().ok_or(ServerFnError::new("Failed to retrieve db pool"))?;
+
+    let user = sqlx::query!(
+        "SELECT id, login, password_hash, role, disabled FROM users WHERE login = $1",
+        login.trim()
+    )
+    .fetch_optional(&pool)
+    .await?
+    .filter(|user| !user.disabled && verify_password(&password, &user.password_hash))
+    .ok_or(ServerFnError::new("Invalid login or password"))?;
+
+    let token = Alphanumeric.sample_string(&mut rand::rng(), 48);
+
+    let mut tr = pool.begin().await?;
+    sqlx::query!("DELETE FROM sessions WHERE expires < LOCALTIMESTAMP")
+        .execute(&mut *tr)
+        .await?;
+    sqlx::query!(
+        "INSERT INTO sessions (id, user_id, expires) VALUES ($1, $2, LOCALTIMESTAMP + make_interval(days => $3))",
+        token,
+        user.id,
+        SESSION_DAYS as i32
+    )
+    .execute(&mut *tr)
+    .await?;
+    tr.commit().await?;
+
+    set_session_cookie(&token, SESSION_DAYS * 24 * 60 * 60)?;
+
+    Ok(UserDto {
+        id: user.id,
+        login: user.login,
+        role: user.role.parse().map_err(ServerFnError::new)?,
+        disabled: user.disabled,
+    })
+}
+
+#[server]
+pub async fn logout() -> Result<(), ServerFnError> {
+    use sqlx::postgres::PgPool;
+
+    let pool: PgPool = use_context().ok_or(ServerFnError::new("Failed to retrieve db pool"))?;
+
+    if let Some(token) = session_token().await {
+        sqlx::query!("DELETE FROM sessions WHERE id = $1", token)
+            .execute(&pool)
+            .await?;
+    }
+    set_session_cookie("", 0)
+}
+
+#[server]
+pub async fn get_current_user() -> Result<Option<UserDto>, ServerFnError> {
+    use sqlx::postgres::PgPool;
+
+    let pool: PgPool = use_context().ok_or(ServerFnError::new("Failed to retrieve db pool"))?;
+    current_user(&pool).await
+}
+
+#[server]
+pub async fn get_users() -> Result<Vec<UserDto>, ServerFnError> {
+    use sqlx::postgres::PgPool;
+
+    let pool: PgPool = use_context().ok_or(ServerFnError::new("Failed to retrieve db pool"))?;
+    authorize(&pool, &[Role::Admin]).await?;
+
+    sqlx::query!("SELECT id, login, role, disabled FROM users ORDER BY login")
+        .fetch_all(&pool)
+        .await?
+        .into_iter()
+        .map(|row| {
+            Ok(UserDto {
+                id: row.id,
+                login: row.login,
+                role: row.role.parse().map_err(ServerFnError::new)?,
+                disabled: row.disabled,
+            })
+        })
+        .collect()
+}
+
+#[server]
+pub async fn create_user(dto: CreateUserDto) -> Result<Uuid, ServerFnError> {
+    use crate::services::audit::{record, snapshot};
+    use dto::audit::{AuditAction, AuditEntity};
+    use sqlx::postgres::PgPool;
+
+    let pool: PgPool = use_context().ok_or(ServerFnError::new("Failed to retrieve db pool"))?;
+    let actor = authorize(&pool, &[Role::Admin]).await?;
+
+    let login = dto.login.trim();
+    if login.is_empty() {
+        return Err(ServerFnError::new("Login cannot be empty"));
+    }
+    validate_password(&dto.password)?;
+    let hash = hash_password(&dto.password)?;
+
+    let mut tr = pool.begin().await?;
+    let id = sqlx::query!(
+        "INSERT INTO users (login, password_hash, role) VALUES ($1, $2, $3) RETURNING id",
+        login,
+        hash,
+        dto.role.as_str()
+    )
+    .fetch_one(&mut *tr)
+    .await?
+    .id;
+
+    let after = snapshot(&mut tr, AuditEntity::User, id).await?;
+    record(
+        &mut tr,
+        &actor,
+        AuditAction::Create,
+        AuditEntity::User,
+        id,
+        None,
+        after,
+    )
+    .await?;
+
+    tr.commit().await?;
+    Ok(id)
+}
+
+#[server]
+pub async fn update_user(dto: UpdateUserDto) -> Result<(), ServerFnError> {
+    use crate::services::audit::{record, snapshot};
+    use dto::audit::{AuditAction, AuditEntity};
+    use sqlx::postgres::PgPool;
+
+    let pool: PgPool = use_context().ok_or(ServerFnError::new("Failed to retrieve db pool"))?;
+    let actor = authorize(&pool, &[Role::Admin]).await?;
+
+    if actor.id == dto.id && (dto.disabled || dto.role != Role::Admin) {
+        return Err(ServerFnError::new(
+            "Cannot disable or demote currently logged in account",
+        ));
+    }
+
+    let mut tr = pool.begin().await?;
+    let before = snapshot(&mut tr, AuditEntity::User, dto.id).await?;
+    sqlx::query!(
+        "UPDATE users SET role = $2, disabled = $3 WHERE id = $1",
+        dto.id,
+        dto.role.as_str(),
+        dto.disabled
+    )
+    .execute(&mut *tr)
+    .await?;
+
+    if let Some(password) = dto.password.filter(|p| !p.is_empty()) {
+        validate_password(&password)?;
+        let hash = hash_password(&password)?;
+        sqlx::query!(
+            "UPDATE users SET password_hash = $2 WHERE id = $1",
+            dto.id,
+            hash
+        )
+        .execute(&mut *tr)
+        .await?;
+    }
+
+    if dto.disabled {
+        sqlx::query!("DELETE FROM sessions WHERE user_id = $1", dto.id)
+            .execute(&mut *tr)
+            .await?;
+    }
+
+    let after = snapshot(&mut tr, AuditEntity::User, dto.id).await?;
+    record(
+        &mut tr,
+        &actor,
+        AuditAction::Update,
+        AuditEntity::User,
+        dto.id,
+        before,
+        after,
+    )
+    .await?;
+
+    tr.commit().await?;
+
+    Ok(())
+}
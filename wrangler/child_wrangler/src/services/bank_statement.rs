@@ -0,0 +1,452 @@
+#[cfg(feature = "ssr")]
+use chrono::NaiveDate;
+#[cfg(feature = "ssr")]
+use dto::import::ImportFileDto;
+#[cfg(feature = "ssr")]
+use leptos::prelude::ServerFnError;
+
+/// Single operation read from a bank statement, amount is in grosze and negative for outgoing transfers
+#[cfg(feature = "ssr")]
+#[derive(Debug, Clone)]
+pub struct BankTransfer {
+    pub booked: NaiveDate,
+    pub amount: i64,
+    pub title: String,
+    pub counterparty: Option<String>,
+    pub account: Option<String>,
+}
+
+#[cfg(feature = "ssr")]
+impl BankTransfer {
+    /// Identifies the transfer across imports, `occurrence` tells apart identical transfers of one statement
+    pub fn fingerprint(&self, occurrence: usize) -> String {
+        format!(
+            "{}|{}|{}|{}|{}",
+            self.booked,
+            self.amount,
+            self.account.as_deref().unwrap_or_default(),
+            self.title,
+            occurrence
+        )
+    }
+}
+
+#[cfg(feature = "ssr")]
+pub fn parse_bank_statement(file: &ImportFileDto) -> Result<Vec<BankTransfer>, ServerFnError> {
+    use crate::services::import::{decode_text, read_records};
+
+    let extension = file
+        .filename
+        .rsplit_once('.')
+        .map(|(_, extension)| extension.to_lowercase())
+        .unwrap_or_default();
+
+    let content = decode_text(&file.content);
+    if matches!(extension.as_str(), "sta" | "mt940" | "940") || content.contains(":61:") {
+        return parse_mt940(&content);
+    }
+
+    parse_csv(read_records(file)?)
+}
+
+/// Accepts amounts as written by banks: `-1 234,56`, `1234.56 PLN` or `1.234,56`
+#[cfg(feature = "ssr")]
+fn parse_signed_amount(amount: &str) -> Option<i64> {
+    let amount: String = amount
+        .chars()
+        .filter(|c| c.is_ascii_digit() || matches!(c, ',' | '.' | '-' | '+'))
+        .collect();
+    let negative = amount.starts_with('-');
+    let amount = amount.trim_start_matches(['-', '+']);
+
+    let (whole, fraction) = match amount.rfind([',', '.']) {
+        Some(position) if amount.len() - position <= 3 => {
+            (&amount[..position], &amount[position + 1..])
+        }
+        _ => (amount, ""),
+    };
+    let whole: String = whole.chars().filter(char::is_ascii_digit).collect();
+    if whole.is_empty() && fraction.is_empty() {
+        return None;
+    }
+
+    let whole: i64 = if whole.is_empty() { 0 } else { whole.parse().ok()? };
+    let fraction: i64 = format!("{:0<2}", fraction).parse().ok()?;
+    let amount = whole * 100 + fraction;
+    Some(if negative { -amount } else { amount })
+}
+
+#[cfg(feature = "ssr")]
+fn parse_date(date: &str) -> Option<NaiveDate> {
+    let date = date.trim();
+    let date = date.split_whitespace().next().unwrap_or(date);
+    ["%Y-%m-%d", "%d-%m-%Y", "%d.%m.%Y", "%Y.%m.%d", "%d/%m/%Y", "%Y/%m/%d"]
+        .iter()
+        .find_map(|format| NaiveDate::parse_from_str(date, format).ok())
+}
+
+#[cfg(feature = "ssr")]
+enum Column {
+    Date,
+    Amount,
+    Title,
+    Counterparty,
+    Account,
+}
+
+#[cfg(feature = "ssr")]
+fn into_column(header: &str) -> Option<Column> {
+    match header.trim().trim_start_matches('#').trim().to_lowercase().as_str() {
+        "data" | "data operacji" | "data księgowania" | "data ksiegowania" | "data transakcji"
+        | "date" | "booking date" => Some(Column::Date),
+        "kwota" | "kwota operacji" | "kwota transakcji" | "amount" => Some(Column::Amount),
+        "tytuł" | "tytul" | "tytuł operacji" | "tytuł przelewu" | "opis" | "opis operacji"
+        | "opis transakcji" | "title" | "description" => Some(Column::Title),
+        "nadawca" | "nadawca/odbiorca" | "nadawca / odbiorca" | "kontrahent"
+        | "nazwa kontrahenta" | "dane kontrahenta" | "odbiorca/zleceniodawca"
+        | "counterparty" | "sender" => Some(Column::Counterparty),
+        "rachunek" | "nr rachunku" | "numer rachunku" | "numer konta" | "rachunek kontrahenta"
+        | "konto" | "account" => Some(Column::Account),
+        _ => None,
+    }
+}
+
+/// Bank csv exports often start with a few lines describing the account,
+/// transfers are read from the first record that has at least date, amount and title columns
+#[cfg(feature = "ssr")]
+fn parse_csv(records: Vec<Vec<String>>) -> Result<Vec<BankTransfer>, ServerFnError> {
+    let mut records = records.into_iter();
+
+    let mut columns = None;
+    for record in records.by_ref() {
+        let mut date = None;
+        let mut amount = None;
+        let mut title = None;
+        let mut counterparty = None;
+        let mut account = None;
+        for (i, header) in record.iter().enumerate() {
+            match into_column(header) {
+                Some(Column::Date) => date = date.or(Some(i)),
+                Some(Column::Amount) => amount = amount.or(Some(i)),
+                Some(Column::Title) => title = title.or(Some(i)),
+                Some(Column::Counterparty) => counterparty = counterparty.or(Some(i)),
+                Some(Column::Account) => account = account.or(Some(i)),
+                None => {}
+            }
+        }
+        if let (Some(date), Some(amount), Some(title)) = (date, amount, title) {
+            columns = Some((date, amount, title, counterparty, account));
+            break;
+        }
+    }
+
+    let (date, amount, title, counterparty, account) = columns.ok_or(ServerFnError::new(
+        "Statement needs date, amount and title columns",
+    ))?;
+
+    let cell = |record: &Vec<String>, i: usize| {
+        record
+            .get(i)
+            .map(|value| value.trim().to_string())
+            .filter(|value| !value.is_empty())
+    };
+
+    Ok(records
+        .filter_map(|record| {
+            Some(BankTransfer {
+                booked: parse_date(&cell(&record, date)?)?,
+                amount: parse_signed_amount(&cell(&record, amount)?)?,
+                title: cell(&record, title).unwrap_or_default(),
+                counterparty: counterparty.and_then(|i| cell(&record, i)),
+                account: account.and_then(|i| cell(&record, i)),
+            })
+        })
+        .collect())
+}
+
+/// Splits MT940 into fields, each starting with `:tag:` at the beginning of a line
+#[cfg(feature = "ssr")]
+fn mt940_fields(content: &str) -> Vec<(&str, String)> {
+    let mut fields: Vec<(&str, String)> = vec![];
+    for line in content.lines() {
+        let line = line.trim_end();
+        let tag = line
+            .strip_prefix(':')
+            .and_then(|rest| rest.split_once(':'))
+            .filter(|(tag, _)| !tag.is_empty() && tag.len() <= 3);
+        match (tag, fields.last_mut()) {
+            (Some((tag, value)), _) => fields.push((tag, String::from(value))),
+            (None, Some((_, value))) => value.push_str(line),
+            (None, None) => {}
+        }
+    }
+    fields
+}
+
+/// Parses the `:61:` statement line: date, optional entry date, debit/credit mark and amount
+#[cfg(feature = "ssr")]
+fn parse_mt940_line(line: &str) -> Option<(NaiveDate, i64)> {
+    let booked = NaiveDate::parse_from_str(&format!("20{}", line.get(..6)?), "%Y%m%d").ok()?;
+    let mut rest = &line[6..];
+    if rest.get(..4).is_some_and(|s| s.chars().all(|c| c.is_ascii_digit())) {
+        rest = &rest[4..];
+    }
+
+    let (negative, rest) = if let Some(rest) = rest.strip_prefix("RC") {
+        (true, rest)
+    } else if let Some(rest) = rest.strip_prefix("RD") {
+        (false, rest)
+    } else if let Some(rest) = rest.strip_prefix('C') {
+        (false, rest)
+    } else {
+        (true, rest.strip_prefix('D')?)
+    };
+    let rest = rest.trim_start_matches(|c: char| c.is_ascii_alphabetic());
+
+    let amount: String = rest
+        .chars()
+        .take_while(|c| c.is_ascii_digit() || *c == ',')
+        .collect();
+    let amount = parse_signed_amount(&amount)?;
+    Some((booked, if negative { -amount } else { amount }))
+}
+
+/// Polish banks structure `:86:` as `~NN` subfields: 20-25 title, 27-29 and 32-33 counterparty, 38 account.
+/// Title is cut into subfields mid-word so its parts are joined as they are.
+/// Statements without subfields keep the whole field as title
+#[cfg(feature = "ssr")]
+fn parse_mt940_details(details: &str) -> (String, Option<String>, Option<String>) {
+    let separator = details
+        .chars()
+        .nth(3)
+        .filter(|c| matches!(c, '~' | '^' | '<' | '?'));
+    let Some(separator) = separator else {
+        return (String::from(details.trim()), None, None);
+    };
+
+    let mut title = String::new();
+    let mut counterparty = String::new();
+    let mut account = None;
+    for subfield in details.split(separator).skip(1) {
+        let (Some(code), Some(value)) = (subfield.get(..2), subfield.get(2..)) else {
+            continue;
+        };
+        match code.parse::<u32>() {
+            Ok(20..=26) => title.push_str(value),
+            Ok(27..=29) | Ok(32..=33) => {
+                counterparty.push(' ');
+                counterparty.push_str(value.trim());
+            }
+            Ok(38) => account = Some(String::from(value.trim())),
+            _ => {}
+        }
+    }
+
+    let counterparty = Some(String::from(counterparty.trim())).filter(|c| !c.is_empty());
+    (String::from(title.trim()), counterparty, account)
+}
+
+#[cfg(feature = "ssr")]
+fn parse_mt940(content: &str) -> Result<Vec<BankTransfer>, ServerFnError> {
+    let mut transfers: Vec<BankTransfer> = vec![];
+    let mut last_line_parsed = false;
+
+    for (tag, value) in mt940_fields(content) {
+        match tag {
+            "61" => {
+                last_line_parsed = false;
+                if let Some((booked, amount)) = parse_mt940_line(&value) {
+                    transfers.push(BankTransfer {
+                        booked,
+                        amount,
+                        title: String::new(),
+                        counterparty: None,
+                        account: None,
+                    });
+                    last_line_parsed = true;
+                }
+            }
+            "86" if last_line_parsed => {
+                if let Some(transfer) = transfers.last_mut() {
+                    let (title, counterparty, account) = parse_mt940_details(&value);
+                    transfer.title = title;
+                    transfer.counterparty = counterparty;
+                    transfer.account = account;
+                }
+                last_line_parsed = false;
+            }
+            _ => {}
+        }
+    }
+
+    if transfers.is_empty() {
+        return Err(ServerFnError::new("Statement contains no transfers"));
+    }
+    Ok(transfers)
+}
+
+#[cfg(all(test, feature = "ssr"))]
+mod tests {
+    use super::*;
+
+    fn file(filename: &str, content: &str) -> ImportFileDto {
+        ImportFileDto {
+            filename: String::from(filename),
+            content: content.as_bytes().to_vec(),
+        }
+    }
+
+    fn date(year: i32, month: u32, day: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(year, month, day).unwrap()
+    }
+
+    #[test]
+    fn signed_amount_accepts_bank_formats() {
+        assert_eq!(parse_signed_amount("12,50"), Some(1250));
+        assert_eq!(parse_signed_amount("12.50"), Some(1250));
+        assert_eq!(parse_signed_amount("12,5"), Some(1250));
+        assert_eq!(parse_signed_amount("12"), Some(1200));
+        assert_eq!(parse_signed_amount("-45,10 PLN"), Some(-4510));
+        assert_eq!(parse_signed_amount("+45.10"), Some(4510));
+        assert_eq!(parse_signed_amount("1 234,56"), Some(123456));
+        assert_eq!(parse_signed_amount("-1 234,56 zł"), Some(-123456));
+        assert_eq!(parse_signed_amount("1.234,56"), Some(123456));
+        assert_eq!(parse_signed_amount("1,234.56"), Some(123456));
+        assert_eq!(parse_signed_amount("1.234"), Some(123400));
+        assert_eq!(parse_signed_amount(",50"), Some(50));
+        assert_eq!(parse_signed_amount("PLN"), None);
+        assert_eq!(parse_signed_amount(""), None);
+    }
+
+    #[test]
+    fn csv_statement_skips_account_header() {
+        let transfers = parse_bank_statement(&file(
+            "historia.csv",
+            "#Klient;\n\
+            JAN KOWALSKI;\n\
+            \n\
+            #Data operacji;#Opis operacji;#Rachunek;#Kategoria;#Kwota;\n\
+            2025-01-15;\"Opłata za posiłki Jan Kowalski\";\"12 1090 1014 0000 0712 1981 2874\";\"Wpływy\";1 234,56 PLN;\n\
+            2025-01-16;\"Zakupy\";\"\";\"Wydatki\";-45,10 PLN;\n\
+            15.01.2025;\"Bez kwoty\";\"\";\"\";;\n",
+        ))
+        .unwrap();
+
+        assert_eq!(transfers.len(), 2);
+        assert_eq!(transfers[0].booked, date(2025, 1, 15));
+        assert_eq!(transfers[0].amount, 123456);
+        assert_eq!(transfers[0].title, "Opłata za posiłki Jan Kowalski");
+        assert_eq!(
+            transfers[0].account.as_deref(),
+            Some("12 1090 1014 0000 0712 1981 2874")
+        );
+        assert_eq!(transfers[0].counterparty, None);
+        assert_eq!(transfers[1].amount, -4510);
+        assert_eq!(transfers[1].account, None);
+    }
+
+    #[test]
+    fn csv_statement_reads_comma_separated_export() {
+        let transfers = parse_bank_statement(&file(
+            "export.csv",
+            "Date,Amount,Description,Sender\n\
+            15/01/2025,\"1,234.56\",Posiłki styczeń,ANNA NOWAK\n\
+            2025/01/20,-5.00,Fee,\n",
+        ))
+        .unwrap();
+
+        assert_eq!(transfers.len(), 2);
+        assert_eq!(transfers[0].booked, date(2025, 1, 15));
+        assert_eq!(transfers[0].amount, 123456);
+        assert_eq!(transfers[0].counterparty.as_deref(), Some("ANNA NOWAK"));
+        assert_eq!(transfers[1].booked, date(2025, 1, 20));
+        assert_eq!(transfers[1].amount, -500);
+    }
+
+    #[test]
+    fn csv_statement_without_required_columns_is_rejected() {
+        assert!(
+            parse_bank_statement(&file("historia.csv", "Data;Kwota\n2025-01-15;10,00\n")).is_err()
+        );
+    }
+
+    #[test]
+    fn mt940_statement_with_structured_details() {
+        let transfers = parse_bank_statement(&file(
+            "wyciag.sta",
+            ":20:ST250131\r\n\
+            :25:/PL61109010140000071219812874\r\n\
+            :28C:1/1\r\n\
+            :60F:C250101PLN1000,00\r\n\
+            :61:2501150115CN1234,56NTRFNONREF//BR25015000001\r\n\
+            :86:020~00PRZELEW~20Oplata za posilki Jan K\r\n\
+            ~21owalski styczen\r\n\
+            ~27JANINA KOWALSKA~28UL. LIPOWA 1~29WARSZAWA\r\n\
+            ~3810901014000007121981287\r\n\
+            :61:250120D50,00NTRFNONREF\r\n\
+            :86:Oplata za prowadzenie rachunku\r\n\
+            :61:250121RC10,00NTRFNONREF\r\n\
+            :62F:C250131PLN2174,56\r\n",
+        ))
+        .unwrap();
+
+        assert_eq!(transfers.len(), 3);
+        assert_eq!(transfers[0].booked, date(2025, 1, 15));
+        assert_eq!(transfers[0].amount, 123456);
+        assert_eq!(transfers[0].title, "Oplata za posilki Jan Kowalski styczen");
+        assert_eq!(
+            transfers[0].counterparty.as_deref(),
+            Some("JANINA KOWALSKA UL. LIPOWA 1 WARSZAWA")
+        );
+        assert_eq!(
+            transfers[0].account.as_deref(),
+            Some("10901014000007121981287")
+        );
+
+        assert_eq!(transfers[1].booked, date(2025, 1, 20));
+        assert_eq!(transfers[1].amount, -5000);
+        assert_eq!(transfers[1].title, "Oplata za prowadzenie rachunku");
+        assert_eq!(transfers[1].counterparty, None);
+
+        // Reversal of a credit takes money back
+        assert_eq!(transfers[2].amount, -1000);
+        assert_eq!(transfers[2].title, "");
+    }
+
+    #[test]
+    fn mt940_is_detected_by_content() {
+        let transfers = parse_bank_statement(&file(
+            "wyciag.txt",
+            ":20:1\n:61:250301C12,5NTRF\n:86:Posilki za marzec\n",
+        ))
+        .unwrap();
+
+        assert_eq!(transfers.len(), 1);
+        assert_eq!(transfers[0].booked, date(2025, 3, 1));
+        assert_eq!(transfers[0].amount, 1250);
+        assert_eq!(transfers[0].title, "Posilki za marzec");
+    }
+
+    #[test]
+    fn mt940_without_transfers_is_rejected() {
+        assert!(parse_mt940(":20:1\n:60F:C250101PLN0,00\n:62F:C250101PLN0,00\n").is_err());
+    }
+
+    #[test]
+    fn mt940_line_parses_marks() {
+        assert_eq!(
+            parse_mt940_line("2501150115CN1234,56NTRF"),
+            Some((date(2025, 1, 15), 123456))
+        );
+        assert_eq!(
+            parse_mt940_line("250115D0,99NTRF"),
+            Some((date(2025, 1, 15), -99))
+        );
+        assert_eq!(
+            parse_mt940_line("250115RD5,00NTRF"),
+            Some((date(2025, 1, 15), 500))
+        );
+        assert_eq!(parse_mt940_line("250115X5,00NTRF"), None);
+        assert_eq!(parse_mt940_line("251315C5,00NTRF"), None);
+    }
+}
@@ -0,0 +1,594 @@
+use chrono::NaiveDate;
+use dto::billing::{CreateMealPriceDto, GuardianStatementDto, MealPriceDto};
+use leptos::prelude::*;
+use uuid::Uuid;
+
+#[cfg(feature = "ssr")]
+pub fn month_range(year: i32, month: u32) -> Result<(NaiveDate, NaiveDate), ServerFnError> {
+    use chrono::Months;
+
+    let start = NaiveDate::from_ymd_opt(year, month, 1).ok_or(ServerFnError::new(
+        "Failed to construct start date from provided arguments",
+    ))?;
+    let end = start
+        .checked_add_months(Months::new(1))
+        .ok_or(ServerFnError::new(
+            "Failed to construct end date from provided arguments",
+        ))?;
+    Ok((start, end))
+}
+
+/// Builds statements of every guardian (or just `guardian`) for meals ordered between `start` and `end`.
+/// Each meal is charged at the price valid on its day, cancelled meals are refunded at the same price.
+/// Days are billed by the catering the student was placed in at the time, meals dropped from it since are still billed.
+/// Meals of a student are billed once, to the guardian picked by `student_payers`, other guardians are
+/// only listed on the lines
+#[cfg(feature = "ssr")]
+pub async fn load_statements(
+    conn: &mut sqlx::PgConnection,
+    start: NaiveDate,
+    end: NaiveDate,
+    guardian: Option<Uuid>,
+) -> Result<Vec<GuardianStatementDto>, ServerFnError> {
+    use chrono::Datelike;
+    use dto::billing::StatementLineDto;
+
+    let rows = sqlx::query!(
+        r#"SELECT guardians.id AS guardian_id, guardians.fullname, guardians.phone,
+        students.id AS student_id, students.name, students.surname, meals.name AS meal, catering_meal_prices.price AS "price?",
+        COUNT(*) AS "ordered!", COUNT(*) FILTER (WHERE NOT total_attendance.value) AS "cancelled!",
+        ARRAY(
+            SELECT others.fullname FROM student_guardians AS other_relations
+            INNER JOIN guardians AS others ON others.id = other_relations.guardian_id AND others.removed = false
+            WHERE other_relations.student_id = students.id AND others.id <> guardians.id
+            ORDER BY others.fullname
+        ) AS "other_guardians!"
+        FROM total_attendance
+        INNER JOIN students ON students.id = total_attendance.student_id
+        INNER JOIN student_payers ON student_payers.student_id = students.id
+        INNER JOIN guardians ON guardians.id = student_payers.guardian_id
+        INNER JOIN meals ON meals.id = total_attendance.meal_id
+        INNER JOIN student_targets ON student_targets.student_id = students.id
+            AND total_attendance.day BETWEEN student_targets.since AND student_targets.until
+        INNER JOIN caterings ON caterings.group_id = student_targets.target
+        LEFT JOIN catering_meals ON catering_meals.catering_id = caterings.id AND catering_meals.meal_id = total_attendance.meal_id
+        LEFT JOIN catering_meal_prices ON catering_meal_prices.catering_id = caterings.id
+            AND catering_meal_prices.meal_id = total_attendance.meal_id
+            AND catering_meal_prices.since <= total_attendance.day
+            AND (catering_meal_prices.until IS NULL OR catering_meal_prices.until >= total_attendance.day)
+        WHERE total_attendance.day >= $1 AND total_attendance.day < $2 AND ($3::uuid IS NULL OR guardians.id = $3)
+        GROUP BY guardians.id, students.id, meals.id, catering_meals.meal_order, catering_meal_prices.price
+        ORDER BY guardians.fullname, guardians.id, students.surname, students.name, students.id, catering_meals.meal_order NULLS LAST, meals.name, catering_meal_prices.price"#,
+        start,
+        end,
+        guardian
+    )
+    .fetch_all(&mut *conn)
+    .await?;
+
+    let mut statements: Vec<GuardianStatementDto> = vec![];
+    for row in rows {
+        let unit_price = row.price;
+        let ordered = row.ordered as u32;
+        let cancelled = row.cancelled as u32;
+        let charged = unit_price.unwrap_or(0) as i64 * ordered as i64;
+        let refunded = unit_price.unwrap_or(0) as i64 * cancelled as i64;
+
+        if statements
+            .last()
+            .is_none_or(|statement| statement.guardian_id != row.guardian_id)
+        {
+            statements.push(GuardianStatementDto {
+                guardian_id: row.guardian_id,
+                guardian: row.fullname,
+                phone: row.phone,
+                year: start.year(),
+                month: start.month(),
+                lines: vec![],
+                charged: 0,
+                refunded: 0,
+                due: 0,
+            });
+        }
+        let Some(statement) = statements.last_mut() else {
+            continue;
+        };
+
+        statement.charged += charged;
+        statement.refunded += refunded;
+        statement.due = statement.charged - statement.refunded;
+        statement.lines.push(StatementLineDto {
+            student_id: row.student_id,
+            student: format!("{} {}", row.name, row.surname),
+            meal: row.meal,
+            unit_price,
+            ordered,
+            cancelled,
+            charged,
+            refunded,
+            other_guardians: row.other_guardians,
+        });
+    }
+
+    Ok(statements)
+}
+
+/// Replaces polish letters with their base letter, built-in pdf fonts only cover latin-1
+#[cfg(feature = "ssr")]
+pub fn strip_diacritics(text: &str) -> String {
+    text.chars()
+        .map(|c| match c {
+            'ą' => 'a',
+            'ć' => 'c',
+            'ę' => 'e',
+            'ł' => 'l',
+            'ń' => 'n',
+            'ś' => 's',
+            'ź' | 'ż' => 'z',
+            'Ą' => 'A',
+            'Ć' => 'C',
+            'Ę' => 'E',
+            'Ł' => 'L',
+            'Ń' => 'N',
+            'Ś' => 'S',
+            'Ź' | 'Ż' => 'Z',
+            c => c,
+        })
+        .collect()
+}
+
+#[cfg(feature = "ssr")]
+fn render_statements_pdf(statements: &[GuardianStatementDto]) -> Result<Vec<u8>, ServerFnError> {
+    use dto::billing::format_amount;
+    use printpdf::{BuiltinFont, Mm, PdfDocument};
+
+    const PAGE_WIDTH: Mm = Mm(210.0);
+    const PAGE_HEIGHT: Mm = Mm(297.0);
+    const TOP: f32 = 277.0;
+    const BOTTOM: f32 = 20.0;
+    const LINE_HEIGHT: f32 = 6.0;
+    const COLUMNS: [f32; 7] = [15.0, 70.0, 105.0, 127.0, 145.0, 163.0, 184.0];
+
+    let (doc, page, layer) =
+        PdfDocument::new("Rozliczenie posiłków", PAGE_WIDTH, PAGE_HEIGHT, "Rozliczenie");
+    let font = doc.add_builtin_font(BuiltinFont::Helvetica)?;
+    let bold = doc.add_builtin_font(BuiltinFont::HelveticaBold)?;
+    let mut layer = doc.get_page(page).get_layer(layer);
+    let mut y = TOP;
+
+    for (i, statement) in statements.iter().enumerate() {
+        if i > 0 {
+            let (page, page_layer) = doc.add_page(PAGE_WIDTH, PAGE_HEIGHT, "Rozliczenie");
+            layer = doc.get_page(page).get_layer(page_layer);
+            y = TOP;
+        }
+
+        layer.use_text(
+            strip_diacritics(&format!(
+                "Rozliczenie posiłków za {:02}.{}",
+                statement.month, statement.year
+            )),
+            16.0,
+            Mm(COLUMNS[0]),
+            Mm(y),
+            &bold,
+        );
+        y -= LINE_HEIGHT * 2.0;
+        layer.use_text(
+            strip_diacritics(&statement.guardian),
+            12.0,
+            Mm(COLUMNS[0]),
+            Mm(y),
+            &font,
+        );
+        if let Some(phone) = &statement.phone {
+            y -= LINE_HEIGHT;
+            layer.use_text(strip_diacritics(phone), 10.0, Mm(COLUMNS[0]), Mm(y), &font);
+        }
+        y -= LINE_HEIGHT * 2.0;
+
+        let header = [
+            "Uczeń", "Posiłek", "Cena", "Zamówione", "Odwołane", "Kwota", "Zwrot",
+        ];
+        for (text, x) in header.iter().zip(COLUMNS) {
+            layer.use_text(strip_diacritics(text), 9.0, Mm(x), Mm(y), &bold);
+        }
+        y -= LINE_HEIGHT;
+
+        for line in &statement.lines {
+            if y < BOTTOM {
+                let (page, page_layer) = doc.add_page(PAGE_WIDTH, PAGE_HEIGHT, "Rozliczenie");
+                layer = doc.get_page(page).get_layer(page_layer);
+                y = TOP;
+            }
+            let columns = [
+                line.student.clone(),
+                line.meal.clone(),
+                line.unit_price
+                    .map(|price| format_amount(price as i64))
+                    .unwrap_or(String::from("brak ceny")),
+                line.ordered.to_string(),
+                line.cancelled.to_string(),
+                format_amount(line.charged),
+                format_amount(line.refunded),
+            ];
+            for (text, x) in columns.iter().zip(COLUMNS) {
+                layer.use_text(strip_diacritics(text), 9.0, Mm(x), Mm(y), &font);
+            }
+            y -= LINE_HEIGHT;
+        }
+
+        let mut listed: Vec<&str> = vec![];
+        for line in &statement.lines {
+            if line.other_guardians.is_empty() || listed.contains(&line.student.as_str()) {
+                continue;
+            }
+            listed.push(&line.student);
+            if y < BOTTOM {
+                let (page, page_layer) = doc.add_page(PAGE_WIDTH, PAGE_HEIGHT, "Rozliczenie");
+                layer = doc.get_page(page).get_layer(page_layer);
+                y = TOP;
+            }
+            layer.use_text(
+                strip_diacritics(&format!(
+                    "{}: pozostali opiekunowie {}, nie są obciążani",
+                    line.student,
+                    line.other_guardians.join(", ")
+                )),
+                9.0,
+                Mm(COLUMNS[0]),
+                Mm(y),
+                &font,
+            );
+            y -= LINE_HEIGHT;
+        }
+
+        if y < BOTTOM + LINE_HEIGHT * 3.0 {
+            let (page, page_layer) = doc.add_page(PAGE_WIDTH, PAGE_HEIGHT, "Rozliczenie");
+            layer = doc.get_page(page).get_layer(page_layer);
+            y = TOP;
+        }
+        y -= LINE_HEIGHT;
+        for (label, amount) in [
+            ("Razem", statement.charged),
+            ("Zwrot za odwołane posiłki", statement.refunded),
+            ("Do zapłaty", statement.due),
+        ] {
+            layer.use_text(strip_diacritics(label), 10.0, Mm(COLUMNS[3]), Mm(y), &bold);
+            layer.use_text(
+                strip_diacritics(&format_amount(amount)),
+                10.0,
+                Mm(COLUMNS[5]),
+                Mm(y),
+                &font,
+            );
+            y -= LINE_HEIGHT;
+        }
+    }
+
+    Ok(doc.save_to_bytes()?)
+}
+
+#[server]
+pub async fn get_meal_prices(catering_id: Uuid) -> Result<Vec<MealPriceDto>, ServerFnError> {
+    use sqlx::postgres::PgPool;
+    use crate::services::auth::authorize;
+    use dto::user::Role;
+
+    let pool: PgPool = use_context().ok_or(ServerFnError::new("Failed to retrieve db pool"))?;
+    authorize(&pool, &[Role::Admin]).await?;
+
+    let prices = sqlx::query_as!(
+        MealPriceDto,
+        "SELECT catering_meal_prices.id, meals.id AS meal_id, meals.name AS meal, price, since, until FROM catering_meal_prices
+        INNER JOIN meals ON meals.id = catering_meal_prices.meal_id
+        INNER JOIN catering_meals ON catering_meals.catering_id = catering_meal_prices.catering_id AND catering_meals.meal_id = meals.id
+        WHERE catering_meal_prices.catering_id = $1
+        ORDER BY catering_meals.meal_order, since",
+        catering_id
+    )
+    .fetch_all(&pool)
+    .await?;
+
+    Ok(prices)
+}
+
+#[server]
+pub async fn add_meal_price(price: CreateMealPriceDto) -> Result<Uuid, ServerFnError> {
+    use sqlx::postgres::PgPool;
+    use crate::services::audit::{record, snapshot};
+    use crate::services::auth::authorize;
+    use dto::audit::{AuditAction, AuditEntity};
+    use dto::user::Role;
+
+    let pool: PgPool = use_context().ok_or(ServerFnError::new("Failed to retrieve db pool"))?;
+    let actor = authorize(&pool, &[Role::Admin]).await?;
+
+    if price.price < 0 {
+        return Err(ServerFnError::new("Price can't be negative"));
+    }
+    if price.until.is_some_and(|until| until < price.since) {
+        return Err(ServerFnError::new("Price must be valid for at least a day"));
+    }
+
+    let mut tr = pool.begin().await?;
+
+    let group_id = sqlx::query!(
+        "SELECT caterings.group_id FROM caterings
+        INNER JOIN catering_meals ON catering_meals.catering_id = caterings.id
+        WHERE caterings.id = $1 AND catering_meals.meal_id = $2",
+        price.catering_id,
+        price.meal_id
+    )
+    .fetch_optional(&mut *tr)
+    .await?
+    .ok_or(ServerFnError::new("Meal is not served by this catering"))?
+    .group_id;
+
+    let overlapping = sqlx::query!(
+        "SELECT id FROM catering_meal_prices WHERE catering_id = $1 AND meal_id = $2
+        AND ($4::date IS NULL OR since <= $4) AND (until IS NULL OR until >= $3)",
+        price.catering_id,
+        price.meal_id,
+        price.since,
+        price.until
+    )
+    .fetch_optional(&mut *tr)
+    .await?;
+
+    if overlapping.is_some() {
+        return Err(ServerFnError::new(
+            "Another price of this meal is valid in the same period",
+        ));
+    }
+
+    let before = snapshot(&mut tr, AuditEntity::Catering, group_id).await?;
+
+    let id = sqlx::query!(
+        "INSERT INTO catering_meal_prices (catering_id, meal_id, price, since, until) VALUES ($1,$2,$3,$4,$5) RETURNING id",
+        price.catering_id,
+        price.meal_id,
+        price.price,
+        price.since,
+        price.until
+    )
+    .fetch_one(&mut *tr)
+    .await?
+    .id;
+
+    let after = snapshot(&mut tr, AuditEntity::Catering, group_id).await?;
+    record(
+        &mut tr,
+        &actor,
+        AuditAction::Update,
+        AuditEntity::Catering,
+        group_id,
+        before,
+        after,
+    )
+    .await?;
+
+    tr.commit().await?;
+    Ok(id)
+}
+
+#[server]
+pub async fn remove_meal_price(id: Uuid) -> Result<(), ServerFnError> {
+    use sqlx::postgres::PgPool;
+    use crate::services::audit::{record, snapshot};
+    use crate::services::auth::authorize;
+    use dto::audit::{AuditAction, AuditEntity};
+    use dto::user::Role;
+
+    let pool: PgPool = use_context().ok_or(ServerFnError::new("Failed to retrieve db pool"))?;
+    let actor = authorize(&pool, &[Role::Admin]).await?;
+
+    let mut tr = pool.begin().await?;
+
+    let group_id = sqlx::query!(
+        "SELECT caterings.group_id FROM catering_meal_prices
+        INNER JOIN caterings ON caterings.id = catering_meal_prices.catering_id
+        WHERE catering_meal_prices.id = $1",
+        id
+    )
+    .fetch_optional(&mut *tr)
+    .await?
+    .ok_or(ServerFnError::new("Price not found"))?
+    .group_id;
+
+    let before = snapshot(&mut tr, AuditEntity::Catering, group_id).await?;
+    sqlx::query!("DELETE FROM catering_meal_prices WHERE id = $1", id)
+        .execute(&mut *tr)
+        .await?;
+    let after = snapshot(&mut tr, AuditEntity::Catering, group_id).await?;
+    record(
+        &mut tr,
+        &actor,
+        AuditAction::Update,
+        AuditEntity::Catering,
+        group_id,
+        before,
+        after,
+    )
+    .await?;
+
+    tr.commit().await?;
+    Ok(())
+}
+
+#[server]
+pub async fn get_statements(
+    year: i32,
+    month: u32,
+) -> Result<Vec<GuardianStatementDto>, ServerFnError> {
+    use sqlx::postgres::PgPool;
+    use crate::services::auth::authorize;
+    use dto::user::Role;
+
+    let pool: PgPool = use_context().ok_or(ServerFnError::new("Failed to retrieve db pool"))?;
+    authorize(&pool, &[Role::Admin]).await?;
+
+    let (start, end) = month_range(year, month)?;
+    let mut conn = pool.acquire().await?;
+    load_statements(&mut conn, start, end, None).await
+}
+
+#[server]
+pub async fn get_statements_csv(year: i32, month: u32) -> Result<String, ServerFnError> {
+    use sqlx::postgres::PgPool;
+    use crate::services::auth::authorize;
+    use csv::WriterBuilder;
+    use dto::billing::{format_amount, StatementCsvRow};
+    use dto::user::Role;
+
+    let pool: PgPool = use_context().ok_or(ServerFnError::new("Failed to retrieve db pool"))?;
+    authorize(&pool, &[Role::Admin]).await?;
+
+    let (start, end) = month_range(year, month)?;
+    let mut conn = pool.acquire().await?;
+    let statements = load_statements(&mut conn, start, end, None).await?;
+
+    let mut wrtr = WriterBuilder::new().from_writer(vec![]);
+
+    for statement in statements {
+        for line in &statement.lines {
+            wrtr.serialize(StatementCsvRow {
+                guardian: statement.guardian.clone(),
+                phone: statement.phone.clone(),
+                student: line.student.clone(),
+                meal: line.meal.clone(),
+                unit_price: line.unit_price.map(|price| format_amount(price as i64)),
+                ordered: line.ordered,
+                cancelled: line.cancelled,
+                charged: format_amount(line.charged),
+                refunded: format_amount(line.refunded),
+                due: format_amount(line.charged - line.refunded),
+                other_guardians: line.other_guardians.join(", "),
+            })?;
+        }
+    }
+
+    wrtr.flush()?;
+
+    Ok(String::from_utf8(wrtr.into_inner()?)?)
+}
+
+/// Renders statements of the month as pdf, one guardian per page, limited to `guardian` if given
+#[server]
+pub async fn get_statements_pdf(
+    year: i32,
+    month: u32,
+    guardian: Option<Uuid>,
+) -> Result<Vec<u8>, ServerFnError> {
+    use sqlx::postgres::PgPool;
+    use crate::services::auth::authorize;
+    use dto::user::Role;
+
+    let pool: PgPool = use_context().ok_or(ServerFnError::new("Failed to retrieve db pool"))?;
+    authorize(&pool, &[Role::Admin]).await?;
+
+    let (start, end) = month_range(year, month)?;
+    let mut conn = pool.acquire().await?;
+    let statements = load_statements(&mut conn, start, end, guardian).await?;
+
+    if statements.is_empty() {
+        return Err(ServerFnError::new("No meals were ordered in this month"));
+    }
+
+    render_statements_pdf(&statements)
+}
+
+/// Queues a text with the amount due for the month to every guardian when `guardians` is `None`,
+/// only to the listed ones otherwise (nothing for an empty slice).
+/// Guardians without a phone or with nothing to pay are skipped, returns the number of messages queued.
+/// Messages queued without an `actor` are recorded as sent by the server
+#[cfg(feature = "ssr")]
+pub async fn queue_statement_notices(
+    conn: &mut sqlx::PgConnection,
+    actor: Option<&dto::user::UserDto>,
+    year: i32,
+    month: u32,
+    guardians: Option<&[Uuid]>,
+) -> Result<u32, ServerFnError> {
+    use crate::services::audit::{record, record_system, snapshot};
+    use dto::audit::{AuditAction, AuditEntity};
+    use dto::billing::format_amount;
+
+    let (start, end) = month_range(year, month)?;
+    let account = std::env::var("BILLING_ACCOUNT").ok();
+
+    let statements = load_statements(&mut *conn, start, end, None).await?;
+
+    let mut sent = 0;
+    for statement in statements.into_iter().filter(|statement| {
+        guardians.is_none_or(|guardians| guardians.contains(&statement.guardian_id))
+            && statement.due > 0
+    }) {
+        let Some(phone) = statement.phone else {
+            continue;
+        };
+
+        let mut content = format!(
+            "Należność za posiłki za {:02}.{}: {}.",
+            month,
+            year,
+            format_amount(statement.due)
+        );
+        if let Some(account) = &account {
+            content.push_str(&format!(" Nr konta: {}", account));
+        }
+
+        let id = sqlx::query!(
+            "INSERT INTO messages (phone, content, outgoing) VALUES ($1,$2,true) RETURNING id",
+            phone,
+            content
+        )
+        .fetch_one(&mut *conn)
+        .await?
+        .id;
+
+        let after = snapshot(&mut *conn, AuditEntity::Message, id).await?;
+        match actor {
+            Some(actor) => {
+                record(
+                    &mut *conn,
+                    actor,
+                    AuditAction::Send,
+                    AuditEntity::Message,
+                    id,
+                    None,
+                    after,
+                )
+                .await?
+            }
+            None => {
+                record_system(&mut *conn, AuditAction::Send, AuditEntity::Message, id, None, after)
+                    .await?
+            }
+        }
+        sent += 1;
+    }
+
+    Ok(sent)
+}
+
+/// Texts guardians the amount due for the month. Guardians without a phone or with nothing to pay are skipped,
+/// returns the number of messages queued
+#[server]
+pub async fn notify_statements(
+    year: i32,
+    month: u32,
+    guardians: Vec<Uuid>,
+) -> Result<u32, ServerFnError> {
+    use sqlx::postgres::PgPool;
+    use crate::services::auth::authorize;
+    use dto::user::Role;
+
+    let pool: PgPool = use_context().ok_or(ServerFnError::new("Failed to retrieve db pool"))?;
+    let actor = authorize(&pool, &[Role::Admin]).await?;
+
+    let mut tr = pool.begin().await?;
+    let sent = queue_statement_notices(&mut tr, Some(&actor), year, month, Some(&guardians)).await?;
+    tr.commit().await?;
+    Ok(sent)
+}
@@ -0,0 +1,229 @@
+use dto::broadcast::{
+    BroadcastDetailsDto, BroadcastDto, BroadcastRecipientDto, CreateBroadcastDto,
+};
+use leptos::prelude::*;
+use uuid::Uuid;
+
+/// Number of broadcasts listed on the broadcast page
+#[cfg(feature = "ssr")]
+const BROADCAST_HISTORY_LENGTH: i64 = 50;
+
+/// Guardians of students in the group or any of its sub groups, each listed once
+#[cfg(feature = "ssr")]
+pub async fn load_recipients(
+    conn: &mut sqlx::PgConnection,
+    group_id: Uuid,
+) -> Result<Vec<BroadcastRecipientDto>, ServerFnError> {
+    let recipients = sqlx::query!(
+        r#"SELECT guardians.id, guardians.fullname, guardians.phone,
+        array_agg(students.name || ' ' || students.surname ORDER BY students.surname, students.name) AS "students!"
+        FROM group_relations
+        INNER JOIN groups ON groups.id = group_relations.parent AND groups.removed = false
+        INNER JOIN students ON students.id = group_relations.child AND students.removed = false
+        INNER JOIN student_guardians ON student_guardians.student_id = students.id
+        INNER JOIN guardians ON guardians.id = student_guardians.guardian_id AND guardians.removed = false
+        WHERE group_relations.parent = $1
+        GROUP BY guardians.id
+        ORDER BY guardians.fullname"#,
+        group_id
+    )
+    .fetch_all(&mut *conn)
+    .await?
+    .into_iter()
+    .map(|row| BroadcastRecipientDto {
+        guardian_id: row.id,
+        fullname: row.fullname,
+        phone: row.phone,
+        students: row.students,
+    })
+    .collect();
+
+    Ok(recipients)
+}
+
+#[server]
+pub async fn preview_broadcast(
+    group_id: Uuid,
+) -> Result<Vec<BroadcastRecipientDto>, ServerFnError> {
+    use sqlx::postgres::PgPool;
+    use crate::services::auth::authorize;
+    use dto::user::Role;
+
+    let pool: PgPool = use_context().ok_or(ServerFnError::new("Failed to retrieve db pool"))?;
+    authorize(&pool, &[Role::Admin]).await?;
+
+    let mut conn = pool.acquire().await?;
+    load_recipients(&mut conn, group_id).await
+}
+
+/// Queues the message for every guardian of the group with a phone, returns the broadcast id
+#[server]
+pub async fn send_broadcast(broadcast: CreateBroadcastDto) -> Result<Uuid, ServerFnError> {
+    use sqlx::postgres::PgPool;
+    use crate::services::audit::{record, snapshot};
+    use crate::services::auth::authorize;
+    use crate::services::template::render_for_recipient;
+    use dto::audit::{AuditAction, AuditEntity};
+    use dto::user::Role;
+
+    let pool: PgPool = use_context().ok_or(ServerFnError::new("Failed to retrieve db pool"))?;
+    let actor = authorize(&pool, &[Role::Admin]).await?;
+
+    let content = broadcast.content.trim();
+    if content.is_empty() {
+        return Err(ServerFnError::new("Message must not be empty"));
+    }
+
+    let mut tr = pool.begin().await?;
+
+    let recipients = load_recipients(&mut tr, broadcast.group_id).await?;
+    let recipients = recipients
+        .into_iter()
+        .filter(|recipient| recipient.phone.is_some())
+        .collect::<Vec<_>>();
+    if recipients.is_empty() {
+        return Err(ServerFnError::new("No guardian of the group has a phone"));
+    }
+
+    let id = sqlx::query!(
+        "INSERT INTO broadcasts (user_id, group_id, content) VALUES ($1,$2,$3) RETURNING id",
+        actor.id,
+        broadcast.group_id,
+        content
+    )
+    .fetch_one(&mut *tr)
+    .await?
+    .id;
+
+    // The broadcast keeps the template, each recipient gets it with their own names filled in
+    for recipient in recipients {
+        let message = render_for_recipient(content, &recipient.fullname, &recipient.students)?;
+        sqlx::query!(
+            "WITH message AS (INSERT INTO messages (phone, content, outgoing) VALUES ($3,$4,true) RETURNING id)
+            INSERT INTO broadcast_recipients (broadcast_id, guardian_id, message_id) SELECT $1, $2, id FROM message",
+            id,
+            recipient.guardian_id,
+            recipient.phone,
+            message
+        )
+        .execute(&mut *tr)
+        .await?;
+    }
+
+    let after = snapshot(&mut tr, AuditEntity::Broadcast, id).await?;
+    record(
+        &mut tr,
+        &actor,
+        AuditAction::Send,
+        AuditEntity::Broadcast,
+        id,
+        None,
+        after,
+    )
+    .await?;
+
+    tr.commit().await?;
+    Ok(id)
+}
+
+#[cfg(feature = "ssr")]
+async fn load_broadcasts(
+    pool: &sqlx::PgPool,
+    id: Option<Uuid>,
+) -> Result<Vec<BroadcastDto>, ServerFnError> {
+    let broadcasts = sqlx::query!(
+        r#"SELECT broadcasts.id, broadcasts.created, users.login, COALESCE(groups.name, 'Usunięta grupa') AS "name!", broadcasts.content,
+        COUNT(messages.id) AS "recipients!",
+        COUNT(messages.id) FILTER (WHERE messages.status IN ('queued', 'sending')) AS "pending!",
+        COUNT(messages.id) FILTER (WHERE messages.status = 'sent') AS "sent!",
+        COUNT(messages.id) FILTER (WHERE messages.status = 'delivered') AS "delivered!",
+        COUNT(messages.id) FILTER (WHERE messages.status = 'failed') AS "failed!"
+        FROM broadcasts
+        INNER JOIN users ON users.id = broadcasts.user_id
+        LEFT JOIN groups ON groups.id = broadcasts.group_id
+        LEFT JOIN broadcast_recipients ON broadcast_recipients.broadcast_id = broadcasts.id
+        LEFT JOIN messages ON messages.id = broadcast_recipients.message_id
+        WHERE $1::uuid IS NULL OR broadcasts.id = $1
+        GROUP BY broadcasts.id, users.login, groups.name
+        ORDER BY broadcasts.created DESC
+        LIMIT $2"#,
+        id,
+        BROADCAST_HISTORY_LENGTH
+    )
+    .fetch_all(pool)
+    .await?
+    .into_iter()
+    .map(|row| BroadcastDto {
+        id: row.id,
+        created: row.created,
+        author: row.login,
+        group: row.name,
+        content: row.content,
+        recipients: row.recipients as u32,
+        pending: row.pending as u32,
+        sent: row.sent as u32,
+        delivered: row.delivered as u32,
+        failed: row.failed as u32,
+    })
+    .collect();
+
+    Ok(broadcasts)
+}
+
+/// Latest broadcasts, newest first
+#[server]
+pub async fn get_broadcasts() -> Result<Vec<BroadcastDto>, ServerFnError> {
+    use sqlx::postgres::PgPool;
+    use crate::services::auth::authorize;
+    use dto::user::Role;
+
+    let pool: PgPool = use_context().ok_or(ServerFnError::new("Failed to retrieve db pool"))?;
+    authorize(&pool, &[Role::Admin, Role::Teacher]).await?;
+
+    load_broadcasts(&pool, None).await
+}
+
+#[server]
+pub async fn get_broadcast(id: Uuid) -> Result<BroadcastDetailsDto, ServerFnError> {
+    use sqlx::postgres::PgPool;
+    use crate::services::auth::authorize;
+    use dto::broadcast::BroadcastDeliveryDto;
+    use dto::messages::DeliveryStatus;
+    use dto::user::Role;
+
+    let pool: PgPool = use_context().ok_or(ServerFnError::new("Failed to retrieve db pool"))?;
+    authorize(&pool, &[Role::Admin, Role::Teacher]).await?;
+
+    let broadcast = load_broadcasts(&pool, Some(id))
+        .await?
+        .pop()
+        .ok_or(ServerFnError::new("Broadcast not found"))?;
+
+    let deliveries = sqlx::query!(
+        "SELECT guardians.id, guardians.fullname, messages.phone, messages.status, messages.attempts, messages.last_error, messages.sent
+        FROM broadcast_recipients
+        INNER JOIN guardians ON guardians.id = broadcast_recipients.guardian_id
+        INNER JOIN messages ON messages.id = broadcast_recipients.message_id
+        WHERE broadcast_recipients.broadcast_id = $1
+        ORDER BY guardians.fullname",
+        id
+    )
+    .fetch_all(&pool)
+    .await?
+    .into_iter()
+    .map(|row| BroadcastDeliveryDto {
+        guardian_id: row.id,
+        fullname: row.fullname,
+        phone: row.phone,
+        status: row.status.parse().unwrap_or(DeliveryStatus::Queued),
+        attempts: row.attempts,
+        last_error: row.last_error,
+        sent: row.sent,
+    })
+    .collect();
+
+    Ok(BroadcastDetailsDto {
+        broadcast,
+        deliveries,
+    })
+}
@@ -1,24 +1,71 @@
-use dto::catering::{CateringDto, CreateCateringDto};
+use dto::catering::{
+    CateringDetailsDto, CateringDto, CateringUpdateImpactDto, CreateCateringDto, MealDto,
+    UpdateCateringDto,
+};
 use leptos::html::P;
 use leptos::logging::log;
 use leptos::prelude::*;
 use uuid::Uuid;
 
+/// Days of week starting from monday stored as bits of a smallint, same as `caterings.dow`
+pub fn dow_mask(dow: &[bool]) -> i16 {
+    dow.iter()
+        .enumerate()
+        .map(|(i, d)| (*d as i16) * 2_i16.pow(i as u32))
+        .sum()
+}
+
+pub fn dow_days(mask: i16) -> Vec<bool> {
+    (0..7).map(|i| (mask >> i) & 1 == 1).collect()
+}
+
+/// Meals of a catering as columns of `catering_meals`, names are normalized and duplicates dropped.
+/// Meals have to close no later than the catering itself, at `grace_period` of their day
+#[cfg(feature = "ssr")]
+fn catering_meals(
+    meals: &[dto::catering::CateringMealSettingsDto],
+    grace_period: chrono::NaiveTime,
+) -> Result<(Vec<String>, Vec<i16>, Vec<chrono::NaiveTime>), ServerFnError> {
+    let mut names: Vec<String> = vec![];
+    let mut cutoff_days = vec![];
+    let mut cutoff_times = vec![];
+    for meal in meals {
+        let name = String::from(meal.name.to_lowercase().trim());
+        if name.is_empty() || names.contains(&name) {
+            continue;
+        }
+        if meal.cutoff.days_before == 0 && meal.cutoff.time > grace_period {
+            return Err(ServerFnError::new(format!(
+                "Cut-off of {} is later than the cut-off of the catering",
+                name
+            )));
+        }
+        cutoff_days.push(
+            i16::try_from(meal.cutoff.days_before)
+                .map_err(|_| ServerFnError::new(format!("Invalid cut-off of {}", name)))?,
+        );
+        cutoff_times.push(meal.cutoff.time);
+        names.push(name);
+    }
+    Ok((names, cutoff_days, cutoff_times))
+}
+
 #[server]
 pub async fn create_catering(catering_dto: CreateCateringDto) -> Result<Uuid, ServerFnError> {
     use chrono::TimeDelta;
     use leptos_axum::extract;
     use sqlx::postgres::types::PgInterval;
     use sqlx::postgres::PgPool;
+    use crate::services::audit::{record, snapshot};
+    use crate::services::auth::authorize;
+    use crate::services::closure::apply_closures;
+    use dto::audit::{AuditAction, AuditEntity};
+    use dto::user::Role;
 
-    let dow: i16 = catering_dto
-        .dow
-        .iter()
-        .enumerate()
-        .map(|(i, d)| (*d as i16) * 2_i16.pow(i as u32))
-        .sum();
+    let dow = dow_mask(&catering_dto.dow);
 
     let pool: PgPool = use_context().ok_or(ServerFnError::new("Failed to retrieve db pool"))?;
+    let actor = authorize(&pool, &[Role::Admin]).await?;
 
     let mut tr = pool.begin().await?;
 
@@ -45,11 +92,7 @@ pub async fn create_catering(catering_dto: CreateCateringDto) -> Result<Uuid, Se
         ));
     }
 
-    let meals = catering_dto
-        .meals
-        .into_iter()
-        .map(|meal| String::from(meal.to_lowercase().trim()))
-        .collect::<Vec<_>>();
+    let (meals, cutoff_days, cutoff_times) = catering_meals(&catering_dto.meals, grace_period)?;
 
     sqlx::query!(
         "INSERT INTO meals (name) SELECT * FROM UNNEST($1::text[]) ON CONFLICT DO NOTHING",
@@ -89,7 +132,7 @@ pub async fn create_catering(catering_dto: CreateCateringDto) -> Result<Uuid, Se
         .enumerate()
         .map(|(i, _)| i as i32)
         .collect::<Vec<_>>();
-    sqlx::query!("INSERT INTO catering_meals (catering_id, meal_id, meal_order) SELECT $1, meals.id, m_order FROM UNNEST($2::text[],$3::integer[]) as meal_names(name,m_order) INNER JOIN meals ON meals.name = meal_names.name", catering_id, &meals, &meal_order)
+    sqlx::query!("INSERT INTO catering_meals (catering_id, meal_id, meal_order, cutoff_days, cutoff_time) SELECT $1, meals.id, m_order, c_days, c_time FROM UNNEST($2::text[],$3::integer[],$4::smallint[],$5::time[]) as meal_names(name,m_order,c_days,c_time) INNER JOIN meals ON meals.name = meal_names.name", catering_id, &meals, &meal_order, &cutoff_days, &cutoff_times)
         .execute(&mut *tr).await?;
 
     sqlx::query!(
@@ -99,6 +142,20 @@ pub async fn create_catering(catering_dto: CreateCateringDto) -> Result<Uuid, Se
     .execute(&mut *tr)
     .await?;
 
+    apply_closures(&mut tr, Some(catering_id), None).await?;
+
+    let after = snapshot(&mut tr, AuditEntity::Catering, group_id).await?;
+    record(
+        &mut tr,
+        &actor,
+        AuditAction::Create,
+        AuditEntity::Catering,
+        group_id,
+        None,
+        after,
+    )
+    .await?;
+
     tr.commit().await?;
 
     Ok(catering_id)
@@ -107,9 +164,336 @@ pub async fn create_catering(catering_dto: CreateCateringDto) -> Result<Uuid, Se
 #[server]
 pub async fn get_caterings() -> Result<Vec<CateringDto>, ServerFnError> {
     use sqlx::postgres::PgPool;
+    use crate::services::auth::authorize;
+    use dto::user::Role;
 
     let pool: PgPool = use_context().ok_or(ServerFnError::new("Failed to retrieve db pool"))?;
+    authorize(&pool, Role::ALL).await?;
 
     let caterings = sqlx::query!("SELECT caterings.id, groups.name FROM caterings INNER JOIN groups ON groups.id = caterings.group_id").fetch_all(&pool).await?.into_iter().map(|row| CateringDto{id: row.id, name: row.name}).collect();
     Ok(caterings)
 }
+
+#[server]
+pub async fn get_catering_meals(catering_id: Uuid) -> Result<Vec<MealDto>, ServerFnError> {
+    use sqlx::postgres::PgPool;
+    use crate::services::auth::authorize;
+    use dto::user::Role;
+
+    let pool: PgPool = use_context().ok_or(ServerFnError::new("Failed to retrieve db pool"))?;
+    authorize(&pool, Role::ALL).await?;
+
+    let meals = sqlx::query_as!(
+        MealDto,
+        "SELECT meals.id, meals.name FROM catering_meals
+        INNER JOIN meals ON meals.id = catering_meals.meal_id
+        WHERE catering_meals.catering_id = $1 ORDER BY catering_meals.meal_order",
+        catering_id
+    )
+    .fetch_all(&pool)
+    .await?;
+    Ok(meals)
+}
+
+#[server]
+pub async fn get_catering_details(group_id: Uuid) -> Result<CateringDetailsDto, ServerFnError> {
+    use sqlx::postgres::PgPool;
+    use dto::attendance::CateringMealDto;
+    use dto::catering::MealCutoffDto;
+    use crate::services::auth::authorize;
+    use dto::user::Role;
+
+    let pool: PgPool = use_context().ok_or(ServerFnError::new("Failed to retrieve db pool"))?;
+    authorize(&pool, &[Role::Admin]).await?;
+
+    let catering = sqlx::query!(
+        "SELECT caterings.id, groups.name, caterings.since, caterings.until, caterings.grace_period, caterings.dow
+        FROM caterings INNER JOIN groups ON groups.id = caterings.group_id
+        WHERE caterings.group_id = $1",
+        group_id
+    )
+    .fetch_optional(&pool)
+    .await?
+    .ok_or(ServerFnError::new("Catering not found"))?;
+
+    let meals = sqlx::query!(
+        "SELECT meals.id, meals.name, catering_meals.cutoff_days, catering_meals.cutoff_time FROM catering_meals
+        INNER JOIN meals ON meals.id = catering_meals.meal_id
+        WHERE catering_meals.catering_id = $1 ORDER BY catering_meals.meal_order",
+        catering.id
+    )
+    .fetch_all(&pool)
+    .await?
+    .into_iter()
+    .map(|row| CateringMealDto {
+        id: row.id,
+        name: row.name,
+        cutoff: MealCutoffDto {
+            days_before: row.cutoff_days.max(0) as u32,
+            time: row.cutoff_time,
+        },
+    })
+    .collect();
+
+    Ok(CateringDetailsDto {
+        id: catering.id,
+        group_id,
+        name: catering.name,
+        since: catering.since,
+        until: catering.until,
+        grace_period: catering.grace_period,
+        meals,
+        dow: dow_days(catering.dow),
+    })
+}
+
+/// Changes the catering and brings attendance of its students in line with the new schedule.
+/// Only days that can still be cancelled are touched, each meal from the first day its own cut-off
+/// leaves open, earlier attendance stays as it was recorded.
+/// Slots dropped from the schedule lose their attendance, slots added to it are ordered for every student.
+#[cfg(feature = "ssr")]
+pub async fn apply_catering_update(
+    conn: &mut sqlx::PgConnection,
+    catering_dto: &UpdateCateringDto,
+) -> Result<CateringUpdateImpactDto, ServerFnError> {
+    use attendance_rules::{first_open_day, Actor, Catering, Cutoff, Meal};
+    use chrono::{Local, NaiveDate, TimeDelta};
+    use crate::services::closure::apply_closures;
+
+    if (catering_dto.until - catering_dto.since) < TimeDelta::days(1) {
+        return Err(ServerFnError::new("Catering must last at least a day"));
+    }
+
+    if catering_dto.meals.is_empty() {
+        return Err(ServerFnError::new("Catering must have at least one meal"));
+    }
+
+    if !catering_dto.dow.iter().any(|d| *d) {
+        return Err(ServerFnError::new(
+            "Catering needs to specify at least one day of week",
+        ));
+    }
+
+    let (meals, cutoff_days, cutoff_times) =
+        catering_meals(&catering_dto.meals, catering_dto.grace_period)?;
+
+    let previous = sqlx::query!(
+        "SELECT grace_period FROM caterings WHERE id = $1 FOR UPDATE",
+        catering_dto.id
+    )
+    .fetch_optional(&mut *conn)
+    .await?
+    .ok_or(ServerFnError::new("Catering not found"))?;
+
+    sqlx::query!(
+        "INSERT INTO meals (name) SELECT * FROM UNNEST($1::text[]) ON CONFLICT DO NOTHING",
+        &meals
+    )
+    .execute(&mut *conn)
+    .await?;
+
+    let now = Local::now().naive_local();
+    // A day is history once the kitchen closed it at the grace period, meals may close even earlier
+    let grace_open_day = first_open_day(previous.grace_period, now)
+        .ok_or(ServerFnError::new("Invalid date"))?;
+
+    // Meals served so far keep the cut-off their days were locked with, added meals get the new one
+    let mut cutoffs = vec![];
+    for row in sqlx::query!(
+        "SELECT catering_meals.meal_id AS \"meal_id!\", catering_meals.cutoff_days AS \"cutoff_days!\", catering_meals.cutoff_time AS \"cutoff_time!\" FROM catering_meals
+        WHERE catering_meals.catering_id = $1
+        UNION ALL
+        SELECT meals.id, meal_cutoffs.c_days, meal_cutoffs.c_time FROM UNNEST($2::text[],$3::smallint[],$4::time[]) AS meal_cutoffs(name,c_days,c_time)
+        INNER JOIN meals ON meals.name = meal_cutoffs.name
+        WHERE meals.id NOT IN (SELECT meal_id FROM catering_meals WHERE catering_id = $1)",
+        catering_dto.id,
+        &meals,
+        &cutoff_days,
+        &cutoff_times
+    )
+    .fetch_all(&mut *conn)
+    .await?
+    {
+        cutoffs.push(Meal {
+            id: row.meal_id,
+            cutoff: Cutoff::new(row.cutoff_days.max(0) as u32, row.cutoff_time),
+        });
+    }
+    // Unbounded, the catering may be moved to start earlier than it did
+    let schedule = Catering::new(NaiveDate::MIN, NaiveDate::MAX, cutoffs);
+    let (open_meals, open_days): (Vec<Uuid>, Vec<NaiveDate>) = schedule
+        .meals
+        .iter()
+        .map(|meal| {
+            let day = schedule
+                .first_open_day_of(Actor::Guardian, now, meal.id)
+                .map_or(grace_open_day, |day| day.max(grace_open_day));
+            (meal.id, day)
+        })
+        .unzip();
+    let effective = open_days.iter().min().copied().unwrap_or(grace_open_day);
+
+    sqlx::query!(
+        "UPDATE caterings SET grace_period = $2, dow = $3, since = $4, until = $5 WHERE id = $1",
+        catering_dto.id,
+        catering_dto.grace_period,
+        dow_mask(&catering_dto.dow),
+        catering_dto.since,
+        catering_dto.until
+    )
+    .execute(&mut *conn)
+    .await?;
+
+    sqlx::query!(
+        "DELETE FROM catering_meals WHERE catering_id = $1",
+        catering_dto.id
+    )
+    .execute(&mut *conn)
+    .await?;
+
+    let meal_order = (0..meals.len() as i32).collect::<Vec<_>>();
+    sqlx::query!("INSERT INTO catering_meals (catering_id, meal_id, meal_order, cutoff_days, cutoff_time) SELECT $1, meals.id, m_order, c_days, c_time FROM UNNEST($2::text[],$3::integer[],$4::smallint[],$5::time[]) as meal_names(name,m_order,c_days,c_time) INNER JOIN meals ON meals.name = meal_names.name", catering_dto.id, &meals, &meal_order, &cutoff_days, &cutoff_times)
+        .execute(&mut *conn).await?;
+
+    let dropped = sqlx::query!(
+        r#"WITH dropped AS (
+            SELECT attendance.target, attendance.day, attendance.meal_id, attendance.value FROM caterings
+            INNER JOIN group_relations ON group_relations.parent = caterings.group_id
+            INNER JOIN attendance ON attendance.target = group_relations.child
+            INNER JOIN UNNEST($2::uuid[], $3::date[]) AS open_meals(meal_id, day) ON open_meals.meal_id = attendance.meal_id
+            WHERE caterings.id = $1 AND attendance.day >= open_meals.day
+            AND attendance.cause_id NOT IN (SELECT override_id FROM closures)
+            AND NOT (
+                attendance.day BETWEEN caterings.since AND caterings.until
+                AND ((caterings.dow >> (EXTRACT(DOW FROM attendance.day)::smallint + 6) % 7) & 1) = 1
+                AND attendance.meal_id IN (SELECT meal_id FROM catering_meals WHERE catering_id = caterings.id)
+            )
+        )
+        SELECT
+            (SELECT COUNT(*) FROM (SELECT DISTINCT target, day, meal_id FROM dropped) AS slots) AS "removed!",
+            (SELECT COUNT(*) FROM (SELECT DISTINCT target, day, meal_id FROM dropped WHERE value = false) AS slots) AS "cancellations!""#,
+        catering_dto.id,
+        &open_meals,
+        &open_days
+    )
+    .fetch_one(&mut *conn)
+    .await?;
+
+    sqlx::query!(
+        "DELETE FROM attendance USING caterings, group_relations, UNNEST($2::uuid[], $3::date[]) AS open_meals(meal_id, day)
+        WHERE caterings.id = $1 AND group_relations.parent = caterings.group_id
+        AND attendance.target = group_relations.child
+        AND open_meals.meal_id = attendance.meal_id AND attendance.day >= open_meals.day
+        AND NOT (
+            attendance.day BETWEEN caterings.since AND caterings.until
+            AND ((caterings.dow >> (EXTRACT(DOW FROM attendance.day)::smallint + 6) % 7) & 1) = 1
+            AND attendance.meal_id IN (SELECT meal_id FROM catering_meals WHERE catering_id = caterings.id)
+        )",
+        catering_dto.id,
+        &open_meals,
+        &open_days
+    )
+    .execute(&mut *conn)
+    .await?;
+
+    let added = sqlx::query!(
+        "INSERT INTO attendance (cause_id, target, day, meal_id, value)
+        SELECT $4, students.id, days.day, catering_meals.meal_id, true FROM caterings
+        INNER JOIN group_relations ON group_relations.parent = caterings.group_id
+        INNER JOIN students ON students.id = group_relations.child AND students.removed = false
+        INNER JOIN catering_meals ON catering_meals.catering_id = caterings.id
+        INNER JOIN UNNEST($2::uuid[], $3::date[]) AS open_meals(meal_id, day) ON open_meals.meal_id = catering_meals.meal_id
+        INNER JOIN generate_series(GREATEST(caterings.since, open_meals.day, students.joined_on), LEAST(caterings.until, students.left_on), '1 day') as days(day) ON ((caterings.dow >> (EXTRACT(DOW FROM days.day)::smallint + 6) % 7 )&1) = 1
+        WHERE caterings.id = $1 AND NOT EXISTS (
+            SELECT 1 FROM attendance WHERE attendance.target = students.id
+            AND attendance.day = days.day AND attendance.meal_id = catering_meals.meal_id
+        )",
+        catering_dto.id,
+        &open_meals,
+        &open_days,
+        Uuid::new_v4()
+    )
+    .execute(&mut *conn)
+    .await?
+    .rows_affected();
+
+    apply_closures(&mut *conn, Some(catering_dto.id), None).await?;
+
+    Ok(CateringUpdateImpactDto {
+        effective,
+        added,
+        removed: dropped.removed as u64,
+        cancellations: dropped.cancellations as u64,
+    })
+}
+
+/// Shows what `update_catering` would do without saving anything
+#[server]
+pub async fn preview_catering_update(
+    catering_dto: UpdateCateringDto,
+) -> Result<CateringUpdateImpactDto, ServerFnError> {
+    use sqlx::postgres::PgPool;
+    use crate::services::auth::authorize;
+    use dto::user::Role;
+
+    let pool: PgPool = use_context().ok_or(ServerFnError::new("Failed to retrieve db pool"))?;
+    authorize(&pool, &[Role::Admin]).await?;
+
+    let mut tr = pool.begin().await?;
+    let impact = apply_catering_update(&mut tr, &catering_dto).await?;
+    tr.rollback().await?;
+
+    Ok(impact)
+}
+
+/// Refuses to drop recorded cancellations unless `confirmed`, the client previews the change first and asks the user
+#[server]
+pub async fn update_catering(
+    catering_dto: UpdateCateringDto,
+    confirmed: bool,
+) -> Result<CateringUpdateImpactDto, ServerFnError> {
+    use sqlx::postgres::PgPool;
+    use crate::services::audit::{record, snapshot};
+    use crate::services::auth::authorize;
+    use dto::audit::{AuditAction, AuditEntity};
+    use dto::user::Role;
+
+    let pool: PgPool = use_context().ok_or(ServerFnError::new("Failed to retrieve db pool"))?;
+    let actor = authorize(&pool, &[Role::Admin]).await?;
+
+    let mut tr = pool.begin().await?;
+
+    let group_id = sqlx::query_scalar!(
+        "SELECT group_id FROM caterings WHERE id = $1",
+        catering_dto.id
+    )
+    .fetch_optional(&mut *tr)
+    .await?
+    .ok_or(ServerFnError::new("Catering not found"))?;
+
+    let before = snapshot(&mut tr, AuditEntity::Catering, group_id).await?;
+    let impact = apply_catering_update(&mut tr, &catering_dto).await?;
+
+    if impact.cancellations > 0 && !confirmed {
+        return Err(ServerFnError::new(format!(
+            "Change would remove {} recorded cancellations",
+            impact.cancellations
+        )));
+    }
+
+    let after = snapshot(&mut tr, AuditEntity::Catering, group_id).await?;
+    record(
+        &mut tr,
+        &actor,
+        AuditAction::Update,
+        AuditEntity::Catering,
+        group_id,
+        before,
+        after,
+    )
+    .await?;
+
+    tr.commit().await?;
+
+    Ok(impact)
+}
@@ -0,0 +1,249 @@
+use dto::closure::{ClosureDto, CreateClosureDto};
+use leptos::prelude::*;
+use uuid::Uuid;
+
+#[cfg(feature = "ssr")]
+use chrono::NaiveDate;
+#[cfg(feature = "ssr")]
+use std::collections::BTreeMap;
+
+/// Closures block meals the same way as an override of the whole catering,
+/// every scheduled meal of a closed day gets an absent entry for the catering group.
+/// Called again whenever caterings change so closures cover newly scheduled meals too.
+#[cfg(feature = "ssr")]
+pub async fn apply_closures(
+    conn: &mut sqlx::PgConnection,
+    catering_id: Option<Uuid>,
+    closure_id: Option<Uuid>,
+) -> Result<u64, ServerFnError> {
+    let inserted = sqlx::query!(
+        "INSERT INTO attendance (cause_id, target, day, meal_id, value)
+        SELECT closures.override_id, caterings.group_id, days.day, catering_meals.meal_id, false FROM closures
+        INNER JOIN caterings ON closures.catering_id IS NULL OR closures.catering_id = caterings.id
+        INNER JOIN generate_series(GREATEST(closures.since, caterings.since), LEAST(closures.until, caterings.until), '1 day') AS days(day) ON ((caterings.dow >> (EXTRACT(DOW FROM day)::smallint + 6) % 7 )&1) = 1
+        INNER JOIN catering_meals ON catering_meals.catering_id = caterings.id
+        WHERE ($1::uuid IS NULL OR caterings.id = $1) AND ($2::uuid IS NULL OR closures.id = $2)
+        AND NOT EXISTS (
+            SELECT 1 FROM attendance WHERE attendance.cause_id = closures.override_id
+            AND attendance.target = caterings.group_id AND attendance.day = days.day
+            AND attendance.meal_id = catering_meals.meal_id
+        )",
+        catering_id,
+        closure_id
+    )
+    .execute(&mut *conn)
+    .await?
+    .rows_affected();
+
+    Ok(inserted)
+}
+
+/// Reasons of the days closed for the catering between `start` and `end` (exclusive)
+#[cfg(feature = "ssr")]
+pub async fn closed_days(
+    conn: &mut sqlx::PgConnection,
+    catering_id: Uuid,
+    start: NaiveDate,
+    end: NaiveDate,
+) -> Result<BTreeMap<NaiveDate, String>, ServerFnError> {
+    let rows = sqlx::query!(
+        r#"SELECT days.day::date AS "day!", closures.reason FROM closures
+        INNER JOIN generate_series(GREATEST(closures.since, $2::date), LEAST(closures.until, $3::date - 1), '1 day') AS days(day) ON true
+        WHERE closures.catering_id IS NULL OR closures.catering_id = $1
+        ORDER BY closures.created"#,
+        catering_id,
+        start,
+        end
+    )
+    .fetch_all(&mut *conn)
+    .await?;
+
+    let mut days = BTreeMap::new();
+    for row in rows {
+        days.entry(row.day).or_insert(row.reason);
+    }
+    Ok(days)
+}
+
+#[cfg(feature = "ssr")]
+pub async fn insert_closure(
+    conn: &mut sqlx::PgConnection,
+    actor: &dto::user::UserDto,
+    closure: &CreateClosureDto,
+) -> Result<Uuid, ServerFnError> {
+    use crate::services::audit::{record, snapshot};
+    use dto::audit::{AuditAction, AuditEntity};
+
+    let reason = closure.reason.trim();
+    if reason.is_empty() {
+        return Err(ServerFnError::new("Closure needs a reason"));
+    }
+
+    if closure.until < closure.since {
+        return Err(ServerFnError::new("Closure must end after it starts"));
+    }
+
+    let override_id = sqlx::query!(
+        "INSERT INTO attendance_override (note) VALUES ($1) RETURNING id",
+        reason
+    )
+    .fetch_one(&mut *conn)
+    .await?
+    .id;
+
+    let id = sqlx::query!(
+        "INSERT INTO closures (catering_id, since, until, reason, override_id) VALUES ($1, $2, $3, $4, $5) RETURNING id",
+        closure.catering_id,
+        closure.since,
+        closure.until,
+        reason,
+        override_id
+    )
+    .fetch_one(&mut *conn)
+    .await?
+    .id;
+
+    apply_closures(&mut *conn, None, Some(id)).await?;
+
+    let after = snapshot(&mut *conn, AuditEntity::Closure, id).await?;
+    record(
+        &mut *conn,
+        actor,
+        AuditAction::Create,
+        AuditEntity::Closure,
+        id,
+        None,
+        after,
+    )
+    .await?;
+
+    Ok(id)
+}
+
+#[server]
+pub async fn get_closures(year: i32) -> Result<Vec<ClosureDto>, ServerFnError> {
+    use sqlx::postgres::PgPool;
+    use crate::services::auth::authorize;
+    use dto::user::Role;
+
+    let pool: PgPool = use_context().ok_or(ServerFnError::new("Failed to retrieve db pool"))?;
+    authorize(&pool, Role::ALL).await?;
+
+    let closures = sqlx::query_as!(
+        ClosureDto,
+        r#"SELECT closures.id, closures.catering_id, groups.name AS "catering?", closures.since, closures.until, closures.reason FROM closures
+        LEFT JOIN caterings ON caterings.id = closures.catering_id
+        LEFT JOIN groups ON groups.id = caterings.group_id
+        WHERE EXTRACT(YEAR FROM closures.since) <= $1 AND EXTRACT(YEAR FROM closures.until) >= $1
+        ORDER BY closures.since, groups.name NULLS FIRST"#,
+        year as f64
+    )
+    .fetch_all(&pool)
+    .await?;
+
+    Ok(closures)
+}
+
+#[server]
+pub async fn add_closure(closure: CreateClosureDto) -> Result<Uuid, ServerFnError> {
+    use sqlx::postgres::PgPool;
+    use crate::services::auth::authorize;
+    use dto::user::Role;
+
+    let pool: PgPool = use_context().ok_or(ServerFnError::new("Failed to retrieve db pool"))?;
+    let actor = authorize(&pool, &[Role::Admin]).await?;
+
+    let mut tr = pool.begin().await?;
+    let id = insert_closure(&mut tr, &actor, &closure).await?;
+    tr.commit().await?;
+
+    Ok(id)
+}
+
+#[server]
+pub async fn remove_closure(id: Uuid) -> Result<(), ServerFnError> {
+    use sqlx::postgres::PgPool;
+    use crate::services::audit::{record, snapshot};
+    use crate::services::auth::authorize;
+    use dto::audit::{AuditAction, AuditEntity};
+    use dto::user::Role;
+
+    let pool: PgPool = use_context().ok_or(ServerFnError::new("Failed to retrieve db pool"))?;
+    let actor = authorize(&pool, &[Role::Admin]).await?;
+
+    let mut tr = pool.begin().await?;
+
+    let before = snapshot(&mut tr, AuditEntity::Closure, id).await?;
+
+    let override_id = sqlx::query_scalar!("DELETE FROM closures WHERE id = $1 RETURNING override_id", id)
+        .fetch_optional(&mut *tr)
+        .await?
+        .ok_or(ServerFnError::new("Closure not found"))?;
+
+    sqlx::query!("DELETE FROM attendance WHERE cause_id = $1", override_id)
+        .execute(&mut *tr)
+        .await?;
+
+    sqlx::query!("DELETE FROM attendance_override WHERE id = $1", override_id)
+        .execute(&mut *tr)
+        .await?;
+
+    record(
+        &mut tr,
+        &actor,
+        AuditAction::Delete,
+        AuditEntity::Closure,
+        id,
+        before,
+        None,
+    )
+    .await?;
+
+    tr.commit().await?;
+    Ok(())
+}
+
+/// Adds public holidays of the year that aren't closed already, returns how many were added
+#[server]
+pub async fn import_public_holidays(
+    year: i32,
+    catering_id: Option<Uuid>,
+) -> Result<u32, ServerFnError> {
+    use sqlx::postgres::PgPool;
+    use crate::services::auth::authorize;
+    use dto::closure::polish_holidays;
+    use dto::user::Role;
+
+    let pool: PgPool = use_context().ok_or(ServerFnError::new("Failed to retrieve db pool"))?;
+    let actor = authorize(&pool, &[Role::Admin]).await?;
+
+    let mut tr = pool.begin().await?;
+    let mut added = 0;
+
+    for (day, name) in polish_holidays(year) {
+        let closed = sqlx::query!(
+            "SELECT id FROM closures WHERE $1 BETWEEN since AND until
+            AND (catering_id IS NULL OR catering_id IS NOT DISTINCT FROM $2) LIMIT 1",
+            day,
+            catering_id
+        )
+        .fetch_optional(&mut *tr)
+        .await?;
+
+        if closed.is_some() {
+            continue;
+        }
+
+        let closure = CreateClosureDto {
+            catering_id,
+            since: day,
+            until: day,
+            reason: String::from(name),
+        };
+        insert_closure(&mut tr, &actor, &closure).await?;
+        added += 1;
+    }
+
+    tr.commit().await?;
+    Ok(added)
+}
@@ -10,51 +10,98 @@ use uuid::Uuid;
 #[server]
 pub async fn create_group(group: CreateGroupDto) -> Result<Uuid, ServerFnError> {
     use sqlx::postgres::PgPool;
+    use crate::services::audit::{record, snapshot};
+    use crate::services::auth::authorize;
+    use dto::audit::{AuditAction, AuditEntity};
+    use dto::user::Role;
     use uuid::Uuid;
 
     let pool: PgPool = use_context().ok_or(ServerFnError::new("Failed to retrieve db pool"))?;
+    let actor = authorize(&pool, &[Role::Admin]).await?;
     let mut tr = pool.begin().await?;
 
+    let id = insert_group(&mut tr, &group.name, group.parent).await?;
+
+    let after = snapshot(&mut tr, AuditEntity::Group, id).await?;
+    record(
+        &mut tr,
+        &actor,
+        AuditAction::Create,
+        AuditEntity::Group,
+        id,
+        None,
+        after,
+    )
+    .await?;
+
+    tr.commit().await?;
+    Ok(id)
+}
+
+/// Inserts group under `parent`, which must not contain students
+#[cfg(feature = "ssr")]
+pub async fn insert_group(
+    conn: &mut sqlx::PgConnection,
+    name: &str,
+    parent: Uuid,
+) -> Result<Uuid, ServerFnError> {
     let is_group = sqlx::query!(
         "SELECT groups.id FROM groups 
         WHERE groups.id = $1 AND NOT EXISTS (SELECT * FROM group_relations INNER JOIN students ON students.id = group_relations.child AND group_relations.parent = groups.id AND group_relations.level = 1)",
-        group.parent
+        parent
     )
-    .fetch_optional(&mut *tr)
+    .fetch_optional(&mut *conn)
     .await?;
 
     if is_group.is_none() {
         log!(
             "Group {} has students, so it cannot also have groups",
-            group.parent
+            parent
         );
         return Err(ServerFnError::new("Invalid group selected"));
     }
 
-    let name = String::from(group.name.trim());
+    let name = String::from(name.trim());
     let id: Uuid = sqlx::query!("INSERT INTO groups (name) VALUES ($1) RETURNING id", name)
-        .fetch_one(&mut *tr)
+        .fetch_one(&mut *conn)
         .await?
         .id;
 
-    sqlx::query!("INSERT INTO group_relations (child,parent,level) SELECT $1,parent,level + 1 FROM group_relations WHERE child=$2 UNION SELECT $1::uuid,$1::uuid,0", id, group.parent).execute(&mut *tr).await?;
-
-    log!("Created group {} with parent {}", id, group.parent);
+    sqlx::query!("INSERT INTO group_relations (child,parent,level) SELECT $1,parent,level + 1 FROM group_relations WHERE child=$2 UNION SELECT $1::uuid,$1::uuid,0", id, parent).execute(&mut *conn).await?;
 
-    tr.commit().await?;
+    log!("Created group {} with parent {}", id, parent);
     Ok(id)
 }
 
 #[server]
 pub async fn modify_group(dto: ModifyGroupDto) -> Result<(), ServerFnError> {
     use sqlx::PgPool;
+    use crate::services::audit::{record, snapshot};
+    use crate::services::auth::authorize;
+    use dto::audit::{AuditAction, AuditEntity};
+    use dto::user::Role;
 
     let pool: PgPool = use_context().ok_or(ServerFnError::new("Failed to retrieve db pool"))?;
+    let actor = authorize(&pool, &[Role::Admin]).await?;
     let mut tr = pool.begin().await?;
+
+    let before = snapshot(&mut tr, AuditEntity::Group, dto.id).await?;
     let rows = sqlx::query!("UPDATE groups set name= $2 WHERE id=$1", dto.id, dto.name)
         .execute(&mut *tr)
         .await?
         .rows_affected();
+    let after = snapshot(&mut tr, AuditEntity::Group, dto.id).await?;
+    record(
+        &mut tr,
+        &actor,
+        AuditAction::Update,
+        AuditEntity::Group,
+        dto.id,
+        before,
+        after,
+    )
+    .await?;
+
     tr.commit().await?;
 
     Ok(())
@@ -63,8 +110,11 @@ pub async fn modify_group(dto: ModifyGroupDto) -> Result<(), ServerFnError> {
 #[server]
 pub async fn get_groups() -> Result<Vec<GroupDto>, ServerFnError> {
     use sqlx::postgres::PgPool;
+    use crate::services::auth::authorize;
+    use dto::user::Role;
 
     let pool: PgPool = use_context().ok_or(ServerFnError::new("Failed to retrieve db pool"))?;
+    authorize(&pool, Role::ALL).await?;
 
     let result = sqlx::query_as!(
         GroupDto,
@@ -81,18 +131,60 @@ pub async fn get_groups() -> Result<Vec<GroupDto>, ServerFnError> {
 #[server]
 pub async fn transfer_group(transfer: (Uuid, Uuid)) -> Result<(), ServerFnError> {
     use sqlx::postgres::PgPool;
+    use crate::services::audit::{node_entity, record, snapshot};
+    use crate::services::auth::authorize;
+    use crate::services::student::place_student;
+    use dto::audit::{AuditAction, AuditEntity};
+    use dto::user::Role;
 
     let (child, new_parent) = transfer;
 
     let pool: PgPool = use_context().ok_or(ServerFnError::new("Failed to retrieve db pool"))?;
+    let actor = authorize(&pool, &[Role::Admin]).await?;
     let mut tr = pool.begin().await?;
 
+    let entity = node_entity(&mut tr, child).await?;
+    let before = snapshot(&mut tr, entity, child).await?;
+
+    if entity == AuditEntity::Student {
+        let today = sqlx::query_scalar!(r#"SELECT CURRENT_DATE AS "today!""#)
+            .fetch_one(&mut *tr)
+            .await?;
+        place_student(&mut tr, child, new_parent, today).await?;
+    } else {
+        transfer_node(&mut tr, child, new_parent).await?;
+    }
+
+    let after = snapshot(&mut tr, entity, child).await?;
+    record(
+        &mut tr,
+        &actor,
+        AuditAction::Transfer,
+        entity,
+        child,
+        before,
+        after,
+    )
+    .await?;
+
+    tr.commit().await?;
+
+    Ok(())
+}
+
+/// Checks that student or group `child` can be moved under `new_parent`
+#[cfg(feature = "ssr")]
+pub async fn check_transfer(
+    conn: &mut sqlx::PgConnection,
+    child: Uuid,
+    new_parent: Uuid,
+) -> Result<(), ServerFnError> {
     let is_invalid_group = sqlx::query!(
         "SELECT group_relations.child FROM group_relations WHERE child = $1 AND parent = $2 LIMIT 1",
         new_parent,
         child
     )
-    .fetch_optional(&mut *tr)
+    .fetch_optional(&mut *conn)
     .await?.is_some();
 
     if is_invalid_group {
@@ -103,13 +195,13 @@ pub async fn transfer_group(transfer: (Uuid, Uuid)) -> Result<(), ServerFnError>
     }
 
     let is_student = sqlx::query!("SELECT * FROM students WHERE id = $1", child)
-        .fetch_optional(&mut *tr)
+        .fetch_optional(&mut *conn)
         .await?
         .is_some();
 
-    let is_group_node = sqlx::query!("SELECT * FROM group_relations INNER JOIN groups ON groups.id = group_relations.child WHERE parent=$1 AND level = 1 LIMIT 1", new_parent).fetch_optional(&mut *tr).await?.is_some();
+    let is_group_node = sqlx::query!("SELECT * FROM group_relations INNER JOIN groups ON groups.id = group_relations.child WHERE parent=$1 AND level = 1 AND NOT groups.removed LIMIT 1", new_parent).fetch_optional(&mut *conn).await?.is_some();
 
-    let is_student_node = sqlx::query!("SELECT * FROM group_relations INNER JOIN students ON students.id = group_relations.child WHERE parent=$1 AND level = 1 LIMIT 1", new_parent).fetch_optional(&mut *tr).await?.is_some();
+    let is_student_node = sqlx::query!("SELECT * FROM group_relations INNER JOIN students ON students.id = group_relations.child WHERE parent=$1 AND level = 1 AND NOT students.removed LIMIT 1", new_parent).fetch_optional(&mut *conn).await?.is_some();
 
     if (is_group_node && is_student) {
         return Err(ServerFnError::new(
@@ -123,6 +215,18 @@ pub async fn transfer_group(transfer: (Uuid, Uuid)) -> Result<(), ServerFnError>
         ));
     }
 
+    Ok(())
+}
+
+/// Moves student or group `child` with its whole subtree under `new_parent`
+#[cfg(feature = "ssr")]
+pub async fn transfer_node(
+    conn: &mut sqlx::PgConnection,
+    child: Uuid,
+    new_parent: Uuid,
+) -> Result<(), ServerFnError> {
+    check_transfer(&mut *conn, child, new_parent).await?;
+
     sqlx::query!(
         "DELETE FROM group_relations AS a
                 USING group_relations AS gr1 
@@ -130,7 +234,7 @@ pub async fn transfer_group(transfer: (Uuid, Uuid)) -> Result<(), ServerFnError>
                 WHERE gr1.parent = $1 AND gr2.child = $1 AND gr2.level > 0 AND a.parent = gr2.parent AND a.child = gr1.child",
         child
     )
-    .execute(&mut *tr)
+    .execute(&mut *conn)
     .await?;
 
     let new_entries = sqlx::query!(
@@ -141,21 +245,22 @@ pub async fn transfer_group(transfer: (Uuid, Uuid)) -> Result<(), ServerFnError>
         child,
         new_parent
     )
-    .execute(&mut *tr)
+    .execute(&mut *conn)
     .await?;
 
     log!("New entries: {:?}", new_entries);
 
-    tr.commit().await?;
-
     Ok(())
 }
 
 #[server]
 pub async fn get_group_info(id: Uuid) -> Result<GroupInfoDto, ServerFnError> {
     use sqlx::postgres::PgPool;
+    use crate::services::auth::authorize;
+    use dto::user::Role;
 
     let pool: PgPool = use_context().ok_or(ServerFnError::new("Failed to retrieve db pool"))?;
+    authorize(&pool, Role::ALL).await?;
     let info = sqlx::query!("SELECT * FROM groups WHERE id = $1", id)
         .fetch_one(&pool)
         .await?;
@@ -195,13 +300,34 @@ pub async fn get_group_info(id: Uuid) -> Result<GroupInfoDto, ServerFnError> {
 #[server]
 pub async fn delete_group(id: Uuid) -> Result<(), ServerFnError> {
     use sqlx::postgres::PgPool;
+    use crate::services::audit::{node_entity, record, snapshot};
+    use crate::services::auth::authorize;
+    use dto::audit::AuditAction;
+    use dto::user::Role;
 
     let pool: PgPool = use_context().ok_or(ServerFnError::new("Failed to retrieve db pool"))?;
+    let actor = authorize(&pool, &[Role::Admin]).await?;
     let mut tr = pool.begin().await?;
 
-    let group_rows = sqlx::query!("UPDATE groups SET removed = true FROM groups AS gr JOIN group_relations ON group_relations.child = gr.id AND group_relations.parent = $1 WHERE groups.id = gr.id", id).execute(&mut *tr).await?.rows_affected();
+    let entity = node_entity(&mut tr, id).await?;
+    let before = snapshot(&mut tr, entity, id).await?;
+
+    // Nodes removed earlier stay in the recycle bin as separate entries
+    let group_rows = sqlx::query!("UPDATE groups SET removed = true, removed_at = LOCALTIMESTAMP(0), removed_by = $2, removed_with = $1 FROM groups AS gr JOIN group_relations ON group_relations.child = gr.id AND group_relations.parent = $1 WHERE groups.id = gr.id AND NOT groups.removed", id, actor.id).execute(&mut *tr).await?.rows_affected();
 
-    let student_rows = sqlx::query!("UPDATE students SET removed = true FROM students AS gr JOIN group_relations ON group_relations.child = gr.id AND group_relations.parent = $1 WHERE students.id = gr.id", id).execute(&mut *tr).await?.rows_affected();
+    let student_rows = sqlx::query!("UPDATE students SET removed = true, removed_at = LOCALTIMESTAMP(0), removed_by = $2, removed_with = $1 FROM students AS gr JOIN group_relations ON group_relations.child = gr.id AND group_relations.parent = $1 WHERE students.id = gr.id AND NOT students.removed", id, actor.id).execute(&mut *tr).await?.rows_affected();
+
+    let after = snapshot(&mut tr, entity, id).await?;
+    record(
+        &mut tr,
+        &actor,
+        AuditAction::Delete,
+        entity,
+        id,
+        before,
+        after,
+    )
+    .await?;
 
     tr.commit().await?;
 
@@ -217,8 +343,11 @@ pub async fn delete_group(id: Uuid) -> Result<(), ServerFnError> {
 #[server]
 pub async fn get_breadcrumbs(id: Uuid) -> Result<Vec<GroupDto>, ServerFnError> {
     use sqlx::postgres::PgPool;
+    use crate::services::auth::authorize;
+    use dto::user::Role;
 
     let pool: PgPool = use_context().ok_or(ServerFnError::new("Failed to retrieve db pool"))?;
+    authorize(&pool, Role::ALL).await?;
     let results = sqlx::query!("SELECT groups.id, groups.name FROM group_relations INNER JOIN groups ON groups.id = group_relations.parent WHERE group_relations.child = $1 ORDER BY level DESC", id)
     .fetch_all(&pool).await?
         .into_iter().map(|row| GroupDto{id: row.id, name: row.name, parent: None});
@@ -237,8 +366,11 @@ pub async fn get_breadcrumbs(id: Uuid) -> Result<Vec<GroupDto>, ServerFnError> {
 #[server]
 pub async fn get_search_terms() -> Result<Vec<SearchTerm>, ServerFnError> {
     use sqlx::postgres::PgPool;
+    use crate::services::auth::authorize;
+    use dto::user::Role;
 
     let pool: PgPool = use_context().ok_or(ServerFnError::new("Failed to retrieve db pool"))?;
+    authorize(&pool, Role::ALL).await?;
 
     let group_terms = sqlx::query!(
         r#"SELECT groups.id AS "id!", groups.name AS "name!", parents.name AS parent_name FROM groups
@@ -274,12 +406,19 @@ pub async fn get_search_terms() -> Result<Vec<SearchTerm>, ServerFnError> {
 #[server]
 pub async fn get_details(id: Uuid) -> Result<EntityDto, ServerFnError> {
     use sqlx::postgres::PgPool;
+    use crate::services::auth::authorize;
+    use dto::student::PlacementDto;
+    use dto::user::Role;
 
     let pool: PgPool = use_context().ok_or(ServerFnError::new("Failed to retrieve db pool"))?;
+    authorize(&pool, Role::ALL).await?;
 
-    let student = sqlx::query!("SELECT id,name, surname FROM students WHERE id = $1", id)
-        .fetch_optional(&pool)
-        .await?;
+    let student = sqlx::query!(
+        "SELECT id, name, surname, joined_on, left_on FROM students WHERE id = $1",
+        id
+    )
+    .fetch_optional(&pool)
+    .await?;
 
     if let Some(student) = student {
         let allergies = sqlx::query!(
@@ -299,6 +438,23 @@ pub async fn get_details(id: Uuid) -> Result<EntityDto, ServerFnError> {
         .fetch_all(&pool)
         .await?;
 
+        let placements = sqlx::query!(
+            r#"SELECT student_placements.group_id, COALESCE(groups.name, 'usunięta grupa') AS "name!", student_placements.since, student_placements.until FROM student_placements
+            LEFT JOIN groups ON groups.id = student_placements.group_id
+            WHERE student_placements.student_id = $1 ORDER BY student_placements.since NULLS FIRST"#,
+            id
+        )
+        .fetch_all(&pool)
+        .await?
+        .into_iter()
+        .map(|row| PlacementDto {
+            group_id: row.group_id,
+            group_name: row.name,
+            since: row.since,
+            until: row.until,
+        })
+        .collect();
+
         Ok(EntityDto::Student(StudentDetailsDto {
             id,
             name: student.name,
@@ -318,6 +474,9 @@ pub async fn get_details(id: Uuid) -> Result<EntityDto, ServerFnError> {
                     id: row.id,
                 })
                 .collect(),
+            joined: student.joined_on,
+            left: student.left_on,
+            placements,
         }))
     } else {
         let group = sqlx::query!("SELECT id, name, gr_parent.parent AS \"parent:Option<Uuid>\" FROM groups 
@@ -0,0 +1,749 @@
+#[cfg(feature = "ssr")]
+use std::collections::{HashMap, HashSet};
+
+use dto::import::{ImportFileDto, ImportPreviewDto, ImportResultDto};
+#[cfg(feature = "ssr")]
+use dto::import::{
+    ImportErrorDto, ImportGuardianDto, ImportPreviewRowDto, ImportRowDto, ImportRowStatus,
+};
+use leptos::prelude::*;
+use leptos::server_fn::codec::Json;
+#[cfg(feature = "ssr")]
+use uuid::Uuid;
+
+#[cfg(feature = "ssr")]
+enum Column {
+    Group,
+    Name,
+    Surname,
+    Allergies,
+    Guardians,
+    Phones,
+}
+
+#[cfg(feature = "ssr")]
+fn into_column(header: &str) -> Option<Column> {
+    match header.trim().to_lowercase().as_str() {
+        "grupa" | "klasa" | "group" => Some(Column::Group),
+        "imię" | "imie" | "name" => Some(Column::Name),
+        "nazwisko" | "surname" => Some(Column::Surname),
+        "alergie" | "allergies" => Some(Column::Allergies),
+        "opiekunowie" | "opiekun" | "guardians" => Some(Column::Guardians),
+        "telefony" | "telefon" | "phones" => Some(Column::Phones),
+        _ => None,
+    }
+}
+
+#[cfg(feature = "ssr")]
+fn split_list(value: &str) -> Vec<String> {
+    value
+        .split([',', ';', '\n'])
+        .map(|item| item.trim().to_string())
+        .filter(|item| !item.is_empty())
+        .collect()
+}
+
+#[cfg(feature = "ssr")]
+fn normalize_phone(phone: &str) -> String {
+    phone
+        .chars()
+        .filter(|c| !c.is_whitespace() && *c != '-')
+        .collect()
+}
+
+/// Bank and spreadsheet exports made on Windows are often in cp1250 rather than utf-8
+#[cfg(feature = "ssr")]
+pub fn decode_text(content: &[u8]) -> String {
+    match std::str::from_utf8(content) {
+        Ok(content) => String::from(content.trim_start_matches('\u{feff}')),
+        Err(_) => encoding_rs::WINDOWS_1250.decode(content).0.into_owned(),
+    }
+}
+
+#[cfg(feature = "ssr")]
+pub fn read_records(file: &ImportFileDto) -> Result<Vec<Vec<String>>, ServerFnError> {
+    use calamine::{open_workbook_auto_from_rs, Reader};
+    use std::io::Cursor;
+
+    let extension = file
+        .filename
+        .rsplit_once('.')
+        .map(|(_, extension)| extension.to_lowercase())
+        .unwrap_or_default();
+
+    if matches!(extension.as_str(), "xlsx" | "xlsm" | "xls" | "ods") {
+        let mut workbook = open_workbook_auto_from_rs(Cursor::new(file.content.as_slice()))?;
+        let sheet = workbook
+            .worksheet_range_at(0)
+            .ok_or(ServerFnError::new("Spreadsheet has no sheets"))??;
+        return Ok(sheet
+            .rows()
+            .map(|row| row.iter().map(|cell| cell.to_string()).collect())
+            .collect());
+    }
+
+    let content = decode_text(&file.content);
+    let header = content.lines().next().unwrap_or_default();
+    let delimiter = if header.matches(';').count() > header.matches(',').count() {
+        b';'
+    } else {
+        b','
+    };
+
+    let mut reader = csv::ReaderBuilder::new()
+        .has_headers(false)
+        .flexible(true)
+        .delimiter(delimiter)
+        .from_reader(content.as_bytes());
+
+    let mut records = vec![];
+    for record in reader.records() {
+        records.push(record?.iter().map(String::from).collect());
+    }
+    Ok(records)
+}
+
+/// Parses spreadsheet records, first record being the header naming the columns
+#[cfg(feature = "ssr")]
+fn parse_rows(records: Vec<Vec<String>>) -> (Vec<ImportRowDto>, Vec<ImportErrorDto>) {
+    let mut records = records.into_iter();
+    let mut errors = vec![];
+
+    let Some(header) = records.next() else {
+        errors.push(ImportErrorDto {
+            line: 1,
+            message: String::from("Plik jest pusty"),
+        });
+        return (vec![], errors);
+    };
+
+    let columns = header.iter().map(|h| into_column(h)).collect::<Vec<_>>();
+    let has_column = |wanted: fn(&Column) -> bool| columns.iter().flatten().any(wanted);
+
+    for (present, name) in [
+        (has_column(|c| matches!(c, Column::Group)), "grupa"),
+        (has_column(|c| matches!(c, Column::Name)), "imię"),
+        (has_column(|c| matches!(c, Column::Surname)), "nazwisko"),
+    ] {
+        if !present {
+            errors.push(ImportErrorDto {
+                line: 1,
+                message: format!("Brak kolumny '{}'", name),
+            });
+        }
+    }
+    if !errors.is_empty() {
+        return (vec![], errors);
+    }
+
+    let mut rows = vec![];
+    for (i, record) in records.enumerate() {
+        let line = i + 2;
+        if record.iter().all(|cell| cell.trim().is_empty()) {
+            continue;
+        }
+
+        let mut row = ImportRowDto {
+            line,
+            group_path: vec![],
+            name: String::new(),
+            surname: String::new(),
+            allergies: vec![],
+            guardians: vec![],
+        };
+        let mut phones = vec![];
+
+        for (column, cell) in columns.iter().zip(record.iter()) {
+            match column {
+                Some(Column::Group) => {
+                    row.group_path = cell
+                        .split(['/', '>'])
+                        .map(|group| group.trim().to_string())
+                        .filter(|group| !group.is_empty())
+                        .collect()
+                }
+                Some(Column::Name) => row.name = cell.trim().to_string(),
+                Some(Column::Surname) => row.surname = cell.trim().to_string(),
+                Some(Column::Allergies) => row.allergies = split_list(cell),
+                Some(Column::Guardians) => {
+                    row.guardians = split_list(cell)
+                        .into_iter()
+                        .map(|fullname| ImportGuardianDto {
+                            fullname,
+                            phone: None,
+                        })
+                        .collect()
+                }
+                Some(Column::Phones) => phones = split_list(cell),
+                None => {}
+            }
+        }
+
+        if row.name.is_empty() || row.surname.is_empty() {
+            errors.push(ImportErrorDto {
+                line,
+                message: String::from("Brak imienia lub nazwiska"),
+            });
+            continue;
+        }
+        if row.group_path.is_empty() {
+            errors.push(ImportErrorDto {
+                line,
+                message: String::from("Brak grupy"),
+            });
+            continue;
+        }
+        if phones.len() > row.guardians.len() {
+            errors.push(ImportErrorDto {
+                line,
+                message: String::from("Więcej numerów telefonu niż opiekunów"),
+            });
+            continue;
+        }
+
+        for (guardian, phone) in row.guardians.iter_mut().zip(phones) {
+            guardian.phone = Some(normalize_phone(&phone));
+        }
+        rows.push(row);
+    }
+
+    (rows, errors)
+}
+
+#[cfg(feature = "ssr")]
+struct ImportGroup {
+    name: String,
+    parent: Option<Uuid>,
+}
+
+/// Group tree as it is in the database
+#[cfg(feature = "ssr")]
+struct GroupTree {
+    groups: HashMap<Uuid, ImportGroup>,
+    with_students: HashSet<Uuid>,
+}
+
+#[cfg(feature = "ssr")]
+enum GroupTarget {
+    Existing(Uuid),
+    /// Deepest existing group and names of groups to create below it
+    New(Uuid, Vec<String>),
+}
+
+#[cfg(feature = "ssr")]
+impl GroupTree {
+    async fn load(conn: &mut sqlx::PgConnection) -> Result<Self, ServerFnError> {
+        let groups = sqlx::query!(
+            "SELECT groups.id, groups.name, group_relations.parent AS \"parent?\" FROM groups
+            LEFT JOIN group_relations ON group_relations.child = groups.id AND group_relations.level = 1
+            WHERE groups.removed = false"
+        )
+        .fetch_all(&mut *conn)
+        .await?
+        .into_iter()
+        .map(|row| {
+            (
+                row.id,
+                ImportGroup {
+                    name: row.name,
+                    parent: row.parent,
+                },
+            )
+        })
+        .collect();
+
+        let with_students = sqlx::query!(
+            "SELECT DISTINCT group_relations.parent FROM group_relations
+            INNER JOIN students ON students.id = group_relations.child AND students.removed = false
+            WHERE group_relations.level = 1"
+        )
+        .fetch_all(&mut *conn)
+        .await?
+        .into_iter()
+        .map(|row| row.parent)
+        .collect();
+
+        Ok(GroupTree {
+            groups,
+            with_students,
+        })
+    }
+
+    fn child(&self, parent: Option<Uuid>, name: &str) -> Option<Uuid> {
+        self.groups
+            .iter()
+            .find(|(_, group)| group.parent == parent && group.name.trim().to_lowercase() == name.to_lowercase())
+            .map(|(id, _)| *id)
+    }
+
+    fn has_subgroups(&self, id: Uuid) -> bool {
+        self.groups.values().any(|group| group.parent == Some(id))
+    }
+
+    fn path(&self, mut id: Uuid) -> String {
+        let mut path = vec![];
+        while let Some(group) = self.groups.get(&id) {
+            path.push(group.name.as_str());
+            match group.parent {
+                Some(parent) => id = parent,
+                None => break,
+            }
+        }
+        path.reverse();
+        path.join("/")
+    }
+
+    fn resolve(&self, path: &[String]) -> Result<GroupTarget, String> {
+        let mut current = None;
+        for (i, name) in path.iter().enumerate() {
+            match self.child(current, name) {
+                Some(id) => current = Some(id),
+                None => {
+                    let Some(parent) = current else {
+                        return Err(format!("Nie znaleziono grupy głównej '{}'", name));
+                    };
+                    if self.with_students.contains(&parent) {
+                        return Err(format!(
+                            "Grupa '{}' zawiera uczniów, nie można dodać do niej podgrupy",
+                            self.path(parent)
+                        ));
+                    }
+                    return Ok(GroupTarget::New(parent, path[i..].to_vec()));
+                }
+            }
+        }
+
+        let id = current.ok_or(String::from("Brak grupy"))?;
+        if self.has_subgroups(id) {
+            return Err(format!(
+                "Grupa '{}' zawiera podgrupy, uczniowie muszą należeć do grupy najniższego poziomu",
+                self.path(id)
+            ));
+        }
+        Ok(GroupTarget::Existing(id))
+    }
+}
+
+#[cfg(feature = "ssr")]
+struct ExistingStudent {
+    id: Uuid,
+    group_id: Uuid,
+    allergies: Vec<String>,
+    guardians: Vec<String>,
+}
+
+#[cfg(feature = "ssr")]
+fn sorted(items: &[String]) -> Vec<String> {
+    let mut items = items.to_vec();
+    items.sort();
+    items
+}
+
+/// Compares the row with data in the database, without modifying anything
+#[cfg(feature = "ssr")]
+async fn plan_import(
+    conn: &mut sqlx::PgConnection,
+    file: &ImportFileDto,
+) -> Result<ImportPreviewDto, ServerFnError> {
+    let (rows, mut errors) = parse_rows(read_records(file)?);
+    let tree = GroupTree::load(&mut *conn).await?;
+
+    let mut existing: HashMap<(String, String), Vec<ExistingStudent>> = HashMap::new();
+    for student in sqlx::query!(
+        "SELECT students.id, students.name, students.surname, group_relations.parent AS group_id,
+        COALESCE((SELECT ARRAY_AGG(allergies.name) FROM allergy_combinations INNER JOIN allergies ON allergies.id = allergy_combinations.allergy_id WHERE allergy_combinations.id = students.allergy_combination_id), '{}') AS \"allergies!\",
+        COALESCE((SELECT ARRAY_AGG(guardians.fullname) FROM student_guardians INNER JOIN guardians ON guardians.id = student_guardians.guardian_id WHERE student_guardians.student_id = students.id), '{}') AS \"guardians!\"
+        FROM students
+        INNER JOIN group_relations ON group_relations.child = students.id AND group_relations.level = 1
+        WHERE students.removed = false"
+    )
+    .fetch_all(&mut *conn)
+    .await?
+    {
+        existing
+            .entry((student.name.to_lowercase(), student.surname.to_lowercase()))
+            .or_default()
+            .push(ExistingStudent {
+                id: student.id,
+                group_id: student.group_id,
+                allergies: student.allergies,
+                guardians: student.guardians,
+            });
+    }
+
+    let guardians = sqlx::query!("SELECT fullname, phone FROM guardians")
+        .fetch_all(&mut *conn)
+        .await?
+        .into_iter()
+        .map(|row| (row.fullname, row.phone))
+        .collect::<HashMap<_, _>>();
+    let phone_owners = guardians
+        .iter()
+        .filter_map(|(fullname, phone)| Some((normalize_phone(phone.as_ref()?), fullname.clone())))
+        .collect::<HashMap<_, _>>();
+
+    let mut seen: HashMap<(String, String), usize> = HashMap::new();
+    // Phones given in the file so far, with the guardian and line they were given for
+    let mut file_phones: HashMap<String, (String, usize)> = HashMap::new();
+    let mut new_groups = vec![];
+    let mut new_guardians = vec![];
+    let mut preview = vec![];
+
+    for row in rows {
+        let target = match tree.resolve(&row.group_path) {
+            Ok(target) => target,
+            Err(message) => {
+                errors.push(ImportErrorDto {
+                    line: row.line,
+                    message,
+                });
+                continue;
+            }
+        };
+
+        let phone_conflict = row.guardians.iter().find_map(|guardian| {
+            let phone = guardian.phone.as_ref()?;
+            let owner = phone_owners.get(phone).filter(|owner| **owner != guardian.fullname)?;
+            Some(format!("Numer {} należy już do opiekuna {}", phone, owner))
+        });
+        let phone_conflict = phone_conflict.or_else(|| {
+            row.guardians.iter().enumerate().find_map(|(i, guardian)| {
+                let phone = guardian.phone.as_ref()?;
+                let (owner, line) = file_phones
+                    .get(phone)
+                    .map(|(owner, line)| (owner.as_str(), *line))
+                    .or_else(|| {
+                        row.guardians[..i]
+                            .iter()
+                            .find(|other| other.phone.as_ref() == Some(phone))
+                            .map(|other| (other.fullname.as_str(), row.line))
+                    })
+                    .filter(|(owner, _)| *owner != guardian.fullname)?;
+                Some(format!(
+                    "Numer {} podano już dla opiekuna {} w wierszu {}",
+                    phone, owner, line
+                ))
+            })
+        });
+        if let Some(message) = phone_conflict {
+            errors.push(ImportErrorDto {
+                line: row.line,
+                message,
+            });
+            continue;
+        }
+
+        // Rows rejected above don't claim their phones
+        for guardian in &row.guardians {
+            if let Some(phone) = &guardian.phone {
+                file_phones
+                    .entry(phone.clone())
+                    .or_insert((guardian.fullname.clone(), row.line));
+            }
+        }
+
+        let key = (row.name.to_lowercase(), row.surname.to_lowercase());
+        let status = if let Some(line) = seen.get(&key) {
+            ImportRowStatus::Duplicate(format!("Powtórzony uczeń z wiersza {}", line))
+        } else {
+            seen.insert(key.clone(), row.line);
+            match existing.get(&key).map(|students| students.as_slice()) {
+                None | Some([]) => ImportRowStatus::New,
+                Some([student]) => {
+                    let mut changes = vec![];
+                    let group_changed = match &target {
+                        GroupTarget::Existing(id) => *id != student.group_id,
+                        GroupTarget::New(_, _) => true,
+                    };
+                    if group_changed {
+                        changes.push(format!(
+                            "Grupa: {} → {}",
+                            tree.path(student.group_id),
+                            row.group_path.join("/")
+                        ));
+                    }
+                    if sorted(&student.allergies) != sorted(&row.allergies) {
+                        changes.push(format!(
+                            "Alergie: {} → {}",
+                            student.allergies.join(", "),
+                            row.allergies.join(", ")
+                        ));
+                    }
+                    let row_guardians = row
+                        .guardians
+                        .iter()
+                        .map(|g| g.fullname.clone())
+                        .collect::<Vec<_>>();
+                    if sorted(&student.guardians) != sorted(&row_guardians) {
+                        changes.push(format!(
+                            "Opiekunowie: {} → {}",
+                            student.guardians.join(", "),
+                            row_guardians.join(", ")
+                        ));
+                    }
+                    for guardian in &row.guardians {
+                        let current = guardians.get(&guardian.fullname).cloned().flatten();
+                        let Some(phone) = &guardian.phone else {
+                            continue;
+                        };
+                        if current.as_deref().map(normalize_phone).as_ref() != Some(phone) {
+                            changes.push(format!(
+                                "Telefon {}: {} → {}",
+                                guardian.fullname,
+                                current.unwrap_or_default(),
+                                phone
+                            ));
+                        }
+                    }
+
+                    if changes.is_empty() {
+                        ImportRowStatus::Unchanged(student.id)
+                    } else {
+                        ImportRowStatus::Changed(student.id, changes)
+                    }
+                }
+                Some(_) => ImportRowStatus::Duplicate(String::from(
+                    "W bazie jest kilku uczniów o tym imieniu i nazwisku",
+                )),
+            }
+        };
+
+        // Only applied rows create groups and guardians
+        if matches!(status, ImportRowStatus::New | ImportRowStatus::Changed(_, _)) {
+            if let GroupTarget::New(parent, missing) = &target {
+                let mut path = tree.path(*parent);
+                for name in missing {
+                    path = format!("{}/{}", path, name);
+                    if !new_groups.contains(&path) {
+                        new_groups.push(path.clone());
+                    }
+                }
+            }
+            for guardian in &row.guardians {
+                if !guardians.contains_key(&guardian.fullname)
+                    && !new_guardians.contains(&guardian.fullname)
+                {
+                    new_guardians.push(guardian.fullname.clone());
+                }
+            }
+        }
+
+        preview.push(ImportPreviewRowDto { row, status });
+    }
+
+    Ok(ImportPreviewDto {
+        rows: preview,
+        new_groups,
+        new_guardians,
+        errors,
+    })
+}
+
+#[server(input = Json)]
+pub async fn preview_import(file: ImportFileDto) -> Result<ImportPreviewDto, ServerFnError> {
+    use sqlx::postgres::PgPool;
+    use crate::services::auth::authorize;
+    use dto::user::Role;
+
+    let pool: PgPool = use_context().ok_or(ServerFnError::new("Failed to retrieve db pool"))?;
+    authorize(&pool, &[Role::Admin]).await?;
+    let mut conn = pool.acquire().await?;
+
+    plan_import(&mut conn, &file).await
+}
+
+/// Applies the whole import in a single transaction, nothing is changed if the file has any errors
+#[server(input = Json)]
+pub async fn apply_import(file: ImportFileDto) -> Result<ImportResultDto, ServerFnError> {
+    use sqlx::postgres::PgPool;
+    use crate::services::audit::{record, snapshot};
+    use crate::services::auth::authorize;
+    use crate::services::group::insert_group;
+    use crate::services::student::{
+        allergy_combination, guardians_by_name, insert_student, place_student,
+    };
+    use dto::audit::{AuditAction, AuditEntity};
+    use dto::student::CreateStudentDto;
+    use dto::user::Role;
+
+    let pool: PgPool = use_context().ok_or(ServerFnError::new("Failed to retrieve db pool"))?;
+    let actor = authorize(&pool, &[Role::Admin]).await?;
+    let mut tr = pool.begin().await?;
+
+    let preview = plan_import(&mut tr, &file).await?;
+    if let Some(error) = preview.errors.first() {
+        return Err(ServerFnError::new(format!(
+            "Import contains errors, first in line {}: {}",
+            error.line, error.message
+        )));
+    }
+
+    let mut result = ImportResultDto {
+        created_guardians: preview.new_guardians.len(),
+        ..Default::default()
+    };
+    let mut created_groups: HashMap<String, Uuid> = HashMap::new();
+    let tree = GroupTree::load(&mut tr).await?;
+    let today = sqlx::query_scalar!(r#"SELECT CURRENT_DATE AS "today!""#)
+        .fetch_one(&mut *tr)
+        .await?;
+
+    for ImportPreviewRowDto { row, status } in preview.rows {
+        if matches!(
+            status,
+            ImportRowStatus::Unchanged(_) | ImportRowStatus::Duplicate(_)
+        ) {
+            result.skipped += 1;
+            continue;
+        }
+
+        let group_id = match tree.resolve(&row.group_path).map_err(ServerFnError::new)? {
+            GroupTarget::Existing(id) => id,
+            GroupTarget::New(mut parent, missing) => {
+                let mut path = tree.path(parent);
+                for name in missing {
+                    path = format!("{}/{}", path, name);
+                    parent = match created_groups.get(&path) {
+                        Some(id) => *id,
+                        None => {
+                            let id = insert_group(&mut tr, &name, parent).await?;
+                            let after = snapshot(&mut tr, AuditEntity::Group, id).await?;
+                            record(
+                                &mut tr,
+                                &actor,
+                                AuditAction::Create,
+                                AuditEntity::Group,
+                                id,
+                                None,
+                                after,
+                            )
+                            .await?;
+                            created_groups.insert(path.clone(), id);
+                            id
+                        }
+                    };
+                }
+                parent
+            }
+        };
+
+        let guardian_names = row
+            .guardians
+            .iter()
+            .map(|g| g.fullname.clone())
+            .collect::<Vec<_>>();
+
+        match status {
+            ImportRowStatus::Changed(student_id, _) => {
+                let before = snapshot(&mut tr, AuditEntity::Student, student_id).await?;
+
+                let current_group = sqlx::query!(
+                    "SELECT parent FROM group_relations WHERE child = $1 AND level = 1",
+                    student_id
+                )
+                .fetch_one(&mut *tr)
+                .await?
+                .parent;
+                if current_group != group_id {
+                    place_student(&mut tr, student_id, group_id, today).await?;
+                }
+
+                let allergy_combination_id = allergy_combination(&mut tr, &row.allergies).await?;
+                let guardian_ids = guardians_by_name(&mut tr, &guardian_names).await?;
+
+                sqlx::query!(
+                    "UPDATE students SET allergy_combination_id = $2 WHERE id = $1",
+                    student_id,
+                    allergy_combination_id
+                )
+                .execute(&mut *tr)
+                .await?;
+                sqlx::query!("DELETE FROM student_guardians WHERE student_id = $1", student_id)
+                    .execute(&mut *tr)
+                    .await?;
+                sqlx::query!("INSERT INTO student_guardians (student_id, guardian_id) SELECT $1, * FROM UNNEST($2::uuid[])", student_id, &guardian_ids).execute(&mut *tr).await?;
+
+                let after = snapshot(&mut tr, AuditEntity::Student, student_id).await?;
+                record(
+                    &mut tr,
+                    &actor,
+                    AuditAction::Update,
+                    AuditEntity::Student,
+                    student_id,
+                    before,
+                    after,
+                )
+                .await?;
+                result.updated_students += 1;
+            }
+            _ => {
+                let student_id = insert_student(
+                    &mut tr,
+                    CreateStudentDto {
+                        name: row.name.clone(),
+                        surname: row.surname.clone(),
+                        group_id,
+                        allergies: row.allergies.clone(),
+                        guardians: guardian_names,
+                        joined: None,
+                        left: None,
+                    },
+                )
+                .await?;
+
+                let after = snapshot(&mut tr, AuditEntity::Student, student_id).await?;
+                record(
+                    &mut tr,
+                    &actor,
+                    AuditAction::Create,
+                    AuditEntity::Student,
+                    student_id,
+                    None,
+                    after,
+                )
+                .await?;
+                result.created_students += 1;
+            }
+        }
+
+        for guardian in &row.guardians {
+            let Some(phone) = &guardian.phone else {
+                continue;
+            };
+            let Some(guardian_id) = sqlx::query!(
+                "SELECT id FROM guardians WHERE fullname = $1 AND phone IS DISTINCT FROM $2",
+                guardian.fullname,
+                phone
+            )
+            .fetch_optional(&mut *tr)
+            .await?
+            .map(|row| row.id) else {
+                continue;
+            };
+
+            let before = snapshot(&mut tr, AuditEntity::Guardian, guardian_id).await?;
+            sqlx::query!(
+                "UPDATE guardians SET phone = $2 WHERE id = $1",
+                guardian_id,
+                phone
+            )
+            .execute(&mut *tr)
+            .await?;
+            let after = snapshot(&mut tr, AuditEntity::Guardian, guardian_id).await?;
+            record(
+                &mut tr,
+                &actor,
+                AuditAction::Update,
+                AuditEntity::Guardian,
+                guardian_id,
+                before,
+                after,
+            )
+            .await?;
+        }
+    }
+
+    result.created_groups = created_groups.len();
+    tr.commit().await?;
+    Ok(result)
+}
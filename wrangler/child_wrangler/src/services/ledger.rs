@@ -0,0 +1,672 @@
+use dto::import::ImportFileDto;
+use dto::ledger::{
+    ArrearsDto, BankImportResultDto, BankTransferDto, CreateLedgerEntryDto, GuardianLedgerDto,
+};
+use leptos::prelude::*;
+use leptos::server_fn::codec::Json;
+use uuid::Uuid;
+
+/// Guardian that a bank transfer may be matched to, `names` are already folded with `fold_text`
+#[cfg(feature = "ssr")]
+struct MatchCandidate {
+    guardian_id: Uuid,
+    names: Vec<String>,
+    balance: i64,
+}
+
+/// Bank titles come in fixed width parts that may split words or glue them together,
+/// so text is compared without whitespace, case and polish letters
+#[cfg(feature = "ssr")]
+fn fold_text(text: &str) -> String {
+    use crate::services::billing::strip_diacritics;
+
+    strip_diacritics(&text.to_lowercase())
+        .chars()
+        .filter(|c| !c.is_whitespace())
+        .collect()
+}
+
+#[cfg(feature = "ssr")]
+async fn match_candidates(
+    conn: &mut sqlx::PgConnection,
+) -> Result<Vec<MatchCandidate>, ServerFnError> {
+    let candidates = sqlx::query!(
+        r#"SELECT guardians.id, guardians.fullname,
+        (SELECT COALESCE(SUM(amount), 0) FROM ledger_entries WHERE ledger_entries.guardian_id = guardians.id) AS "balance!",
+        COALESCE(array_agg(students.name || ' ' || students.surname) FILTER (WHERE students.id IS NOT NULL), '{}') AS "students!"
+        FROM guardians
+        LEFT JOIN student_guardians ON student_guardians.guardian_id = guardians.id
+        LEFT JOIN students ON students.id = student_guardians.student_id AND students.removed = false
+        WHERE guardians.removed = false
+        GROUP BY guardians.id"#
+    )
+    .fetch_all(&mut *conn)
+    .await?
+    .into_iter()
+    .map(|row| {
+        // Single words like a lone first name would match far too many titles
+        let mut names = vec![row.fullname];
+        for student in row.students {
+            let reversed = student.split(' ').rev().collect::<Vec<_>>().join(" ");
+            names.push(student);
+            names.push(reversed);
+        }
+        let names = names
+            .iter()
+            .filter(|name| name.split_whitespace().count() > 1)
+            .map(|name| fold_text(name))
+            .collect();
+        MatchCandidate {
+            guardian_id: row.id,
+            names,
+            balance: row.balance,
+        }
+    })
+    .collect();
+    Ok(candidates)
+}
+
+/// Picks the guardian a transfer comes from. Title naming exactly one guardian (or one of their children) wins,
+/// when the title is ambiguous or names nobody the transfer is matched only if exactly one candidate owes that amount
+#[cfg(feature = "ssr")]
+fn match_transfer(text: &str, amount: i64, candidates: &[MatchCandidate]) -> Option<usize> {
+    let text = fold_text(text);
+    let by_title = candidates
+        .iter()
+        .enumerate()
+        .filter(|(_, candidate)| candidate.names.iter().any(|name| text.contains(name)))
+        .map(|(i, _)| i)
+        .collect::<Vec<_>>();
+
+    if let [single] = by_title.as_slice() {
+        return Some(*single);
+    }
+
+    let pool = if by_title.is_empty() {
+        (0..candidates.len()).collect()
+    } else {
+        by_title
+    };
+    let owing = pool
+        .into_iter()
+        .filter(|i| -candidates[*i].balance == amount)
+        .collect::<Vec<_>>();
+
+    match owing.as_slice() {
+        [single] => Some(*single),
+        _ => None,
+    }
+}
+
+/// Books a bank transfer as payment of the guardian
+#[cfg(feature = "ssr")]
+async fn book_transfer(
+    conn: &mut sqlx::PgConnection,
+    transfer_id: Uuid,
+    guardian_id: Uuid,
+) -> Result<(), ServerFnError> {
+    let booked = sqlx::query!(
+        "INSERT INTO ledger_entries (guardian_id, kind, amount, booked, description, transfer_id)
+        SELECT $2, 'payment', amount, booked, title, id FROM bank_transfers WHERE id = $1 AND amount > 0",
+        transfer_id,
+        guardian_id
+    )
+    .execute(&mut *conn)
+    .await?
+    .rows_affected();
+
+    if booked == 0 {
+        return Err(ServerFnError::new("Transfer not found"));
+    }
+    Ok(())
+}
+
+#[server]
+pub async fn get_guardian_ledger(guardian_id: Uuid) -> Result<GuardianLedgerDto, ServerFnError> {
+    use sqlx::postgres::PgPool;
+    use crate::services::auth::authorize;
+    use dto::ledger::LedgerEntryDto;
+    use dto::user::Role;
+
+    let pool: PgPool = use_context().ok_or(ServerFnError::new("Failed to retrieve db pool"))?;
+    authorize(&pool, &[Role::Admin, Role::Teacher]).await?;
+
+    let entries = sqlx::query!(
+        "SELECT id, kind, amount, booked, description, transfer_id FROM ledger_entries
+        WHERE guardian_id = $1 ORDER BY booked DESC, created DESC",
+        guardian_id
+    )
+    .fetch_all(&pool)
+    .await?
+    .into_iter()
+    .map(|row| {
+        Ok(LedgerEntryDto {
+            id: row.id,
+            kind: row.kind.parse().map_err(ServerFnError::new)?,
+            amount: row.amount as i64,
+            booked: row.booked,
+            description: row.description,
+            from_bank: row.transfer_id.is_some(),
+        })
+    })
+    .collect::<Result<Vec<_>, ServerFnError>>()?;
+
+    Ok(GuardianLedgerDto {
+        guardian_id,
+        balance: entries.iter().map(|entry| entry.amount).sum(),
+        entries,
+    })
+}
+
+#[server]
+pub async fn get_arrears() -> Result<Vec<ArrearsDto>, ServerFnError> {
+    use sqlx::postgres::PgPool;
+    use crate::services::auth::authorize;
+    use dto::user::Role;
+
+    let pool: PgPool = use_context().ok_or(ServerFnError::new("Failed to retrieve db pool"))?;
+    authorize(&pool, &[Role::Admin, Role::Teacher]).await?;
+
+    let arrears = sqlx::query!(
+        r#"WITH balances AS (SELECT guardian_id, SUM(amount) AS balance, COALESCE(SUM(amount) FILTER (WHERE kind != 'charge'), 0) AS credits
+            FROM ledger_entries GROUP BY guardian_id),
+        charges AS (SELECT guardian_id, booked, SUM(-amount) OVER (PARTITION BY guardian_id ORDER BY booked, id) AS charged
+            FROM ledger_entries WHERE kind = 'charge')
+        SELECT guardians.id, guardians.fullname, guardians.phone, balances.balance AS "balance!",
+        (SELECT MIN(charges.booked) FROM charges WHERE charges.guardian_id = guardians.id AND charges.charged > balances.credits) AS overdue_since
+        FROM balances
+        INNER JOIN guardians ON guardians.id = balances.guardian_id
+        WHERE balances.balance < 0
+        ORDER BY balances.balance, guardians.fullname"#
+    )
+    .fetch_all(&pool)
+    .await?
+    .into_iter()
+    .map(|row| ArrearsDto {
+        guardian_id: row.id,
+        fullname: row.fullname,
+        phone: row.phone,
+        balance: row.balance,
+        overdue_since: row.overdue_since,
+    })
+    .collect();
+
+    Ok(arrears)
+}
+
+#[server]
+pub async fn add_ledger_entry(entry: CreateLedgerEntryDto) -> Result<Uuid, ServerFnError> {
+    use sqlx::postgres::PgPool;
+    use crate::services::audit::{record, snapshot};
+    use crate::services::auth::authorize;
+    use dto::audit::{AuditAction, AuditEntity};
+    use dto::ledger::LedgerEntryKind;
+    use dto::user::Role;
+
+    let pool: PgPool = use_context().ok_or(ServerFnError::new("Failed to retrieve db pool"))?;
+    let actor = authorize(&pool, &[Role::Admin]).await?;
+
+    match entry.kind {
+        LedgerEntryKind::Charge => {
+            return Err(ServerFnError::new(
+                "Charges are posted from monthly statements",
+            ))
+        }
+        LedgerEntryKind::Payment if entry.amount <= 0 => {
+            return Err(ServerFnError::new("Payment must be positive"))
+        }
+        LedgerEntryKind::Adjustment if entry.amount == 0 => {
+            return Err(ServerFnError::new("Adjustment can't be zero"))
+        }
+        _ => {}
+    }
+
+    let amount: i32 = entry
+        .amount
+        .try_into()
+        .map_err(|_| ServerFnError::new("Amount is too large"))?;
+    let description = entry.description.trim();
+    if description.is_empty() {
+        return Err(ServerFnError::new("Entry needs a description"));
+    }
+
+    let mut tr = pool.begin().await?;
+    let before = snapshot(&mut tr, AuditEntity::Ledger, entry.guardian_id).await?;
+
+    let id = sqlx::query!(
+        "INSERT INTO ledger_entries (guardian_id, kind, amount, booked, description) VALUES ($1,$2,$3,$4,$5) RETURNING id",
+        entry.guardian_id,
+        entry.kind.as_str(),
+        amount,
+        entry.booked,
+        description
+    )
+    .fetch_one(&mut *tr)
+    .await?
+    .id;
+
+    let after = snapshot(&mut tr, AuditEntity::Ledger, entry.guardian_id).await?;
+    record(
+        &mut tr,
+        &actor,
+        AuditAction::Create,
+        AuditEntity::Ledger,
+        entry.guardian_id,
+        before,
+        after,
+    )
+    .await?;
+
+    tr.commit().await?;
+    Ok(id)
+}
+
+/// Removes an entry, payments booked from a bank transfer return to the list of unmatched transfers
+#[server]
+pub async fn remove_ledger_entry(id: Uuid) -> Result<(), ServerFnError> {
+    use sqlx::postgres::PgPool;
+    use crate::services::audit::{record, snapshot};
+    use crate::services::auth::authorize;
+    use dto::audit::{AuditAction, AuditEntity};
+    use dto::user::Role;
+
+    let pool: PgPool = use_context().ok_or(ServerFnError::new("Failed to retrieve db pool"))?;
+    let actor = authorize(&pool, &[Role::Admin]).await?;
+
+    let mut tr = pool.begin().await?;
+
+    let guardian_id = sqlx::query!("SELECT guardian_id FROM ledger_entries WHERE id = $1", id)
+        .fetch_optional(&mut *tr)
+        .await?
+        .ok_or(ServerFnError::new("Ledger entry not found"))?
+        .guardian_id;
+
+    let before = snapshot(&mut tr, AuditEntity::Ledger, guardian_id).await?;
+    sqlx::query!("DELETE FROM ledger_entries WHERE id = $1", id)
+        .execute(&mut *tr)
+        .await?;
+    let after = snapshot(&mut tr, AuditEntity::Ledger, guardian_id).await?;
+    record(
+        &mut tr,
+        &actor,
+        AuditAction::Delete,
+        AuditEntity::Ledger,
+        guardian_id,
+        before,
+        after,
+    )
+    .await?;
+
+    tr.commit().await?;
+    Ok(())
+}
+
+/// Charges every guardian the amount due from their monthly statement.
+/// Posting the same month again updates the charges and zeroes the charges of guardians who no longer
+/// have a statement for it, returns the number of charges that changed
+#[server]
+pub async fn post_monthly_charges(year: i32, month: u32) -> Result<u32, ServerFnError> {
+    use chrono::Days;
+    use sqlx::postgres::PgPool;
+    use crate::services::audit::{record, snapshot};
+    use crate::services::auth::authorize;
+    use crate::services::billing::{load_statements, month_range};
+    use dto::audit::{AuditAction, AuditEntity};
+    use dto::user::Role;
+
+    let pool: PgPool = use_context().ok_or(ServerFnError::new("Failed to retrieve db pool"))?;
+    let actor = authorize(&pool, &[Role::Admin]).await?;
+
+    let (start, end) = month_range(year, month)?;
+    let booked = end - Days::new(1);
+    let description = format!("Posiłki za {:02}.{}", month, year);
+
+    let mut tr = pool.begin().await?;
+    let statements = load_statements(&mut tr, start, end, None).await?;
+
+    let billed = statements
+        .iter()
+        .map(|statement| statement.guardian_id)
+        .collect::<Vec<_>>();
+
+    let mut changed = 0;
+    for statement in statements {
+        let amount: i32 = (-statement.due.max(0))
+            .try_into()
+            .map_err(|_| ServerFnError::new("Amount is too large"))?;
+
+        let before = snapshot(&mut tr, AuditEntity::Ledger, statement.guardian_id).await?;
+        let updated = sqlx::query!(
+            "INSERT INTO ledger_entries (guardian_id, kind, amount, booked, description, period) VALUES ($1, 'charge', $2, $3, $4, $5)
+            ON CONFLICT (guardian_id, period) WHERE kind = 'charge' DO UPDATE SET amount = EXCLUDED.amount
+            WHERE ledger_entries.amount != EXCLUDED.amount
+            RETURNING id",
+            statement.guardian_id,
+            amount,
+            booked,
+            description,
+            start
+        )
+        .fetch_optional(&mut *tr)
+        .await?;
+
+        if updated.is_some() {
+            let after = snapshot(&mut tr, AuditEntity::Ledger, statement.guardian_id).await?;
+            record(
+                &mut tr,
+                &actor,
+                AuditAction::Update,
+                AuditEntity::Ledger,
+                statement.guardian_id,
+                before,
+                after,
+            )
+            .await?;
+            changed += 1;
+        }
+    }
+
+    // Meals billed before may have moved to another guardian, e.g. after the paying guardian changed
+    let stale = sqlx::query_scalar!(
+        "SELECT guardian_id FROM ledger_entries WHERE kind = 'charge' AND period = $1 AND amount != 0 AND guardian_id != ALL($2)",
+        start,
+        &billed
+    )
+    .fetch_all(&mut *tr)
+    .await?;
+
+    for guardian_id in stale {
+        let before = snapshot(&mut tr, AuditEntity::Ledger, guardian_id).await?;
+        sqlx::query!(
+            "UPDATE ledger_entries SET amount = 0 WHERE kind = 'charge' AND period = $1 AND guardian_id = $2",
+            start,
+            guardian_id
+        )
+        .execute(&mut *tr)
+        .await?;
+
+        let after = snapshot(&mut tr, AuditEntity::Ledger, guardian_id).await?;
+        record(
+            &mut tr,
+            &actor,
+            AuditAction::Update,
+            AuditEntity::Ledger,
+            guardian_id,
+            before,
+            after,
+        )
+        .await?;
+        changed += 1;
+    }
+
+    tr.commit().await?;
+    Ok(changed)
+}
+
+/// Imports incoming transfers of a CSV or MT940 bank statement and books the ones that can be matched to a guardian.
+/// Transfers imported before are skipped, so overlapping statements can be imported safely
+#[server(input = Json)]
+pub async fn import_bank_statement(
+    file: ImportFileDto,
+) -> Result<BankImportResultDto, ServerFnError> {
+    use std::collections::HashMap;
+    use sqlx::postgres::PgPool;
+    use crate::services::audit::{record, snapshot};
+    use crate::services::auth::authorize;
+    use crate::services::bank_statement::parse_bank_statement;
+    use dto::audit::{AuditAction, AuditEntity};
+    use dto::user::Role;
+
+    let pool: PgPool = use_context().ok_or(ServerFnError::new("Failed to retrieve db pool"))?;
+    let actor = authorize(&pool, &[Role::Admin]).await?;
+
+    let transfers = parse_bank_statement(&file)?;
+
+    let mut tr = pool.begin().await?;
+    let mut candidates = match_candidates(&mut tr).await?;
+    let mut occurrences = HashMap::new();
+    let mut result = BankImportResultDto {
+        imported: 0,
+        duplicates: 0,
+        skipped: 0,
+        matched: 0,
+    };
+
+    for transfer in transfers {
+        let occurrence = occurrences.entry(transfer.fingerprint(0)).or_insert(0);
+        let fingerprint = transfer.fingerprint(*occurrence);
+        *occurrence += 1;
+
+        let Ok(amount) = i32::try_from(transfer.amount) else {
+            result.skipped += 1;
+            continue;
+        };
+        if amount <= 0 {
+            result.skipped += 1;
+            continue;
+        }
+
+        let inserted = sqlx::query!(
+            "INSERT INTO bank_transfers (fingerprint, booked, amount, title, counterparty, account) VALUES ($1,$2,$3,$4,$5,$6)
+            ON CONFLICT (fingerprint) DO NOTHING RETURNING id",
+            fingerprint,
+            transfer.booked,
+            amount,
+            transfer.title,
+            transfer.counterparty,
+            transfer.account
+        )
+        .fetch_optional(&mut *tr)
+        .await?;
+
+        let Some(inserted) = inserted else {
+            result.duplicates += 1;
+            continue;
+        };
+        result.imported += 1;
+
+        let text = format!(
+            "{} {}",
+            transfer.title,
+            transfer.counterparty.as_deref().unwrap_or_default()
+        );
+        let Some(candidate) = match_transfer(&text, transfer.amount, &candidates) else {
+            continue;
+        };
+        let guardian_id = candidates[candidate].guardian_id;
+
+        let before = snapshot(&mut tr, AuditEntity::Ledger, guardian_id).await?;
+        book_transfer(&mut tr, inserted.id, guardian_id).await?;
+        let after = snapshot(&mut tr, AuditEntity::Ledger, guardian_id).await?;
+        record(
+            &mut tr,
+            &actor,
+            AuditAction::Create,
+            AuditEntity::Ledger,
+            guardian_id,
+            before,
+            after,
+        )
+        .await?;
+
+        candidates[candidate].balance += transfer.amount;
+        result.matched += 1;
+    }
+
+    tr.commit().await?;
+    Ok(result)
+}
+
+#[server]
+pub async fn get_unmatched_transfers() -> Result<Vec<BankTransferDto>, ServerFnError> {
+    use sqlx::postgres::PgPool;
+    use crate::services::auth::authorize;
+    use dto::user::Role;
+
+    let pool: PgPool = use_context().ok_or(ServerFnError::new("Failed to retrieve db pool"))?;
+    authorize(&pool, &[Role::Admin]).await?;
+
+    let transfers = sqlx::query!(
+        "SELECT bank_transfers.id, bank_transfers.booked, bank_transfers.amount, title, counterparty, account FROM bank_transfers
+        LEFT JOIN ledger_entries ON ledger_entries.transfer_id = bank_transfers.id
+        WHERE ledger_entries.id IS NULL AND NOT bank_transfers.ignored
+        ORDER BY bank_transfers.booked DESC"
+    )
+    .fetch_all(&pool)
+    .await?
+    .into_iter()
+    .map(|row| BankTransferDto {
+        id: row.id,
+        booked: row.booked,
+        amount: row.amount as i64,
+        title: row.title,
+        counterparty: row.counterparty,
+        account: row.account,
+    })
+    .collect();
+
+    Ok(transfers)
+}
+
+#[server]
+pub async fn assign_transfer(transfer_id: Uuid, guardian_id: Uuid) -> Result<(), ServerFnError> {
+    use sqlx::postgres::PgPool;
+    use crate::services::audit::{record, snapshot};
+    use crate::services::auth::authorize;
+    use dto::audit::{AuditAction, AuditEntity};
+    use dto::user::Role;
+
+    let pool: PgPool = use_context().ok_or(ServerFnError::new("Failed to retrieve db pool"))?;
+    let actor = authorize(&pool, &[Role::Admin]).await?;
+
+    let mut tr = pool.begin().await?;
+
+    let assigned = sqlx::query!(
+        "SELECT id FROM ledger_entries WHERE transfer_id = $1",
+        transfer_id
+    )
+    .fetch_optional(&mut *tr)
+    .await?;
+    if assigned.is_some() {
+        return Err(ServerFnError::new("Transfer is already assigned"));
+    }
+
+    let before = snapshot(&mut tr, AuditEntity::Ledger, guardian_id).await?;
+    book_transfer(&mut tr, transfer_id, guardian_id).await?;
+    let after = snapshot(&mut tr, AuditEntity::Ledger, guardian_id).await?;
+    record(
+        &mut tr,
+        &actor,
+        AuditAction::Create,
+        AuditEntity::Ledger,
+        guardian_id,
+        before,
+        after,
+    )
+    .await?;
+
+    tr.commit().await?;
+    Ok(())
+}
+
+/// Hides a transfer that isn't a payment for meals from the list of unmatched transfers
+#[server]
+pub async fn ignore_transfer(transfer_id: Uuid) -> Result<(), ServerFnError> {
+    use sqlx::postgres::PgPool;
+    use crate::services::auth::authorize;
+    use dto::user::Role;
+
+    let pool: PgPool = use_context().ok_or(ServerFnError::new("Failed to retrieve db pool"))?;
+    authorize(&pool, &[Role::Admin]).await?;
+
+    sqlx::query!(
+        "UPDATE bank_transfers SET ignored = true WHERE id = $1",
+        transfer_id
+    )
+    .execute(&pool)
+    .await?;
+    Ok(())
+}
+
+#[cfg(all(test, feature = "ssr"))]
+mod tests {
+    use super::*;
+
+    fn candidate(names: &[&str], balance: i64) -> MatchCandidate {
+        MatchCandidate {
+            guardian_id: Uuid::new_v4(),
+            names: names.iter().map(|name| fold_text(name)).collect(),
+            balance,
+        }
+    }
+
+    fn candidates() -> Vec<MatchCandidate> {
+        vec![
+            candidate(&["Janina Kowalska", "Jan Kowalski", "Kowalski Jan"], -12000),
+            candidate(&["Piotr Kowalski", "Jan Kowalski", "Kowalski Jan"], -12000),
+            candidate(&["Anna Nowak", "Zofia Nowak", "Nowak Zofia"], -8000),
+            candidate(
+                &["Ewa Wiśniewska", "Ola Wiśniewska", "Wiśniewska Ola"],
+                -4550,
+            ),
+        ]
+    }
+
+    #[test]
+    fn title_naming_one_guardian_matches() {
+        let candidates = candidates();
+        assert_eq!(
+            match_transfer("Opłata za posiłki ZOFIA NOWAK styczeń", 1, &candidates),
+            Some(2)
+        );
+        // Bank titles are cut mid-word and lose polish letters
+        assert_eq!(
+            match_transfer("Posilki Ola Wisni ewska", 4550, &candidates),
+            Some(3)
+        );
+        assert_eq!(
+            match_transfer("WPLATA OD: JANINA KOWALSKA", 999, &candidates),
+            Some(0)
+        );
+    }
+
+    #[test]
+    fn ambiguous_title_is_settled_by_amount() {
+        let mut candidates = candidates();
+        // Both guardians of Jan owe the same amount, the transfer can't be told apart
+        assert_eq!(
+            match_transfer("Posiłki Jan Kowalski", 12000, &candidates),
+            None
+        );
+
+        candidates[1].balance = -3000;
+        assert_eq!(
+            match_transfer("Posiłki Jan Kowalski", 12000, &candidates),
+            Some(0)
+        );
+        assert_eq!(
+            match_transfer("Posiłki Jan Kowalski", 3000, &candidates),
+            Some(1)
+        );
+        // Amount owed by someone not named in the title doesn't count
+        assert_eq!(
+            match_transfer("Posiłki Jan Kowalski", 8000, &candidates),
+            None
+        );
+    }
+
+    #[test]
+    fn title_naming_nobody_falls_back_to_amount() {
+        let candidates = candidates();
+        assert_eq!(
+            match_transfer("Przelew za obiady", 8000, &candidates),
+            Some(2)
+        );
+        assert_eq!(
+            match_transfer("Przelew za obiady", 12000, &candidates),
+            None
+        );
+        assert_eq!(match_transfer("Przelew za obiady", 100, &candidates), None);
+        assert_eq!(match_transfer("Przelew za obiady", 8000, &[]), None);
+    }
+}
@@ -3,7 +3,10 @@ use std::collections::HashMap;
 use chrono::TimeDelta;
 use dto::{
     guardian::{GuardianDetailDto, GuardianDto},
-    messages::{parse_message, ContactDto, DbMessage, Message, MessageProcessing, PhoneStatusDto},
+    messages::{
+        parse_message, ConnectorStatusDto, ContactDto, DbMessage, Message, MessageProcessing,
+        PhoneStatusDto,
+    },
     student::StudentDto,
 };
 use leptos::prelude::*;
@@ -14,9 +17,12 @@ use crate::components::snackbar::MsgType;
 #[server]
 pub async fn get_contacts() -> Result<Vec<ContactDto>, ServerFnError> {
     use sqlx::postgres::PgPool;
+    use crate::services::auth::authorize;
+    use dto::user::Role;
     use uuid::Uuid;
 
     let pool: PgPool = use_context().ok_or(ServerFnError::new("Failed to retrieve db pool"))?;
+    authorize(&pool, &[Role::Admin, Role::Teacher]).await?;
 
     let guardians = sqlx::query!("WITH randomz AS (SELECT DISTINCT phone FROM messages)
         SELECT fullname, COALESCE(guardians.phone,randomz.phone) AS phone, id FROM guardians 
@@ -53,8 +59,11 @@ pub async fn get_contacts() -> Result<Vec<ContactDto>, ServerFnError> {
 #[server]
 pub async fn get_guardian_details(id: Uuid) -> Result<GuardianDetailDto, ServerFnError> {
     use sqlx::postgres::PgPool;
+    use crate::services::auth::authorize;
+    use dto::user::Role;
 
     let pool: PgPool = use_context().ok_or(ServerFnError::new("Failed to retrieve db pool"))?;
+    authorize(&pool, &[Role::Admin, Role::Teacher]).await?;
 
     let guardian = sqlx::query!(
         "SELECT guardians.id, guardians.fullname, guardians.phone FROM guardians
@@ -98,10 +107,17 @@ pub async fn get_guardian_details(id: Uuid) -> Result<GuardianDetailDto, ServerF
 #[server]
 pub async fn update_guardian(guardian: GuardianDetailDto) -> Result<(), ServerFnError> {
     use sqlx::postgres::PgPool;
+    use crate::services::audit::{record, snapshot};
+    use crate::services::auth::authorize;
+    use dto::audit::{AuditAction, AuditEntity};
+    use dto::user::Role;
 
     let pool: PgPool = use_context().ok_or(ServerFnError::new("Failed to retrieve db pool"))?;
+    let actor = authorize(&pool, &[Role::Admin]).await?;
     let mut tr = pool.begin().await?;
 
+    let before = snapshot(&mut tr, AuditEntity::Guardian, guardian.id).await?;
+
     let affected = sqlx::query!(
         "UPDATE guardians SET phone=$2 , fullname=$3 WHERE id = $1",
         guardian.id,
@@ -113,6 +129,18 @@ pub async fn update_guardian(guardian: GuardianDetailDto) -> Result<(), ServerFn
     if affected.rows_affected() != 1 {
         return Err(ServerFnError::new("Failed to update guardian"));
     }
+    let after = snapshot(&mut tr, AuditEntity::Guardian, guardian.id).await?;
+    record(
+        &mut tr,
+        &actor,
+        AuditAction::Update,
+        AuditEntity::Guardian,
+        guardian.id,
+        before,
+        after,
+    )
+    .await?;
+
     tr.commit().await?;
     Ok(())
 }
@@ -120,25 +148,49 @@ pub async fn update_guardian(guardian: GuardianDetailDto) -> Result<(), ServerFn
 #[server]
 pub async fn send_message(phone: String, content: String) -> Result<Uuid, ServerFnError> {
     use sqlx::postgres::PgPool;
+    use crate::services::audit::{record, snapshot};
+    use crate::services::auth::authorize;
+    use crate::services::template::render_for_phone;
+    use dto::audit::{AuditAction, AuditEntity};
+    use dto::user::Role;
 
     let pool: PgPool = use_context().ok_or(ServerFnError::new("Failed to retrieve db pool"))?;
+    let actor = authorize(&pool, &[Role::Admin]).await?;
+    let mut tr = pool.begin().await?;
+    let content = render_for_phone(&mut tr, &phone, &content).await?;
     let id = sqlx::query!(
         "INSERT INTO messages (phone, content, outgoing) VALUES ($1,$2,true) RETURNING id",
         phone,
         content
     )
-    .fetch_one(&pool)
+    .fetch_one(&mut *tr)
     .await?
     .id;
 
+    let after = snapshot(&mut tr, AuditEntity::Message, id).await?;
+    record(
+        &mut tr,
+        &actor,
+        AuditAction::Send,
+        AuditEntity::Message,
+        id,
+        None,
+        after,
+    )
+    .await?;
+
+    tr.commit().await?;
     Ok(id)
 }
 
 #[server]
 pub async fn get_messages(phone: String) -> Result<Vec<Message>, ServerFnError> {
     use sqlx::postgres::PgPool;
+    use crate::services::auth::authorize;
+    use dto::user::Role;
 
     let pool: PgPool = use_context().ok_or(ServerFnError::new("Failed to retrieve db pool"))?;
+    authorize(&pool, &[Role::Admin, Role::Teacher]).await?;
 
     let messages = sqlx::query_as!(
         DbMessage,
@@ -155,17 +207,40 @@ pub async fn get_messages(phone: String) -> Result<Vec<Message>, ServerFnError>
 #[server]
 pub async fn requeue_message(msg_id: Uuid) -> Result<(), ServerFnError> {
     use sqlx::postgres::PgPool;
+    use crate::services::audit::{record, snapshot};
+    use crate::services::auth::authorize;
+    use dto::audit::{AuditAction, AuditEntity};
+    use dto::user::Role;
 
     let pool: PgPool = use_context().ok_or(ServerFnError::new("Failed to retrieve db pool"))?;
+    let actor = authorize(&pool, &[Role::Admin]).await?;
     let mut tr = pool.begin().await?;
 
+    let before = snapshot(&mut tr, AuditEntity::Message, msg_id).await?;
+
     sqlx::query!(
-        "UPDATE messages SET processed = false WHERE id = $1",
+        "UPDATE messages SET processed = false,
+        status = CASE WHEN outgoing THEN 'queued' ELSE status END,
+        attempts = CASE WHEN outgoing THEN 0 ELSE attempts END,
+        next_attempt = NULL, last_error = NULL
+        WHERE id = $1",
         msg_id
     )
     .execute(&mut *tr)
     .await?;
 
+    let after = snapshot(&mut tr, AuditEntity::Message, msg_id).await?;
+    record(
+        &mut tr,
+        &actor,
+        AuditAction::Requeue,
+        AuditEntity::Message,
+        msg_id,
+        before,
+        after,
+    )
+    .await?;
+
     tr.commit().await?;
     Ok(())
 }
@@ -175,8 +250,11 @@ pub async fn get_message_processing_info(
     msg: Uuid,
 ) -> Result<HashMap<Uuid, Vec<MessageProcessing>>, ServerFnError> {
     use sqlx::postgres::PgPool;
+    use crate::services::auth::authorize;
+    use dto::user::Role;
 
     let pool: PgPool = use_context().ok_or(ServerFnError::new("Failed to retrieve db pool"))?;
+    authorize(&pool, &[Role::Admin, Role::Teacher]).await?;
 
     let processing_info = sqlx::query!(
         "SELECT processing_step.* FROM processing_step 
@@ -198,21 +276,132 @@ pub async fn get_message_processing_info(
     Ok(result)
 }
 
+/// Line is considered offline when the connector hasn't reported it for this long
+#[cfg(feature = "ssr")]
+const PHONE_OFFLINE_AFTER: TimeDelta = TimeDelta::minutes(3);
+#[cfg(feature = "ssr")]
+const PHONE_HISTORY_LENGTH: i64 = 10;
+
+/// Time after which an offline or locked line raises an alert, `PHONE_ALERT_MINUTES` or 15 minutes
+#[cfg(feature = "ssr")]
+fn phone_alert_after() -> TimeDelta {
+    std::env::var("PHONE_ALERT_MINUTES")
+        .ok()
+        .and_then(|minutes| minutes.parse().ok())
+        .map(TimeDelta::minutes)
+        .unwrap_or(TimeDelta::minutes(15))
+}
+
 #[server]
-pub async fn get_phone_status() -> Result<Option<PhoneStatusDto>, ServerFnError> {
+pub async fn get_phone_status() -> Result<Vec<PhoneStatusDto>, ServerFnError> {
     use sqlx::postgres::PgPool;
+    use crate::services::auth::authorize;
+    use chrono::Local;
+    use dto::messages::PhoneStateChangeDto;
+    use dto::user::Role;
 
     let pool: PgPool = use_context().ok_or(ServerFnError::new("Failed to retrieve db pool"))?;
+    authorize(&pool, &[Role::Admin, Role::Teacher]).await?;
+
+    let phones = sqlx::query!(
+        "SELECT number, signal, state, locked, last_seen, state_since, total_sent, total_received
+        FROM phones ORDER BY number"
+    )
+    .fetch_all(&pool)
+    .await?;
+
+    let now = Local::now().naive_local();
+    let alert_after = phone_alert_after();
+    let mut result = Vec::with_capacity(phones.len());
+
+    for phone in phones {
+        let history = sqlx::query_as!(
+            PhoneStateChangeDto,
+            "SELECT recorded, state, signal, locked FROM phone_state_history
+            WHERE number = $1 ORDER BY recorded DESC LIMIT $2",
+            phone.number,
+            PHONE_HISTORY_LENGTH
+        )
+        .fetch_all(&pool)
+        .await?;
+
+        let state = phone.state.unwrap_or(0);
+        let problem = if now - phone.last_seen > PHONE_OFFLINE_AFTER {
+            Some(("Brak kontaktu z modemem", phone.last_seen))
+        } else if phone.locked {
+            Some(("Karta SIM zablokowana", phone.state_since))
+        } else if state < 8 {
+            Some(("Modem nie jest zarejestrowany w sieci", phone.state_since))
+        } else {
+            None
+        };
+        let alert = problem
+            .filter(|(_, since)| now - *since > alert_after)
+            .map(|(problem, since)| format!("{} od {}", problem, since.format("%Y-%m-%d %H:%M")));
+
+        result.push(PhoneStatusDto {
+            number: phone.number,
+            last_updated: phone.last_seen,
+            state_since: phone.state_since,
+            total_sent: phone.total_sent,
+            total_received: phone.total_received,
+            signal: phone.signal.unwrap_or(0),
+            state,
+            locked: phone.locked,
+            alert,
+            history,
+        });
+    }
 
-    Ok(None)
+    Ok(result)
+}
+
+/// Connector is considered down when it hasn't confirmed it's alive for this long
+#[cfg(feature = "ssr")]
+const CONNECTOR_OFFLINE_AFTER: TimeDelta = TimeDelta::minutes(2);
+
+#[server]
+pub async fn get_connector_status() -> Result<ConnectorStatusDto, ServerFnError> {
+    use sqlx::postgres::PgPool;
+    use crate::services::auth::authorize;
+    use chrono::Local;
+    use dto::user::Role;
+
+    let pool: PgPool = use_context().ok_or(ServerFnError::new("Failed to retrieve db pool"))?;
+    authorize(&pool, &[Role::Admin, Role::Teacher]).await?;
+
+    let status = sqlx::query!("SELECT state, detail, since, heartbeat FROM connector_status")
+        .fetch_optional(&pool)
+        .await?;
+
+    let now = Local::now().naive_local();
+    Ok(match status {
+        Some(status) => ConnectorStatusDto {
+            state: status.state.parse().ok(),
+            detail: status.detail,
+            since: Some(status.since),
+            heartbeat: Some(status.heartbeat),
+            online: now - status.heartbeat < CONNECTOR_OFFLINE_AFTER,
+        },
+        None => ConnectorStatusDto {
+            state: None,
+            detail: None,
+            since: None,
+            heartbeat: None,
+            online: false,
+        },
+    })
 }
 
 #[server]
 pub async fn get_latest_messages(time_span: TimeDelta) -> Result<Vec<Message>, ServerFnError> {
     use sqlx::postgres::types::PgInterval;
     use sqlx::postgres::PgPool;
+    use crate::services::auth::authorize;
+    use dto::user::Role;
 
     let pool: PgPool = use_context().ok_or(ServerFnError::new("Failed to retrieve db pool"))?;
+    authorize(&pool, &[Role::Admin, Role::Teacher]).await?;
     let interval: PgInterval = time_span.try_into().unwrap();
 
     let messages = sqlx::query_as!(
@@ -231,8 +420,11 @@ pub async fn get_latest_messages(time_span: TimeDelta) -> Result<Vec<Message>, S
 #[server]
 pub async fn simulate_message(from: String, content: String) -> Result<(), ServerFnError> {
     use sqlx::postgres::PgPool;
+    use crate::services::auth::authorize;
+    use dto::user::Role;
 
     let pool: PgPool = use_context().ok_or(ServerFnError::new("Failed to retrieve db pool"))?;
+    authorize(&pool, &[Role::Admin]).await?;
 
     sqlx::query!(
         "INSERT INTO messages (phone, content, outgoing, sent) VALUES ($1,$2,false,NOW())",
@@ -0,0 +1,402 @@
+use dto::portal::{PortalAttendanceDto, PortalGuardianDto, PortalMonthDto};
+use leptos::prelude::*;
+use uuid::Uuid;
+
+#[cfg(feature = "ssr")]
+const PORTAL_COOKIE: &str = "wrangler_portal";
+#[cfg(feature = "ssr")]
+const PORTAL_SESSION_DAYS: i64 = 30;
+#[cfg(feature = "ssr")]
+const CODE_MINUTES: i32 = 10;
+#[cfg(feature = "ssr")]
+const CODE_ATTEMPTS: i32 = 5;
+#[cfg(feature = "ssr")]
+const CODE_RESEND_SECONDS: f64 = 60.0;
+/// Codes sent and failed logins are limited per guardian within this window, the guardian is locked
+/// out until the window passes once either limit is reached
+#[cfg(feature = "ssr")]
+const CODE_WINDOW_HOURS: i32 = 24;
+#[cfg(feature = "ssr")]
+const CODES_PER_WINDOW: i32 = 5;
+#[cfg(feature = "ssr")]
+const FAILED_ATTEMPTS_PER_WINDOW: i32 = 10;
+
+#[cfg(feature = "ssr")]
+async fn find_guardian(
+    pool: &sqlx::PgPool,
+    phone: &str,
+) -> Result<Option<(Uuid, String)>, ServerFnError> {
+    let phone = phone
+        .chars()
+        .filter(|c| !c.is_whitespace() && *c != '-')
+        .collect::<String>();
+
+    Ok(sqlx::query!(
+        r#"SELECT id, phone AS "phone!" FROM guardians
+        WHERE NOT COALESCE(removed, false) AND phone IS NOT NULL
+        AND (phone = $1 OR format('+48%s', phone) = $1 OR phone = format('+48%s', $1))"#,
+        phone
+    )
+    .fetch_optional(pool)
+    .await?
+    .map(|row| (row.id, row.phone)))
+}
+
+#[cfg(feature = "ssr")]
+pub async fn current_guardian(pool: &sqlx::PgPool) -> Result<Option<Uuid>, ServerFnError> {
+    use crate::services::auth::read_cookie;
+
+    let Some(token) = read_cookie(PORTAL_COOKIE).await else {
+        return Ok(None);
+    };
+
+    Ok(sqlx::query_scalar!(
+        "SELECT guardians.id FROM guardian_sessions
+        INNER JOIN guardians ON guardians.id = guardian_sessions.guardian_id
+        WHERE guardian_sessions.id = $1 AND guardian_sessions.expires > LOCALTIMESTAMP AND NOT COALESCE(guardians.removed, false)",
+        token
+    )
+    .fetch_optional(pool)
+    .await?)
+}
+
+/// Logged in guardian, the student has to be one of their children
+#[cfg(feature = "ssr")]
+async fn authorize_student(pool: &sqlx::PgPool, student_id: Uuid) -> Result<Uuid, ServerFnError> {
+    let guardian_id = current_guardian(pool)
+        .await?
+        .ok_or(ServerFnError::new("Not logged in"))?;
+
+    sqlx::query!(
+        "SELECT students.id FROM student_guardians
+        INNER JOIN students ON students.id = student_guardians.student_id
+        WHERE student_guardians.guardian_id = $1 AND students.id = $2 AND NOT students.removed",
+        guardian_id,
+        student_id
+    )
+    .fetch_optional(pool)
+    .await?
+    .ok_or(ServerFnError::new("Insufficient permissions"))?;
+
+    Ok(guardian_id)
+}
+
+#[cfg(feature = "ssr")]
+async fn load_guardian(
+    pool: &sqlx::PgPool,
+    guardian_id: Uuid,
+) -> Result<PortalGuardianDto, ServerFnError> {
+    use dto::portal::PortalStudentDto;
+
+    let fullname = sqlx::query_scalar!("SELECT fullname FROM guardians WHERE id = $1", guardian_id)
+        .fetch_one(pool)
+        .await?;
+
+    let students = sqlx::query!(
+        "SELECT students.id, students.name, students.surname FROM student_guardians
+        INNER JOIN students ON students.id = student_guardians.student_id
+        WHERE student_guardians.guardian_id = $1 AND NOT students.removed
+        ORDER BY students.name, students.surname",
+        guardian_id
+    )
+    .fetch_all(pool)
+    .await?
+    .into_iter()
+    .map(|row| PortalStudentDto {
+        id: row.id,
+        name: row.name,
+        surname: row.surname,
+    })
+    .collect();
+
+    Ok(PortalGuardianDto {
+        id: guardian_id,
+        fullname,
+        students,
+    })
+}
+
+/// Sends a one-time login code to the guardian. The response is the same for unknown numbers and for
+/// guardians that are throttled or locked out, so it can't be used to tell which numbers are known
+#[server]
+pub async fn request_portal_code(phone: String) -> Result<(), ServerFnError> {
+    use crate::services::auth::hash_password;
+    use rand::Rng;
+    use sqlx::postgres::PgPool;
+
+    let pool: PgPool = use_context().ok_or(ServerFnError::new("Failed to retrieve db pool"))?;
+
+    // Hashed up front so unknown numbers take as long to answer as known ones
+    let code = format!("{:06}", rand::rng().random_range(0..1_000_000));
+    let hash = hash_password(&code)?;
+
+    let Some((guardian_id, phone)) = find_guardian(&pool, &phone).await? else {
+        return Ok(());
+    };
+
+    let mut tr = pool.begin().await?;
+
+    let throttled = sqlx::query_scalar!(
+        r#"SELECT created > LOCALTIMESTAMP - make_interval(secs => $2)
+            OR (window_started > LOCALTIMESTAMP - make_interval(hours => $3) AND (codes_sent >= $4 OR failed_attempts >= $5)) AS "throttled!"
+        FROM guardian_login_codes WHERE guardian_id = $1 FOR UPDATE"#,
+        guardian_id,
+        CODE_RESEND_SECONDS,
+        CODE_WINDOW_HOURS,
+        CODES_PER_WINDOW,
+        FAILED_ATTEMPTS_PER_WINDOW
+    )
+    .fetch_optional(&mut *tr)
+    .await?
+    .unwrap_or(false);
+    if throttled {
+        return Ok(());
+    }
+
+    sqlx::query!(
+        "INSERT INTO guardian_login_codes (guardian_id, code_hash, expires) VALUES ($1, $2, LOCALTIMESTAMP + make_interval(mins => $3))
+        ON CONFLICT (guardian_id) DO UPDATE SET code_hash = EXCLUDED.code_hash, attempts = 0, created = EXCLUDED.created, expires = EXCLUDED.expires,
+        codes_sent = CASE WHEN guardian_login_codes.window_started > LOCALTIMESTAMP - make_interval(hours => $4) THEN guardian_login_codes.codes_sent + 1 ELSE 1 END,
+        failed_attempts = CASE WHEN guardian_login_codes.window_started > LOCALTIMESTAMP - make_interval(hours => $4) THEN guardian_login_codes.failed_attempts ELSE 0 END,
+        window_started = CASE WHEN guardian_login_codes.window_started > LOCALTIMESTAMP - make_interval(hours => $4) THEN guardian_login_codes.window_started ELSE EXCLUDED.window_started END",
+        guardian_id,
+        hash,
+        CODE_MINUTES,
+        CODE_WINDOW_HOURS
+    )
+    .execute(&mut *tr)
+    .await?;
+
+    sqlx::query!(
+        "INSERT INTO messages (phone, content, outgoing) VALUES ($1, $2, true)",
+        phone,
+        format!(
+            "Kod logowania do portalu rodzica: {}. Kod jest ważny {} minut.",
+            code, CODE_MINUTES
+        )
+    )
+    .execute(&mut *tr)
+    .await?;
+
+    tr.commit().await?;
+    Ok(())
+}
+
+#[server]
+pub async fn portal_login(phone: String, code: String) -> Result<PortalGuardianDto, ServerFnError> {
+    use crate::services::auth::{set_cookie, verify_password};
+    use rand::distr::{Alphanumeric, SampleString};
+    use sqlx::postgres::PgPool;
+
+    let pool: PgPool = use_context().ok_or(ServerFnError::new("Failed to retrieve db pool"))?;
+
+    let (guardian_id, _) = find_guardian(&pool, &phone)
+        .await?
+        .ok_or(ServerFnError::new("Invalid phone or code"))?;
+
+    let mut tr = pool.begin().await?;
+
+    let hash = sqlx::query_scalar!(
+        "SELECT code_hash FROM guardian_login_codes
+        WHERE guardian_id = $1 AND expires > LOCALTIMESTAMP AND attempts < $2 AND failed_attempts < $3 FOR UPDATE",
+        guardian_id,
+        CODE_ATTEMPTS,
+        FAILED_ATTEMPTS_PER_WINDOW
+    )
+    .fetch_optional(&mut *tr)
+    .await?
+    .ok_or(ServerFnError::new("Invalid phone or code"))?;
+
+    if !verify_password(code.trim(), &hash) {
+        sqlx::query!(
+            "UPDATE guardian_login_codes SET attempts = attempts + 1, failed_attempts = failed_attempts + 1 WHERE guardian_id = $1",
+            guardian_id
+        )
+        .execute(&mut *tr)
+        .await?;
+        tr.commit().await?;
+        return Err(ServerFnError::new("Invalid phone or code"));
+    }
+
+    let token = Alphanumeric.sample_string(&mut rand::rng(), 48);
+
+    sqlx::query!(
+        "DELETE FROM guardian_login_codes WHERE guardian_id = $1",
+        guardian_id
+    )
+    .execute(&mut *tr)
+    .await?;
+    sqlx::query!("DELETE FROM guardian_sessions WHERE expires < LOCALTIMESTAMP")
+        .execute(&mut *tr)
+        .await?;
+    sqlx::query!(
+        "INSERT INTO guardian_sessions (id, guardian_id, expires) VALUES ($1, $2, LOCALTIMESTAMP + make_interval(days => $3))",
+        token,
+        guardian_id,
+        PORTAL_SESSION_DAYS as i32
+    )
+    .execute(&mut *tr)
+    .await?;
+    tr.commit().await?;
+
+    set_cookie(PORTAL_COOKIE, &token, PORTAL_SESSION_DAYS * 24 * 60 * 60)?;
+
+    load_guardian(&pool, guardian_id).await
+}
+
+#[server]
+pub async fn portal_logout() -> Result<(), ServerFnError> {
+    use crate::services::auth::{read_cookie, set_cookie};
+    use sqlx::postgres::PgPool;
+
+    let pool: PgPool = use_context().ok_or(ServerFnError::new("Failed to retrieve db pool"))?;
+
+    if let Some(token) = read_cookie(PORTAL_COOKIE).await {
+        sqlx::query!("DELETE FROM guardian_sessions WHERE id = $1", token)
+            .execute(&pool)
+            .await?;
+    }
+    set_cookie(PORTAL_COOKIE, "", 0)
+}
+
+#[server]
+pub async fn get_portal_guardian() -> Result<Option<PortalGuardianDto>, ServerFnError> {
+    use sqlx::postgres::PgPool;
+
+    let pool: PgPool = use_context().ok_or(ServerFnError::new("Failed to retrieve db pool"))?;
+
+    match current_guardian(&pool).await? {
+        Some(guardian_id) => Ok(Some(load_guardian(&pool, guardian_id).await?)),
+        None => Ok(None),
+    }
+}
+
+#[server]
+pub async fn get_portal_month(
+    student_id: Uuid,
+    year: i32,
+    month: u32,
+) -> Result<PortalMonthDto, ServerFnError> {
+    use crate::services::attendance::effective_attendance;
+    use crate::services::closure::closed_days;
+    use attendance_rules::store::load_catering;
+    use attendance_rules::Actor;
+    use chrono::{Local, Months, NaiveDate};
+    use dto::attendance::CateringMealDto;
+    use dto::catering::MealCutoffDto;
+    use sqlx::postgres::PgPool;
+
+    let pool: PgPool = use_context().ok_or(ServerFnError::new("Failed to retrieve db pool"))?;
+    authorize_student(&pool, student_id).await?;
+
+    let start = NaiveDate::from_ymd_opt(year, month, 1)
+        .ok_or(ServerFnError::new("Failed to parse provided date"))?;
+    let end = start
+        .checked_add_months(Months::new(1))
+        .ok_or(ServerFnError::new("Failed to parse provided date"))?;
+
+    let catering_id = sqlx::query_scalar!(
+        "SELECT caterings.id FROM caterings
+        INNER JOIN group_relations ON group_relations.parent = caterings.group_id
+        WHERE group_relations.child = $1",
+        student_id
+    )
+    .fetch_optional(&pool)
+    .await?
+    .ok_or(ServerFnError::new("No catering found for given student"))?;
+    let rules = load_catering(student_id, &mut *pool.acquire().await?)
+        .await?
+        .ok_or(ServerFnError::new("No catering found for given student"))?;
+
+    let meals = sqlx::query!(
+        "SELECT meals.id, meals.name, catering_meals.cutoff_days, catering_meals.cutoff_time FROM meals
+        INNER JOIN catering_meals ON catering_meals.meal_id = meals.id
+        WHERE catering_meals.catering_id = $1 ORDER BY meal_order",
+        catering_id
+    )
+    .fetch_all(&pool)
+    .await?
+    .into_iter()
+    .map(|row| CateringMealDto {
+        id: row.id,
+        name: row.name,
+        cutoff: MealCutoffDto {
+            days_before: row.cutoff_days.max(0) as u32,
+            time: row.cutoff_time,
+        },
+    })
+    .collect();
+
+    let closures = closed_days(&mut *pool.acquire().await?, catering_id, start, end).await?;
+    let attendance = effective_attendance(&pool, student_id, start, end).await?;
+    let now = Local::now().naive_local();
+
+    Ok(PortalMonthDto {
+        meals,
+        days_of_week: rules.days_of_week.to_vec(),
+        start: rules.since,
+        end: rules.until,
+        closures,
+        attendance,
+        editable_since: rules
+            .first_open_day(Actor::Guardian, now)
+            .ok_or(ServerFnError::new("Invalid date"))?,
+        loaded: now,
+    })
+}
+
+#[server]
+pub async fn update_portal_attendance(dto: PortalAttendanceDto) -> Result<(), ServerFnError> {
+    use attendance_rules::store::{load_catering, record_attendance};
+    use attendance_rules::Actor;
+    use chrono::Local;
+    use sqlx::postgres::PgPool;
+
+    let pool: PgPool = use_context().ok_or(ServerFnError::new("Failed to retrieve db pool"))?;
+    let guardian_id = authorize_student(&pool, dto.student_id).await?;
+
+    if dto.meals.is_empty() {
+        return Err(ServerFnError::new("No meals selected"));
+    }
+
+    let mut tr = pool.begin().await?;
+
+    let catering = load_catering(dto.student_id, &mut *tr)
+        .await?
+        .ok_or(ServerFnError::new("No catering found for given student"))?;
+    let meals = catering
+        .select_meals(&dto.meals)
+        .map_err(ServerFnError::new)?;
+    let changes = catering
+        .open_meals(
+            Actor::Guardian,
+            Local::now().naive_local(),
+            &meals,
+            dto.since,
+            dto.until,
+        )
+        .map_err(ServerFnError::new)?;
+
+    let request_id = sqlx::query_scalar!(
+        "INSERT INTO guardian_requests (guardian_id) VALUES ($1) RETURNING id",
+        guardian_id
+    )
+    .fetch_one(&mut *tr)
+    .await?;
+
+    for (meal_id, days) in changes {
+        record_attendance(
+            request_id,
+            dto.student_id,
+            Actor::Guardian,
+            &days,
+            &[meal_id],
+            dto.present,
+            &mut *tr,
+        )
+        .await?;
+    }
+
+    tr.commit().await?;
+    Ok(())
+}
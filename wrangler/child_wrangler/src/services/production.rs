@@ -0,0 +1,276 @@
+use chrono::NaiveDate;
+use dto::production::ProductionReportDto;
+use leptos::prelude::*;
+
+/// Counts portions of every catering serving on `day`. Only attendance recorded before the catering's
+/// cut-off (the day at its grace period) is taken into account, so changes made after the kitchen
+/// started cooking don't alter the report
+#[cfg(feature = "ssr")]
+pub async fn load_production_report(
+    conn: &mut sqlx::PgConnection,
+    day: NaiveDate,
+) -> Result<ProductionReportDto, ServerFnError> {
+    use dto::production::{CateringProductionDto, GroupProductionDto, MealProductionDto};
+
+    let caterings = sqlx::query!(
+        r#"SELECT caterings.id, groups.name, ($1::date + caterings.grace_period) AS "cutoff!", ($1::date + caterings.grace_period) <= LOCALTIMESTAMP AS "locked!" FROM caterings
+        INNER JOIN groups ON groups.id = caterings.group_id
+        WHERE caterings.since <= $1 AND caterings.until >= $1 AND NOT groups.removed
+        AND ((caterings.dow >> (EXTRACT(DOW FROM $1::date)::smallint + 6) % 7 )&1) = 1
+        ORDER BY groups.name"#,
+        day
+    )
+    .fetch_all(&mut *conn)
+    .await?;
+
+    let mut report = ProductionReportDto {
+        day,
+        caterings: vec![],
+    };
+
+    for catering in caterings {
+        let rows = sqlx::query!(
+            r#"WITH effective AS (SELECT DISTINCT ON (meal_id, target) meal_id, target, value FROM attendance
+            WHERE day = $2 AND originated <= $3
+            ORDER BY meal_id, target, originated DESC, cause_id),
+            present AS (SELECT students.id, students.allergy_combination_id, effective.meal_id FROM caterings
+            INNER JOIN student_targets AS catering_target ON catering_target.target = caterings.group_id AND $2 BETWEEN catering_target.since AND catering_target.until
+            INNER JOIN students ON students.id = catering_target.student_id
+            INNER JOIN student_targets ON student_targets.student_id = students.id AND $2 BETWEEN student_targets.since AND student_targets.until
+            INNER JOIN effective ON effective.target = student_targets.target
+            WHERE caterings.id = $1
+            GROUP BY students.id, effective.meal_id
+            HAVING bool_and(effective.value)),
+            diets AS (SELECT allergy_combinations.id, string_agg(allergies.name, ', ' ORDER BY allergies.name) AS name FROM allergy_combinations
+            INNER JOIN allergies ON allergies.id = allergy_combinations.allergy_id
+            GROUP BY allergy_combinations.id)
+            SELECT meals.id AS meal_id, meals.name AS meal, groups.name AS "group", diets.name AS "diet?", COUNT(*) AS "portions!" FROM present
+            INNER JOIN meals ON meals.id = present.meal_id
+            INNER JOIN catering_meals ON catering_meals.catering_id = $1 AND catering_meals.meal_id = meals.id
+            INNER JOIN student_targets AS placement ON placement.student_id = present.id AND placement.level = 1 AND $2 BETWEEN placement.since AND placement.until
+            INNER JOIN groups ON groups.id = placement.target
+            LEFT JOIN diets ON diets.id = present.allergy_combination_id
+            GROUP BY meals.id, catering_meals.meal_order, groups.id, diets.name
+            ORDER BY catering_meals.meal_order, groups.name, groups.id, diets.name NULLS FIRST"#,
+            catering.id,
+            day,
+            catering.cutoff
+        )
+        .fetch_all(&mut *conn)
+        .await?;
+
+        let mut meals: Vec<MealProductionDto> = vec![];
+        for row in rows {
+            if meals.last().is_none_or(|meal| meal.meal_id != row.meal_id) {
+                meals.push(MealProductionDto {
+                    meal_id: row.meal_id,
+                    meal: row.meal,
+                    groups: vec![],
+                    total: Default::default(),
+                });
+            }
+            let Some(meal) = meals.last_mut() else {
+                continue;
+            };
+
+            // Combinations without allergies are regular portions
+            let diet = row.diet.filter(|diet| !diet.is_empty());
+            let portions = row.portions as u32;
+            meal.total.add(diet.clone(), portions);
+
+            if meal.groups.last().is_none_or(|group| group.group != row.group) {
+                meal.groups.push(GroupProductionDto {
+                    group: row.group,
+                    portions: Default::default(),
+                });
+            }
+            if let Some(group) = meal.groups.last_mut() {
+                group.portions.add(diet, portions);
+            }
+        }
+
+        report.caterings.push(CateringProductionDto {
+            catering_id: catering.id,
+            catering: catering.name,
+            cutoff: catering.cutoff,
+            locked: catering.locked,
+            meals,
+        });
+    }
+
+    Ok(report)
+}
+
+#[cfg(feature = "ssr")]
+fn render_production_pdf(report: &ProductionReportDto) -> Result<Vec<u8>, ServerFnError> {
+    use crate::services::billing::strip_diacritics;
+    use printpdf::{BuiltinFont, Mm, PdfDocument};
+
+    const PAGE_WIDTH: Mm = Mm(210.0);
+    const PAGE_HEIGHT: Mm = Mm(297.0);
+    const TOP: f32 = 277.0;
+    const BOTTOM: f32 = 20.0;
+    const LINE_HEIGHT: f32 = 6.0;
+    const COLUMNS: [f32; 3] = [15.0, 80.0, 175.0];
+
+    let (doc, page, layer) =
+        PdfDocument::new("Zestawienie produkcji", PAGE_WIDTH, PAGE_HEIGHT, "Produkcja");
+    let font = doc.add_builtin_font(BuiltinFont::Helvetica)?;
+    let bold = doc.add_builtin_font(BuiltinFont::HelveticaBold)?;
+    let mut layer = doc.get_page(page).get_layer(layer);
+    let mut y = TOP;
+
+    for (i, catering) in report.caterings.iter().enumerate() {
+        if i > 0 {
+            let (page, page_layer) = doc.add_page(PAGE_WIDTH, PAGE_HEIGHT, "Produkcja");
+            layer = doc.get_page(page).get_layer(page_layer);
+            y = TOP;
+        }
+
+        layer.use_text(
+            strip_diacritics(&format!("{} - {}", catering.catering, report.day)),
+            16.0,
+            Mm(COLUMNS[0]),
+            Mm(y),
+            &bold,
+        );
+        y -= LINE_HEIGHT;
+        layer.use_text(
+            strip_diacritics(&if catering.locked {
+                format!("Stan na {}", catering.cutoff.format("%H:%M"))
+            } else {
+                format!(
+                    "Wstępne zestawienie, odwołania przyjmowane do {}",
+                    catering.cutoff.format("%H:%M")
+                )
+            }),
+            10.0,
+            Mm(COLUMNS[0]),
+            Mm(y),
+            &font,
+        );
+        y -= LINE_HEIGHT * 2.0;
+
+        for meal in &catering.meals {
+            let lines = meal
+                .groups
+                .iter()
+                .map(|group| (group.group.as_str(), &group.portions))
+                .chain([("Razem", &meal.total)]);
+
+            for (j, (group, portions)) in lines.enumerate() {
+                if y < BOTTOM + LINE_HEIGHT * (portions.diets.len() as f32 + 2.0) {
+                    let (page, page_layer) = doc.add_page(PAGE_WIDTH, PAGE_HEIGHT, "Produkcja");
+                    layer = doc.get_page(page).get_layer(page_layer);
+                    y = TOP;
+                }
+                if j == 0 {
+                    layer.use_text(strip_diacritics(&meal.meal), 12.0, Mm(COLUMNS[0]), Mm(y), &bold);
+                    y -= LINE_HEIGHT;
+                }
+
+                let is_total = j == meal.groups.len();
+                let group_font = if is_total { &bold } else { &font };
+                layer.use_text(strip_diacritics(group), 10.0, Mm(COLUMNS[0]), Mm(y), group_font);
+                layer.use_text(strip_diacritics("Zwykłe"), 10.0, Mm(COLUMNS[1]), Mm(y), &font);
+                layer.use_text(
+                    portions.regular.to_string(),
+                    10.0,
+                    Mm(COLUMNS[2]),
+                    Mm(y),
+                    group_font,
+                );
+                y -= LINE_HEIGHT;
+                for (diet, count) in &portions.diets {
+                    layer.use_text(strip_diacritics(diet), 10.0, Mm(COLUMNS[1]), Mm(y), &font);
+                    layer.use_text(count.to_string(), 10.0, Mm(COLUMNS[2]), Mm(y), group_font);
+                    y -= LINE_HEIGHT;
+                }
+            }
+            y -= LINE_HEIGHT;
+        }
+    }
+
+    Ok(doc.save_to_bytes()?)
+}
+
+#[server]
+pub async fn get_production_report(day: NaiveDate) -> Result<ProductionReportDto, ServerFnError> {
+    use sqlx::postgres::PgPool;
+    use crate::services::auth::authorize;
+    use dto::user::Role;
+
+    let pool: PgPool = use_context().ok_or(ServerFnError::new("Failed to retrieve db pool"))?;
+    authorize(&pool, Role::ALL).await?;
+
+    let mut conn = pool.acquire().await?;
+    load_production_report(&mut conn, day).await
+}
+
+#[server]
+pub async fn get_production_report_csv(day: NaiveDate) -> Result<String, ServerFnError> {
+    use sqlx::postgres::PgPool;
+    use crate::services::auth::authorize;
+    use csv::WriterBuilder;
+    use dto::production::ProductionCsvRow;
+    use dto::user::Role;
+
+    let pool: PgPool = use_context().ok_or(ServerFnError::new("Failed to retrieve db pool"))?;
+    authorize(&pool, Role::ALL).await?;
+
+    let mut conn = pool.acquire().await?;
+    let report = load_production_report(&mut conn, day).await?;
+
+    let mut wrtr = WriterBuilder::new().from_writer(vec![]);
+
+    for catering in &report.caterings {
+        for meal in &catering.meals {
+            let lines = meal
+                .groups
+                .iter()
+                .map(|group| (Some(group.group.clone()), &group.portions))
+                .chain([(None, &meal.total)]);
+
+            for (group, portions) in lines {
+                let diets = [(None, portions.regular)]
+                    .into_iter()
+                    .chain(portions.diets.iter().map(|(diet, count)| (Some(diet.clone()), *count)));
+
+                for (diet, portions) in diets {
+                    wrtr.serialize(ProductionCsvRow {
+                        day,
+                        catering: catering.catering.clone(),
+                        meal: meal.meal.clone(),
+                        group: group.clone(),
+                        diet,
+                        portions,
+                        locked: catering.locked,
+                    })?;
+                }
+            }
+        }
+    }
+
+    wrtr.flush()?;
+
+    Ok(String::from_utf8(wrtr.into_inner()?)?)
+}
+
+#[server]
+pub async fn get_production_report_pdf(day: NaiveDate) -> Result<Vec<u8>, ServerFnError> {
+    use sqlx::postgres::PgPool;
+    use crate::services::auth::authorize;
+    use dto::user::Role;
+
+    let pool: PgPool = use_context().ok_or(ServerFnError::new("Failed to retrieve db pool"))?;
+    authorize(&pool, Role::ALL).await?;
+
+    let mut conn = pool.acquire().await?;
+    let report = load_production_report(&mut conn, day).await?;
+
+    if report.caterings.is_empty() {
+        return Err(ServerFnError::new("No meals are served on this day"));
+    }
+
+    render_production_pdf(&report)
+}
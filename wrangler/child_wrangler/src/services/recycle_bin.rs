@@ -0,0 +1,282 @@
+use dto::recycle_bin::RemovedEntityDto;
+use leptos::prelude::*;
+use uuid::Uuid;
+
+/// Removed groups and students are purged for good this long after removal
+#[cfg(feature = "ssr")]
+const RECYCLE_BIN_RETENTION_DAYS: i32 = 30;
+
+/// Entities removed by staff, newest first. Nodes removed along with a group are listed under it
+#[server]
+pub async fn get_removed_entities() -> Result<Vec<RemovedEntityDto>, ServerFnError> {
+    use sqlx::postgres::PgPool;
+    use crate::services::auth::authorize;
+    use dto::audit::AuditEntity;
+    use dto::user::Role;
+
+    let pool: PgPool = use_context().ok_or(ServerFnError::new("Failed to retrieve db pool"))?;
+    authorize(&pool, &[Role::Admin]).await?;
+
+    let entities = sqlx::query!(
+        r#"SELECT roots.id AS "id!", roots.is_student AS "is_student!", roots.name AS "name!", parent_group.name AS "parent_name?",
+        roots.removed_at AS "removed_at!", users.login AS "removed_by?",
+        (SELECT COUNT(*) FROM groups WHERE groups.removed_with = roots.id) AS "groups!",
+        (SELECT COUNT(*) FROM students WHERE students.removed_with = roots.id) AS "students!",
+        roots.removed_at + make_interval(days => $1) AS "purge_at!"
+        FROM (
+            SELECT id, false AS is_student, name, removed_at, removed_by FROM groups WHERE removed AND removed_with = id
+            UNION ALL
+            SELECT id, true, name || ' ' || surname, removed_at, removed_by FROM students WHERE removed AND removed_with = id
+        ) AS roots
+        LEFT JOIN group_relations ON group_relations.child = roots.id AND group_relations.level = 1
+        LEFT JOIN groups AS parent_group ON parent_group.id = group_relations.parent
+        LEFT JOIN users ON users.id = roots.removed_by
+        ORDER BY roots.removed_at DESC"#,
+        RECYCLE_BIN_RETENTION_DAYS
+    )
+    .fetch_all(&pool)
+    .await?
+    .into_iter()
+    .map(|row| RemovedEntityDto {
+        id: row.id,
+        entity: if row.is_student {
+            AuditEntity::Student
+        } else {
+            AuditEntity::Group
+        },
+        name: row.name,
+        parent_name: row.parent_name,
+        removed_at: row.removed_at,
+        removed_by: row.removed_by,
+        groups: row.groups as u32,
+        students: row.students as u32,
+        purge_at: row.purge_at,
+    })
+    .collect();
+
+    Ok(entities)
+}
+
+/// Brings back the entity with everything removed along with it, into the group it was removed
+/// from. The group has to be in place and still accept the entity, like in a transfer
+#[server]
+pub async fn restore_removed(id: Uuid) -> Result<(), ServerFnError> {
+    use sqlx::postgres::PgPool;
+    use crate::services::audit::{node_entity, record, snapshot};
+    use crate::services::auth::authorize;
+    use crate::services::group::check_transfer;
+    use crate::services::student::{apply_due_transfers, seed_attendance};
+    use dto::audit::AuditAction;
+    use dto::user::Role;
+
+    let pool: PgPool = use_context().ok_or(ServerFnError::new("Failed to retrieve db pool"))?;
+    let actor = authorize(&pool, &[Role::Admin]).await?;
+    let mut tr = pool.begin().await?;
+
+    if !is_removal_root(&mut tr, id).await? {
+        return Err(ServerFnError::new("Entity is not in the recycle bin"));
+    }
+
+    let parent = sqlx::query!(
+        "SELECT groups.id, groups.removed FROM group_relations
+        INNER JOIN groups ON groups.id = group_relations.parent
+        WHERE group_relations.child = $1 AND group_relations.level = 1",
+        id
+    )
+    .fetch_optional(&mut *tr)
+    .await?;
+
+    if let Some(parent) = parent {
+        if parent.removed {
+            return Err(ServerFnError::new(
+                "Parent group is removed, restore it first",
+            ));
+        }
+        check_transfer(&mut tr, id, parent.id).await?;
+    }
+
+    let entity = node_entity(&mut tr, id).await?;
+    let before = snapshot(&mut tr, entity, id).await?;
+
+    sqlx::query!(
+        "UPDATE groups SET removed = false, removed_at = NULL, removed_by = NULL, removed_with = NULL WHERE removed_with = $1",
+        id
+    )
+    .execute(&mut *tr)
+    .await?;
+
+    let students = sqlx::query_scalar!(
+        "UPDATE students SET removed = false, removed_at = NULL, removed_by = NULL, removed_with = NULL WHERE removed_with = $1 RETURNING id",
+        id
+    )
+    .fetch_all(&mut *tr)
+    .await?;
+
+    // Transfers and catering changes made in the meantime skipped removed students
+    for student in students {
+        apply_due_transfers(&mut tr, Some(student)).await?;
+        seed_attendance(&mut tr, student, None).await?;
+    }
+
+    let after = snapshot(&mut tr, entity, id).await?;
+    record(
+        &mut tr,
+        &actor,
+        AuditAction::Restore,
+        entity,
+        id,
+        before,
+        after,
+    )
+    .await?;
+
+    tr.commit().await?;
+    Ok(())
+}
+
+/// Purges the entity from the recycle bin before its retention period ends
+#[server]
+pub async fn purge_removed(id: Uuid) -> Result<(), ServerFnError> {
+    use sqlx::postgres::PgPool;
+    use crate::services::audit::{node_entity, record, snapshot};
+    use crate::services::auth::authorize;
+    use dto::audit::AuditAction;
+    use dto::user::Role;
+
+    let pool: PgPool = use_context().ok_or(ServerFnError::new("Failed to retrieve db pool"))?;
+    let actor = authorize(&pool, &[Role::Admin]).await?;
+    let mut tr = pool.begin().await?;
+
+    if !is_removal_root(&mut tr, id).await? {
+        return Err(ServerFnError::new("Entity is not in the recycle bin"));
+    }
+
+    let entity = node_entity(&mut tr, id).await?;
+    let before = snapshot(&mut tr, entity, id).await?;
+
+    purge_node(&mut tr, id).await?;
+
+    record(
+        &mut tr,
+        &actor,
+        AuditAction::Purge,
+        entity,
+        id,
+        before,
+        None,
+    )
+    .await?;
+
+    tr.commit().await?;
+    Ok(())
+}
+
+/// Purges entities removed longer than the retention period ago, returns how many were purged
+#[cfg(feature = "ssr")]
+pub async fn purge_expired(conn: &mut sqlx::PgConnection) -> Result<usize, ServerFnError> {
+    use crate::services::audit::{node_entity, record_system, snapshot};
+    use dto::audit::AuditAction;
+
+    let expired = sqlx::query_scalar!(
+        r#"SELECT id AS "id!" FROM groups WHERE removed AND removed_with = id AND removed_at < LOCALTIMESTAMP - make_interval(days => $1)
+        UNION ALL
+        SELECT id FROM students WHERE removed AND removed_with = id AND removed_at < LOCALTIMESTAMP - make_interval(days => $1)"#,
+        RECYCLE_BIN_RETENTION_DAYS
+    )
+    .fetch_all(&mut *conn)
+    .await?;
+
+    for &id in &expired {
+        let entity = node_entity(&mut *conn, id).await?;
+        let before = snapshot(&mut *conn, entity, id).await?;
+        purge_node(&mut *conn, id).await?;
+        record_system(&mut *conn, AuditAction::Purge, entity, id, before, None).await?;
+    }
+
+    Ok(expired.len())
+}
+
+#[cfg(feature = "ssr")]
+async fn is_removal_root(conn: &mut sqlx::PgConnection, id: Uuid) -> Result<bool, ServerFnError> {
+    Ok(sqlx::query!(
+        "SELECT id FROM groups WHERE id = $1 AND removed AND removed_with = $1
+        UNION ALL
+        SELECT id FROM students WHERE id = $1 AND removed AND removed_with = $1",
+        id
+    )
+    .fetch_optional(&mut *conn)
+    .await?
+    .is_some())
+}
+
+/// Deletes the node with its whole subtree, including caterings of the removed groups and
+/// everything recorded for them
+#[cfg(feature = "ssr")]
+async fn purge_node(conn: &mut sqlx::PgConnection, id: Uuid) -> Result<(), ServerFnError> {
+    let nodes = sqlx::query_scalar!("SELECT child FROM group_relations WHERE parent = $1", id)
+        .fetch_all(&mut *conn)
+        .await?;
+    let caterings = sqlx::query_scalar!(
+        "SELECT id FROM caterings WHERE group_id = ANY($1)",
+        &nodes
+    )
+    .fetch_all(&mut *conn)
+    .await?;
+    let overrides = sqlx::query_scalar!(
+        "DELETE FROM closures WHERE catering_id = ANY($1) RETURNING override_id",
+        &caterings
+    )
+    .fetch_all(&mut *conn)
+    .await?;
+
+    sqlx::query!(
+        "DELETE FROM attendance WHERE target = ANY($1) OR cause_id = ANY($2)",
+        &nodes,
+        &overrides
+    )
+    .execute(&mut *conn)
+    .await?;
+    sqlx::query!("DELETE FROM attendance_override WHERE id = ANY($1)", &overrides)
+        .execute(&mut *conn)
+        .await?;
+    sqlx::query!(
+        "DELETE FROM meal_snapshot_entries WHERE student_id = ANY($1) OR catering_id = ANY($2)",
+        &nodes,
+        &caterings
+    )
+    .execute(&mut *conn)
+    .await?;
+    sqlx::query!("DELETE FROM meal_snapshots WHERE catering_id = ANY($1)", &caterings)
+        .execute(&mut *conn)
+        .await?;
+    sqlx::query!(
+        "DELETE FROM catering_meal_prices WHERE catering_id = ANY($1)",
+        &caterings
+    )
+    .execute(&mut *conn)
+    .await?;
+    sqlx::query!("DELETE FROM catering_meals WHERE catering_id = ANY($1)", &caterings)
+        .execute(&mut *conn)
+        .await?;
+    sqlx::query!("DELETE FROM caterings WHERE id = ANY($1)", &caterings)
+        .execute(&mut *conn)
+        .await?;
+    sqlx::query!("DELETE FROM student_guardians WHERE student_id = ANY($1)", &nodes)
+        .execute(&mut *conn)
+        .await?;
+    // Placements of other students in the purged groups are kept, without the group
+    sqlx::query!("DELETE FROM student_placements WHERE student_id = ANY($1)", &nodes)
+        .execute(&mut *conn)
+        .await?;
+    sqlx::query!("DELETE FROM group_relations WHERE child = ANY($1)", &nodes)
+        .execute(&mut *conn)
+        .await?;
+    sqlx::query!("DELETE FROM students WHERE id = ANY($1)", &nodes)
+        .execute(&mut *conn)
+        .await?;
+    sqlx::query!("DELETE FROM groups WHERE id = ANY($1)", &nodes)
+        .execute(&mut *conn)
+        .await?;
+
+    Ok(())
+}
@@ -0,0 +1,326 @@
+use dto::jobs::{JobDto, UpdateJobDto};
+use leptos::prelude::*;
+use uuid::Uuid;
+
+#[cfg(feature = "ssr")]
+use dto::jobs::Job;
+
+/// How often the server looks for jobs that are due
+#[cfg(feature = "ssr")]
+const SCHEDULER_INTERVAL: std::time::Duration = std::time::Duration::from_secs(30);
+
+/// Processed messages older than this lose their processing history
+#[cfg(feature = "ssr")]
+const PROCESSING_STEP_RETENTION_DAYS: i32 = 90;
+
+#[cfg(feature = "ssr")]
+async fn execute(pool: &sqlx::PgPool, job: Job) -> Result<String, ServerFnError> {
+    match job {
+        Job::MealSnapshots => {
+            let taken = crate::services::snapshot::take_due_snapshots(pool).await?;
+            Ok(format!("Zapisano {} zestawień", taken))
+        }
+        Job::ProcessingStepCleanup => {
+            let mut tr = pool.begin().await?;
+            let steps = sqlx::query!(
+                "DELETE FROM processing_step WHERE cause_id IN (
+                    SELECT id FROM messages WHERE processed AND inserted < LOCALTIMESTAMP - make_interval(days => $1)
+                )",
+                PROCESSING_STEP_RETENTION_DAYS
+            )
+            .execute(&mut *tr)
+            .await?
+            .rows_affected();
+            let runs = sqlx::query!(
+                "DELETE FROM job_runs WHERE started < LOCALTIMESTAMP - make_interval(days => $1)",
+                PROCESSING_STEP_RETENTION_DAYS
+            )
+            .execute(&mut *tr)
+            .await?
+            .rows_affected();
+            tr.commit().await?;
+            Ok(format!(
+                "Usunięto {} kroków przetwarzania i {} uruchomień zadań",
+                steps, runs
+            ))
+        }
+        Job::MonthlyStatements => {
+            use chrono::{Datelike, Months};
+
+            let today = sqlx::query_scalar!(r#"SELECT CURRENT_DATE AS "today!""#)
+                .fetch_one(pool)
+                .await?;
+            let previous = today
+                .checked_sub_months(Months::new(1))
+                .ok_or(ServerFnError::new("Failed to compute previous month"))?;
+
+            let mut tr = pool.begin().await?;
+            let sent = crate::services::billing::queue_statement_notices(
+                &mut tr,
+                None,
+                previous.year(),
+                previous.month(),
+                None,
+            )
+            .await?;
+            tr.commit().await?;
+            Ok(format!("Wysłano {} wiadomości", sent))
+        }
+        Job::StudentTransfers => {
+            let mut tr = pool.begin().await?;
+            let moved = crate::services::student::apply_due_transfers(&mut tr, None).await?;
+            tr.commit().await?;
+            Ok(format!("Przeniesiono {} uczniów", moved))
+        }
+        Job::RecycleBinPurge => {
+            let mut tr = pool.begin().await?;
+            let purged = crate::services::recycle_bin::purge_expired(&mut tr).await?;
+            tr.commit().await?;
+            Ok(format!("Usunięto na stałe {} wpisów z kosza", purged))
+        }
+    }
+}
+
+/// Runs the job if it's due, returns false when it isn't or another instance is running it.
+/// A session advisory lock keeps replicas from running the same job at once, and `next_run` is
+/// checked again under the lock so a run finished by another replica isn't repeated
+#[cfg(feature = "ssr")]
+pub async fn run_job(pool: &sqlx::PgPool, job: Job) -> Result<bool, ServerFnError> {
+    let mut conn = pool.acquire().await?;
+    let key = format!("scheduled_job:{}", job.as_str());
+
+    let locked = sqlx::query_scalar!(r#"SELECT pg_try_advisory_lock(hashtext($1)) AS "locked!""#, key)
+        .fetch_one(&mut *conn)
+        .await?;
+    if !locked {
+        return Ok(false);
+    }
+
+    let result = run_locked(pool, &mut conn, job).await;
+
+    sqlx::query_scalar!("SELECT pg_advisory_unlock(hashtext($1))", key)
+        .fetch_one(&mut *conn)
+        .await?;
+
+    result
+}
+
+#[cfg(feature = "ssr")]
+async fn run_locked(
+    pool: &sqlx::PgPool,
+    conn: &mut sqlx::PgConnection,
+    job: Job,
+) -> Result<bool, ServerFnError> {
+    use dto::jobs::CronSchedule;
+
+    let Some(scheduled) = sqlx::query!(
+        r#"SELECT id, schedule, next_run, LOCALTIMESTAMP(0) AS "now!" FROM scheduled_jobs
+        WHERE name = $1 AND enabled AND (next_run IS NULL OR next_run <= LOCALTIMESTAMP)"#,
+        job.as_str()
+    )
+    .fetch_optional(&mut *conn)
+    .await?
+    else {
+        return Ok(false);
+    };
+
+    let schedule = scheduled.schedule.parse::<CronSchedule>();
+    if let Ok(schedule) = &schedule {
+        sqlx::query!(
+            "UPDATE scheduled_jobs SET next_run = $2 WHERE id = $1",
+            scheduled.id,
+            schedule.next_after(scheduled.now)
+        )
+        .execute(&mut *conn)
+        .await?;
+
+        // Newly enabled or rescheduled jobs wait for their first scheduled time
+        if scheduled.next_run.is_none() {
+            return Ok(false);
+        }
+    }
+
+    let run_id = sqlx::query!(
+        "INSERT INTO job_runs (job_id) VALUES ($1) RETURNING id",
+        scheduled.id
+    )
+    .fetch_one(&mut *conn)
+    .await?
+    .id;
+
+    // An invalid schedule stops the job until it's fixed, instead of retrying it on every tick
+    let result = match schedule {
+        Ok(_) => execute(pool, job).await,
+        Err(e) => {
+            sqlx::query!(
+                "UPDATE scheduled_jobs SET enabled = false WHERE id = $1",
+                scheduled.id
+            )
+            .execute(&mut *conn)
+            .await?;
+            Err(ServerFnError::new(format!("Invalid schedule: {}", e)))
+        }
+    };
+
+    let (success, output) = match result {
+        Ok(output) => (true, output),
+        Err(e) => {
+            log::error!("Scheduled job {} failed: {}", job.as_str(), e);
+            (false, e.to_string())
+        }
+    };
+
+    sqlx::query!(
+        "UPDATE job_runs SET finished = LOCALTIMESTAMP(0), success = $2, output = $3 WHERE id = $1",
+        run_id,
+        success,
+        output
+    )
+    .execute(&mut *conn)
+    .await?;
+
+    Ok(true)
+}
+
+#[cfg(feature = "ssr")]
+async fn run_due_jobs(pool: &sqlx::PgPool) -> Result<(), ServerFnError> {
+    let due = sqlx::query!(
+        "SELECT name FROM scheduled_jobs WHERE enabled AND (next_run IS NULL OR next_run <= LOCALTIMESTAMP)"
+    )
+    .fetch_all(pool)
+    .await?;
+
+    for row in due {
+        match row.name.parse::<Job>() {
+            Ok(job) => {
+                // One failing job mustn't hold back the others due in this tick
+                if let Err(e) = run_job(pool, job).await {
+                    log::error!("Failed to run scheduled job {}: {}", job.as_str(), e);
+                }
+            }
+            Err(e) => log::warn!("Skipping scheduled job: {}", e),
+        }
+    }
+
+    Ok(())
+}
+
+/// Runs forever, executing jobs from `scheduled_jobs` as they become due
+#[cfg(feature = "ssr")]
+pub async fn run_scheduler(pool: sqlx::PgPool) {
+    let mut interval = tokio::time::interval(SCHEDULER_INTERVAL);
+    loop {
+        interval.tick().await;
+        if let Err(e) = run_due_jobs(&pool).await {
+            log::error!("Failed to run scheduled jobs: {}", e);
+        }
+    }
+}
+
+#[server]
+pub async fn get_jobs() -> Result<Vec<JobDto>, ServerFnError> {
+    use sqlx::postgres::PgPool;
+    use crate::services::auth::authorize;
+    use dto::jobs::JobRunDto;
+    use dto::user::Role;
+
+    let pool: PgPool = use_context().ok_or(ServerFnError::new("Failed to retrieve db pool"))?;
+    authorize(&pool, &[Role::Admin]).await?;
+
+    let jobs = sqlx::query!("SELECT id, name, schedule, enabled, next_run FROM scheduled_jobs ORDER BY name")
+        .fetch_all(&pool)
+        .await?;
+
+    let mut result = vec![];
+    for row in jobs {
+        let Ok(job) = row.name.parse::<Job>() else {
+            continue;
+        };
+
+        let runs = sqlx::query_as!(
+            JobRunDto,
+            "SELECT id, started, finished, success, output FROM job_runs WHERE job_id = $1 ORDER BY started DESC LIMIT 10",
+            row.id
+        )
+        .fetch_all(&pool)
+        .await?;
+
+        result.push(JobDto {
+            id: row.id,
+            job,
+            schedule: row.schedule,
+            enabled: row.enabled,
+            next_run: row.next_run,
+            runs,
+        });
+    }
+
+    Ok(result)
+}
+
+#[server]
+pub async fn update_job(job: UpdateJobDto) -> Result<(), ServerFnError> {
+    use sqlx::postgres::PgPool;
+    use crate::services::audit::{record, snapshot};
+    use crate::services::auth::authorize;
+    use dto::audit::{AuditAction, AuditEntity};
+    use dto::jobs::CronSchedule;
+    use dto::user::Role;
+
+    let pool: PgPool = use_context().ok_or(ServerFnError::new("Failed to retrieve db pool"))?;
+    let actor = authorize(&pool, &[Role::Admin]).await?;
+
+    let schedule = job.schedule.trim();
+    schedule.parse::<CronSchedule>().map_err(ServerFnError::new)?;
+
+    let mut tr = pool.begin().await?;
+    let before = snapshot(&mut tr, AuditEntity::Job, job.id).await?;
+
+    // Clearing the next run lets the scheduler compute it from the new schedule, so an enabled job
+    // doesn't catch up on runs it missed while disabled
+    sqlx::query!(
+        "UPDATE scheduled_jobs SET schedule = $2, enabled = $3, next_run = CASE WHEN schedule = $2 AND enabled = $3 THEN next_run END WHERE id = $1",
+        job.id,
+        schedule,
+        job.enabled
+    )
+    .execute(&mut *tr)
+    .await?;
+
+    let after = snapshot(&mut tr, AuditEntity::Job, job.id).await?;
+    record(
+        &mut tr,
+        &actor,
+        AuditAction::Update,
+        AuditEntity::Job,
+        job.id,
+        before,
+        after,
+    )
+    .await?;
+
+    tr.commit().await?;
+    Ok(())
+}
+
+/// Runs the job right away, regardless of its schedule. Returns false when it's already running
+#[server]
+pub async fn run_job_now(id: Uuid) -> Result<bool, ServerFnError> {
+    use sqlx::postgres::PgPool;
+    use crate::services::auth::authorize;
+    use dto::user::Role;
+
+    let pool: PgPool = use_context().ok_or(ServerFnError::new("Failed to retrieve db pool"))?;
+    authorize(&pool, &[Role::Admin]).await?;
+
+    let name = sqlx::query_scalar!(
+        "UPDATE scheduled_jobs SET next_run = LOCALTIMESTAMP(0) WHERE id = $1 AND enabled RETURNING name",
+        id
+    )
+    .fetch_optional(&pool)
+    .await?
+    .ok_or(ServerFnError::new("Job not found or disabled"))?;
+
+    let job = name.parse::<Job>().map_err(ServerFnError::new)?;
+    run_job(&pool, job).await
+}
@@ -0,0 +1,214 @@
+use chrono::NaiveDate;
+use dto::production::CateringSnapshotDto;
+use leptos::prelude::*;
+
+#[cfg(feature = "ssr")]
+use chrono::NaiveDateTime;
+#[cfg(feature = "ssr")]
+use uuid::Uuid;
+
+/// Freezes attendance of every student enrolled and placed under the catering on `day` as it was at `cutoff`.
+/// Returns false when the snapshot of the day was already taken (e.g. by another instance)
+#[cfg(feature = "ssr")]
+pub async fn take_snapshot(
+    conn: &mut sqlx::PgConnection,
+    catering_id: Uuid,
+    day: NaiveDate,
+    cutoff: NaiveDateTime,
+) -> Result<bool, ServerFnError> {
+    let inserted = sqlx::query!(
+        "INSERT INTO meal_snapshots (catering_id, day, cutoff) VALUES ($1, $2, $3) ON CONFLICT DO NOTHING",
+        catering_id,
+        day,
+        cutoff
+    )
+    .execute(&mut *conn)
+    .await?
+    .rows_affected();
+
+    if inserted == 0 {
+        return Ok(false);
+    }
+
+    sqlx::query!(
+        "INSERT INTO meal_snapshot_entries (catering_id, day, student_id, meal_id, present)
+        WITH effective AS (SELECT DISTINCT ON (meal_id, target) meal_id, target, value FROM attendance
+        WHERE day = $2 AND originated <= $3
+        ORDER BY meal_id, target, originated DESC, cause_id)
+        SELECT caterings.id, $2, students.id, effective.meal_id, bool_and(effective.value) FROM caterings
+        INNER JOIN student_targets AS catering_target ON catering_target.target = caterings.group_id AND $2 BETWEEN catering_target.since AND catering_target.until
+        INNER JOIN students ON students.id = catering_target.student_id
+        INNER JOIN student_targets ON student_targets.student_id = students.id AND $2 BETWEEN student_targets.since AND student_targets.until
+        INNER JOIN effective ON effective.target = student_targets.target
+        INNER JOIN catering_meals ON catering_meals.catering_id = caterings.id AND catering_meals.meal_id = effective.meal_id
+        WHERE caterings.id = $1
+        GROUP BY caterings.id, students.id, effective.meal_id",
+        catering_id,
+        day,
+        cutoff
+    )
+    .execute(&mut *conn)
+    .await?;
+
+    Ok(true)
+}
+
+/// Takes snapshots of caterings serving on the day whose cut-off passed most recently
+#[cfg(feature = "ssr")]
+pub async fn take_due_snapshots(pool: &sqlx::PgPool) -> Result<u32, ServerFnError> {
+    let due = sqlx::query!(
+        r#"SELECT caterings.id, days.day AS "day!", (days.day + caterings.grace_period) AS "cutoff!" FROM caterings
+        CROSS JOIN LATERAL (SELECT (LOCALTIMESTAMP - caterings.grace_period)::date AS day) AS days
+        WHERE caterings.since <= days.day AND caterings.until >= days.day
+        AND ((caterings.dow >> (EXTRACT(DOW FROM days.day)::smallint + 6) % 7 )&1) = 1
+        AND NOT EXISTS (SELECT 1 FROM meal_snapshots WHERE meal_snapshots.catering_id = caterings.id AND meal_snapshots.day = days.day)"#
+    )
+    .fetch_all(pool)
+    .await?;
+
+    let mut taken = 0;
+    for catering in due {
+        let mut tr = pool.begin().await?;
+        if take_snapshot(&mut tr, catering.id, catering.day, catering.cutoff).await? {
+            taken += 1;
+        }
+        tr.commit().await?;
+    }
+
+    Ok(taken)
+}
+
+#[server]
+pub async fn get_snapshots(day: NaiveDate) -> Result<Vec<CateringSnapshotDto>, ServerFnError> {
+    use sqlx::postgres::PgPool;
+    use crate::services::auth::authorize;
+    use dto::production::{LateChangeDto, SnapshotMealCountDto};
+    use dto::user::Role;
+
+    let pool: PgPool = use_context().ok_or(ServerFnError::new("Failed to retrieve db pool"))?;
+    authorize(&pool, Role::ALL).await?;
+
+    let snapshots = sqlx::query!(
+        "SELECT meal_snapshots.catering_id, groups.name, meal_snapshots.cutoff, meal_snapshots.taken FROM meal_snapshots
+        INNER JOIN caterings ON caterings.id = meal_snapshots.catering_id
+        INNER JOIN groups ON groups.id = caterings.group_id
+        WHERE meal_snapshots.day = $1
+        ORDER BY groups.name",
+        day
+    )
+    .fetch_all(&pool)
+    .await?;
+
+    let mut result = vec![];
+    for snapshot in snapshots {
+        let meals = sqlx::query!(
+            r#"WITH counts AS (
+                SELECT meal_id, COUNT(*) FILTER (WHERE present) AS snapshot, 0 AS difference FROM meal_snapshot_entries
+                WHERE catering_id = $1 AND day = $2 GROUP BY meal_id
+                UNION ALL
+                SELECT meal_id, 0, COUNT(*) FILTER (WHERE final) - COUNT(*) FILTER (WHERE snapshot) FROM meal_snapshot_changes
+                WHERE catering_id = $1 AND day = $2 GROUP BY meal_id
+            )
+            SELECT meals.id, meals.name, SUM(counts.snapshot)::bigint AS "snapshot!", SUM(counts.difference)::bigint AS "difference!" FROM counts
+            INNER JOIN meals ON meals.id = counts.meal_id
+            LEFT JOIN catering_meals ON catering_meals.catering_id = $1 AND catering_meals.meal_id = meals.id
+            GROUP BY meals.id, catering_meals.meal_order
+            ORDER BY catering_meals.meal_order NULLS LAST, meals.name"#,
+            snapshot.catering_id,
+            day
+        )
+        .fetch_all(&pool)
+        .await?
+        .into_iter()
+        .map(|row| SnapshotMealCountDto {
+            meal_id: row.id,
+            meal: row.name,
+            snapshot: row.snapshot as u32,
+            actual: (row.snapshot + row.difference).max(0) as u32,
+        })
+        .collect();
+
+        let changes = sqlx::query!(
+            "SELECT students.id, students.name, students.surname, meals.name AS meal, meal_snapshot_changes.snapshot, meal_snapshot_changes.final FROM meal_snapshot_changes
+            INNER JOIN students ON students.id = meal_snapshot_changes.student_id
+            INNER JOIN meals ON meals.id = meal_snapshot_changes.meal_id
+            WHERE meal_snapshot_changes.catering_id = $1 AND meal_snapshot_changes.day = $2
+            ORDER BY students.surname, students.name, meals.name",
+            snapshot.catering_id,
+            day
+        )
+        .fetch_all(&pool)
+        .await?
+        .into_iter()
+        .map(|row| LateChangeDto {
+            student_id: row.id,
+            student: format!("{} {}", row.name, row.surname),
+            meal: row.meal,
+            snapshot: row.snapshot.unwrap_or(false),
+            actual: row.r#final.unwrap_or(false),
+        })
+        .collect();
+
+        result.push(CateringSnapshotDto {
+            catering_id: snapshot.catering_id,
+            catering: snapshot.name,
+            cutoff: snapshot.cutoff,
+            taken: snapshot.taken,
+            meals,
+            changes,
+        });
+    }
+
+    Ok(result)
+}
+
+/// Every attendance change made after the cut-off during the month, for reporting
+#[server]
+pub async fn get_late_changes_csv(year: i32, month: u32) -> Result<String, ServerFnError> {
+    use sqlx::postgres::PgPool;
+    use crate::services::auth::authorize;
+    use crate::services::billing::month_range;
+    use csv::WriterBuilder;
+    use dto::production::LateChangeCsvRow;
+    use dto::user::Role;
+
+    let pool: PgPool = use_context().ok_or(ServerFnError::new("Failed to retrieve db pool"))?;
+    authorize(&pool, Role::ALL).await?;
+
+    let (start, end) = month_range(year, month)?;
+
+    let rows = sqlx::query!(
+        r#"SELECT meal_snapshot_changes.day AS "day!", catering_groups.name AS catering, students.name, students.surname, student_groups.name AS "group?", meals.name AS meal,
+        meal_snapshot_changes.snapshot AS "snapshot!", meal_snapshot_changes.final AS "final!" FROM meal_snapshot_changes
+        INNER JOIN caterings ON caterings.id = meal_snapshot_changes.catering_id
+        INNER JOIN groups AS catering_groups ON catering_groups.id = caterings.group_id
+        INNER JOIN students ON students.id = meal_snapshot_changes.student_id
+        INNER JOIN meals ON meals.id = meal_snapshot_changes.meal_id
+        LEFT JOIN group_relations ON group_relations.child = students.id AND group_relations.level = 1
+        LEFT JOIN groups AS student_groups ON student_groups.id = group_relations.parent
+        WHERE meal_snapshot_changes.day >= $1 AND meal_snapshot_changes.day < $2
+        ORDER BY meal_snapshot_changes.day, catering_groups.name, students.surname, students.name, meals.name"#,
+        start,
+        end
+    )
+    .fetch_all(&pool)
+    .await?;
+
+    let mut wrtr = WriterBuilder::new().from_writer(vec![]);
+
+    for row in rows {
+        wrtr.serialize(LateChangeCsvRow {
+            day: row.day,
+            catering: row.catering,
+            student: format!("{} {}", row.name, row.surname),
+            group: row.group,
+            meal: row.meal,
+            snapshot: row.snapshot,
+            actual: row.r#final,
+        })?;
+    }
+
+    wrtr.flush()?;
+
+    Ok(String::from_utf8(wrtr.into_inner()?)?)
+}
@@ -1,7 +1,9 @@
 use dto::catering::{AllergyDto, MealDto};
 use dto::details::StudentDetailsDto;
 use dto::guardian::GuardianDto;
-use dto::student::{AllergyCombinationDto, CreateGuardianDto, CreateStudentDto, StudentDto};
+use dto::student::{
+    AllergyCombinationDto, CreateGuardianDto, CreateStudentDto, StudentDto, TransferStudentDto,
+};
 use leptos::logging::log;
 use leptos::prelude::*;
 use uuid::Uuid;
@@ -10,104 +12,342 @@ use uuid::Uuid;
 pub async fn create_student(student: CreateStudentDto) -> Result<Uuid, ServerFnError> {
     use leptos_axum::extract;
     use sqlx::postgres::PgPool;
+    use crate::services::audit::{record, snapshot};
+    use crate::services::auth::authorize;
+    use dto::audit::{AuditAction, AuditEntity};
+    use dto::user::Role;
 
     let pool: PgPool = use_context().ok_or(ServerFnError::new("Failed to retrieve db pool"))?;
+    let actor = authorize(&pool, &[Role::Admin]).await?;
     let mut tr = pool.begin().await?;
 
-    let name = String::from(student.name.trim());
-    let surname = String::from(student.surname.trim());
+    let student_id = insert_student(&mut tr, student).await?;
+
+    let after = snapshot(&mut tr, AuditEntity::Student, student_id).await?;
+    record(
+        &mut tr,
+        &actor,
+        AuditAction::Create,
+        AuditEntity::Student,
+        student_id,
+        None,
+        after,
+    )
+    .await?;
 
-    let allergies = student
-        .allergies
-        .into_iter()
-        .map(|a| String::from(a.trim()))
-        .collect::<Vec<_>>();
+    tr.commit().await?;
+
+    Ok(student_id)
+}
+
+/// Finds or creates combination of allergies with given names, `None` if there are no allergies
+#[cfg(feature = "ssr")]
+pub async fn allergy_combination(
+    conn: &mut sqlx::PgConnection,
+    allergies: &[String],
+) -> Result<Option<Uuid>, ServerFnError> {
+    let allergies = allergies.to_vec();
 
     sqlx::query!(
         "INSERT INTO allergies (name) SELECT * FROM UNNEST($1::text[]) ON CONFLICT DO NOTHING",
         &allergies
     )
-    .execute(&mut *tr)
+    .execute(&mut *conn)
     .await?;
 
     let allergy_combination_id = sqlx::query!("WITH combinations AS (SELECT allergy_combinations.id, ARRAY_AGG(allergies.name) AS al_id FROM allergy_combinations JOIN allergies ON allergies.id = allergy_id GROUP BY allergy_combinations.id)
             SELECT id FROM combinations
             WHERE $1::text[] @> combinations.al_id AND $1::text[] <@ combinations.al_id
-", &allergies).fetch_optional(&mut *tr).await?.map(|row| row.id);
+", &allergies).fetch_optional(&mut *conn).await?.map(|row| row.id);
 
-    let allergy_combination_id = match allergy_combination_id {
+    Ok(match allergy_combination_id {
         Some(id) => Some(id),
         None => {
             let id = Uuid::new_v4();
             log!("Creating new allergy");
-            let rows = sqlx::query!("INSERT INTO allergy_combinations (allergy_id,id) SELECT allergies.id, $2 FROM allergies INNER JOIN UNNEST($1::text[]) AS names(name) on allergies.name = names.name", &allergies, id).execute(&mut *tr).await?.rows_affected();
+            let rows = sqlx::query!("INSERT INTO allergy_combinations (allergy_id,id) SELECT allergies.id, $2 FROM allergies INNER JOIN UNNEST($1::text[]) AS names(name) on allergies.name = names.name", &allergies, id).execute(&mut *conn).await?.rows_affected();
             if rows == 0 {
                 None
             } else {
                 Some(id)
             }
         }
-    };
+    })
+}
 
-    let guardian_names = student
-        .guardians
-        .into_iter()
-        .map(|g| String::from(g.trim()))
-        .filter(|g| !g.is_empty())
-        .collect::<Vec<_>>();
+/// Finds or creates guardians with given names, returning their ids
+#[cfg(feature = "ssr")]
+pub async fn guardians_by_name(
+    conn: &mut sqlx::PgConnection,
+    guardian_names: &[String],
+) -> Result<Vec<Uuid>, ServerFnError> {
+    let guardian_names = guardian_names.to_vec();
 
     sqlx::query!(
         "INSERT INTO guardians (fullname) SELECT * FROM UNNEST($1::text[]) ON CONFLICT DO NOTHING",
         &guardian_names
     )
-    .execute(&mut *tr)
+    .execute(&mut *conn)
     .await?;
 
-    let guardian_ids = sqlx::query!(
+    Ok(sqlx::query!(
         "SELECT id FROM UNNEST($1::text[]) AS input(fullname) INNER JOIN  guardians ON guardians.fullname = input.fullname",
         &guardian_names
     )
-    .fetch_all(&mut *tr)
+    .fetch_all(&mut *conn)
     .await?
         .into_iter()
-    .map(|row| row.id).collect::<Vec<_>>();
+    .map(|row| row.id).collect::<Vec<_>>())
+}
+
+/// Inserts student into the group, linking guardians and seeding attendance from the group's caterings
+#[cfg(feature = "ssr")]
+pub async fn insert_student(
+    conn: &mut sqlx::PgConnection,
+    student: CreateStudentDto,
+) -> Result<Uuid, ServerFnError> {
+    let name = String::from(student.name.trim());
+    let surname = String::from(student.surname.trim());
+
+    let allergies = student
+        .allergies
+        .into_iter()
+        .map(|a| String::from(a.trim()))
+        .collect::<Vec<_>>();
+
+    let allergy_combination_id = allergy_combination(&mut *conn, &allergies).await?;
+
+    let guardian_names = student
+        .guardians
+        .into_iter()
+        .map(|g| String::from(g.trim()))
+        .filter(|g| !g.is_empty())
+        .collect::<Vec<_>>();
+
+    let guardian_ids = guardians_by_name(&mut *conn, &guardian_names).await?;
 
     let is_group = sqlx::query!(
         "SELECT groups.id FROM groups 
         WHERE groups.id = $1 AND NOT EXISTS (SELECT * FROM group_relations INNER JOIN groups AS gr ON gr.id = group_relations.child AND group_relations.parent = groups.id AND group_relations.level = 1)",
         student.group_id
     )
-    .fetch_optional(&mut *tr)
+    .fetch_optional(&mut *conn)
     .await?;
 
     if is_group.is_none() {
         return Err(ServerFnError::new("Invalid group selected"));
     }
 
-    let student_id = sqlx::query!("INSERT INTO students (name, surname, allergy_combination_id) VALUES ($1,$2,$3) RETURNING id", name, surname, allergy_combination_id).fetch_one(&mut *tr).await?.id;
+    check_enrolment(student.joined, student.left)?;
+
+    let student_id = sqlx::query!("INSERT INTO students (name, surname, allergy_combination_id, joined_on, left_on) VALUES ($1,$2,$3,$4,$5) RETURNING id", name, surname, allergy_combination_id, student.joined, student.left).fetch_one(&mut *conn).await?.id;
 
-    sqlx::query!("INSERT INTO student_guardians (student_id, guardian_id) SELECT $1, * FROM UNNEST($2::uuid[])", student_id, &guardian_ids).execute(&mut*tr).await?;
+    sqlx::query!("INSERT INTO student_guardians (student_id, guardian_id) SELECT $1, * FROM UNNEST($2::uuid[])", student_id, &guardian_ids).execute(&mut *conn).await?;
 
-    sqlx::query!("INSERT INTO group_relations (child,parent,level) SELECT $1,parent,level + 1 FROM group_relations WHERE child=$2 UNION SELECT $1::uuid,$1::uuid,0", student_id, student.group_id).execute(&mut *tr).await?;
+    sqlx::query!("INSERT INTO group_relations (child,parent,level) SELECT $1,parent,level + 1 FROM group_relations WHERE child=$2 UNION SELECT $1::uuid,$1::uuid,0", student_id, student.group_id).execute(&mut *conn).await?;
 
-    sqlx::query!("INSERT INTO attendance (cause_id, target, day, meal_id, value) 
+    sqlx::query!(
+        "INSERT INTO student_placements (student_id, group_id) VALUES ($1, $2)",
+        student_id,
+        student.group_id
+    )
+    .execute(&mut *conn)
+    .await?;
+
+    seed_attendance(&mut *conn, student_id, None).await?;
+
+    Ok(student_id)
+}
+
+#[cfg(feature = "ssr")]
+fn check_enrolment(
+    joined: Option<chrono::NaiveDate>,
+    left: Option<chrono::NaiveDate>,
+) -> Result<(), ServerFnError> {
+    match (joined, left) {
+        (Some(joined), Some(left)) if left < joined => Err(ServerFnError::new(
+            "Student cannot leave before joining",
+        )),
+        _ => Ok(()),
+    }
+}
+
+/// Marks the student present on every day and meal of their catering from `since` on, within
+/// the enrolment. Days and meals with recorded attendance are left as they are
+#[cfg(feature = "ssr")]
+pub async fn seed_attendance(
+    conn: &mut sqlx::PgConnection,
+    student_id: Uuid,
+    since: Option<chrono::NaiveDate>,
+) -> Result<u64, ServerFnError> {
+    Ok(sqlx::query!("INSERT INTO attendance (cause_id, target, day, meal_id, value) 
 SELECT $2, $1, day, meal_id, true FROM caterings 
 INNER JOIN group_relations ON group_relations.parent = caterings.group_id AND group_relations.child = $1
-INNER JOIN generate_series(caterings.since, caterings.until, '1 day') as days(day) ON ((caterings.dow >> (EXTRACT(DOW FROM day)::smallint + 6) % 7 )&1) = 1
+INNER JOIN students ON students.id = $1
+INNER JOIN generate_series(GREATEST(caterings.since, students.joined_on, $3), LEAST(caterings.until, students.left_on), '1 day') as days(day) ON ((caterings.dow >> (EXTRACT(DOW FROM day)::smallint + 6) % 7 )&1) = 1
 INNER JOIN catering_meals ON catering_meals.catering_id = caterings.id
-", student_id, Uuid::new_v4()).execute(&mut*tr).await?;
+WHERE NOT EXISTS (SELECT 1 FROM attendance WHERE attendance.target = $1 AND attendance.day = days.day AND attendance.meal_id = catering_meals.meal_id)
+", student_id, Uuid::new_v4(), since).execute(&mut *conn).await?.rows_affected())
+}
+
+#[server]
+pub async fn transfer_student(dto: TransferStudentDto) -> Result<(), ServerFnError> {
+    use sqlx::postgres::PgPool;
+    use crate::services::audit::{record, snapshot};
+    use crate::services::auth::authorize;
+    use dto::audit::{AuditAction, AuditEntity};
+    use dto::user::Role;
+
+    let pool: PgPool = use_context().ok_or(ServerFnError::new("Failed to retrieve db pool"))?;
+    let actor = authorize(&pool, &[Role::Admin]).await?;
+    let mut tr = pool.begin().await?;
+
+    let before = snapshot(&mut tr, AuditEntity::Student, dto.student_id).await?;
+
+    place_student(&mut tr, dto.student_id, dto.group_id, dto.effective).await?;
+
+    let after = snapshot(&mut tr, AuditEntity::Student, dto.student_id).await?;
+    record(
+        &mut tr,
+        &actor,
+        AuditAction::Transfer,
+        AuditEntity::Student,
+        dto.student_id,
+        before,
+        after,
+    )
+    .await?;
 
     tr.commit().await?;
+    Ok(())
+}
 
-    Ok(student_id)
+/// Places the student in `group_id` from `effective` on, replacing placements that would start
+/// later. A transfer that is already due is applied right away, later ones wait for the scheduled job
+#[cfg(feature = "ssr")]
+pub async fn place_student(
+    conn: &mut sqlx::PgConnection,
+    student_id: Uuid,
+    group_id: Uuid,
+    effective: chrono::NaiveDate,
+) -> Result<(), ServerFnError> {
+    use crate::services::group::check_transfer;
+
+    let student = sqlx::query!(
+        "SELECT joined_on, left_on FROM students WHERE id = $1 AND NOT removed",
+        student_id
+    )
+    .fetch_optional(&mut *conn)
+    .await?
+    .ok_or(ServerFnError::new("No such student"))?;
+
+    if student.joined_on.is_some_and(|joined| effective < joined)
+        || student.left_on.is_some_and(|left| effective > left)
+    {
+        return Err(ServerFnError::new(
+            "Transfer has to take effect while the student is enrolled",
+        ));
+    }
+
+    check_transfer(&mut *conn, student_id, group_id).await?;
+
+    sqlx::query!(
+        "DELETE FROM student_placements WHERE student_id = $1 AND since >= $2",
+        student_id,
+        effective
+    )
+    .execute(&mut *conn)
+    .await?;
+
+    sqlx::query!(
+        "UPDATE student_placements SET until = $2::date - 1 WHERE student_id = $1 AND COALESCE(until, 'infinity') >= $2",
+        student_id,
+        effective
+    )
+    .execute(&mut *conn)
+    .await?;
+
+    // Moving back to the group the student is leaving just reopens its placement
+    let reopened = sqlx::query!(
+        "UPDATE student_placements SET until = NULL WHERE student_id = $1 AND group_id = $2 AND until = $3::date - 1",
+        student_id,
+        group_id,
+        effective
+    )
+    .execute(&mut *conn)
+    .await?
+    .rows_affected();
+
+    if reopened == 0 {
+        sqlx::query!(
+            "INSERT INTO student_placements (student_id, group_id, since) VALUES ($1, $2, $3)",
+            student_id,
+            group_id,
+            effective
+        )
+        .execute(&mut *conn)
+        .await?;
+    }
+
+    apply_due_transfers(&mut *conn, Some(student_id)).await?;
+
+    Ok(())
+}
+
+/// Moves students whose current placement differs from their group, all of them or only `student`.
+/// Attendance that doesn't fit the new catering is removed from the transfer on, missing days are
+/// seeded. Returns the number of moved students
+#[cfg(feature = "ssr")]
+pub async fn apply_due_transfers(
+    conn: &mut sqlx::PgConnection,
+    student: Option<Uuid>,
+) -> Result<usize, ServerFnError> {
+    use crate::services::group::transfer_node;
+
+    let due = sqlx::query!(
+        r#"SELECT student_placements.student_id, student_placements.group_id AS "group_id!", student_placements.since FROM student_placements
+        INNER JOIN students ON students.id = student_placements.student_id AND NOT students.removed
+        INNER JOIN group_relations ON group_relations.child = student_placements.student_id AND group_relations.level = 1
+        WHERE COALESCE(student_placements.since, '-infinity') <= CURRENT_DATE AND COALESCE(student_placements.until, 'infinity') >= CURRENT_DATE
+        AND group_relations.parent <> student_placements.group_id AND ($1::uuid IS NULL OR student_placements.student_id = $1)"#,
+        student
+    )
+    .fetch_all(&mut *conn)
+    .await?;
+
+    for transfer in &due {
+        transfer_node(&mut *conn, transfer.student_id, transfer.group_id).await?;
+
+        sqlx::query!(
+            "DELETE FROM attendance USING caterings, group_relations
+            WHERE attendance.target = $1 AND attendance.day >= COALESCE($2::date, '-infinity')
+            AND group_relations.child = $1 AND group_relations.parent = caterings.group_id
+            AND NOT (
+                attendance.day BETWEEN caterings.since AND caterings.until
+                AND ((caterings.dow >> (EXTRACT(DOW FROM attendance.day)::smallint + 6) % 7) & 1) = 1
+                AND attendance.meal_id IN (SELECT meal_id FROM catering_meals WHERE catering_id = caterings.id)
+            )",
+            transfer.student_id,
+            transfer.since
+        )
+        .execute(&mut *conn)
+        .await?;
+
+        seed_attendance(&mut *conn, transfer.student_id, transfer.since).await?;
+    }
+
+    Ok(due.len())
 }
 
 #[server]
 pub async fn get_meals() -> Result<Vec<MealDto>, ServerFnError> {
     use leptos_axum::extract;
     use sqlx::postgres::PgPool;
+    use crate::services::auth::authorize;
+    use dto::user::Role;
 
     let pool: PgPool = use_context().ok_or(ServerFnError::new("Failed to retrieve db pool"))?;
+    authorize(&pool, Role::ALL).await?;
 
     let meals = sqlx::query!("SELECT id,name FROM meals")
         .fetch_all(&pool)
@@ -125,8 +365,11 @@ pub async fn get_meals() -> Result<Vec<MealDto>, ServerFnError> {
 pub async fn get_allergies() -> Result<Vec<AllergyDto>, ServerFnError> {
     use leptos_axum::extract;
     use sqlx::postgres::PgPool;
+    use crate::services::auth::authorize;
+    use dto::user::Role;
 
     let pool: PgPool = use_context().ok_or(ServerFnError::new("Failed to retrieve db pool"))?;
+    authorize(&pool, Role::ALL).await?;
 
     let allergies = sqlx::query!("SELECT id,name FROM allergies")
         .fetch_all(&pool)
@@ -144,8 +387,11 @@ pub async fn get_allergies() -> Result<Vec<AllergyDto>, ServerFnError> {
 pub async fn get_guardians() -> Result<Vec<GuardianDto>, ServerFnError> {
     use leptos_axum::extract;
     use sqlx::postgres::PgPool;
+    use crate::services::auth::authorize;
+    use dto::user::Role;
 
     let pool: PgPool = use_context().ok_or(ServerFnError::new("Failed to retrieve db pool"))?;
+    authorize(&pool, Role::ALL).await?;
 
     let guardians = sqlx::query!("SELECT * FROM guardians")
         .fetch_all(&pool)
@@ -164,8 +410,11 @@ pub async fn get_guardians() -> Result<Vec<GuardianDto>, ServerFnError> {
 pub async fn get_students() -> Result<Vec<StudentDto>, ServerFnError> {
     use leptos_axum::extract;
     use sqlx::postgres::PgPool;
+    use crate::services::auth::authorize;
+    use dto::user::Role;
 
     let pool: PgPool = use_context().ok_or(ServerFnError::new("Failed to retrieve db pool"))?;
+    authorize(&pool, Role::ALL).await?;
 
     let students = sqlx::query!("SELECT id,name,surname,parent as group_id FROM students INNER JOIN group_relations ON child=id AND level=1 WHERE removed= false")
         .fetch_all(&pool)
@@ -184,10 +433,17 @@ pub async fn get_students() -> Result<Vec<StudentDto>, ServerFnError> {
 #[server]
 pub async fn update_student(dto: StudentDetailsDto) -> Result<(), ServerFnError> {
     use sqlx::postgres::PgPool;
+    use crate::services::audit::{record, snapshot};
+    use crate::services::auth::authorize;
+    use dto::audit::{AuditAction, AuditEntity};
+    use dto::user::Role;
 
     let pool: PgPool = use_context().ok_or(ServerFnError::new("Failed to retrieve db pool"))?;
+    let actor = authorize(&pool, &[Role::Admin]).await?;
     let mut tr = pool.begin().await?;
 
+    let before = snapshot(&mut tr, AuditEntity::Student, dto.id).await?;
+
     if dto.guardians.is_empty() {
         return Err(ServerFnError::new(
             "Student needs to have at least one guardian",
@@ -234,16 +490,46 @@ pub async fn update_student(dto: StudentDetailsDto) -> Result<(), ServerFnError>
 
     sqlx::query!("INSERT INTO student_guardians (student_id, guardian_id) SELECT $1,id FROM UNNEST($2::uuid[]) AS guard(name) INNER JOIN guardians ON guardians.id = guard.name", dto.id, &guardian_ids).execute(&mut *tr).await?;
 
+    check_enrolment(dto.joined, dto.left)?;
+
     sqlx::query!(
-        "UPDATE students SET name=$2, surname=$3, allergy_combination_id=$4 WHERE id = $1",
+        "UPDATE students SET name=$2, surname=$3, allergy_combination_id=$4, joined_on=$5, left_on=$6 WHERE id = $1",
         dto.id,
         dto.name,
         dto.surname,
-        allergy_combination
+        allergy_combination,
+        dto.joined,
+        dto.left
     )
     .execute(&mut *tr)
     .await?;
 
+    // Meals after the student leaves are no longer ordered
+    if let Some(left) = dto.left {
+        sqlx::query!(
+            "DELETE FROM attendance WHERE target = $1 AND day > $2",
+            dto.id,
+            left
+        )
+        .execute(&mut *tr)
+        .await?;
+    }
+
+    // Days the enrolment was extended by have no attendance yet
+    seed_attendance(&mut tr, dto.id, None).await?;
+
+    let after = snapshot(&mut tr, AuditEntity::Student, dto.id).await?;
+    record(
+        &mut tr,
+        &actor,
+        AuditAction::Update,
+        AuditEntity::Student,
+        dto.id,
+        before,
+        after,
+    )
+    .await?;
+
     tr.commit().await?;
     Ok(())
 }
@@ -251,8 +537,13 @@ pub async fn update_student(dto: StudentDetailsDto) -> Result<(), ServerFnError>
 #[server]
 pub async fn create_guardian(dto: CreateGuardianDto) -> Result<(), ServerFnError> {
     use sqlx::postgres::PgPool;
+    use crate::services::audit::{record, snapshot};
+    use crate::services::auth::authorize;
+    use dto::audit::{AuditAction, AuditEntity};
+    use dto::user::Role;
 
     let pool: PgPool = use_context().ok_or(ServerFnError::new("Failed to retrieve db pool"))?;
+    let actor = authorize(&pool, &[Role::Admin]).await?;
     let mut tr = pool.begin().await?;
 
     let fullname = dto.fullname.trim();
@@ -278,6 +569,18 @@ pub async fn create_guardian(dto: CreateGuardianDto) -> Result<(), ServerFnError
         ));
     }
 
+    let after = snapshot(&mut tr, AuditEntity::Guardian, guardian_id).await?;
+    record(
+        &mut tr,
+        &actor,
+        AuditAction::Create,
+        AuditEntity::Guardian,
+        guardian_id,
+        None,
+        after,
+    )
+    .await?;
+
     tr.commit().await?;
     Ok(())
 }
@@ -285,8 +588,11 @@ pub async fn create_guardian(dto: CreateGuardianDto) -> Result<(), ServerFnError
 #[server]
 pub async fn get_allergy_combinations() -> Result<Vec<AllergyCombinationDto>, ServerFnError> {
     use sqlx::postgres::PgPool;
+    use crate::services::auth::authorize;
+    use dto::user::Role;
 
     let pool: PgPool = use_context().ok_or(ServerFnError::new("Failed to retrieve db pool"))?;
+    authorize(&pool, Role::ALL).await?;
     let dto = sqlx::query!("SELECT allergy_combinations.id, ARRAY_AGG(allergies.name) AS allergies FROM allergy_combinations
     INNER JOIN allergies ON allergies.id = allergy_combinations.allergy_id
     GROUP BY allergy_combinations.id").fetch_all(&pool).await?.into_iter().map(|row| AllergyCombinationDto{id: row.id, allergies: row.allergies.unwrap_or(vec![])}).collect();
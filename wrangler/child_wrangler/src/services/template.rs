@@ -0,0 +1,221 @@
+use dto::templates::{SaveTemplateDto, TemplateDto};
+use leptos::prelude::*;
+use uuid::Uuid;
+
+#[cfg(feature = "ssr")]
+use dto::templates::{Placeholder, SystemTemplate};
+
+/// Fills in the recipient placeholders of a message composed by staff
+#[cfg(feature = "ssr")]
+pub fn render_for_recipient(
+    content: &str,
+    guardian: &str,
+    students: &[String],
+) -> Result<String, ServerFnError> {
+    use dto::templates::{render, validate};
+
+    validate(content, Placeholder::RECIPIENT).map_err(ServerFnError::new)?;
+    Ok(render(
+        content,
+        &[
+            (Placeholder::Guardian, String::from(guardian)),
+            (Placeholder::Students, students.join(", ")),
+        ],
+    ))
+}
+
+/// Renders the message for the guardian with the phone, messages without placeholders are returned as they are
+#[cfg(feature = "ssr")]
+pub async fn render_for_phone(
+    conn: &mut sqlx::PgConnection,
+    phone: &str,
+    content: &str,
+) -> Result<String, ServerFnError> {
+    if dto::templates::placeholder_names(content).is_empty() {
+        return Ok(String::from(content));
+    }
+
+    let guardian = sqlx::query!(
+        r#"SELECT guardians.fullname,
+        array_remove(array_agg(students.name || ' ' || students.surname ORDER BY students.surname, students.name), NULL) AS "students!"
+        FROM guardians
+        LEFT JOIN student_guardians ON student_guardians.guardian_id = guardians.id
+        LEFT JOIN students ON students.id = student_guardians.student_id AND students.removed = false
+        WHERE guardians.phone = $1 OR format('+48%s', guardians.phone) = $1
+        GROUP BY guardians.id"#,
+        phone
+    )
+    .fetch_optional(&mut *conn)
+    .await?
+    .ok_or(ServerFnError::new(
+        "Placeholders can only be used in messages to guardians",
+    ))?;
+
+    render_for_recipient(content, &guardian.fullname, &guardian.students)
+}
+
+/// System templates first, in the order of the message processing, followed by staff templates
+#[server]
+pub async fn get_templates() -> Result<Vec<TemplateDto>, ServerFnError> {
+    use sqlx::postgres::PgPool;
+    use crate::services::auth::authorize;
+    use dto::user::Role;
+
+    let pool: PgPool = use_context().ok_or(ServerFnError::new("Failed to retrieve db pool"))?;
+    authorize(&pool, &[Role::Admin, Role::Teacher]).await?;
+
+    let rows = sqlx::query!("SELECT id, name, content FROM message_templates ORDER BY name")
+        .fetch_all(&pool)
+        .await?;
+
+    let mut result = SystemTemplate::ALL
+        .iter()
+        .map(|system| {
+            let row = rows.iter().find(|row| row.name == system.as_str());
+            TemplateDto {
+                id: row.map(|row| row.id),
+                name: String::from(system.as_str()),
+                content: row
+                    .map(|row| row.content.clone())
+                    .unwrap_or(String::from(system.default_content())),
+                system: Some(*system),
+            }
+        })
+        .collect::<Vec<_>>();
+
+    result.extend(
+        rows.into_iter()
+            .filter(|row| row.name.parse::<SystemTemplate>().is_err())
+            .map(|row| TemplateDto {
+                id: Some(row.id),
+                name: row.name,
+                content: row.content,
+                system: None,
+            }),
+    );
+
+    Ok(result)
+}
+
+#[server]
+pub async fn save_template(template: SaveTemplateDto) -> Result<Uuid, ServerFnError> {
+    use sqlx::postgres::PgPool;
+    use crate::services::audit::{record, snapshot};
+    use crate::services::auth::authorize;
+    use dto::audit::{AuditAction, AuditEntity};
+    use dto::templates::validate;
+    use dto::user::Role;
+
+    let pool: PgPool = use_context().ok_or(ServerFnError::new("Failed to retrieve db pool"))?;
+    let actor = authorize(&pool, &[Role::Admin]).await?;
+
+    let name = template.name.trim();
+    if name.is_empty() {
+        return Err(ServerFnError::new("Template must have a name"));
+    }
+    let allowed = match name.parse::<SystemTemplate>() {
+        Ok(system) => system.placeholders(),
+        Err(_) => Placeholder::RECIPIENT,
+    };
+    validate(&template.content, allowed).map_err(ServerFnError::new)?;
+
+    let mut tr = pool.begin().await?;
+
+    let before = match template.id {
+        Some(id) => snapshot(&mut tr, AuditEntity::Template, id).await?,
+        None => None,
+    };
+
+    let id = match template.id {
+        Some(id) => {
+            let current =
+                sqlx::query_scalar!("SELECT name FROM message_templates WHERE id = $1", id)
+                    .fetch_optional(&mut *tr)
+                    .await?
+                    .ok_or(ServerFnError::new("Template not found"))?;
+            // The daemon finds system templates by name, so they can't be renamed
+            if current != name
+                && (current.parse::<SystemTemplate>().is_ok()
+                    || name.parse::<SystemTemplate>().is_ok())
+            {
+                return Err(ServerFnError::new("System templates can't be renamed"));
+            }
+
+            sqlx::query!(
+                "UPDATE message_templates SET name = $2, content = $3 WHERE id = $1",
+                id,
+                name,
+                template.content
+            )
+            .execute(&mut *tr)
+            .await?;
+            id
+        }
+        None => {
+            sqlx::query!(
+                "INSERT INTO message_templates (name, content) VALUES ($1, $2) RETURNING id",
+                name,
+                template.content
+            )
+            .fetch_one(&mut *tr)
+            .await?
+            .id
+        }
+    };
+
+    let after = snapshot(&mut tr, AuditEntity::Template, id).await?;
+    record(
+        &mut tr,
+        &actor,
+        if before.is_some() {
+            AuditAction::Update
+        } else {
+            AuditAction::Create
+        },
+        AuditEntity::Template,
+        id,
+        before,
+        after,
+    )
+    .await?;
+
+    tr.commit().await?;
+    Ok(id)
+}
+
+/// Removes a staff template, system templates go back to their default content
+#[server]
+pub async fn delete_template(id: Uuid) -> Result<(), ServerFnError> {
+    use sqlx::postgres::PgPool;
+    use crate::services::audit::{record, snapshot};
+    use crate::services::auth::authorize;
+    use dto::audit::{AuditAction, AuditEntity};
+    use dto::user::Role;
+
+    let pool: PgPool = use_context().ok_or(ServerFnError::new("Failed to retrieve db pool"))?;
+    let actor = authorize(&pool, &[Role::Admin]).await?;
+    let mut tr = pool.begin().await?;
+
+    let before = snapshot(&mut tr, AuditEntity::Template, id).await?;
+
+    let affected = sqlx::query!("DELETE FROM message_templates WHERE id = $1", id)
+        .execute(&mut *tr)
+        .await?;
+    if affected.rows_affected() != 1 {
+        return Err(ServerFnError::new("Template not found"));
+    }
+
+    record(
+        &mut tr,
+        &actor,
+        AuditAction::Delete,
+        AuditEntity::Template,
+        id,
+        before,
+        None,
+    )
+    .await?;
+
+    tr.commit().await?;
+    Ok(())
+}
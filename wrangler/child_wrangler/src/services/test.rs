@@ -10,7 +10,7 @@ pub async fn generate_random_data(
     use std::collections::HashSet;
 
     use chrono::{Days, NaiveTime, Utc};
-    use dto::catering::{CateringDto, CreateCateringDto};
+    use dto::catering::{CateringDto, CateringMealSettingsDto, CreateCateringDto, MealCutoffDto};
     use dto::group::CreateGroupDto;
     use dto::guardian::GuardianDetailDto;
     use dto::student::CreateStudentDto;
@@ -28,6 +28,12 @@ pub async fn generate_random_data(
     use fake::locales::EN;
     use fake::Fake;
     use rand::seq::IndexedRandom;
+    use sqlx::postgres::PgPool;
+    use crate::services::auth::authorize;
+    use dto::user::Role;
+
+    let pool: PgPool = use_context().ok_or(ServerFnError::new("Failed to retrieve db pool"))?;
+    authorize(&pool, &[Role::Admin]).await?;
 
     let now = Utc::now().date_naive();
 
@@ -42,21 +48,29 @@ pub async fn generate_random_data(
 
     for i in 0..catering_count {
         log!("Generating catering");
+        let grace_period = NaiveTime::from_hms_milli_opt(
+            (0..24).fake(),
+            (0..60).fake(),
+            (0..60).fake(),
+            (0..1000).fake(),
+        )
+        .unwrap();
         create_catering(CreateCateringDto {
             name: format!("Catering {}", i),
             since: now.checked_sub_days(Days::new((1..200).fake())).unwrap(),
             until: now.checked_add_days(Days::new((1..200).fake())).unwrap(),
-            grace_period: NaiveTime::from_hms_milli_opt(
-                (0..24).fake(),
-                (0..60).fake(),
-                (0..60).fake(),
-                (0..1000).fake(),
-            )
-            .unwrap(),
+            grace_period,
             meals: meals
                 .clone()
                 .into_iter()
                 .take((1..meals.len()).fake::<usize>())
+                .map(|name| CateringMealSettingsDto {
+                    name,
+                    cutoff: MealCutoffDto {
+                        days_before: (0..2).fake(),
+                        time: grace_period,
+                    },
+                })
                 .collect(),
             dow: (0..7).map(|m| (0..2).fake::<u32>() == 1).collect(),
         })
@@ -109,6 +123,8 @@ pub async fn generate_random_data(
             surname: LastName(EN).fake(),
             allergies: vec![],
             guardians,
+            joined: None,
+            left: None,
         })
         .await?;
 
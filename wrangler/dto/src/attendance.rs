@@ -4,7 +4,10 @@ use chrono::{NaiveDate, NaiveDateTime};
 use serde::{Deserialize, Serialize};
 use uuid::Uuid;
 
-use crate::{catering::MealDto, group::GroupDto};
+use crate::{
+    catering::{MealCutoffDto, MealDto},
+    group::GroupDto,
+};
 
 #[derive(Serialize, Deserialize, Clone, Debug)]
 pub struct GetMonthAttendanceDto {
@@ -17,6 +20,7 @@ pub struct GetMonthAttendanceDto {
 pub struct CateringMealDto {
     pub id: Uuid,
     pub name: String,
+    pub cutoff: MealCutoffDto,
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug)]
@@ -26,6 +30,9 @@ pub struct MonthAttendanceDto {
     pub start: NaiveDate,
     pub end: NaiveDate,
     pub attendance: BTreeMap<NaiveDate, BTreeMap<Uuid, u32>>,
+    /// Closed days of the month with the reason
+    #[serde(default)]
+    pub closures: BTreeMap<NaiveDate, String>,
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Copy)]
@@ -70,6 +77,8 @@ pub struct GetAttendanceHistoryDto {
 pub enum AttendanceItemDto {
     Cancellation(Uuid, String, String),
     Override(Uuid, String),
+    /// Change made by the guardian in the portal
+    Portal(String),
     Init,
 }
 
@@ -0,0 +1,156 @@
+use std::str::FromStr;
+
+use chrono::NaiveDateTime;
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum AuditEntity {
+    Student,
+    Guardian,
+    Group,
+    Catering,
+    Attendance,
+    Message,
+    User,
+    Ledger,
+    Closure,
+    Job,
+    Broadcast,
+    Template,
+}
+
+impl AuditEntity {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            AuditEntity::Student => "student",
+            AuditEntity::Guardian => "guardian",
+            AuditEntity::Group => "group",
+            AuditEntity::Catering => "catering",
+            AuditEntity::Attendance => "attendance",
+            AuditEntity::Message => "message",
+            AuditEntity::User => "user",
+            AuditEntity::Ledger => "ledger",
+            AuditEntity::Closure => "closure",
+            AuditEntity::Job => "job",
+            AuditEntity::Broadcast => "broadcast",
+            AuditEntity::Template => "template",
+        }
+    }
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            AuditEntity::Student => "Uczeń",
+            AuditEntity::Guardian => "Rodzic",
+            AuditEntity::Group => "Grupa",
+            AuditEntity::Catering => "Catering",
+            AuditEntity::Attendance => "Obecność",
+            AuditEntity::Message => "Wiadomość",
+            AuditEntity::User => "Użytkownik",
+            AuditEntity::Ledger => "Rozliczenie",
+            AuditEntity::Closure => "Dzień wolny",
+            AuditEntity::Job => "Zadanie",
+            AuditEntity::Broadcast => "Wiadomość zbiorcza",
+            AuditEntity::Template => "Szablon wiadomości",
+        }
+    }
+}
+
+impl FromStr for AuditEntity {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "student" => Ok(AuditEntity::Student),
+            "guardian" => Ok(AuditEntity::Guardian),
+            "group" => Ok(AuditEntity::Group),
+            "catering" => Ok(AuditEntity::Catering),
+            "attendance" => Ok(AuditEntity::Attendance),
+            "message" => Ok(AuditEntity::Message),
+            "user" => Ok(AuditEntity::User),
+            "ledger" => Ok(AuditEntity::Ledger),
+            "closure" => Ok(AuditEntity::Closure),
+            "job" => Ok(AuditEntity::Job),
+            "broadcast" => Ok(AuditEntity::Broadcast),
+            "template" => Ok(AuditEntity::Template),
+            _ => Err(format!("Unknown audit entity: {}", s)),
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum AuditAction {
+    Create,
+    Update,
+    Delete,
+    Transfer,
+    Send,
+    Requeue,
+    Restore,
+    Purge,
+}
+
+impl AuditAction {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            AuditAction::Create => "create",
+            AuditAction::Update => "update",
+            AuditAction::Delete => "delete",
+            AuditAction::Transfer => "transfer",
+            AuditAction::Send => "send",
+            AuditAction::Requeue => "requeue",
+            AuditAction::Restore => "restore",
+            AuditAction::Purge => "purge",
+        }
+    }
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            AuditAction::Create => "Utworzenie",
+            AuditAction::Update => "Modyfikacja",
+            AuditAction::Delete => "Usunięcie",
+            AuditAction::Transfer => "Przeniesienie",
+            AuditAction::Send => "Wysłanie",
+            AuditAction::Requeue => "Ponowienie",
+            AuditAction::Restore => "Przywrócenie",
+            AuditAction::Purge => "Trwałe usunięcie",
+        }
+    }
+}
+
+impl FromStr for AuditAction {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "create" => Ok(AuditAction::Create),
+            "update" => Ok(AuditAction::Update),
+            "delete" => Ok(AuditAction::Delete),
+            "transfer" => Ok(AuditAction::Transfer),
+            "send" => Ok(AuditAction::Send),
+            "requeue" => Ok(AuditAction::Requeue),
+            "restore" => Ok(AuditAction::Restore),
+            "purge" => Ok(AuditAction::Purge),
+            _ => Err(format!("Unknown audit action: {}", s)),
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct AuditChangeDto {
+    pub field: String,
+    pub before: Option<String>,
+    pub after: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct AuditEntryDto {
+    pub id: Uuid,
+    pub created: NaiveDateTime,
+    pub actor: Option<String>,
+    pub action: AuditAction,
+    pub entity: AuditEntity,
+    pub entity_id: Uuid,
+    pub entity_name: Option<String>,
+    pub changes: Vec<AuditChangeDto>,
+}
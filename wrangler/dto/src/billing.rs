@@ -0,0 +1,98 @@
+use chrono::NaiveDate;
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+/// Amounts are kept in grosze to avoid rounding issues, this formats them as `12,50 zł`
+pub fn format_amount(amount: i64) -> String {
+    let sign = if amount < 0 { "-" } else { "" };
+    let amount = amount.abs();
+    format!("{}{},{:02} zł", sign, amount / 100, amount % 100)
+}
+
+/// Parses amounts typed by users, accepts both `12,5` and `12.50`
+pub fn parse_amount(amount: &str) -> Option<i64> {
+    let amount = amount.trim().trim_end_matches("zł").trim().replace(',', ".");
+    let (whole, fraction) = match amount.split_once('.') {
+        Some((whole, fraction)) => (whole, fraction),
+        None => (amount.as_str(), ""),
+    };
+    if fraction.len() > 2 || !fraction.chars().all(|c| c.is_ascii_digit()) {
+        return None;
+    }
+    let whole: i64 = whole.parse().ok()?;
+    let fraction: i64 = format!("{:0<2}", fraction).parse().ok()?;
+    if whole < 0 {
+        return None;
+    }
+    Some(whole * 100 + fraction)
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct MealPriceDto {
+    pub id: Uuid,
+    pub meal_id: Uuid,
+    pub meal: String,
+    pub price: i32,
+    pub since: NaiveDate,
+    pub until: Option<NaiveDate>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct CreateMealPriceDto {
+    pub catering_id: Uuid,
+    pub meal_id: Uuid,
+    pub price: i32,
+    pub since: NaiveDate,
+    pub until: Option<NaiveDate>,
+}
+
+/// Meals of a single student billed at a single price
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct StatementLineDto {
+    pub student_id: Uuid,
+    pub student: String,
+    pub meal: String,
+    /// `None` when no price was configured for some of the days
+    pub unit_price: Option<i32>,
+    pub ordered: u32,
+    pub cancelled: u32,
+    pub charged: i64,
+    pub refunded: i64,
+    /// Other guardians of the student, listed for information, they aren't billed for these meals
+    pub other_guardians: Vec<String>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct GuardianStatementDto {
+    pub guardian_id: Uuid,
+    pub guardian: String,
+    pub phone: Option<String>,
+    pub year: i32,
+    pub month: u32,
+    pub lines: Vec<StatementLineDto>,
+    pub charged: i64,
+    pub refunded: i64,
+    pub due: i64,
+}
+
+impl GuardianStatementDto {
+    pub fn has_missing_prices(&self) -> bool {
+        self.lines.iter().any(|line| line.unit_price.is_none())
+    }
+}
+
+/// Flat statement line, as written to the csv export
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct StatementCsvRow {
+    pub guardian: String,
+    pub phone: Option<String>,
+    pub student: String,
+    pub meal: String,
+    pub unit_price: Option<String>,
+    pub ordered: u32,
+    pub cancelled: u32,
+    pub charged: String,
+    pub refunded: String,
+    pub due: String,
+    pub other_guardians: String,
+}
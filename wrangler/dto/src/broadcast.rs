@@ -0,0 +1,53 @@
+use chrono::NaiveDateTime;
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::messages::DeliveryStatus;
+
+/// Guardian reached by a broadcast, once even when several of their children are in the group
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct BroadcastRecipientDto {
+    pub guardian_id: Uuid,
+    pub fullname: String,
+    /// Guardians without a phone are listed, but can't be sent the message
+    pub phone: Option<String>,
+    pub students: Vec<String>,
+}
+
+/// Message for all guardians of students in the group and its sub groups, a catering is its root group
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct CreateBroadcastDto {
+    pub group_id: Uuid,
+    pub content: String,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct BroadcastDto {
+    pub id: Uuid,
+    pub created: NaiveDateTime,
+    pub author: String,
+    pub group: String,
+    pub content: String,
+    pub recipients: u32,
+    pub pending: u32,
+    pub sent: u32,
+    pub delivered: u32,
+    pub failed: u32,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct BroadcastDeliveryDto {
+    pub guardian_id: Uuid,
+    pub fullname: String,
+    pub phone: String,
+    pub status: DeliveryStatus,
+    pub attempts: i32,
+    pub last_error: Option<String>,
+    pub sent: Option<NaiveDateTime>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct BroadcastDetailsDto {
+    pub broadcast: BroadcastDto,
+    pub deliveries: Vec<BroadcastDeliveryDto>,
+}
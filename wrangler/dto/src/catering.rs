@@ -1,17 +1,47 @@
-use chrono::{NaiveDate, NaiveTime};
+use chrono::{Days, NaiveDate, NaiveDateTime, NaiveTime};
 use serde::{Deserialize, Serialize};
 use uuid::Uuid;
 
+use crate::attendance::CateringMealDto;
+
 #[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct CreateCateringDto {
     pub name: String,
     pub since: NaiveDate,
     pub until: NaiveDate,
     pub grace_period: NaiveTime,
-    pub meals: Vec<String>,
+    pub meals: Vec<CateringMealSettingsDto>,
     pub dow: Vec<bool>,
 }
 
+/// Guardians can change a meal until `time` on the day `days_before` days before it's served
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MealCutoffDto {
+    pub days_before: u32,
+    pub time: NaiveTime,
+}
+
+impl MealCutoffDto {
+    pub fn deadline(&self, day: NaiveDate) -> NaiveDateTime {
+        (day - Days::new(self.days_before as u64)).and_time(self.time)
+    }
+
+    pub fn label(&self) -> String {
+        match self.days_before {
+            0 => format!("do {} w dniu posiłku", self.time.format("%H:%M")),
+            1 => format!("do {} dzień wcześniej", self.time.format("%H:%M")),
+            days => format!("do {} {} dni wcześniej", self.time.format("%H:%M"), days),
+        }
+    }
+}
+
+/// Meal of a catering being created or changed, meals are matched by name
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct CateringMealSettingsDto {
+    pub name: String,
+    pub cutoff: MealCutoffDto,
+}
+
 #[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct MealDto {
     pub id: Uuid,
@@ -29,3 +59,35 @@ pub struct CateringDto {
     pub id: Uuid,
     pub name: String,
 }
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct CateringDetailsDto {
+    pub id: Uuid,
+    pub group_id: Uuid,
+    pub name: String,
+    pub since: NaiveDate,
+    pub until: NaiveDate,
+    pub grace_period: NaiveTime,
+    pub meals: Vec<CateringMealDto>,
+    pub dow: Vec<bool>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct UpdateCateringDto {
+    pub id: Uuid,
+    pub since: NaiveDate,
+    pub until: NaiveDate,
+    pub grace_period: NaiveTime,
+    pub meals: Vec<CateringMealSettingsDto>,
+    pub dow: Vec<bool>,
+}
+
+/// Effect of a catering change on attendance, days before `effective` are history and are never changed.
+/// Meals with an earlier cut-off are left alone for a few more days
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct CateringUpdateImpactDto {
+    pub effective: NaiveDate,
+    pub added: u64,
+    pub removed: u64,
+    pub cancellations: u64,
+}
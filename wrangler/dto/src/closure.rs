@@ -0,0 +1,160 @@
+use chrono::{Days, NaiveDate};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+/// Days when the kitchen doesn't serve meals, for a single catering or all of them when `catering_id` is empty
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct ClosureDto {
+    pub id: Uuid,
+    pub catering_id: Option<Uuid>,
+    pub catering: Option<String>,
+    pub since: NaiveDate,
+    pub until: NaiveDate,
+    pub reason: String,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct CreateClosureDto {
+    pub catering_id: Option<Uuid>,
+    pub since: NaiveDate,
+    pub until: NaiveDate,
+    pub reason: String,
+}
+
+/// Closure overlapping a cancellation request, sent back to the guardian instead of counting those days
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq, Hash)]
+pub struct ClosureNotice {
+    pub since: NaiveDate,
+    pub until: NaiveDate,
+    pub reason: String,
+}
+
+/// Easter sunday in the gregorian calendar (anonymous gregorian algorithm)
+pub fn easter_sunday(year: i32) -> Option<NaiveDate> {
+    let a = year % 19;
+    let b = year / 100;
+    let c = year % 100;
+    let d = b / 4;
+    let e = b % 4;
+    let f = (b + 8) / 25;
+    let g = (b - f + 1) / 3;
+    let h = (19 * a + b - d - g + 15) % 30;
+    let i = c / 4;
+    let k = c % 4;
+    let l = (32 + 2 * e + 2 * i - h - k) % 7;
+    let m = (a + 11 * h + 22 * l) / 451;
+    let month = (h + l - 7 * m + 114) / 31;
+    let day = (h + l - 7 * m + 114) % 31 + 1;
+    NaiveDate::from_ymd_opt(year, month as u32, day as u32)
+}
+
+/// Statutory public holidays in Poland, Christmas Eve is a holiday since 2025
+pub fn polish_holidays(year: i32) -> Vec<(NaiveDate, &'static str)> {
+    let fixed = [
+        (1, 1, "Nowy Rok"),
+        (1, 6, "Święto Trzech Króli"),
+        (5, 1, "Święto Pracy"),
+        (5, 3, "Święto Konstytucji 3 Maja"),
+        (8, 15, "Wniebowzięcie Najświętszej Maryi Panny"),
+        (11, 1, "Wszystkich Świętych"),
+        (11, 11, "Narodowe Święto Niepodległości"),
+        (12, 24, "Wigilia Bożego Narodzenia"),
+        (12, 25, "Boże Narodzenie"),
+        (12, 26, "Drugi dzień Bożego Narodzenia"),
+    ];
+
+    let mut holidays = fixed
+        .into_iter()
+        .filter(|(month, day, _)| year >= 2025 || (*month, *day) != (12, 24))
+        .filter_map(|(month, day, name)| {
+            NaiveDate::from_ymd_opt(year, month, day).map(|date| (date, name))
+        })
+        .collect::<Vec<_>>();
+
+    if let Some(easter) = easter_sunday(year) {
+        let movable = [
+            (0, "Wielkanoc"),
+            (1, "Poniedziałek Wielkanocny"),
+            (49, "Zielone Świątki"),
+            (60, "Boże Ciało"),
+        ];
+        holidays.extend(movable.into_iter().filter_map(|(offset, name)| {
+            easter
+                .checked_add_days(Days::new(offset))
+                .map(|date| (date, name))
+        }));
+    }
+
+    holidays.sort();
+    holidays
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::Datelike;
+
+    use super::*;
+
+    fn date(year: i32, month: u32, day: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(year, month, day).unwrap()
+    }
+
+    fn holiday(year: i32, name: &str) -> Option<NaiveDate> {
+        polish_holidays(year)
+            .into_iter()
+            .find(|(_, holiday)| *holiday == name)
+            .map(|(date, _)| date)
+    }
+
+    #[test]
+    fn easter_sunday_known_dates() {
+        assert_eq!(easter_sunday(2024), Some(date(2024, 3, 31)));
+        assert_eq!(easter_sunday(2025), Some(date(2025, 4, 20)));
+        assert_eq!(easter_sunday(2026), Some(date(2026, 4, 5)));
+        assert_eq!(easter_sunday(2000), Some(date(2000, 4, 23)));
+    }
+
+    #[test]
+    fn movable_holidays_follow_easter() {
+        assert_eq!(holiday(2025, "Wielkanoc"), Some(date(2025, 4, 20)));
+        assert_eq!(
+            holiday(2025, "Poniedziałek Wielkanocny"),
+            Some(date(2025, 4, 21))
+        );
+        assert_eq!(holiday(2025, "Zielone Świątki"), Some(date(2025, 6, 8)));
+        assert_eq!(holiday(2025, "Boże Ciało"), Some(date(2025, 6, 19)));
+
+        assert_eq!(holiday(2024, "Zielone Świątki"), Some(date(2024, 5, 19)));
+        assert_eq!(holiday(2024, "Boże Ciało"), Some(date(2024, 5, 30)));
+        assert_eq!(holiday(2026, "Zielone Świątki"), Some(date(2026, 5, 24)));
+        assert_eq!(holiday(2026, "Boże Ciało"), Some(date(2026, 6, 4)));
+    }
+
+    #[test]
+    fn christmas_eve_is_a_holiday_since_2025() {
+        assert_eq!(holiday(2024, "Wigilia Bożego Narodzenia"), None);
+        assert_eq!(
+            holiday(2025, "Wigilia Bożego Narodzenia"),
+            Some(date(2025, 12, 24))
+        );
+        assert_eq!(
+            holiday(2026, "Wigilia Bożego Narodzenia"),
+            Some(date(2026, 12, 24))
+        );
+        assert_eq!(polish_holidays(2024).len(), 13);
+        assert_eq!(polish_holidays(2025).len(), 14);
+    }
+
+    #[test]
+    fn holidays_are_sorted() {
+        for year in 2024..=2030 {
+            let holidays = polish_holidays(year);
+            assert!(
+                holidays.windows(2).all(|pair| pair[0].0 < pair[1].0),
+                "holidays of {} are out of order",
+                year
+            );
+            assert!(holidays.iter().all(|(date, _)| date.year() == year));
+        }
+    }
+}
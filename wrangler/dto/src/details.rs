@@ -1,7 +1,8 @@
+use chrono::NaiveDate;
 use serde::{Deserialize, Serialize};
 use uuid::Uuid;
 
-use crate::{catering::AllergyDto, group::GroupDto, guardian::GuardianDto};
+use crate::{catering::AllergyDto, group::GroupDto, guardian::GuardianDto, student::PlacementDto};
 
 #[derive(Serialize, Debug, Clone, Deserialize)]
 pub struct Breadcrumb {
@@ -17,6 +18,13 @@ pub struct StudentDetailsDto {
     pub guardians: Vec<GuardianDto>,
     #[serde(default)]
     pub allergies: Vec<AllergyDto>,
+    #[serde(default)]
+    pub joined: Option<NaiveDate>,
+    #[serde(default)]
+    pub left: Option<NaiveDate>,
+    /// Groups the student was, is and will be in, oldest first
+    #[serde(default)]
+    pub placements: Vec<PlacementDto>,
 }
 
 #[derive(Serialize, Debug, Clone, Deserialize)]
@@ -0,0 +1,78 @@
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+/// Spreadsheet uploaded for import, CSV or XLSX depending on the extension
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct ImportFileDto {
+    pub filename: String,
+    pub content: Vec<u8>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct ImportGuardianDto {
+    pub fullname: String,
+    pub phone: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct ImportRowDto {
+    /// Line in the uploaded file, counting the header
+    pub line: usize,
+    /// Names of groups from the root group down to the student's group
+    pub group_path: Vec<String>,
+    pub name: String,
+    pub surname: String,
+    pub allergies: Vec<String>,
+    pub guardians: Vec<ImportGuardianDto>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub enum ImportRowStatus {
+    New,
+    Unchanged(Uuid),
+    Changed(Uuid, Vec<String>),
+    /// Row is skipped, student can't be told apart from another one
+    Duplicate(String),
+}
+
+impl ImportRowStatus {
+    pub fn label(&self) -> &'static str {
+        match self {
+            ImportRowStatus::New => "Nowy",
+            ImportRowStatus::Unchanged(_) => "Bez zmian",
+            ImportRowStatus::Changed(_, _) => "Zmieniony",
+            ImportRowStatus::Duplicate(_) => "Duplikat",
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct ImportPreviewRowDto {
+    pub row: ImportRowDto,
+    pub status: ImportRowStatus,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct ImportErrorDto {
+    pub line: usize,
+    pub message: String,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct ImportPreviewDto {
+    pub rows: Vec<ImportPreviewRowDto>,
+    /// Paths of groups which will be created, joined with `/`
+    pub new_groups: Vec<String>,
+    pub new_guardians: Vec<String>,
+    /// Import can't be applied while there are any errors
+    pub errors: Vec<ImportErrorDto>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, Default)]
+pub struct ImportResultDto {
+    pub created_students: usize,
+    pub updated_students: usize,
+    pub created_groups: usize,
+    pub created_guardians: usize,
+    pub skipped: usize,
+}
@@ -0,0 +1,354 @@
+use std::str::FromStr;
+
+use chrono::{Datelike, Days, NaiveDate, NaiveDateTime, NaiveTime, Timelike};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+/// Work the server knows how to run on a schedule, stored in `scheduled_jobs` under `as_str`
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Job {
+    MealSnapshots,
+    ProcessingStepCleanup,
+    MonthlyStatements,
+    StudentTransfers,
+    RecycleBinPurge,
+}
+
+impl Job {
+    pub const ALL: &'static [Job] = &[
+        Job::MealSnapshots,
+        Job::ProcessingStepCleanup,
+        Job::MonthlyStatements,
+        Job::StudentTransfers,
+        Job::RecycleBinPurge,
+    ];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Job::MealSnapshots => "meal_snapshots",
+            Job::ProcessingStepCleanup => "processing_step_cleanup",
+            Job::MonthlyStatements => "monthly_statements",
+            Job::StudentTransfers => "student_transfers",
+            Job::RecycleBinPurge => "recycle_bin_purge",
+        }
+    }
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            Job::MealSnapshots => "Zestawienia posiłków po zamknięciu",
+            Job::ProcessingStepCleanup => "Czyszczenie historii przetwarzania wiadomości",
+            Job::MonthlyStatements => "SMS z należnością za poprzedni miesiąc",
+            Job::StudentTransfers => "Przeniesienia uczniów w zaplanowanym dniu",
+            Job::RecycleBinPurge => "Opróżnianie kosza z przeterminowanych wpisów",
+        }
+    }
+}
+
+impl FromStr for Job {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "meal_snapshots" => Ok(Job::MealSnapshots),
+            "processing_step_cleanup" => Ok(Job::ProcessingStepCleanup),
+            "monthly_statements" => Ok(Job::MonthlyStatements),
+            "student_transfers" => Ok(Job::StudentTransfers),
+            "recycle_bin_purge" => Ok(Job::RecycleBinPurge),
+            _ => Err(format!("Unknown job: {}", s)),
+        }
+    }
+}
+
+/// Cron-like schedule: minute, hour, day of month, month and day of week (0 or 7 is sunday).
+/// Fields accept `*`, single values, ranges, lists and steps, e.g. `*/15 6-18 * * 1-5`
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct CronSchedule {
+    minutes: u64,
+    hours: u32,
+    days: u32,
+    months: u16,
+    weekdays: u8,
+    any_day: bool,
+    any_weekday: bool,
+}
+
+fn parse_field(field: &str, min: u32, max: u32) -> Result<u64, String> {
+    let mut mask = 0u64;
+    for part in field.split(',') {
+        let (range, step) = match part.split_once('/') {
+            Some((range, step)) => (
+                range,
+                step.parse::<u32>()
+                    .ok()
+                    .filter(|step| *step > 0)
+                    .ok_or(format!("Invalid step: {}", part))?,
+            ),
+            None => (part, 1),
+        };
+        let (start, end) = match range {
+            "*" => (min, max),
+            range => match range.split_once('-') {
+                Some((start, end)) => (
+                    start.parse().map_err(|_| format!("Invalid value: {}", part))?,
+                    end.parse().map_err(|_| format!("Invalid value: {}", part))?,
+                ),
+                None => {
+                    let value = range
+                        .parse()
+                        .map_err(|_| format!("Invalid value: {}", part))?;
+                    // A single value with a step runs from the value until the end of the range
+                    (value, if part.contains('/') { max } else { value })
+                }
+            },
+        };
+        if start < min || end > max || start > end {
+            return Err(format!("Value out of range {}-{}: {}", min, max, part));
+        }
+        for value in (start..=end).step_by(step as usize) {
+            mask |= 1 << value;
+        }
+    }
+    Ok(mask)
+}
+
+impl FromStr for CronSchedule {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let fields = s.split_whitespace().collect::<Vec<_>>();
+        let [minutes, hours, days, months, weekdays] = fields[..] else {
+            return Err(String::from(
+                "Schedule needs 5 fields: minute, hour, day, month and weekday",
+            ));
+        };
+
+        let days = parse_field(days, 1, 31)? as u32;
+        let weekdays = parse_field(weekdays, 0, 7)?;
+        // Sunday can be written both as 0 and 7
+        let weekdays = ((weekdays | (weekdays >> 7)) & 0x7f) as u8;
+        Ok(CronSchedule {
+            minutes: parse_field(minutes, 0, 59)?,
+            hours: parse_field(hours, 0, 23)? as u32,
+            days,
+            months: parse_field(months, 1, 12)? as u16,
+            weekdays,
+            any_day: days == u32::MAX << 1,
+            any_weekday: weekdays == 0x7f,
+        })
+    }
+}
+
+impl CronSchedule {
+    fn matches_day(&self, day: NaiveDate) -> bool {
+        let day_matches = self.days & (1 << day.day()) != 0;
+        let weekday_matches = self.weekdays & (1 << day.weekday().num_days_from_sunday()) != 0;
+
+        self.months & (1 << day.month()) != 0
+            && match (self.any_day, self.any_weekday) {
+                (true, true) => true,
+                (true, false) => weekday_matches,
+                (false, true) => day_matches,
+                // Like cron, restricting both fields runs the job when either matches
+                (false, false) => day_matches || weekday_matches,
+            }
+    }
+
+    pub fn matches(&self, time: NaiveDateTime) -> bool {
+        self.matches_day(time.date())
+            && self.hours & (1 << time.hour()) != 0
+            && self.minutes & (1 << time.minute()) != 0
+    }
+
+    /// First minute strictly after `time` matching the schedule, searched up to 5 years ahead
+    pub fn next_after(&self, time: NaiveDateTime) -> Option<NaiveDateTime> {
+        let start = time.date();
+        for offset in 0..(5 * 366) {
+            let day = start.checked_add_days(Days::new(offset))?;
+            if !self.matches_day(day) {
+                continue;
+            }
+            for hour in (0..24).filter(|hour| self.hours & (1 << hour) != 0) {
+                for minute in (0..60).filter(|minute| self.minutes & (1 << minute) != 0) {
+                    let candidate = day.and_time(NaiveTime::from_hms_opt(hour, minute, 0)?);
+                    if candidate > time {
+                        return Some(candidate);
+                    }
+                }
+            }
+        }
+        None
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct JobRunDto {
+    pub id: Uuid,
+    pub started: NaiveDateTime,
+    pub finished: Option<NaiveDateTime>,
+    pub success: Option<bool>,
+    pub output: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct JobDto {
+    pub id: Uuid,
+    pub job: Job,
+    pub schedule: String,
+    pub enabled: bool,
+    pub next_run: Option<NaiveDateTime>,
+    /// Latest runs, newest first
+    pub runs: Vec<JobRunDto>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct UpdateJobDto {
+    pub id: Uuid,
+    pub schedule: String,
+    pub enabled: bool,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn at(date: &str, time: &str) -> NaiveDateTime {
+        NaiveDateTime::parse_from_str(&format!("{} {}", date, time), "%Y-%m-%d %H:%M").unwrap()
+    }
+
+    fn schedule(s: &str) -> CronSchedule {
+        s.parse().unwrap()
+    }
+
+    #[test]
+    fn steps_over_whole_field_and_ranges() {
+        let every_quarter = schedule("*/15 * * * *");
+        for minute in ["00", "15", "30", "45"] {
+            assert!(every_quarter.matches(at("2025-03-03", &format!("10:{}", minute))));
+        }
+        assert!(!every_quarter.matches(at("2025-03-03", "10:05")));
+
+        let odd_hours = schedule("0 7-13/2 * * *");
+        for hour in [7, 9, 11, 13] {
+            assert!(odd_hours.matches(at("2025-03-03", &format!("{:02}:00", hour))));
+        }
+        assert!(!odd_hours.matches(at("2025-03-03", "08:00")));
+        assert!(!odd_hours.matches(at("2025-03-03", "15:00")));
+
+        // A single value with a step runs until the end of the field
+        let from_fifty = schedule("50/5 * * * *");
+        assert!(from_fifty.matches(at("2025-03-03", "10:55")));
+        assert!(!from_fifty.matches(at("2025-03-03", "10:45")));
+    }
+
+    #[test]
+    fn zero_and_seven_are_sunday() {
+        // 2025-03-02 is a sunday, 2025-03-03 a monday
+        for s in ["0 8 * * 0", "0 8 * * 7"] {
+            let sunday = schedule(s);
+            assert!(sunday.matches(at("2025-03-02", "08:00")));
+            assert!(!sunday.matches(at("2025-03-03", "08:00")));
+        }
+        assert_eq!(schedule("0 8 * * 0"), schedule("0 8 * * 7"));
+        assert_eq!(schedule("0 8 * * 5-7"), schedule("0 8 * * 0,5,6"));
+    }
+
+    #[test]
+    fn restricted_day_and_weekday_match_either() {
+        // The 1st of the month or any monday
+        let either = schedule("0 6 1 * 1");
+        assert!(either.matches(at("2025-03-01", "06:00")));
+        assert!(either.matches(at("2025-03-03", "06:00")));
+        assert!(!either.matches(at("2025-03-04", "06:00")));
+
+        // With one side unrestricted only the other one counts
+        let weekdays_only = schedule("0 6 * * 1");
+        assert!(!weekdays_only.matches(at("2025-03-01", "06:00")));
+        assert!(weekdays_only.matches(at("2025-03-03", "06:00")));
+        let days_only = schedule("0 6 1 * *");
+        assert!(days_only.matches(at("2025-03-01", "06:00")));
+        assert!(!days_only.matches(at("2025-03-03", "06:00")));
+    }
+
+    #[test]
+    fn rejects_malformed_and_out_of_range_fields() {
+        for s in [
+            "",
+            "* * * *",
+            "* * * * * *",
+            "60 * * * *",
+            "* 24 * * *",
+            "* * 0 * *",
+            "* * 32 * *",
+            "* * * 0 *",
+            "* * * 13 *",
+            "* * * * 8",
+            "5-1 * * * *",
+            "*/0 * * * *",
+            "*/x * * * *",
+            "a * * * *",
+            "1- * * * *",
+            "1,,2 * * * *",
+            "-1 * * * *",
+        ] {
+            assert!(
+                s.parse::<CronSchedule>().is_err(),
+                "{:?} should be rejected",
+                s
+            );
+        }
+    }
+
+    #[test]
+    fn next_after_is_strictly_later() {
+        let hourly = schedule("0 * * * *");
+        assert_eq!(
+            hourly.next_after(at("2025-03-03", "10:00")),
+            Some(at("2025-03-03", "11:00"))
+        );
+        assert_eq!(
+            hourly.next_after(at("2025-03-03", "10:59")),
+            Some(at("2025-03-03", "11:00"))
+        );
+
+        let daily = schedule("30 6 * * *");
+        assert_eq!(
+            daily.next_after(at("2025-03-03", "06:30")),
+            Some(at("2025-03-04", "06:30"))
+        );
+        assert_eq!(
+            daily.next_after(at("2025-03-03", "06:29")),
+            Some(at("2025-03-03", "06:30"))
+        );
+    }
+
+    #[test]
+    fn next_after_rolls_over_months_and_years() {
+        let monthly = schedule("0 8 1 * *");
+        assert_eq!(
+            monthly.next_after(at("2025-01-31", "12:00")),
+            Some(at("2025-02-01", "08:00"))
+        );
+        assert_eq!(
+            monthly.next_after(at("2025-12-01", "08:00")),
+            Some(at("2026-01-01", "08:00"))
+        );
+
+        let leap_day = schedule("0 0 29 2 *");
+        assert_eq!(
+            leap_day.next_after(at("2025-01-01", "00:00")),
+            Some(at("2028-02-29", "00:00"))
+        );
+    }
+
+    #[test]
+    fn impossible_dates_never_run() {
+        assert_eq!(
+            schedule("0 0 31 2 *").next_after(at("2025-01-01", "00:00")),
+            None
+        );
+        assert_eq!(
+            schedule("0 0 30 2 *").next_after(at("2025-01-01", "00:00")),
+            None
+        );
+    }
+}
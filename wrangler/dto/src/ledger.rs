@@ -0,0 +1,99 @@
+use std::str::FromStr;
+
+use chrono::NaiveDate;
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+/// Amounts in the ledger are signed from the guardian's point of view,
+/// charges are negative and payments positive, so the balance is just their sum
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum LedgerEntryKind {
+    Charge,
+    Payment,
+    Adjustment,
+}
+
+impl LedgerEntryKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            LedgerEntryKind::Charge => "charge",
+            LedgerEntryKind::Payment => "payment",
+            LedgerEntryKind::Adjustment => "adjustment",
+        }
+    }
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            LedgerEntryKind::Charge => "Opłata",
+            LedgerEntryKind::Payment => "Wpłata",
+            LedgerEntryKind::Adjustment => "Korekta",
+        }
+    }
+}
+
+impl FromStr for LedgerEntryKind {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "charge" => Ok(LedgerEntryKind::Charge),
+            "payment" => Ok(LedgerEntryKind::Payment),
+            "adjustment" => Ok(LedgerEntryKind::Adjustment),
+            _ => Err(format!("Unknown ledger entry kind: {}", s)),
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct LedgerEntryDto {
+    pub id: Uuid,
+    pub kind: LedgerEntryKind,
+    pub amount: i64,
+    pub booked: NaiveDate,
+    pub description: String,
+    pub from_bank: bool,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct GuardianLedgerDto {
+    pub guardian_id: Uuid,
+    pub balance: i64,
+    pub entries: Vec<LedgerEntryDto>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct CreateLedgerEntryDto {
+    pub guardian_id: Uuid,
+    pub kind: LedgerEntryKind,
+    pub amount: i64,
+    pub booked: NaiveDate,
+    pub description: String,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct ArrearsDto {
+    pub guardian_id: Uuid,
+    pub fullname: String,
+    pub phone: Option<String>,
+    pub balance: i64,
+    /// Oldest charge that isn't covered by payments yet
+    pub overdue_since: Option<NaiveDate>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct BankTransferDto {
+    pub id: Uuid,
+    pub booked: NaiveDate,
+    pub amount: i64,
+    pub title: String,
+    pub counterparty: Option<String>,
+    pub account: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct BankImportResultDto {
+    pub imported: u32,
+    pub duplicates: u32,
+    pub skipped: u32,
+    pub matched: u32,
+}
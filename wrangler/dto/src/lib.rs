@@ -1,7 +1,19 @@
 pub mod attendance;
+pub mod audit;
+pub mod billing;
+pub mod broadcast;
 pub mod catering;
+pub mod closure;
 pub mod details;
 pub mod group;
 pub mod guardian;
+pub mod import;
+pub mod jobs;
+pub mod ledger;
 pub mod messages;
+pub mod portal;
+pub mod production;
+pub mod recycle_bin;
 pub mod student;
+pub mod templates;
+pub mod user;
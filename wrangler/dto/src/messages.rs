@@ -1,11 +1,11 @@
 use chrono::{NaiveDate, NaiveDateTime};
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
+use std::{collections::HashMap, str::FromStr};
 use uuid::Uuid;
 
 use chrono::NaiveTime;
 
-use crate::guardian::GuardianDto;
+use crate::{catering::MealCutoffDto, closure::ClosureNotice, guardian::GuardianDto};
 
 #[derive(Serialize, Deserialize, Debug, Clone)]
 pub enum ContactDto {
@@ -23,6 +23,57 @@ pub struct DbMessage {
     pub cause_id: Option<Uuid>,
     pub outgoing: bool,
     pub processed: bool,
+    pub status: String,
+    pub attempts: i32,
+    pub next_attempt: Option<NaiveDateTime>,
+    pub last_error: Option<String>,
+    pub via: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum DeliveryStatus {
+    Queued,
+    Sending,
+    Sent,
+    Delivered,
+    Failed,
+}
+
+impl DeliveryStatus {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            DeliveryStatus::Queued => "queued",
+            DeliveryStatus::Sending => "sending",
+            DeliveryStatus::Sent => "sent",
+            DeliveryStatus::Delivered => "delivered",
+            DeliveryStatus::Failed => "failed",
+        }
+    }
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            DeliveryStatus::Queued => "W kolejce",
+            DeliveryStatus::Sending => "Wysyłanie",
+            DeliveryStatus::Sent => "Wysłano",
+            DeliveryStatus::Delivered => "Dostarczono",
+            DeliveryStatus::Failed => "Błąd wysyłki",
+        }
+    }
+}
+
+impl FromStr for DeliveryStatus {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "queued" => Ok(DeliveryStatus::Queued),
+            "sending" => Ok(DeliveryStatus::Sending),
+            "sent" => Ok(DeliveryStatus::Sent),
+            "delivered" => Ok(DeliveryStatus::Delivered),
+            "failed" => Ok(DeliveryStatus::Failed),
+            _ => Err(format!("Unknown delivery status: {}", s)),
+        }
+    }
 }
 
 pub fn parse_message(msg: DbMessage) -> Message {
@@ -35,11 +86,14 @@ pub fn parse_message(msg: DbMessage) -> Message {
         content: msg.content,
     };
 
+    let status = msg.status.parse().unwrap_or(DeliveryStatus::Queued);
+
     match (msg.sent, msg.outgoing) {
         (Some(sent), true) => Message::Sent(SentMessage {
             metadata,
             data,
             sent,
+            status,
         }),
         (Some(received), false) => Message::Received(ReceivedMessage {
             data,
@@ -47,7 +101,13 @@ pub fn parse_message(msg: DbMessage) -> Message {
             received,
             processed: msg.processed,
         }),
-        (None, true) => Message::Pending(PendingMessage { data, metadata }),
+        (None, true) => Message::Pending(PendingMessage {
+            data,
+            metadata,
+            status,
+            attempts: msg.attempts,
+            last_error: msg.last_error,
+        }),
         _ => panic!("Invalid message combination"),
     }
 }
@@ -69,6 +129,7 @@ pub struct SentMessage {
     pub metadata: MessageMetadata,
     pub data: MessageData,
     pub sent: NaiveDateTime,
+    pub status: DeliveryStatus,
 }
 #[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
 pub struct ReceivedMessage {
@@ -81,6 +142,9 @@ pub struct ReceivedMessage {
 pub struct PendingMessage {
     pub metadata: MessageMetadata,
     pub data: MessageData,
+    pub status: DeliveryStatus,
+    pub attempts: i32,
+    pub last_error: Option<String>,
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
@@ -119,10 +183,24 @@ pub struct Student {
     pub ends: NaiveDate,
 }
 
+impl Student {
+    /// Cut-off of one of the student's meals
+    pub fn cutoff(&self, meal: &Meal) -> MealCutoffDto {
+        meal.cutoff.unwrap_or(MealCutoffDto {
+            days_before: 0,
+            time: self.grace_period,
+        })
+    }
+}
+
 #[derive(Serialize, Deserialize, Clone, Debug)]
 pub struct Meal {
     pub id: Uuid,
     pub name: String,
+    /// Missing in contexts saved before meals had their own cut-offs,
+    /// those used the grace period of the catering
+    #[serde(default)]
+    pub cutoff: Option<MealCutoffDto>,
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone)]
@@ -133,13 +211,15 @@ pub struct MessageProcessingContext {
     pub content: String,
 }
 
-#[derive(Serialize, Deserialize, Debug, Clone)]
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq, Hash)]
 pub enum Token {
     Student(Uuid),
     Date(NaiveDate),
+    DateRange(NaiveDate, NaiveDate),
     Meal(Uuid),
     Unknown(String),
     Ambiguous(String),
+    Restore,
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone)]
@@ -173,10 +253,32 @@ pub enum RequestError {
     AmbiguousTerm(String),
 }
 
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct ClarificationOption {
+    pub label: String,
+    pub token: Token,
+}
+
+/// Question sent back to the guardian about `tokens[position]`, answered with an option number
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct Clarification {
+    pub tokens: Vec<Token>,
+    pub position: usize,
+    pub options: Vec<ClarificationOption>,
+}
+
 #[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct CancellationResult {
     pub name: String,
     pub meals: HashMap<String, i64>,
+    /// Closed days within the request, skipped instead of cancelled
+    #[serde(default)]
+    pub closures: Vec<ClosureNotice>,
+    /// First and last day with a changed meal
+    #[serde(default)]
+    pub since: Option<NaiveDate>,
+    #[serde(default)]
+    pub until: Option<NaiveDate>,
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone)]
@@ -186,13 +288,104 @@ pub enum MessageProcessing {
     Cancellation(CancellationRequest),
     StudentCancellation(AttendanceCancellation),
     CancellationResult(Vec<CancellationResult>),
+    Restoration(CancellationRequest),
+    StudentRestoration(AttendanceCancellation),
+    RestorationResult(Vec<CancellationResult>),
     RequestError(RequestError),
+    Clarification(Clarification),
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct PhoneStateChangeDto {
+    pub recorded: NaiveDateTime,
+    pub state: Option<i32>,
+    pub signal: Option<i32>,
+    pub locked: bool,
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct PhoneStatusDto {
+    pub number: String,
     pub last_updated: NaiveDateTime,
+    pub state_since: NaiveDateTime,
     pub total_sent: i32,
     pub total_received: i32,
     pub signal: i32,
+    pub state: i32,
+    pub locked: bool,
+    /// Set when the line has been offline or locked for longer than the configured time
+    pub alert: Option<String>,
+    pub history: Vec<PhoneStateChangeDto>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ConnectorState {
+    Starting,
+    Running,
+    WaitingForModem,
+    Reconnecting,
+}
+
+impl ConnectorState {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ConnectorState::Starting => "starting",
+            ConnectorState::Running => "running",
+            ConnectorState::WaitingForModem => "waiting_for_modem",
+            ConnectorState::Reconnecting => "reconnecting",
+        }
+    }
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            ConnectorState::Starting => "Uruchamianie",
+            ConnectorState::Running => "Działa",
+            ConnectorState::WaitingForModem => "Oczekiwanie na modem",
+            ConnectorState::Reconnecting => "Ponowne łączenie",
+        }
+    }
+}
+
+impl FromStr for ConnectorState {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "starting" => Ok(ConnectorState::Starting),
+            "running" => Ok(ConnectorState::Running),
+            "waiting_for_modem" => Ok(ConnectorState::WaitingForModem),
+            "reconnecting" => Ok(ConnectorState::Reconnecting),
+            _ => Err(format!("Unknown connector state: {}", s)),
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct ConnectorStatusDto {
+    /// `None` if the connector never reported itself
+    pub state: Option<ConnectorState>,
+    pub detail: Option<String>,
+    pub since: Option<NaiveDateTime>,
+    pub heartbeat: Option<NaiveDateTime>,
+    /// Connector reported itself recently enough to be considered alive
+    pub online: bool,
+}
+
+/// Human readable MMModemState
+pub fn phone_state_label(state: i32) -> &'static str {
+    match state {
+        -1 => "Błąd",
+        1 => "Inicjalizacja",
+        2 => "Zablokowany",
+        3 => "Wyłączony",
+        4 => "Wyłączanie",
+        5 => "Włączanie",
+        6 => "Włączony",
+        7 => "Wyszukiwanie sieci",
+        8 => "Zarejestrowany",
+        9 => "Rozłączanie",
+        10 => "Łączenie",
+        11 => "Połączony",
+        _ => "Nieznany",
+    }
 }
@@ -0,0 +1,77 @@
+use std::collections::BTreeMap;
+
+use chrono::{Datelike, NaiveDate, NaiveDateTime};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::attendance::{CateringMealDto, EffectiveAttendance};
+
+/// Guardian logged in to the self-service portal
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct PortalGuardianDto {
+    pub id: Uuid,
+    pub fullname: String,
+    pub students: Vec<PortalStudentDto>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct PortalStudentDto {
+    pub id: Uuid,
+    pub name: String,
+    pub surname: String,
+}
+
+/// Month calendar of a single student as seen by the guardian
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct PortalMonthDto {
+    pub meals: Vec<CateringMealDto>,
+    pub days_of_week: Vec<bool>,
+    pub start: NaiveDate,
+    pub end: NaiveDate,
+    pub closures: BTreeMap<NaiveDate, String>,
+    pub attendance: BTreeMap<NaiveDate, BTreeMap<Uuid, EffectiveAttendance>>,
+    /// First day on which some meal can still be changed, earlier days are past their cut-off
+    pub editable_since: NaiveDate,
+    /// Server time the month was loaded at, meals whose cut-off passed by then are locked
+    pub loaded: NaiveDateTime,
+}
+
+impl PortalMonthDto {
+    pub fn is_served(&self, day: NaiveDate) -> bool {
+        self.days_of_week
+            .get(day.weekday().num_days_from_monday() as usize)
+            .copied()
+            .unwrap_or(false)
+            && day >= self.start
+            && day <= self.end
+            && !self.closures.contains_key(&day)
+    }
+
+    /// Whether the guardian can still change `meal_id` on `day`, staff decisions aside
+    pub fn is_editable(&self, day: NaiveDate, meal_id: Uuid) -> bool {
+        self.is_served(day)
+            && self
+                .meals
+                .iter()
+                .find(|meal| meal.id == meal_id)
+                .is_some_and(|meal| self.loaded < meal.cutoff.deadline(day))
+    }
+
+    pub fn status(&self, day: NaiveDate, meal_id: Uuid) -> EffectiveAttendance {
+        self.attendance
+            .get(&day)
+            .and_then(|meals| meals.get(&meal_id))
+            .copied()
+            .unwrap_or(EffectiveAttendance::Present)
+    }
+}
+
+/// Cancels (`present = false`) or restores the meals of a student between `since` and `until`
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct PortalAttendanceDto {
+    pub student_id: Uuid,
+    pub since: NaiveDate,
+    pub until: NaiveDate,
+    pub meals: Vec<Uuid>,
+    pub present: bool,
+}
@@ -0,0 +1,109 @@
+use std::collections::BTreeMap;
+
+use chrono::{NaiveDate, NaiveDateTime};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+/// Portions to prepare, regular ones and per allergy combination named after its allergies
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq)]
+pub struct PortionsDto {
+    pub regular: u32,
+    pub diets: BTreeMap<String, u32>,
+}
+
+impl PortionsDto {
+    pub fn add(&mut self, diet: Option<String>, portions: u32) {
+        match diet {
+            Some(diet) => *self.diets.entry(diet).or_insert(0) += portions,
+            None => self.regular += portions,
+        }
+    }
+
+    pub fn total(&self) -> u32 {
+        self.regular + self.diets.values().sum::<u32>()
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct GroupProductionDto {
+    pub group: String,
+    pub portions: PortionsDto,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct MealProductionDto {
+    pub meal_id: Uuid,
+    pub meal: String,
+    pub groups: Vec<GroupProductionDto>,
+    pub total: PortionsDto,
+}
+
+/// Production of a single catering, counted from attendance recorded until `cutoff`.
+/// The counts can still change until the cut-off passes and the report gets `locked`
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct CateringProductionDto {
+    pub catering_id: Uuid,
+    pub catering: String,
+    pub cutoff: NaiveDateTime,
+    pub locked: bool,
+    pub meals: Vec<MealProductionDto>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct ProductionReportDto {
+    pub day: NaiveDate,
+    pub caterings: Vec<CateringProductionDto>,
+}
+
+/// Flat production line, as written to the csv export. Group totals are listed with an empty group
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct ProductionCsvRow {
+    pub day: NaiveDate,
+    pub catering: String,
+    pub meal: String,
+    pub group: Option<String>,
+    pub diet: Option<String>,
+    pub portions: u32,
+    pub locked: bool,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct SnapshotMealCountDto {
+    pub meal_id: Uuid,
+    pub meal: String,
+    pub snapshot: u32,
+    pub actual: u32,
+}
+
+/// Attendance of a student that changed after the cut-off snapshot was taken
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct LateChangeDto {
+    pub student_id: Uuid,
+    pub student: String,
+    pub meal: String,
+    pub snapshot: bool,
+    pub actual: bool,
+}
+
+/// Meal counts frozen at the catering's cut-off compared with the attendance as it is now
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct CateringSnapshotDto {
+    pub catering_id: Uuid,
+    pub catering: String,
+    pub cutoff: NaiveDateTime,
+    pub taken: NaiveDateTime,
+    pub meals: Vec<SnapshotMealCountDto>,
+    pub changes: Vec<LateChangeDto>,
+}
+
+/// Late change, as written to the csv export
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct LateChangeCsvRow {
+    pub day: NaiveDate,
+    pub catering: String,
+    pub student: String,
+    pub group: Option<String>,
+    pub meal: String,
+    pub snapshot: bool,
+    pub actual: bool,
+}
@@ -0,0 +1,23 @@
+use chrono::NaiveDateTime;
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::audit::AuditEntity;
+
+/// Student or group removed by staff, together with everything removed along with it
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct RemovedEntityDto {
+    pub id: Uuid,
+    pub entity: AuditEntity,
+    pub name: String,
+    /// Group the entity was removed from, restoring puts it back there
+    pub parent_name: Option<String>,
+    pub removed_at: NaiveDateTime,
+    pub removed_by: Option<String>,
+    /// Groups removed along with it, itself included
+    pub groups: u32,
+    /// Students removed along with it, itself included
+    pub students: u32,
+    /// When the entity is purged for good
+    pub purge_at: NaiveDateTime,
+}
@@ -1,3 +1,4 @@
+use chrono::NaiveDate;
 use serde::{Deserialize, Serialize};
 use uuid::Uuid;
 
@@ -12,6 +13,12 @@ pub struct CreateStudentDto {
     pub allergies: Vec<String>,
     #[serde(default)]
     pub guardians: Vec<String>,
+    /// First day of attendance, the whole catering when missing
+    #[serde(default)]
+    pub joined: Option<NaiveDate>,
+    /// Last day of attendance, the whole catering when missing
+    #[serde(default)]
+    pub left: Option<NaiveDate>,
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug)]
@@ -45,3 +52,21 @@ pub struct AllergyCombinationDto {
     pub id: Uuid,
     pub allergies: Vec<String>,
 }
+
+/// Period the student spent in a group, open ends reach the start or end of the enrolment
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct PlacementDto {
+    /// `None` once the group was purged from the recycle bin
+    pub group_id: Option<Uuid>,
+    pub group_name: String,
+    pub since: Option<NaiveDate>,
+    pub until: Option<NaiveDate>,
+}
+
+/// Moves the student to `group_id` starting on `effective`, earlier days stay with the old group
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct TransferStudentDto {
+    pub student_id: Uuid,
+    pub group_id: Uuid,
+    pub effective: NaiveDate,
+}
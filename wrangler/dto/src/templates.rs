@@ -0,0 +1,456 @@
+use std::{collections::HashMap, str::FromStr};
+
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+/// Value substituted for `{name}` in a template, `name` being `as_str`
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Placeholder {
+    Guardian,
+    Students,
+    Meals,
+    Since,
+    Until,
+    Count,
+    Changes,
+    Closures,
+    Term,
+    Options,
+}
+
+impl Placeholder {
+    pub const ALL: &'static [Placeholder] = &[
+        Placeholder::Guardian,
+        Placeholder::Students,
+        Placeholder::Meals,
+        Placeholder::Since,
+        Placeholder::Until,
+        Placeholder::Count,
+        Placeholder::Changes,
+        Placeholder::Closures,
+        Placeholder::Term,
+        Placeholder::Options,
+    ];
+
+    /// Placeholders of templates composed by staff, filled in for every recipient when sending
+    pub const RECIPIENT: &'static [Placeholder] = &[Placeholder::Guardian, Placeholder::Students];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Placeholder::Guardian => "rodzic",
+            Placeholder::Students => "uczniowie",
+            Placeholder::Meals => "posilki",
+            Placeholder::Since => "od",
+            Placeholder::Until => "do",
+            Placeholder::Count => "liczba",
+            Placeholder::Changes => "zmiany",
+            Placeholder::Closures => "dni_wolne",
+            Placeholder::Term => "termin",
+            Placeholder::Options => "opcje",
+        }
+    }
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            Placeholder::Guardian => "Imię i nazwisko rodzica",
+            Placeholder::Students => "Uczniowie",
+            Placeholder::Meals => "Lista posiłków",
+            Placeholder::Since => "Pierwszy dzień",
+            Placeholder::Until => "Ostatni dzień",
+            Placeholder::Count => "Liczba posiłków",
+            Placeholder::Changes => "Zmiany dla każdego ucznia",
+            Placeholder::Closures => "Dni wolne",
+            Placeholder::Term => "Nierozpoznany termin",
+            Placeholder::Options => "Opcje do wyboru",
+        }
+    }
+
+    /// Value shown in template previews
+    pub fn sample(&self) -> &'static str {
+        match self {
+            Placeholder::Guardian => "Anna Kowalska",
+            Placeholder::Students => "Jan Kowalski, Ola Kowalska",
+            Placeholder::Meals => "obiad, podwieczorek",
+            Placeholder::Since => "02.03",
+            Placeholder::Until => "06.03",
+            Placeholder::Count => "10",
+            Placeholder::Changes => "Jan: obiad 5\nOla: podwieczorek 5",
+            Placeholder::Closures => "04.03 (Rekolekcje)",
+            Placeholder::Term => "obiadd",
+            Placeholder::Options => "1 - obiad\n2 - Ola Kowalska",
+        }
+    }
+}
+
+impl FromStr for Placeholder {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Placeholder::ALL
+            .iter()
+            .find(|placeholder| placeholder.as_str() == s)
+            .copied()
+            .ok_or(format!("Unknown placeholder: {}", s))
+    }
+}
+
+/// Automatic reply of the message daemon, stored in `message_templates` under `as_str`
+/// once it's changed from the default
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum SystemTemplate {
+    Cancelled,
+    Restored,
+    NothingCancelled,
+    NothingRestored,
+    ClosedDays,
+    SkippedClosedDays,
+    InvalidTimeRange,
+    TooManyDates,
+    NoStudentSpecified,
+    NoDateSpecified,
+    UnknownTerm,
+    AmbiguousTerm,
+    ClarifyUnknown,
+    ClarifyAmbiguous,
+    Clarify,
+}
+
+impl SystemTemplate {
+    pub const ALL: &'static [SystemTemplate] = &[
+        SystemTemplate::Cancelled,
+        SystemTemplate::Restored,
+        SystemTemplate::NothingCancelled,
+        SystemTemplate::NothingRestored,
+        SystemTemplate::ClosedDays,
+        SystemTemplate::SkippedClosedDays,
+        SystemTemplate::InvalidTimeRange,
+        SystemTemplate::TooManyDates,
+        SystemTemplate::NoStudentSpecified,
+        SystemTemplate::NoDateSpecified,
+        SystemTemplate::UnknownTerm,
+        SystemTemplate::AmbiguousTerm,
+        SystemTemplate::ClarifyUnknown,
+        SystemTemplate::ClarifyAmbiguous,
+        SystemTemplate::Clarify,
+    ];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            SystemTemplate::Cancelled => "cancelled",
+            SystemTemplate::Restored => "restored",
+            SystemTemplate::NothingCancelled => "nothing_cancelled",
+            SystemTemplate::NothingRestored => "nothing_restored",
+            SystemTemplate::ClosedDays => "closed_days",
+            SystemTemplate::SkippedClosedDays => "skipped_closed_days",
+            SystemTemplate::InvalidTimeRange => "invalid_time_range",
+            SystemTemplate::TooManyDates => "too_many_dates",
+            SystemTemplate::NoStudentSpecified => "no_student_specified",
+            SystemTemplate::NoDateSpecified => "no_date_specified",
+            SystemTemplate::UnknownTerm => "unknown_term",
+            SystemTemplate::AmbiguousTerm => "ambiguous_term",
+            SystemTemplate::ClarifyUnknown => "clarify_unknown",
+            SystemTemplate::ClarifyAmbiguous => "clarify_ambiguous",
+            SystemTemplate::Clarify => "clarify",
+        }
+    }
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            SystemTemplate::Cancelled => "Odwołanie obecności",
+            SystemTemplate::Restored => "Przywrócenie obecności",
+            SystemTemplate::NothingCancelled => "Nic nie odwołano",
+            SystemTemplate::NothingRestored => "Nic nie przywrócono",
+            SystemTemplate::ClosedDays => "Wszystkie dni wolne",
+            SystemTemplate::SkippedClosedDays => "Pominięte dni wolne, dopisywane do odpowiedzi",
+            SystemTemplate::InvalidTimeRange => "Błąd: nieprawidłowy zakres dat",
+            SystemTemplate::TooManyDates => "Błąd: zbyt wiele dat",
+            SystemTemplate::NoStudentSpecified => "Błąd: brak ucznia",
+            SystemTemplate::NoDateSpecified => "Błąd: brak daty",
+            SystemTemplate::UnknownTerm => "Błąd: nieznany termin",
+            SystemTemplate::AmbiguousTerm => "Błąd: niejednoznaczny termin",
+            SystemTemplate::ClarifyUnknown => "Pytanie o nieznany termin",
+            SystemTemplate::ClarifyAmbiguous => "Pytanie o niejednoznaczny termin",
+            SystemTemplate::Clarify => "Pytanie o termin",
+        }
+    }
+
+    pub fn default_content(&self) -> &'static str {
+        match self {
+            SystemTemplate::Cancelled => "Odwołano: \n{zmiany}",
+            SystemTemplate::Restored => "Przywrócono: \n{zmiany}",
+            SystemTemplate::NothingCancelled => "Nie odwołano żadnej obecności",
+            SystemTemplate::NothingRestored => "Nie przywrócono żadnej obecności",
+            SystemTemplate::ClosedDays => {
+                "W wybranych dniach posiłki nie są wydawane: \n{dni_wolne}"
+            }
+            SystemTemplate::SkippedClosedDays => "Pominięto dni wolne: \n{dni_wolne}",
+            SystemTemplate::InvalidTimeRange => "Podano nieprawidłowy zakres dat",
+            SystemTemplate::TooManyDates => {
+                "Podano zbyt wiele dat - należy podać pojedyńczą date nieobecności, lub okres pomiędzy 2 datami odseparowane spacją"
+            }
+            SystemTemplate::NoStudentSpecified => "Nie podano ucznia",
+            SystemTemplate::NoDateSpecified => {
+                "Nie podano żadnej daty - należy podać pojedyńczą date nieobecności, lub okres pomiędzy 2 datami odseparowane spacją"
+            }
+            SystemTemplate::UnknownTerm => {
+                "Termin '{termin}' nie jest prawidłowym określeniem na posiłek / ucznia"
+            }
+            SystemTemplate::AmbiguousTerm => {
+                "Termin '{termin}' może odnosić się do więcej niż jednego posiłku / ucznia"
+            }
+            SystemTemplate::ClarifyUnknown => {
+                "Nie rozpoznano terminu '{termin}', czy chodziło o:\n{opcje}\n0 - pomiń\nOdpowiedz numerem wybranej opcji"
+            }
+            SystemTemplate::ClarifyAmbiguous => {
+                "Termin '{termin}' może oznaczać:\n{opcje}\n0 - pomiń\nOdpowiedz numerem wybranej opcji"
+            }
+            SystemTemplate::Clarify => {
+                "Czy chodziło o:\n{opcje}\n0 - pomiń\nOdpowiedz numerem wybranej opcji"
+            }
+        }
+    }
+
+    pub fn placeholders(&self) -> &'static [Placeholder] {
+        use Placeholder::*;
+        match self {
+            SystemTemplate::Cancelled | SystemTemplate::Restored => {
+                &[Guardian, Students, Meals, Since, Until, Count, Changes]
+            }
+            SystemTemplate::ClosedDays | SystemTemplate::SkippedClosedDays => &[Guardian, Closures],
+            SystemTemplate::UnknownTerm | SystemTemplate::AmbiguousTerm => &[Guardian, Term],
+            SystemTemplate::ClarifyUnknown | SystemTemplate::ClarifyAmbiguous => {
+                &[Guardian, Term, Options]
+            }
+            SystemTemplate::Clarify => &[Guardian, Options],
+            SystemTemplate::NothingCancelled
+            | SystemTemplate::NothingRestored
+            | SystemTemplate::InvalidTimeRange
+            | SystemTemplate::TooManyDates
+            | SystemTemplate::NoStudentSpecified
+            | SystemTemplate::NoDateSpecified => &[Guardian],
+        }
+    }
+}
+
+impl FromStr for SystemTemplate {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        SystemTemplate::ALL
+            .iter()
+            .find(|template| template.as_str() == s)
+            .copied()
+            .ok_or(format!("Unknown system template: {}", s))
+    }
+}
+
+/// Names of every `{name}` in the content, whether it's a known placeholder or not
+pub fn placeholder_names(content: &str) -> Vec<&str> {
+    let mut names = vec![];
+    let mut rest = content;
+    while let Some(start) = rest.find('{') {
+        rest = &rest[start + 1..];
+        if let Some(end) = rest.find('}') {
+            let name = &rest[..end];
+            if !name.is_empty() && name.chars().all(|c| c.is_ascii_lowercase() || c == '_') {
+                names.push(name);
+                rest = &rest[end + 1..];
+            }
+        }
+    }
+    names
+}
+
+/// Replaces placeholders with their values, unknown ones and ones without a value are kept as they are
+pub fn render(content: &str, values: &[(Placeholder, String)]) -> String {
+    let mut result = String::with_capacity(content.len());
+    let mut rest = content;
+    while let Some(start) = rest.find('{') {
+        result.push_str(&rest[..start]);
+        rest = &rest[start..];
+        let value = rest.find('}').and_then(|end| {
+            let placeholder = rest[1..end].parse::<Placeholder>().ok()?;
+            values
+                .iter()
+                .find(|(candidate, _)| *candidate == placeholder)
+                .map(|(_, value)| (value, end))
+        });
+        match value {
+            Some((value, end)) => {
+                result.push_str(value);
+                rest = &rest[end + 1..];
+            }
+            None => {
+                result.push('{');
+                rest = &rest[1..];
+            }
+        }
+    }
+    result.push_str(rest);
+    result
+}
+
+/// Renders the content with sample values of all placeholders
+pub fn preview(content: &str) -> String {
+    let samples = Placeholder::ALL
+        .iter()
+        .map(|placeholder| (*placeholder, String::from(placeholder.sample())))
+        .collect::<Vec<_>>();
+    render(content, &samples)
+}
+
+/// Checks the content isn't empty and uses only the allowed placeholders
+pub fn validate(content: &str, allowed: &[Placeholder]) -> Result<(), String> {
+    if content.trim().is_empty() {
+        return Err(String::from("Template must not be empty"));
+    }
+    for name in placeholder_names(content) {
+        match name.parse::<Placeholder>() {
+            Ok(placeholder) if allowed.contains(&placeholder) => {}
+            _ => {
+                return Err(format!(
+                    "Placeholder {{{}}} can't be used in this template",
+                    name
+                ));
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Contents of the system templates, falling back to the defaults for unchanged ones
+#[derive(Serialize, Deserialize, Clone, Debug, Default)]
+pub struct Templates(HashMap<SystemTemplate, String>);
+
+impl Templates {
+    pub fn new(templates: impl IntoIterator<Item = (SystemTemplate, String)>) -> Self {
+        Templates(templates.into_iter().collect())
+    }
+
+    pub fn content(&self, template: SystemTemplate) -> &str {
+        self.0
+            .get(&template)
+            .map(String::as_str)
+            .unwrap_or(template.default_content())
+    }
+
+    pub fn render(&self, template: SystemTemplate, values: &[(Placeholder, String)]) -> String {
+        render(self.content(template), values)
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct TemplateDto {
+    /// `None` for system templates still using their default content
+    pub id: Option<Uuid>,
+    pub name: String,
+    pub content: String,
+    /// Set for automatic replies, which can't be renamed or removed
+    pub system: Option<SystemTemplate>,
+}
+
+impl TemplateDto {
+    pub fn placeholders(&self) -> &'static [Placeholder] {
+        self.system
+            .map(|system| system.placeholders())
+            .unwrap_or(Placeholder::RECIPIENT)
+    }
+}
+
+/// Creates a staff template when `id` is `None`, system templates are saved under their name
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct SaveTemplateDto {
+    pub id: Option<Uuid>,
+    pub name: String,
+    pub content: String,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn values() -> Vec<(Placeholder, String)> {
+        vec![
+            (Placeholder::Guardian, String::from("Anna Kowalska")),
+            (Placeholder::Students, String::from("Jan")),
+        ]
+    }
+
+    #[test]
+    fn unclosed_brace_is_kept() {
+        assert!(placeholder_names("Dzień dobry {rodzic").is_empty());
+        assert_eq!(
+            render("Dzień dobry {rodzic", &values()),
+            "Dzień dobry {rodzic"
+        );
+        assert_eq!(placeholder_names("{ {rodzic}"), vec!["rodzic"]);
+        assert_eq!(render("{ {rodzic}", &values()), "{ Anna Kowalska");
+        assert!(validate("Dzień dobry {rodzic", Placeholder::RECIPIENT).is_ok());
+    }
+
+    #[test]
+    fn unknown_names_are_kept_verbatim() {
+        assert!(placeholder_names("{Unknown} {a1} {}").is_empty());
+        assert_eq!(
+            render("{Unknown} {a1} {} {posilki}", &values()),
+            "{Unknown} {a1} {} {posilki}"
+        );
+        assert_eq!(placeholder_names("{nieznany}"), vec!["nieznany"]);
+        assert_eq!(render("{nieznany}", &values()), "{nieznany}");
+    }
+
+    #[test]
+    fn adjacent_placeholders() {
+        assert_eq!(
+            placeholder_names("{rodzic}{uczniowie}"),
+            vec!["rodzic", "uczniowie"]
+        );
+        assert_eq!(
+            render("{rodzic}{uczniowie}{rodzic}", &values()),
+            "Anna KowalskaJanAnna Kowalska"
+        );
+    }
+
+    #[test]
+    fn non_ascii_text_around_braces() {
+        let content = "Żółć {rodzic}ąę {uczniowie}—ś {źle}";
+        assert_eq!(placeholder_names(content), vec!["rodzic", "uczniowie"]);
+        assert_eq!(
+            render(content, &values()),
+            "Żółć Anna Kowalskaąę Jan—ś {źle}"
+        );
+        assert_eq!(render("ó{ó}ó", &values()), "ó{ó}ó");
+    }
+
+    #[test]
+    fn validate_checks_allowed_placeholders() {
+        assert!(validate("  \n", Placeholder::RECIPIENT).is_err());
+        assert!(validate("Dzień dobry {rodzic}, {uczniowie}", Placeholder::RECIPIENT).is_ok());
+        assert!(validate("{nieznany}", Placeholder::RECIPIENT).is_err());
+
+        // Placeholders filled in only by the message daemon can't be used by staff
+        for placeholder in Placeholder::ALL {
+            let content = format!("{{{}}}", placeholder.as_str());
+            assert_eq!(
+                validate(&content, Placeholder::RECIPIENT).is_ok(),
+                Placeholder::RECIPIENT.contains(placeholder),
+                "{}",
+                content
+            );
+        }
+        assert!(validate("{zmiany}", SystemTemplate::Cancelled.placeholders()).is_ok());
+        assert!(validate("{opcje}", SystemTemplate::UnknownTerm.placeholders()).is_err());
+    }
+
+    #[test]
+    fn defaults_are_valid() {
+        for template in SystemTemplate::ALL {
+            assert_eq!(
+                validate(template.default_content(), template.placeholders()),
+                Ok(()),
+                "{}",
+                template.as_str()
+            );
+        }
+    }
+}
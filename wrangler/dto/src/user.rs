@@ -0,0 +1,67 @@
+use std::str::FromStr;
+
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Role {
+    Admin,
+    Kitchen,
+    Teacher,
+}
+
+impl Role {
+    pub const ALL: &'static [Role] = &[Role::Admin, Role::Kitchen, Role::Teacher];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Role::Admin => "admin",
+            Role::Kitchen => "kitchen",
+            Role::Teacher => "teacher",
+        }
+    }
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            Role::Admin => "Administrator",
+            Role::Kitchen => "Kuchnia",
+            Role::Teacher => "Nauczyciel",
+        }
+    }
+}
+
+impl FromStr for Role {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "admin" => Ok(Role::Admin),
+            "kitchen" => Ok(Role::Kitchen),
+            "teacher" => Ok(Role::Teacher),
+            _ => Err(format!("Unknown role: {}", s)),
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct UserDto {
+    pub id: Uuid,
+    pub login: String,
+    pub role: Role,
+    pub disabled: bool,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct CreateUserDto {
+    pub login: String,
+    pub password: String,
+    pub role: Role,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct UpdateUserDto {
+    pub id: Uuid,
+    pub role: Role,
+    pub disabled: bool,
+    pub password: Option<String>,
+}
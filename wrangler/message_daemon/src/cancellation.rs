@@ -3,19 +3,26 @@ use std::{
     collections::{HashMap, HashSet},
 };
 
-use chrono::{Days, NaiveDateTime, NaiveTime};
+use attendance_rules::{
+    Actor, Catering, Cutoff, Meal,
+    store::{load_catering, record_attendance},
+};
+use chrono::{NaiveDate, NaiveDateTime};
+use dto::closure::ClosureNotice;
 use dto::messages::{
     AttendanceCancellation, CancellationRequest, CancellationResult, MessageData, MessageMetadata,
     MessageProcessing, ReceivedMessage, RequestError,
 };
+use dto::templates::{Placeholder, SystemTemplate};
 use itertools::Itertools;
 use sqlx::{Connection, Error, Executor, Postgres};
 use uuid::Uuid;
 
-use crate::{Student, StudentCancellation, Token};
+use crate::{Student, StudentCancellation, Token, replies::Replies};
 
 pub async fn save_attendance<C>(
     request: AttendanceCancellation,
+    value: bool,
     cause_id: Uuid,
     connection: &mut C,
 ) -> Result<Vec<CancellationResult>, Error>
@@ -23,15 +30,24 @@ where
     C: Connection<Database = Postgres>,
     for<'a> &'a mut C: Executor<'a, Database = Postgres>,
 {
+    let student_ids: Vec<_> = request.students.iter().map(|s| s.id).collect();
+    let since: Vec<_> = request.students.iter().map(|s| s.since).collect();
+    let until: Vec<_> = request.students.iter().map(|s| s.until).collect();
+
     for student in request.students {
-        sqlx::query!("INSERT INTO attendance (cause_id, target, day, meal_id, value) SELECT $5, $1, day, meals.id, false FROM UNNEST($2::uuid[]) AS meals(id)
-        INNER JOIN group_relations ON group_relations.child = $1
-        INNER JOIN caterings ON caterings.group_id = group_relations.parent
-        INNER JOIN catering_meals ON catering_meals.meal_id = meals.id AND catering_meals.catering_id = caterings.id
-        INNER JOIN generate_series(LEAST(GREATEST($3::date,caterings.since),caterings.until),LEAST(GREATEST($4::date,caterings.since),caterings.until), '1 DAY') AS days(day) ON (caterings.dow >> (EXTRACT(DOW FROM day)::integer + 6) % 7)&1 = 1",
-            student.id, &student.meals, student.since, student.until, cause_id)
-            .execute(&mut *connection)
-            .await?;
+        let Some(catering) = load_catering(student.id, &mut *connection).await? else {
+            continue;
+        };
+        record_attendance(
+            cause_id,
+            student.id,
+            Actor::Guardian,
+            &catering.served_days(student.since, student.until),
+            &student.meals,
+            value,
+            &mut *connection,
+        )
+        .await?;
     }
 
     let effective_attendance = sqlx::query!(
@@ -39,41 +55,145 @@ where
         affected_attendance AS (SELECT DISTINCT ON (ea.day,ea.meal_id,ea.target) ea.day, ea.meal_id, ea.target,group_relations.level FROM attendance AS src
         INNER JOIN group_relations ON group_relations.child = src.target
         INNER JOIN exclusive_attendance AS ea ON ea.day = src.day AND ea.meal_id = src.meal_id AND ea.target= group_relations.parent
-        WHERE src.cause_id = $1 AND ea.value = true
+        WHERE src.cause_id = $1 AND ea.value != $2
         ORDER BY ea.day, ea.meal_id, ea.target, group_relations.level)
-        SELECT students.name AS student_name, meals.name AS meal_name, COUNT(*) AS cancelled FROM affected_attendance 
+        SELECT students.name AS student_name, meals.name AS meal_name, COUNT(*) AS cancelled, MIN(affected_attendance.day) AS since, MAX(affected_attendance.day) AS until FROM affected_attendance 
         INNER JOIN students ON students.id = affected_attendance.target
         INNER JOIN meals ON meals.id = affected_attendance.meal_id
         WHERE level = 0
         GROUP BY students.id, meals.id",
-        cause_id
+        cause_id,
+        value
     ).fetch_all(&mut*connection).await?;
 
     let mut hashmap = HashMap::new();
+    let mut days: HashMap<String, (NaiveDate, NaiveDate)> = HashMap::new();
     for attendance in effective_attendance {
+        if let (Some(since), Some(until)) = (attendance.since, attendance.until) {
+            let range = days
+                .entry(attendance.student_name.clone())
+                .or_insert((since, until));
+            *range = (min(range.0, since), max(range.1, until));
+        }
         hashmap
             .entry(attendance.student_name)
             .or_insert(HashMap::new())
             .insert(attendance.meal_name, attendance.cancelled.unwrap_or(0_i64));
     }
 
+    let closures = sqlx::query!(
+        "SELECT DISTINCT students.name AS student_name, closures.since, closures.until, closures.reason FROM UNNEST($1::uuid[], $2::date[], $3::date[]) AS request(student_id, since, until)
+        INNER JOIN students ON students.id = request.student_id
+        INNER JOIN group_relations ON group_relations.child = students.id
+        INNER JOIN caterings ON caterings.group_id = group_relations.parent
+        INNER JOIN closures ON (closures.catering_id IS NULL OR closures.catering_id = caterings.id)
+            AND closures.since <= request.until AND closures.until >= request.since
+        ORDER BY closures.since",
+        &student_ids,
+        &since,
+        &until
+    )
+    .fetch_all(&mut *connection)
+    .await?;
+
+    let mut closed: HashMap<String, Vec<ClosureNotice>> = HashMap::new();
+    for closure in closures {
+        hashmap.entry(closure.student_name.clone()).or_default();
+        closed
+            .entry(closure.student_name)
+            .or_default()
+            .push(ClosureNotice {
+                since: closure.since,
+                until: closure.until,
+                reason: closure.reason,
+            });
+    }
+
     Ok(hashmap
         .into_iter()
-        .map(|(k, v)| CancellationResult { name: k, meals: v })
+        .map(|(k, v)| CancellationResult {
+            closures: closed.remove(&k).unwrap_or_default(),
+            since: days.get(&k).map(|(since, _)| *since),
+            until: days.get(&k).map(|(_, until)| *until),
+            name: k,
+            meals: v,
+        })
         .collect())
 }
 
 pub fn construct_response(
     changes: &[CancellationResult],
     message: &ReceivedMessage,
+    replies: &Replies,
 ) -> MessageData {
-    if !changes.iter().any(|s| s.meals.iter().any(|(_, m)| *m != 0)) {
-        MessageData {
-            content: format!("Nie odwołano żadnej obecności"),
-            phone: message.data.phone.clone(),
+    summarize_changes(
+        changes,
+        message,
+        replies,
+        SystemTemplate::NothingCancelled,
+        SystemTemplate::Cancelled,
+    )
+}
+
+pub fn construct_restore_response(
+    changes: &[CancellationResult],
+    message: &ReceivedMessage,
+    replies: &Replies,
+) -> MessageData {
+    summarize_changes(
+        changes,
+        message,
+        replies,
+        SystemTemplate::NothingRestored,
+        SystemTemplate::Restored,
+    )
+}
+
+fn summarize_closures(changes: &[CancellationResult]) -> Option<String> {
+    let closures = changes
+        .iter()
+        .flat_map(|student| student.closures.iter())
+        .unique()
+        .sorted_by_key(|closure| closure.since)
+        .map(|closure| {
+            if closure.since == closure.until {
+                format!("{} ({})", closure.since.format("%d.%m"), closure.reason)
+            } else {
+                format!(
+                    "{} - {} ({})",
+                    closure.since.format("%d.%m"),
+                    closure.until.format("%d.%m"),
+                    closure.reason
+                )
+            }
+        })
+        .join("\n");
+    (!closures.is_empty()).then_some(closures)
+}
+
+fn summarize_changes(
+    changes: &[CancellationResult],
+    message: &ReceivedMessage,
+    replies: &Replies,
+    empty: SystemTemplate,
+    header: SystemTemplate,
+) -> MessageData {
+    let closures = summarize_closures(changes);
+    let changed = changes
+        .iter()
+        .filter(|student| student.meals.values().any(|count| *count != 0))
+        .collect::<Vec<_>>();
+
+    let content = if changed.is_empty() {
+        match closures {
+            Some(closures) => replies.render(
+                SystemTemplate::ClosedDays,
+                vec![(Placeholder::Closures, closures)],
+            ),
+            None => replies.render(empty, vec![]),
         }
     } else {
-        let info = changes
+        let info = changed
             .iter()
             .map(|student| {
                 format!(
@@ -92,10 +212,61 @@ pub fn construct_response(
                 )
             })
             .join("\n");
-        MessageData {
-            content: format!("Odwołano: \n{}", info),
-            phone: message.data.phone.clone(),
+        let meals = changed
+            .iter()
+            .flat_map(|student| student.meals.iter())
+            .filter(|(_, count)| **count != 0)
+            .map(|(name, _)| name)
+            .unique()
+            .sorted()
+            .join(", ");
+        let count = changed
+            .iter()
+            .flat_map(|student| student.meals.values())
+            .sum::<i64>();
+        let since = changed.iter().filter_map(|student| student.since).min();
+        let until = changed.iter().filter_map(|student| student.until).max();
+
+        let content = replies.render(
+            header,
+            vec![
+                (Placeholder::Changes, info),
+                (
+                    Placeholder::Students,
+                    changed.iter().map(|student| &student.name).join(", "),
+                ),
+                (Placeholder::Meals, meals),
+                (Placeholder::Count, count.to_string()),
+                (
+                    Placeholder::Since,
+                    since
+                        .map(|day| day.format("%d.%m").to_string())
+                        .unwrap_or_default(),
+                ),
+                (
+                    Placeholder::Until,
+                    until
+                        .map(|day| day.format("%d.%m").to_string())
+                        .unwrap_or_default(),
+                ),
+            ],
+        );
+        match closures {
+            Some(closures) => format!(
+                "{}\n{}",
+                content,
+                replies.render(
+                    SystemTemplate::SkippedClosedDays,
+                    vec![(Placeholder::Closures, closures)],
+                )
+            ),
+            None => content,
         }
+    };
+
+    MessageData {
+        content,
+        phone: message.data.phone.clone(),
     }
 }
 
@@ -103,12 +274,18 @@ pub fn into_request(tokens: &[Token]) -> MessageProcessing {
     let mut dates = vec![];
     let mut student_ids = vec![];
     let mut meals = vec![];
+    let mut restore = false;
 
     for token in tokens {
         match token {
             Token::Student(uuid) => student_ids.push(*uuid),
             Token::Date(naive_date) => dates.push(*naive_date),
+            Token::DateRange(since, until) => {
+                dates.push(*since);
+                dates.push(*until);
+            }
             Token::Meal(uuid) => meals.push(*uuid),
+            Token::Restore => restore = true,
             Token::Unknown(unknown) => {
                 return MessageProcessing::RequestError(RequestError::UnknownTerm(unknown.clone()));
             }
@@ -135,12 +312,19 @@ pub fn into_request(tokens: &[Token]) -> MessageProcessing {
     };
 
     match range {
-        Ok((since, until)) => MessageProcessing::Cancellation(CancellationRequest {
-            since,
-            until,
-            students: student_ids,
-            meals,
-        }),
+        Ok((since, until)) => {
+            let request = CancellationRequest {
+                since,
+                until,
+                students: student_ids,
+                meals,
+            };
+            if restore {
+                MessageProcessing::Restoration(request)
+            } else {
+                MessageProcessing::Cancellation(request)
+            }
+        }
         Err(error) => MessageProcessing::RequestError(error),
     }
 }
@@ -157,38 +341,43 @@ pub fn into_cancellations(
     let students = students
         .into_iter()
         .filter(|s| default_student || request.students.iter().any(|s2| *s2 == s.id))
-        .filter_map(|student| {
-            let student_meals: Vec<_> = student.meals.iter().map(|m| m.id).collect();
-            let meals = if request_meals.iter().any(|_| true) {
-                student_meals
-                    .into_iter()
-                    .filter(|m| request_meals.contains(&m))
-                    .collect()
-            } else {
-                student_meals
-            };
-
-            let min_grace_period = (received
-                - student
-                    .grace_period
-                    .signed_duration_since(NaiveTime::default()))
-            .date()
-            .checked_add_days(Days::new(1))?;
-            let min_allowed = max(min_grace_period, student.starts);
-
-            let since = max(min_allowed, min(student.ends, request.since));
-            let until = max(min_allowed, min(student.ends, request.until));
-
-            if since > until {
-                None
-            } else {
-                Some(StudentCancellation {
-                    id: student.id,
-                    meals,
-                    since,
-                    until,
+        .flat_map(|student| {
+            let meals = student
+                .meals
+                .iter()
+                .filter(|m| request_meals.is_empty() || request_meals.contains(&m.id))
+                .map(|meal| {
+                    let cutoff = student.cutoff(meal);
+                    Meal {
+                        id: meal.id,
+                        cutoff: Cutoff::new(cutoff.days_before, cutoff.time),
+                    }
                 })
+                .collect();
+            let catering = Catering::new(student.starts, student.ends, meals);
+
+            // Meals with different cut-offs may start on different days, each range gets its own entry
+            let mut cancellations: Vec<StudentCancellation> = vec![];
+            for meal in &catering.meals {
+                let Ok((since, until)) =
+                    catering.clamp(Actor::Guardian, received, meal.id, request.since, request.until)
+                else {
+                    continue;
+                };
+                match cancellations
+                    .iter_mut()
+                    .find(|c| c.since == since && c.until == until)
+                {
+                    Some(cancellation) => cancellation.meals.push(meal.id),
+                    None => cancellations.push(StudentCancellation {
+                        id: student.id,
+                        meals: vec![meal.id],
+                        since,
+                        until,
+                    }),
+                }
             }
+            cancellations
         })
         .collect();
 
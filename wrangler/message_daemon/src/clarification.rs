@@ -0,0 +1,183 @@
+use dto::messages::{
+    Clarification, ClarificationOption, MessageData, MessageProcessing, ReceivedMessage, Student,
+    Token,
+};
+use dto::templates::{Placeholder, SystemTemplate};
+use itertools::Itertools;
+use sqlx::{Connection, Error, Executor, Postgres, types::Json};
+use uuid::Uuid;
+
+use crate::{levenshtein::levenshtein, named_tokens, replies::Replies};
+
+const MAX_SUGGESTIONS: usize = 3;
+
+fn describe(token: &Token, students: &[Student]) -> Option<String> {
+    match token {
+        Token::Student(id) => students
+            .iter()
+            .find(|s| s.id == *id)
+            .map(|s| format!("{} {}", s.name, s.surname)),
+        Token::Meal(id) => students
+            .iter()
+            .flat_map(|s| s.meals.iter())
+            .find(|m| m.id == *id)
+            .map(|m| m.name.clone()),
+        _ => None,
+    }
+}
+
+fn into_options(term: &str, ambiguous: bool, students: &[Student]) -> Vec<ClarificationOption> {
+    let candidates = named_tokens(students)
+        .map(|(name, token)| (levenshtein(&name, term), token))
+        .sorted_by_key(|(distance, _)| *distance)
+        .collect::<Vec<_>>();
+
+    let candidates = if ambiguous {
+        candidates
+            .into_iter()
+            .min_set_by_key(|(distance, _)| *distance)
+    } else {
+        candidates.into_iter().take(MAX_SUGGESTIONS).collect()
+    };
+
+    candidates
+        .into_iter()
+        .filter_map(|(_, token)| {
+            Some(ClarificationOption {
+                label: describe(&token, students)?,
+                token,
+            })
+        })
+        .collect()
+}
+
+/// Builds a question about the first unrecognized or ambiguous token, if there is any to ask about
+pub fn into_clarification(tokens: &[Token], students: &[Student]) -> Option<Clarification> {
+    let (position, options) = tokens
+        .iter()
+        .enumerate()
+        .find_map(|(i, token)| match token {
+            Token::Unknown(term) => Some((i, into_options(term, false, students))),
+            Token::Ambiguous(term) => Some((i, into_options(term, true, students))),
+            _ => None,
+        })?;
+
+    if options.is_empty() {
+        return None;
+    }
+
+    Some(Clarification {
+        tokens: tokens.to_vec(),
+        position,
+        options,
+    })
+}
+
+/// Applies the guardian's reply to the stored question, `0` drops the questioned term.
+/// Returns `None` if the reply is not one of the offered numbers
+pub fn resolve_clarification(clarification: &Clarification, reply: &str) -> Option<Vec<Token>> {
+    let choice: usize = reply.trim().parse().ok()?;
+    let mut tokens = clarification.tokens.clone();
+
+    if choice == 0 {
+        tokens.remove(clarification.position);
+    } else {
+        tokens[clarification.position] = clarification.options.get(choice - 1)?.token.clone();
+    }
+
+    Some(tokens)
+}
+
+pub fn construct_clarification_response(
+    clarification: &Clarification,
+    message: &ReceivedMessage,
+    replies: &Replies,
+) -> MessageData {
+    let options = clarification
+        .options
+        .iter()
+        .enumerate()
+        .map(|(i, option)| format!("{} - {}", i + 1, option.label))
+        .join("\n");
+
+    let content = match &clarification.tokens[clarification.position] {
+        Token::Ambiguous(term) => replies.render(
+            SystemTemplate::ClarifyAmbiguous,
+            vec![
+                (Placeholder::Term, term.clone()),
+                (Placeholder::Options, options),
+            ],
+        ),
+        Token::Unknown(term) => replies.render(
+            SystemTemplate::ClarifyUnknown,
+            vec![
+                (Placeholder::Term, term.clone()),
+                (Placeholder::Options, options),
+            ],
+        ),
+        _ => replies.render(
+            SystemTemplate::Clarify,
+            vec![(Placeholder::Options, options)],
+        ),
+    };
+
+    MessageData {
+        phone: message.data.phone.clone(),
+        content,
+    }
+}
+
+pub async fn save_pending_clarification<C>(
+    phone: &str,
+    cause_id: Uuid,
+    connection: &mut C,
+) -> Result<(), Error>
+where
+    C: Connection<Database = Postgres>,
+    for<'a> &'a mut C: Executor<'a, Database = Postgres>,
+{
+    sqlx::query!(
+        "INSERT INTO pending_clarifications (phone, cause_id) VALUES ($1, $2)
+        ON CONFLICT (phone) DO UPDATE SET cause_id = EXCLUDED.cause_id, created = EXCLUDED.created",
+        phone,
+        cause_id
+    )
+    .execute(&mut *connection)
+    .await?;
+    Ok(())
+}
+
+/// Removes the pending question for `phone` and returns it, unless it is older than a day
+pub async fn take_pending_clarification<C>(
+    phone: &str,
+    connection: &mut C,
+) -> Result<Option<Clarification>, Error>
+where
+    C: Connection<Database = Postgres>,
+    for<'a> &'a mut C: Executor<'a, Database = Postgres>,
+{
+    let Some(cause_id) = sqlx::query!(
+        "DELETE FROM pending_clarifications WHERE phone = $1
+        RETURNING cause_id, created > LOCALTIMESTAMP - INTERVAL '1 day' AS \"fresh!\"",
+        phone
+    )
+    .fetch_optional(&mut *connection)
+    .await?
+    .filter(|row| row.fresh)
+    .map(|row| row.cause_id) else {
+        return Ok(None);
+    };
+
+    let state = sqlx::query!(
+        "SELECT value AS \"value: Json<MessageProcessing>\" FROM processing_step
+        WHERE cause_id = $1 ORDER BY id DESC LIMIT 1",
+        cause_id
+    )
+    .fetch_optional(&mut *connection)
+    .await?;
+
+    Ok(match state.map(|row| row.value.0) {
+        Some(MessageProcessing::Clarification(clarification)) => Some(clarification),
+        _ => None,
+    })
+}
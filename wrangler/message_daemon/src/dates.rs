@@ -0,0 +1,172 @@
+use chrono::{Datelike, Days, Months, NaiveDate, NaiveDateTime, Weekday};
+use dto::messages::Token;
+use regex::{Match, Regex};
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum DateExpression {
+    Day(NaiveDate),
+    Range(NaiveDate, NaiveDate),
+    Until(NaiveDate),
+}
+
+const WEEKDAYS: [(&[&str], Weekday); 7] = [
+    (
+        &[
+            "poniedziałek",
+            "poniedzialek",
+            "poniedziałku",
+            "poniedzialku",
+        ],
+        Weekday::Mon,
+    ),
+    (&["wtorek", "wtorku"], Weekday::Tue),
+    (
+        &["środa", "sroda", "środę", "srode", "środy", "srody"],
+        Weekday::Wed,
+    ),
+    (&["czwartek", "czwartku"], Weekday::Thu),
+    (&["piątek", "piatek", "piątku", "piatku"], Weekday::Fri),
+    (&["sobota", "sobotę", "sobote", "soboty"], Weekday::Sat),
+    (
+        &["niedziela", "niedzielę", "niedziele", "niedzieli"],
+        Weekday::Sun,
+    ),
+];
+
+fn into_date(
+    current: NaiveDateTime,
+    day: Option<Match>,
+    month: Option<Match>,
+    year: Option<Match>,
+) -> Option<NaiveDate> {
+    let day: u32 = day?.as_str().parse().ok()?;
+    let month: u32 = month?.as_str().parse().ok()?;
+    if let Some(year) = year {
+        let year: i32 = year.as_str().parse().ok()?;
+        NaiveDate::from_ymd_opt(year, month, day)
+    } else {
+        let current_date = NaiveDate::from_ymd_opt(current.year(), month, day)?;
+        let next_date = NaiveDate::from_ymd_opt(current.year() + 1, month, day)?;
+        if (current_date - current.date()) < (next_date - current.date()) {
+            Some(current_date)
+        } else {
+            Some(next_date)
+        }
+    }
+}
+
+/// Returns `None` if `word` doesn't look like a numeric date at all,
+/// `Token::Unknown` if it does but isn't a valid one
+pub fn into_numeric_date(word: &str, current: NaiveDateTime) -> Option<Token> {
+    let long_date_regex = Regex::new(r"^((\d{1,2})(-|\.|\/)(\d{1,2})(-|\.|\/)(\d{4}))$").unwrap();
+    let middle_date_regex = Regex::new(r"^((\d{1,2})(-|\.|\/)(\d{1,2})(-|\.|\/)(\d{2}))$").unwrap();
+    let short_date_regex = Regex::new(r"^((\d{1,2})(-|\.|\/)(\d{1,2}))$").unwrap();
+
+    let regexes = [long_date_regex, middle_date_regex, short_date_regex];
+
+    for date_regex in regexes {
+        if let Some(date) = date_regex.captures(word) {
+            return match into_date(current, date.get(2), date.get(4), date.get(6)) {
+                Some(date) => Some(Token::Date(date)),
+                None => Some(Token::Unknown(word.into())),
+            };
+        }
+    }
+    None
+}
+
+fn strip_punctuation(word: &str) -> &str {
+    word.trim_matches(|c| matches!(c, ',' | '.' | '!' | '?' | ';' | ':'))
+}
+
+/// First day on or after `since` falling on `weekday`
+fn next_weekday(since: NaiveDate, weekday: Weekday) -> NaiveDate {
+    let offset = (7 + weekday.num_days_from_monday() - since.weekday().num_days_from_monday()) % 7;
+    since + Days::new(offset as u64)
+}
+
+fn end_of_month(date: NaiveDate) -> Option<NaiveDate> {
+    date.with_day(1)?
+        .checked_add_months(Months::new(1))?
+        .pred_opt()
+}
+
+/// Parses a single day from the start of `words`, returning it with the number of words consumed.
+/// Weekdays resolve to the nearest matching day on or after `reference`
+fn into_day(
+    words: &[&str],
+    current: NaiveDateTime,
+    reference: NaiveDate,
+) -> Option<(NaiveDate, usize)> {
+    let first = *words.first()?;
+
+    if let Some(Token::Date(date)) = into_numeric_date(first, current) {
+        return Some((date, 1));
+    }
+
+    let first = strip_punctuation(first);
+    let today = current.date();
+
+    match first {
+        "dziś" | "dzis" | "dzisiaj" => return Some((today, 1)),
+        "jutro" | "jutra" => return Some((today.succ_opt()?, 1)),
+        "pojutrze" => return Some((today.checked_add_days(Days::new(2))?, 1)),
+        _ => {}
+    }
+
+    if let Some((_, weekday)) = WEEKDAYS.iter().find(|(names, _)| names.contains(&first)) {
+        return Some((next_weekday(reference, *weekday), 1));
+    }
+
+    if matches!(first, "koniec" | "końca" | "konca") {
+        match words.get(1).map(|w| strip_punctuation(w)) {
+            Some("tygodnia") => return Some((next_weekday(reference, Weekday::Sun), 2)),
+            Some("miesiąca" | "miesiaca") => return Some((end_of_month(reference)?, 2)),
+            _ => {}
+        }
+    }
+
+    None
+}
+
+/// Whole week (Monday to Sunday) containing `date`, or the following one if `date` is a weekend
+fn into_week(date: NaiveDate) -> (NaiveDate, NaiveDate) {
+    let monday = match date.weekday() {
+        Weekday::Sat | Weekday::Sun => next_weekday(date, Weekday::Mon),
+        _ => date.week(Weekday::Mon).first_day(),
+    };
+    (monday, monday + Days::new(6))
+}
+
+/// Parses a date expression from the start of `words`, returning it with the number of words consumed.
+/// `since` is the previously parsed date, if any, against which the end of a range is resolved
+pub fn into_date_expression(
+    words: &[&str],
+    current: NaiveDateTime,
+    since: Option<NaiveDate>,
+) -> Option<(DateExpression, usize)> {
+    let first = strip_punctuation(words.first()?);
+    let today = current.date();
+
+    match first {
+        "od" => {
+            let (date, consumed) = into_day(&words[1..], current, today)?;
+            Some((DateExpression::Day(date), consumed + 1))
+        }
+        "do" => {
+            let (date, consumed) = into_day(&words[1..], current, since.unwrap_or(today))?;
+            Some((DateExpression::Until(date), consumed + 1))
+        }
+        "cały" | "caly" => match words.get(1).map(|w| strip_punctuation(w)) {
+            Some("tydzień" | "tydzien") => {
+                let (since, until) = into_week(today);
+                Some((DateExpression::Range(since, until), 2))
+            }
+            _ => None,
+        },
+        _ => {
+            let (date, consumed) = into_day(words, current, today)?;
+            Some((DateExpression::Day(date), consumed))
+        }
+    }
+}
@@ -1,23 +1,36 @@
 pub mod cancellation;
+pub mod clarification;
+pub mod dates;
 pub mod levenshtein;
+pub mod replies;
 pub mod tests;
 
 use std::env;
 
-use chrono::{Datelike, NaiveDate, NaiveDateTime};
+use chrono::NaiveTime;
+use dto::catering::MealCutoffDto;
 use dto::messages::{
     DbMessage, Meal, Message, MessageData, MessageProcessing, ReceivedMessage, RequestError,
     Student, StudentCancellation, Token, parse_message,
 };
+use dto::templates::{Placeholder, SystemTemplate};
 use itertools::Itertools;
-use regex::{Match, Regex};
 use simple_logger::SimpleLogger;
 use sqlx::{Connection, Error, Executor, PgPool, Postgres, postgres::PgListener, types::Json};
 use uuid::Uuid;
 
 use crate::{
-    cancellation::{construct_response, into_cancellations, into_request, save_attendance},
+    cancellation::{
+        construct_response, construct_restore_response, into_cancellations, into_request,
+        save_attendance,
+    },
+    clarification::{
+        construct_clarification_response, into_clarification, resolve_clarification,
+        save_pending_clarification, take_pending_clarification,
+    },
+    dates::{DateExpression, into_date_expression, into_numeric_date},
     levenshtein::levenshtein,
+    replies::{Replies, load_templates},
 };
 
 async fn fetch_and_process<'a, 'b>(pool: &PgPool) -> Option<()> {
@@ -112,45 +125,18 @@ async fn main() {
     }
 }
 
-fn into_date(
-    current: NaiveDateTime,
-    day: Option<Match>,
-    month: Option<Match>,
-    year: Option<Match>,
-) -> Option<NaiveDate> {
-    let day: u32 = day?.as_str().parse().ok()?;
-    let month: u32 = month?.as_str().parse().ok()?;
-    if let Some(year) = year {
-        let year: i32 = year.as_str().parse().ok()?;
-        NaiveDate::from_ymd_opt(year, month, day)
-    } else {
-        let current_date = NaiveDate::from_ymd_opt(current.year(), month, day)?;
-        let next_date = NaiveDate::from_ymd_opt(current.year() + 1, month, day)?;
-        if (current_date - current.date()) < (next_date - current.date()) {
-            Some(current_date)
-        } else {
-            Some(next_date)
-        }
-    }
-}
-
-pub fn into_token(word: &str, message: &ReceivedMessage, students: &[Student]) -> Token {
-    let long_date_regex = Regex::new(r"^((\d{1,2})(-|\.|\/)(\d{1,2})(-|\.|\/)(\d{4}))$").unwrap();
-    let middle_date_regex = Regex::new(r"^((\d{1,2})(-|\.|\/)(\d{1,2})(-|\.|\/)(\d{2}))$").unwrap();
-    let short_date_regex = Regex::new(r"^((\d{1,2})(-|\.|\/)(\d{1,2}))$").unwrap();
-
-    let regexes = [long_date_regex, middle_date_regex, short_date_regex];
-
-    for date_regex in regexes {
-        if let Some(date) = date_regex.captures(word) {
-            if let Some(date) = into_date(message.received, date.get(2), date.get(4), date.get(6)) {
-                return Token::Date(date);
-            } else {
-                return Token::Unknown(word.into());
-            }
-        }
-    }
-
+const RESTORE_KEYWORDS: [&str; 8] = [
+    "obecny",
+    "obecna",
+    "obecni",
+    "wraca",
+    "wracają",
+    "wracaja",
+    "przywróć",
+    "przywroc",
+];
+
+pub fn named_tokens(students: &[Student]) -> impl Iterator<Item = (String, Token)> + '_ {
     let meals = students
         .iter()
         .map(|s| s.meals.iter())
@@ -161,8 +147,19 @@ pub fn into_token(word: &str, message: &ReceivedMessage, students: &[Student]) -
         .iter()
         .map(|student| (student.name.clone(), Token::Student(student.id)));
 
-    let target = meals
-        .chain(students)
+    meals.chain(students).unique()
+}
+
+pub fn into_token(word: &str, message: &ReceivedMessage, students: &[Student]) -> Token {
+    if RESTORE_KEYWORDS.contains(&word) {
+        return Token::Restore;
+    }
+
+    if let Some(date) = into_numeric_date(word, message.received) {
+        return date;
+    }
+
+    let target = named_tokens(students)
         .filter(|(name, _)| levenshtein(&name, word) <= 3)
         .min_set_by_key(|(name, _)| levenshtein(&name, word));
 
@@ -175,6 +172,7 @@ pub fn into_token(word: &str, message: &ReceivedMessage, students: &[Student]) -
 
 pub async fn pipeline<C>(
     context: (ReceivedMessage, Vec<Student>, MessageProcessing),
+    replies: &Replies,
     conn: &mut C,
 ) -> Result<Option<(ReceivedMessage, Vec<Student>, MessageProcessing)>, Error>
 where
@@ -187,7 +185,11 @@ where
         MessageProcessing::Init => {
             Some(MessageProcessing::Tokens(into_tokens(&message, &students)))
         }
-        MessageProcessing::Tokens(tokens) => Some(into_request(&tokens)),
+        MessageProcessing::Tokens(tokens) => Some(
+            into_clarification(&tokens, &students)
+                .map(MessageProcessing::Clarification)
+                .unwrap_or_else(|| into_request(&tokens)),
+        ),
         MessageProcessing::Cancellation(cancellation_request) => {
             Some(MessageProcessing::StudentCancellation(into_cancellations(
                 &cancellation_request,
@@ -197,16 +199,39 @@ where
         }
         MessageProcessing::StudentCancellation(student_cancellations) => {
             Some(MessageProcessing::CancellationResult(
-                save_attendance(student_cancellations, message.metadata.id, conn).await?,
+                save_attendance(student_cancellations, false, message.metadata.id, conn).await?,
             ))
         }
         MessageProcessing::CancellationResult(results) => {
-            let response = construct_response(&results, &message);
+            let response = construct_response(&results, &message, replies);
+            enqueue_message(response, message.metadata.id, conn).await?;
+            None
+        }
+        MessageProcessing::Restoration(restoration_request) => {
+            Some(MessageProcessing::StudentRestoration(into_cancellations(
+                &restoration_request,
+                &students,
+                message.received,
+            )))
+        }
+        MessageProcessing::StudentRestoration(student_restorations) => {
+            Some(MessageProcessing::RestorationResult(
+                save_attendance(student_restorations, true, message.metadata.id, conn).await?,
+            ))
+        }
+        MessageProcessing::RestorationResult(results) => {
+            let response = construct_restore_response(&results, &message, replies);
             enqueue_message(response, message.metadata.id, conn).await?;
             None
         }
+        MessageProcessing::Clarification(clarification) => {
+            let response = construct_clarification_response(&clarification, &message, replies);
+            enqueue_message(response, message.metadata.id, conn).await?;
+            save_pending_clarification(&message.data.phone, message.metadata.id, conn).await?;
+            None
+        }
         MessageProcessing::RequestError(request_error) => {
-            let response = construct_err_response(&request_error, &message);
+            let response = construct_err_response(&request_error, &message, replies);
             enqueue_message(response, message.metadata.id, conn).await?;
             None
         }
@@ -220,28 +245,26 @@ where
     }
 }
 
-fn construct_err_response(err: &RequestError, message: &ReceivedMessage) -> MessageData {
+fn construct_err_response(
+    err: &RequestError,
+    message: &ReceivedMessage,
+    replies: &Replies,
+) -> MessageData {
     let content = match err {
-        RequestError::InvalidTimeRange => format!("Podano nieprawidłowy zakres dat"),
-        RequestError::TooManyDates => format!(
-            "Podano zbyt wiele dat - należy podać pojedyńczą date nieobecności, lub okres pomiędzy 2 datami odseparowane spacją"
+        RequestError::InvalidTimeRange => replies.render(SystemTemplate::InvalidTimeRange, vec![]),
+        RequestError::TooManyDates => replies.render(SystemTemplate::TooManyDates, vec![]),
+        RequestError::NoStudentSpecified => {
+            replies.render(SystemTemplate::NoStudentSpecified, vec![])
+        }
+        RequestError::NoDateSpecified => replies.render(SystemTemplate::NoDateSpecified, vec![]),
+        RequestError::UnknownTerm(term) => replies.render(
+            SystemTemplate::UnknownTerm,
+            vec![(Placeholder::Term, term.clone())],
         ),
-        RequestError::NoStudentSpecified => format!("Nie podano ucznia"),
-        RequestError::NoDateSpecified => format!(
-            "Nie podano żadnej daty - należy podać pojedyńczą date nieobecności, lub okres pomiędzy 2 datami odseparowane spacją"
+        RequestError::AmbiguousTerm(term) => replies.render(
+            SystemTemplate::AmbiguousTerm,
+            vec![(Placeholder::Term, term.clone())],
         ),
-        RequestError::UnknownTerm(term) => {
-            format!(
-                "Termin '{}' nie jest prawidłowym określeniem na posiłek / ucznia",
-                term
-            )
-        }
-        RequestError::AmbiguousTerm(term) => {
-            format!(
-                "Termin '{}' może odnosić się do więcej niż jednego posiłku / ucznia",
-                term
-            )
-        }
     };
     MessageData {
         phone: message.data.phone.clone(),
@@ -250,14 +273,39 @@ fn construct_err_response(err: &RequestError, message: &ReceivedMessage) -> Mess
 }
 
 fn into_tokens(message: &ReceivedMessage, students: &[Student]) -> Vec<Token> {
-    message
-        .data
-        .content
-        .to_lowercase()
-        .trim()
-        .split_whitespace()
-        .map(|word| into_token(word, &message, &students))
-        .collect::<Vec<_>>()
+    let content = message.data.content.to_lowercase();
+    let words = content.split_whitespace().collect::<Vec<_>>();
+
+    let mut tokens = vec![];
+    let mut position = 0;
+    while position < words.len() {
+        let since = match tokens.last() {
+            Some(Token::Date(date)) => Some(*date),
+            _ => None,
+        };
+        match into_date_expression(&words[position..], message.received, since) {
+            Some((expression, consumed)) => {
+                let token = match expression {
+                    DateExpression::Day(date) => Token::Date(date),
+                    DateExpression::Range(since, until) => Token::DateRange(since, until),
+                    DateExpression::Until(until) => match since {
+                        Some(since) => {
+                            tokens.pop();
+                            Token::DateRange(since, until)
+                        }
+                        None => Token::DateRange(message.received.date(), until),
+                    },
+                };
+                tokens.push(token);
+                position += consumed;
+            }
+            None => {
+                tokens.push(into_token(words[position], message, students));
+                position += 1;
+            }
+        }
+    }
+    tokens
 }
 
 async fn save_state<C>(state: &MessageProcessing, context: Uuid, conn: &mut C) -> Result<(), Error>
@@ -280,20 +328,22 @@ where
     C: Connection<Database = Postgres>,
     for<'a> &'a mut C: Executor<'a, Database = Postgres>,
 {
-    let Some(guardian_id) = sqlx::query!(
-        "SELECT guardians.id FROM guardians WHERE
+    let Some((guardian_id, fullname)) = sqlx::query!(
+        "SELECT guardians.id, guardians.fullname FROM guardians WHERE
     guardians.phone = $1  OR format('+48%s', guardians.phone) = $1
 ",
         message.data.phone
     )
     .fetch_optional(&mut *tr)
     .await?
-    .map(|row| row.id) else {
+    .map(|row| (row.id, row.fullname)) else {
         return Ok(None);
     };
 
+    let replies = Replies::new(load_templates(&mut *tr).await?, fullname);
+
     let students = sqlx::query!(
-        "SELECT students.id, students.name, students.surname, caterings.grace_period, ARRAY_AGG((meals.id,meals.name)) AS \"meals: Vec<(Uuid,String)> \", caterings.since, caterings.until FROM students 
+        "SELECT students.id, students.name, students.surname, caterings.grace_period, ARRAY_AGG((meals.id,meals.name,catering_meals.cutoff_days,catering_meals.cutoff_time) ORDER BY catering_meals.meal_order) AS \"meals: Vec<(Uuid,String,i16,NaiveTime)> \", GREATEST(caterings.since, students.joined_on) AS \"since!\", LEAST(caterings.until, students.left_on) AS \"until!\" FROM students 
         INNER JOIN student_guardians ON student_guardians.student_id = students.id
         INNER JOIN guardians ON student_guardians.guardian_id = guardians.id
         INNER JOIN group_relations ON group_relations.child = students.id
@@ -311,17 +361,29 @@ where
         id: row.id,
         name: row.name,
         surname: row.surname,
-        meals: row.meals.map(|meals| meals.into_iter().map(|(id,name)| Meal{name,id}).collect()).unwrap_or(vec![]),
+        meals: row.meals.map(|meals| meals.into_iter().map(|(id,name,days_before,time)| Meal{name,id,cutoff: Some(MealCutoffDto{days_before: days_before.max(0) as u32, time})}).collect()).unwrap_or(vec![]),
         grace_period: row.grace_period,
             starts: row.since,
             ends: row.until
     })
     .collect::<Vec<_>>();
 
-    let mut context = (message, students, MessageProcessing::Init);
+    let processing = match take_pending_clarification(&message.data.phone, &mut *tr)
+        .await?
+        .and_then(|clarification| resolve_clarification(&clarification, &message.data.content))
+    {
+        Some(tokens) => {
+            let processing = MessageProcessing::Tokens(tokens);
+            save_state(&processing, message.metadata.id, &mut *tr).await?;
+            processing
+        }
+        None => MessageProcessing::Init,
+    };
+
+    let mut context = (message, students, processing);
 
     loop {
-        if let Some(new_context) = pipeline(context, tr).await? {
+        if let Some(new_context) = pipeline(context, &replies, tr).await? {
             context = new_context;
         } else {
             break;
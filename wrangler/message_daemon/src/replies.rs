@@ -0,0 +1,46 @@
+use dto::templates::{Placeholder, SystemTemplate, Templates};
+use sqlx::{Connection, Error, Executor, Postgres};
+
+/// Templates of the automatic replies along with values common to every reply to a guardian
+#[derive(Debug, Clone, Default)]
+pub struct Replies {
+    templates: Templates,
+    guardian: String,
+}
+
+impl Replies {
+    pub fn new(templates: Templates, guardian: String) -> Self {
+        Replies {
+            templates,
+            guardian,
+        }
+    }
+
+    pub fn render(
+        &self,
+        template: SystemTemplate,
+        mut values: Vec<(Placeholder, String)>,
+    ) -> String {
+        values.push((Placeholder::Guardian, self.guardian.clone()));
+        self.templates.render(template, &values)
+    }
+}
+
+/// Loads system templates changed by staff, the rest keep their default content
+pub async fn load_templates<C>(connection: &mut C) -> Result<Templates, Error>
+where
+    C: Connection<Database = Postgres>,
+    for<'a> &'a mut C: Executor<'a, Database = Postgres>,
+{
+    let templates = sqlx::query!("SELECT name, content FROM message_templates")
+        .fetch_all(&mut *connection)
+        .await?
+        .into_iter()
+        .filter_map(|row| {
+            row.name
+                .parse::<SystemTemplate>()
+                .ok()
+                .map(|template| (template, row.content))
+        });
+    Ok(Templates::new(templates))
+}
@@ -1,7 +1,11 @@
 #[cfg(test)]
 mod test {
-    use chrono::NaiveTime;
-    use dto::messages::{CancellationRequest, MessageMetadata};
+    use chrono::{NaiveDate, NaiveDateTime, NaiveTime};
+    use dto::catering::MealCutoffDto;
+    use dto::closure::ClosureNotice;
+    use dto::messages::{CancellationRequest, CancellationResult, MessageMetadata};
+    use dto::templates::{SystemTemplate, Templates};
+    use std::collections::HashMap;
 
     use crate::*;
 
@@ -25,6 +29,7 @@ mod test {
             meals: vec![Meal {
                 id: meal_1_id,
                 name: String::new(),
+                cutoff: None,
             }],
             starts: NaiveDate::from_ymd_opt(2024, 12, 01).unwrap(),
             ends: NaiveDate::from_ymd_opt(2025, 12, 01).unwrap(),
@@ -50,4 +55,392 @@ mod test {
         assert!(cancellation.students[0].since == NaiveDate::from_ymd_opt(2025, 01, 02).unwrap());
         assert!(cancellation.students[0].until == NaiveDate::from_ymd_opt(2025, 01, 03).unwrap());
     }
+
+    fn test_message(content: &str, received: &str) -> ReceivedMessage {
+        ReceivedMessage {
+            metadata: MessageMetadata {
+                id: Uuid::new_v4(),
+                inserted: NaiveDateTime::default(),
+            },
+            data: MessageData {
+                phone: String::new(),
+                content: String::from(content),
+            },
+            processed: false,
+            received: NaiveDateTime::parse_from_str(received, "%Y-%m-%d %H:%M:%S").unwrap(),
+        }
+    }
+
+    fn test_student(id: Uuid, name: &str, meal_id: Uuid) -> Student {
+        Student {
+            id,
+            name: String::from(name),
+            surname: String::new(),
+            grace_period: NaiveTime::from_hms_opt(7, 0, 0).unwrap(),
+            meals: vec![Meal {
+                id: meal_id,
+                name: String::from("obiad"),
+                cutoff: None,
+            }],
+            starts: NaiveDate::from_ymd_opt(2024, 12, 1).unwrap(),
+            ends: NaiveDate::from_ymd_opt(2025, 12, 1).unwrap(),
+        }
+    }
+
+    #[test]
+    fn restore_keyword_is_recognized() {
+        let students = vec![test_student(Uuid::new_v4(), "jan", Uuid::new_v4())];
+        let message = test_message("Jan wraca 02.01", "2025-01-01 06:00:00");
+
+        assert!(matches!(
+            into_token("wraca", &message, &students),
+            Token::Restore
+        ));
+        assert!(matches!(
+            into_token("obecny", &message, &students),
+            Token::Restore
+        ));
+    }
+
+    #[test]
+    fn restore_message_produces_restoration_request() {
+        let student_id = Uuid::new_v4();
+        let meal_id = Uuid::new_v4();
+        let students = vec![test_student(student_id, "jan", meal_id)];
+        let message = test_message("Jan obecny 02.01 03.01", "2025-01-01 06:00:00");
+
+        let tokens = into_tokens(&message, &students);
+
+        match into_request(&tokens) {
+            MessageProcessing::Restoration(request) => {
+                assert!(request.students == vec![student_id]);
+                assert!(request.since == NaiveDate::from_ymd_opt(2025, 1, 2).unwrap());
+                assert!(request.until == NaiveDate::from_ymd_opt(2025, 1, 3).unwrap());
+            }
+            other => panic!("Expected restoration request, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn restoration_respects_grace_period() {
+        let student_id = Uuid::new_v4();
+        let meal_id = Uuid::new_v4();
+        let students = vec![test_student(student_id, "jan", meal_id)];
+        let message = test_message("obecny 01.01 03.01", "2025-01-01 07:01:00");
+
+        let tokens = into_tokens(&message, &students);
+        let MessageProcessing::Restoration(request) = into_request(&tokens) else {
+            panic!("Expected restoration request");
+        };
+        let restoration = into_cancellations(&request, &students, message.received);
+
+        assert!(restoration.students.len() == 1);
+        assert!(restoration.students[0].since == NaiveDate::from_ymd_opt(2025, 1, 2).unwrap());
+        assert!(restoration.students[0].until == NaiveDate::from_ymd_opt(2025, 1, 3).unwrap());
+    }
+
+    #[test]
+    fn cancellation_respects_meal_cutoffs() {
+        let student_id = Uuid::new_v4();
+        let breakfast_id = Uuid::new_v4();
+        let lunch_id = Uuid::new_v4();
+        let mut student = test_student(student_id, "jan", lunch_id);
+        student.meals.insert(
+            0,
+            Meal {
+                id: breakfast_id,
+                name: String::from("śniadanie"),
+                cutoff: Some(MealCutoffDto {
+                    days_before: 1,
+                    time: NaiveTime::from_hms_opt(15, 0, 0).unwrap(),
+                }),
+            },
+        );
+        let students = vec![student];
+
+        let request = CancellationRequest {
+            since: NaiveDate::from_ymd_opt(2025, 1, 2).unwrap(),
+            until: NaiveDate::from_ymd_opt(2025, 1, 3).unwrap(),
+            students: vec![student_id],
+            meals: vec![],
+        };
+        let received =
+            NaiveDateTime::parse_from_str("2025-01-01 16:00:00", "%Y-%m-%d %H:%M:%S").unwrap();
+
+        let cancellation = into_cancellations(&request, &students, received);
+
+        assert!(cancellation.students.len() == 2);
+        assert!(cancellation.students[0].meals == vec![breakfast_id]);
+        assert!(cancellation.students[0].since == NaiveDate::from_ymd_opt(2025, 1, 3).unwrap());
+        assert!(cancellation.students[1].meals == vec![lunch_id]);
+        assert!(cancellation.students[1].since == NaiveDate::from_ymd_opt(2025, 1, 2).unwrap());
+        assert!(cancellation.students[1].until == NaiveDate::from_ymd_opt(2025, 1, 3).unwrap());
+    }
+
+    #[test]
+    fn cancellation_skips_meals_past_their_cutoff() {
+        let student_id = Uuid::new_v4();
+        let meal_id = Uuid::new_v4();
+        let mut student = test_student(student_id, "jan", meal_id);
+        student.meals[0].cutoff = Some(MealCutoffDto {
+            days_before: 1,
+            time: NaiveTime::from_hms_opt(15, 0, 0).unwrap(),
+        });
+        let students = vec![student];
+        let message = test_message("Jan 02.01", "2025-01-01 16:00:00");
+
+        let tokens = into_tokens(&message, &students);
+        let MessageProcessing::Cancellation(request) = into_request(&tokens) else {
+            panic!("Expected cancellation request");
+        };
+
+        assert!(
+            into_cancellations(&request, &students, message.received)
+                .students
+                .is_empty()
+        );
+    }
+
+    fn date_tokens(content: &str, received: &str) -> Vec<Token> {
+        let students = vec![test_student(Uuid::new_v4(), "jan", Uuid::new_v4())];
+        into_tokens(&test_message(content, received), &students)
+    }
+
+    fn date(year: i32, month: u32, day: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(year, month, day).unwrap()
+    }
+
+    #[test]
+    fn relative_days_are_recognized() {
+        // 2025-01-01 is a wednesday
+        let tokens = date_tokens("jutro pojutrze dzisiaj", "2025-01-01 06:00:00");
+
+        assert!(matches!(tokens[0], Token::Date(d) if d == date(2025, 1, 2)));
+        assert!(matches!(tokens[1], Token::Date(d) if d == date(2025, 1, 3)));
+        assert!(matches!(tokens[2], Token::Date(d) if d == date(2025, 1, 1)));
+    }
+
+    #[test]
+    fn weekday_resolves_to_nearest_upcoming_day() {
+        let tokens = date_tokens("poniedziałek środa", "2025-01-01 06:00:00");
+
+        assert!(matches!(tokens[0], Token::Date(d) if d == date(2025, 1, 6)));
+        assert!(matches!(tokens[1], Token::Date(d) if d == date(2025, 1, 1)));
+    }
+
+    #[test]
+    fn weekday_range_is_recognized() {
+        let tokens = date_tokens("od poniedziałku do piątku", "2025-01-01 06:00:00");
+
+        assert!(tokens.len() == 1);
+        assert!(
+            matches!(tokens[0], Token::DateRange(since, until) if since == date(2025, 1, 6) && until == date(2025, 1, 10))
+        );
+    }
+
+    #[test]
+    fn numeric_range_with_connectives_is_recognized() {
+        let tokens = date_tokens("od 02.01 do 03.01", "2025-01-01 06:00:00");
+
+        assert!(tokens.len() == 1);
+        assert!(
+            matches!(tokens[0], Token::DateRange(since, until) if since == date(2025, 1, 2) && until == date(2025, 1, 3))
+        );
+    }
+
+    #[test]
+    fn whole_week_is_recognized() {
+        let tokens = date_tokens("cały tydzień", "2025-01-01 06:00:00");
+        assert!(
+            matches!(tokens[0], Token::DateRange(since, until) if since == date(2024, 12, 30) && until == date(2025, 1, 5))
+        );
+
+        let tokens = date_tokens("cały tydzień", "2025-01-04 06:00:00");
+        assert!(
+            matches!(tokens[0], Token::DateRange(since, until) if since == date(2025, 1, 6) && until == date(2025, 1, 12))
+        );
+    }
+
+    #[test]
+    fn end_of_month_is_recognized() {
+        let tokens = date_tokens("jan do końca miesiąca", "2025-02-10 06:00:00");
+
+        assert!(tokens.len() == 2);
+        assert!(
+            matches!(tokens[1], Token::DateRange(since, until) if since == date(2025, 2, 10) && until == date(2025, 2, 28))
+        );
+    }
+
+    #[test]
+    fn date_range_produces_cancellation_request() {
+        let student_id = Uuid::new_v4();
+        let students = vec![test_student(student_id, "jan", Uuid::new_v4())];
+        let message = test_message("Jan od jutra do piątku", "2025-01-01 06:00:00");
+
+        match into_request(&into_tokens(&message, &students)) {
+            MessageProcessing::Cancellation(request) => {
+                assert!(request.students == vec![student_id]);
+                assert!(request.since == date(2025, 1, 2));
+                assert!(request.until == date(2025, 1, 3));
+            }
+            other => panic!("Expected cancellation request, got {:?}", other),
+        }
+    }
+
+    fn named_student(id: Uuid, name: &str, surname: &str, meal_id: Uuid) -> Student {
+        Student {
+            surname: String::from(surname),
+            ..test_student(id, name, meal_id)
+        }
+    }
+
+    #[test]
+    fn ambiguous_term_produces_numbered_options() {
+        let jan_id = Uuid::new_v4();
+        let jana_id = Uuid::new_v4();
+        let meal_id = Uuid::new_v4();
+        let students = vec![
+            named_student(jan_id, "jan", "kowalski", meal_id),
+            named_student(jana_id, "jana", "kowalska", meal_id),
+        ];
+        let message = test_message("jann 02.01", "2025-01-01 06:00:00");
+
+        let tokens = into_tokens(&message, &students);
+        let clarification = into_clarification(&tokens, &students).unwrap();
+
+        assert!(clarification.position == 0);
+        assert!(clarification.options.len() == 2);
+
+        let response =
+            construct_clarification_response(&clarification, &message, &Replies::default());
+        assert!(response.content.contains("1 - jan kowalski"));
+        assert!(response.content.contains("2 - jana kowalska"));
+    }
+
+    #[test]
+    fn shared_meal_is_not_ambiguous() {
+        let meal_id = Uuid::new_v4();
+        let students = vec![
+            named_student(Uuid::new_v4(), "jan", "kowalski", meal_id),
+            named_student(Uuid::new_v4(), "anna", "kowalska", meal_id),
+        ];
+        let message = test_message("obiad", "2025-01-01 06:00:00");
+
+        assert!(into_token("obiad", &message, &students) == Token::Meal(meal_id));
+    }
+
+    #[test]
+    fn clarification_reply_resumes_request() {
+        let jan_id = Uuid::new_v4();
+        let jana_id = Uuid::new_v4();
+        let meal_id = Uuid::new_v4();
+        let students = vec![
+            named_student(jan_id, "jan", "kowalski", meal_id),
+            named_student(jana_id, "jana", "kowalska", meal_id),
+        ];
+        let message = test_message("jann 02.01", "2025-01-01 06:00:00");
+
+        let tokens = into_tokens(&message, &students);
+        let clarification = into_clarification(&tokens, &students).unwrap();
+        let chosen = clarification.options[1].token.clone();
+        let tokens = resolve_clarification(&clarification, " 2 ").unwrap();
+
+        assert!(tokens[0] == chosen);
+        assert!(into_clarification(&tokens, &students).is_none());
+        assert!(matches!(
+            into_request(&tokens),
+            MessageProcessing::Cancellation(_)
+        ));
+    }
+
+    #[test]
+    fn clarification_reply_zero_skips_term() {
+        let students = vec![test_student(Uuid::new_v4(), "jan", Uuid::new_v4())];
+        let message = test_message("proszę 02.01", "2025-01-01 06:00:00");
+
+        let tokens = into_tokens(&message, &students);
+        let clarification = into_clarification(&tokens, &students).unwrap();
+
+        let tokens = resolve_clarification(&clarification, "0").unwrap();
+        assert!(tokens.len() == 1);
+        assert!(resolve_clarification(&clarification, "7").is_none());
+        assert!(resolve_clarification(&clarification, "jan 02.01").is_none());
+    }
+
+    fn christmas_closure() -> ClosureNotice {
+        ClosureNotice {
+            since: date(2025, 12, 24),
+            until: date(2025, 12, 26),
+            reason: String::from("Święta"),
+        }
+    }
+
+    #[test]
+    fn closed_days_are_explained() {
+        let message = test_message("jan 24.12", "2025-12-20 06:00:00");
+        let changes = vec![CancellationResult {
+            name: String::from("jan"),
+            meals: HashMap::new(),
+            closures: vec![christmas_closure()],
+            since: None,
+            until: None,
+        }];
+
+        let response = construct_response(&changes, &message, &Replies::default());
+        assert!(response.content.starts_with("W wybranych dniach posiłki nie są wydawane"));
+        assert!(response.content.contains("24.12 - 26.12 (Święta)"));
+    }
+
+    #[test]
+    fn closed_days_are_listed_after_cancellations() {
+        let message = test_message("jan 22.12 - 31.12", "2025-12-20 06:00:00");
+        let changes = vec![
+            CancellationResult {
+                name: String::from("jan"),
+                meals: HashMap::from([(String::from("obiad"), 4)]),
+                closures: vec![christmas_closure()],
+                since: Some(date(2025, 12, 22)),
+                until: Some(date(2025, 12, 31)),
+            },
+            CancellationResult {
+                name: String::from("ola"),
+                meals: HashMap::new(),
+                closures: vec![christmas_closure()],
+                since: None,
+                until: None,
+            },
+        ];
+
+        let response = construct_response(&changes, &message, &Replies::default());
+        assert!(response.content.contains("jan: obiad 4"));
+        assert!(!response.content.contains("ola"));
+        assert!(response.content.matches("24.12 - 26.12").count() == 1);
+    }
+
+    #[test]
+    fn changed_templates_are_used_in_replies() {
+        let message = test_message("jan 22.12 - 31.12", "2025-12-20 06:00:00");
+        let changes = vec![CancellationResult {
+            name: String::from("jan"),
+            meals: HashMap::from([(String::from("obiad"), 4)]),
+            closures: vec![],
+            since: Some(date(2025, 12, 22)),
+            until: Some(date(2025, 12, 31)),
+        }];
+        let replies = Replies::new(
+            Templates::new([(
+                SystemTemplate::Cancelled,
+                String::from("{rodzic}, odwołano {liczba} ({posilki}) od {od} do {do} {nieznane}"),
+            )]),
+            String::from("Anna Kowalska"),
+        );
+
+        let response = construct_response(&changes, &message, &replies);
+        assert!(
+            response.content == "Anna Kowalska, odwołano 4 (obiad) od 22.12 do 31.12 {nieznane}"
+        );
+
+        let response = construct_restore_response(&changes, &message, &replies);
+        assert!(response.content == "Przywrócono: \njan: obiad 4");
+    }
 }
@@ -0,0 +1,16 @@
+CREATE TABLE users(
+	id uuid primary key not null default gen_random_uuid(),
+	created timestamp not null DEFAULT LOCALTIMESTAMP(0),
+	login text not null,
+	password_hash text not null,
+	role text not null CHECK (role IN ('admin', 'kitchen', 'teacher')),
+	disabled bool not null default false,
+	unique (login)
+);
+
+CREATE TABLE sessions(
+	id text primary key not null,
+	user_id uuid references users(id) not null,
+	created timestamp not null DEFAULT LOCALTIMESTAMP(0),
+	expires timestamp not null
+);
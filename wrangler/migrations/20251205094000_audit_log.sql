@@ -0,0 +1,13 @@
+CREATE TABLE audit_log(
+	id uuid primary key not null default gen_random_uuid(),
+	created timestamp not null DEFAULT LOCALTIMESTAMP(0),
+	user_id uuid references users(id),
+	action text not null,
+	entity_type text not null,
+	entity_id uuid not null,
+	before jsonb,
+	after jsonb
+);
+
+CREATE INDEX audit_log_entity ON audit_log (entity_id, created);
+CREATE INDEX audit_log_created ON audit_log (created);
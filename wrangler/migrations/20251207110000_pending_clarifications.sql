@@ -0,0 +1,6 @@
+-- Add migration script here
+CREATE TABLE pending_clarifications(
+	phone text primary key not null,
+	cause_id uuid not null references messages(id),
+	created timestamp not null DEFAULT LOCALTIMESTAMP(0)
+);
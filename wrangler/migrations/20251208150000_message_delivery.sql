@@ -0,0 +1,9 @@
+-- Add migration script here
+ALTER TABLE messages ADD COLUMN status text not null default 'queued'
+	CHECK (status IN ('queued', 'sending', 'sent', 'delivered', 'failed'));
+ALTER TABLE messages ADD COLUMN attempts integer not null default 0;
+ALTER TABLE messages ADD COLUMN next_attempt timestamp;
+ALTER TABLE messages ADD COLUMN last_error text;
+
+UPDATE messages SET status = 'delivered' WHERE NOT outgoing;
+UPDATE messages SET status = 'sent', attempts = 1 WHERE outgoing AND processed;
@@ -0,0 +1,3 @@
+-- Add migration script here
+ALTER TABLE phones ADD COLUMN modem text;
+ALTER TABLE messages ADD COLUMN via text;
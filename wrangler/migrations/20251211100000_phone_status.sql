@@ -0,0 +1,17 @@
+-- Add migration script here
+ALTER TABLE phones ADD COLUMN locked boolean NOT NULL DEFAULT false;
+ALTER TABLE phones ADD COLUMN last_seen timestamp NOT NULL DEFAULT LOCALTIMESTAMP;
+ALTER TABLE phones ADD COLUMN state_since timestamp NOT NULL DEFAULT LOCALTIMESTAMP;
+ALTER TABLE phones ADD COLUMN total_sent integer NOT NULL DEFAULT 0;
+ALTER TABLE phones ADD COLUMN total_received integer NOT NULL DEFAULT 0;
+
+CREATE TABLE phone_state_history (
+	id int primary key generated always as identity,
+	number text not null,
+	state integer,
+	signal integer,
+	locked boolean not null,
+	recorded timestamp not null default LOCALTIMESTAMP
+);
+
+CREATE INDEX phone_state_history_number ON phone_state_history (number, recorded);
@@ -0,0 +1,8 @@
+-- Add migration script here
+CREATE TABLE connector_status (
+	id boolean primary key default true CHECK (id),
+	state text not null CHECK (state IN ('starting', 'running', 'waiting_for_modem', 'reconnecting')),
+	detail text,
+	since timestamp not null,
+	heartbeat timestamp not null
+);
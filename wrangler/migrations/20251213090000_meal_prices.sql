@@ -0,0 +1,12 @@
+-- Add migration script here
+CREATE TABLE catering_meal_prices (
+	id uuid primary key default gen_random_uuid(),
+	catering_id uuid references caterings(id) not null,
+	meal_id uuid references meals(id) not null,
+	price integer not null CHECK (price >= 0),
+	since date not null,
+	until date,
+	CHECK (until IS NULL OR until >= since)
+);
+
+CREATE INDEX catering_meal_prices_lookup ON catering_meal_prices (catering_id, meal_id, since);
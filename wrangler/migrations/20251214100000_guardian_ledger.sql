@@ -0,0 +1,29 @@
+-- Add migration script here
+CREATE TABLE bank_transfers (
+	id uuid primary key default gen_random_uuid(),
+	fingerprint text not null unique,
+	booked date not null,
+	amount integer not null,
+	title text not null,
+	counterparty text,
+	account text,
+	ignored boolean not null default false,
+	imported timestamp not null default LOCALTIMESTAMP(0)
+);
+
+CREATE TABLE ledger_entries (
+	id uuid primary key default gen_random_uuid(),
+	guardian_id uuid references guardians(id) not null,
+	kind text not null CHECK (kind IN ('charge', 'payment', 'adjustment')),
+	amount integer not null,
+	booked date not null,
+	description text not null,
+	period date,
+	transfer_id uuid references bank_transfers(id) unique,
+	created timestamp not null default LOCALTIMESTAMP(0),
+	CHECK (kind != 'charge' OR (amount <= 0 AND period IS NOT NULL)),
+	CHECK (kind != 'payment' OR amount > 0)
+);
+
+CREATE INDEX ledger_entries_guardian ON ledger_entries (guardian_id, booked);
+CREATE UNIQUE INDEX ledger_entries_monthly_charge ON ledger_entries (guardian_id, period) WHERE kind = 'charge';
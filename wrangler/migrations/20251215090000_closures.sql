@@ -0,0 +1,13 @@
+-- Add migration script here
+CREATE TABLE closures (
+	id uuid PRIMARY KEY DEFAULT gen_random_uuid(),
+	catering_id uuid REFERENCES caterings(id),
+	since date NOT NULL,
+	until date NOT NULL,
+	reason text NOT NULL,
+	override_id uuid NOT NULL REFERENCES attendance_override(id),
+	created timestamp NOT NULL DEFAULT LOCALTIMESTAMP(0),
+	CHECK (until >= since)
+);
+
+CREATE INDEX closures_range ON closures (since, until);
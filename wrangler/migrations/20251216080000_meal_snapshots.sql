@@ -0,0 +1,37 @@
+-- Add migration script here
+CREATE TABLE meal_snapshots(
+	catering_id uuid references caterings(id) not null,
+	day date not null,
+	cutoff timestamp not null,
+	taken timestamp not null default LOCALTIMESTAMP(0),
+	primary key (catering_id, day)
+);
+
+CREATE TABLE meal_snapshot_entries(
+	catering_id uuid not null,
+	day date not null,
+	student_id uuid references students(id) not null,
+	meal_id uuid references meals(id) not null,
+	present bool not null,
+	primary key (catering_id, day, student_id, meal_id),
+	foreign key (catering_id, day) references meal_snapshots(catering_id, day) ON DELETE CASCADE
+);
+
+CREATE VIEW meal_snapshot_changes AS
+WITH final AS (
+	SELECT meal_snapshots.catering_id, meal_snapshots.day, total_attendance.student_id, total_attendance.meal_id, total_attendance.value AS present FROM meal_snapshots
+	INNER JOIN caterings ON caterings.id = meal_snapshots.catering_id
+	INNER JOIN group_relations ON group_relations.parent = caterings.group_id
+	INNER JOIN total_attendance ON total_attendance.student_id = group_relations.child AND total_attendance.day = meal_snapshots.day
+	INNER JOIN catering_meals ON catering_meals.catering_id = caterings.id AND catering_meals.meal_id = total_attendance.meal_id
+)
+SELECT COALESCE(entries.catering_id, final.catering_id) AS catering_id,
+	COALESCE(entries.day, final.day) AS day,
+	COALESCE(entries.student_id, final.student_id) AS student_id,
+	COALESCE(entries.meal_id, final.meal_id) AS meal_id,
+	COALESCE(entries.present, false) AS snapshot,
+	COALESCE(final.present, false) AS final
+FROM meal_snapshot_entries AS entries
+FULL JOIN final ON final.catering_id = entries.catering_id AND final.day = entries.day
+	AND final.student_id = entries.student_id AND final.meal_id = entries.meal_id
+WHERE COALESCE(entries.present, false) <> COALESCE(final.present, false);
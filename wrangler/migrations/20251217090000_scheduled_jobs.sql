@@ -0,0 +1,24 @@
+-- Add migration script here
+CREATE TABLE scheduled_jobs(
+	id uuid primary key default gen_random_uuid(),
+	name text unique not null,
+	schedule text not null,
+	enabled bool not null default true,
+	next_run timestamp
+);
+
+CREATE TABLE job_runs(
+	id uuid primary key default gen_random_uuid(),
+	job_id uuid not null references scheduled_jobs(id),
+	started timestamp not null default LOCALTIMESTAMP(0),
+	finished timestamp,
+	success bool,
+	output text
+);
+
+CREATE INDEX job_runs_job ON job_runs (job_id, started);
+
+INSERT INTO scheduled_jobs (name, schedule, enabled) VALUES
+	('meal_snapshots', '*/5 * * * *', true),
+	('processing_step_cleanup', '0 3 * * *', true),
+	('monthly_statements', '0 9 1 * *', false);
@@ -0,0 +1,15 @@
+-- Add migration script here
+CREATE TABLE broadcasts(
+	id uuid primary key default gen_random_uuid(),
+	created timestamp not null default LOCALTIMESTAMP(0),
+	user_id uuid references users(id) not null,
+	group_id uuid references groups(id) not null,
+	content text not null
+);
+
+CREATE TABLE broadcast_recipients(
+	broadcast_id uuid references broadcasts(id) not null,
+	guardian_id uuid references guardians(id) not null,
+	message_id uuid references messages(id) unique not null,
+	primary key (broadcast_id, guardian_id)
+);
@@ -0,0 +1,6 @@
+-- Add migration script here
+CREATE TABLE message_templates(
+	id uuid primary key default gen_random_uuid(),
+	name text unique not null,
+	content text not null
+);
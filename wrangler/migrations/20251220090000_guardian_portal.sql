@@ -0,0 +1,21 @@
+-- Add migration script here
+CREATE TABLE guardian_login_codes(
+	guardian_id uuid primary key references guardians(id) not null,
+	code_hash text not null,
+	attempts integer not null default 0,
+	created timestamp not null DEFAULT LOCALTIMESTAMP(0),
+	expires timestamp not null
+);
+
+CREATE TABLE guardian_sessions(
+	id text primary key not null,
+	guardian_id uuid references guardians(id) not null,
+	created timestamp not null DEFAULT LOCALTIMESTAMP(0),
+	expires timestamp not null
+);
+
+CREATE TABLE guardian_requests(
+	id uuid primary key not null default gen_random_uuid(),
+	guardian_id uuid references guardians(id) not null,
+	created timestamp not null DEFAULT LOCALTIMESTAMP(0)
+);
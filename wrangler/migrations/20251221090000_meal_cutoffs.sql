@@ -0,0 +1,9 @@
+-- Add migration script here
+ALTER TABLE catering_meals ADD COLUMN cutoff_days smallint NOT NULL DEFAULT 0 CHECK (cutoff_days >= 0);
+ALTER TABLE catering_meals ADD COLUMN cutoff_time time;
+
+UPDATE catering_meals SET cutoff_time = caterings.grace_period
+FROM caterings
+WHERE caterings.id = catering_meals.catering_id;
+
+ALTER TABLE catering_meals ALTER COLUMN cutoff_time SET NOT NULL;
@@ -0,0 +1,39 @@
+-- Add migration script here
+ALTER TABLE students ADD COLUMN joined_on date;
+ALTER TABLE students ADD COLUMN left_on date;
+ALTER TABLE students ADD CONSTRAINT students_enrolment_check CHECK (left_on IS NULL OR joined_on IS NULL OR left_on >= joined_on);
+
+CREATE TABLE student_placements(
+	id uuid primary key default gen_random_uuid(),
+	student_id uuid not null references students(id),
+	group_id uuid not null references groups(id),
+	since date,
+	until date,
+	created timestamp not null default LOCALTIMESTAMP(0),
+	CHECK (until IS NULL OR since IS NULL OR until >= since)
+);
+
+CREATE INDEX student_placements_student ON student_placements (student_id, since);
+CREATE INDEX student_placements_group ON student_placements (group_id);
+
+INSERT INTO student_placements (student_id, group_id)
+SELECT group_relations.child, group_relations.parent FROM group_relations
+INNER JOIN students ON students.id = group_relations.child
+WHERE group_relations.level = 1;
+
+CREATE OR REPLACE VIEW total_attendance AS SELECT DISTINCT ON (day, meal_id, students.id) effective_attendance.value, effective_attendance.day, effective_attendance.meal_id, students.id AS student_id, effective_attendance.cause_id FROM students
+						INNER JOIN group_relations ON group_relations.child = students.id
+						INNER JOIN effective_attendance ON effective_attendance.target = group_relations.parent
+						WHERE students.removed = false
+						AND effective_attendance.day BETWEEN COALESCE(students.joined_on, '-infinity') AND COALESCE(students.left_on, 'infinity')
+ORDER BY day, meal_id, students.id, value, level;
+
+CREATE OR REPLACE VIEW rooted_attendance AS SELECT bool_and(effective_attendance.value) AS present, effective_attendance.day, effective_attendance.meal_id, student_relation.child as student_id, group_relations.parent AS root FROM group_relations
+						INNER JOIN group_relations AS student_relation ON student_relation.parent = group_relations.child
+						INNER JOIN students ON students.id = student_relation.child AND students.removed = false
+						INNER JOIN effective_attendance ON effective_attendance.target = student_relation.parent
+						WHERE effective_attendance.day BETWEEN COALESCE(students.joined_on, '-infinity') AND COALESCE(students.left_on, 'infinity')
+						GROUP BY effective_attendance.day, effective_attendance.meal_id, student_relation.child, group_relations.parent;
+
+INSERT INTO scheduled_jobs (name, schedule, enabled) VALUES
+	('student_transfers', '5 0 * * *', true);
@@ -0,0 +1,56 @@
+-- Add migration script here
+ALTER TABLE groups ADD COLUMN removed_at timestamp;
+ALTER TABLE groups ADD COLUMN removed_by uuid REFERENCES users(id);
+ALTER TABLE groups ADD COLUMN removed_with uuid;
+
+ALTER TABLE students ADD COLUMN removed_at timestamp;
+ALTER TABLE students ADD COLUMN removed_by uuid REFERENCES users(id);
+ALTER TABLE students ADD COLUMN removed_with uuid;
+
+CREATE INDEX groups_removed_with ON groups (removed_with);
+CREATE INDEX students_removed_with ON students (removed_with);
+
+-- Nodes removed before are attributed to the topmost removed ancestor they were removed with
+UPDATE groups SET removed_with = (
+	SELECT group_relations.parent FROM group_relations
+	INNER JOIN groups AS ancestor ON ancestor.id = group_relations.parent AND ancestor.removed
+	WHERE group_relations.child = groups.id AND NOT EXISTS (
+		SELECT 1 FROM group_relations AS between_relation
+		INNER JOIN groups AS kept ON kept.id = between_relation.parent AND NOT kept.removed
+		WHERE between_relation.child = groups.id AND between_relation.level < group_relations.level
+	)
+	ORDER BY group_relations.level DESC LIMIT 1
+) WHERE removed;
+
+UPDATE students SET removed_with = COALESCE((
+	SELECT group_relations.parent FROM group_relations
+	INNER JOIN groups AS ancestor ON ancestor.id = group_relations.parent AND ancestor.removed
+	WHERE group_relations.child = students.id AND group_relations.level > 0 AND NOT EXISTS (
+		SELECT 1 FROM group_relations AS between_relation
+		INNER JOIN groups AS kept ON kept.id = between_relation.parent AND NOT kept.removed
+		WHERE between_relation.child = students.id AND between_relation.level > 0 AND between_relation.level < group_relations.level
+	)
+	ORDER BY group_relations.level DESC LIMIT 1
+), students.id) WHERE removed;
+
+UPDATE groups SET removed_at = COALESCE(deletion.created, LOCALTIMESTAMP(0)), removed_by = deletion.user_id
+FROM groups AS root
+LEFT JOIN LATERAL (
+	SELECT created, user_id FROM audit_log WHERE entity_id = root.id AND action = 'delete' ORDER BY created DESC LIMIT 1
+) AS deletion ON true
+WHERE groups.removed AND root.id = groups.removed_with;
+
+UPDATE students SET removed_at = COALESCE(deletion.created, LOCALTIMESTAMP(0)), removed_by = deletion.user_id
+FROM (SELECT id FROM groups UNION ALL SELECT id FROM students) AS root
+LEFT JOIN LATERAL (
+	SELECT created, user_id FROM audit_log WHERE entity_id = root.id AND action = 'delete' ORDER BY created DESC LIMIT 1
+) AS deletion ON true
+WHERE students.removed AND root.id = students.removed_with;
+
+-- Broadcasts outlive groups purged from the recycle bin
+ALTER TABLE broadcasts ALTER COLUMN group_id DROP NOT NULL;
+ALTER TABLE broadcasts DROP CONSTRAINT broadcasts_group_id_fkey;
+ALTER TABLE broadcasts ADD CONSTRAINT broadcasts_group_id_fkey FOREIGN KEY (group_id) REFERENCES groups(id) ON DELETE SET NULL;
+
+INSERT INTO scheduled_jobs (name, schedule, enabled) VALUES
+	('recycle_bin_purge', '30 3 * * *', true);
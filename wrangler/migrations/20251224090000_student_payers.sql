@@ -0,0 +1,5 @@
+-- Add migration script here
+-- Meals of a student are billed to one guardian only, preferring guardians who can be texted
+CREATE VIEW student_payers AS SELECT DISTINCT ON (student_guardians.student_id) student_guardians.student_id, guardians.id AS guardian_id FROM student_guardians
+						INNER JOIN guardians ON guardians.id = student_guardians.guardian_id AND guardians.removed = false
+ORDER BY student_guardians.student_id, guardians.phone IS NULL, guardians.fullname, guardians.id;
@@ -0,0 +1,7 @@
+-- Add migration script here
+-- Codes sent and failed attempts are counted over a longer window, resending a code doesn't reset them
+ALTER TABLE guardian_login_codes ADD COLUMN window_started timestamp not null DEFAULT LOCALTIMESTAMP(0);
+ALTER TABLE guardian_login_codes ADD COLUMN codes_sent integer not null default 1;
+ALTER TABLE guardian_login_codes ADD COLUMN failed_attempts integer not null default 0;
+
+UPDATE guardian_login_codes SET window_started = created, failed_attempts = attempts;
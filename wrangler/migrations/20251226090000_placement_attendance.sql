@@ -0,0 +1,24 @@
+-- Add migration script here
+-- Nodes whose attendance applies to a student, with the days they apply on. Groups are taken from the
+-- placement valid on the day, so days before a transfer keep the old group's overrides and closures
+CREATE VIEW student_targets AS SELECT students.id AS student_id, students.id AS target, 0 AS level,
+						COALESCE(students.joined_on, '-infinity') AS since, COALESCE(students.left_on, 'infinity') AS until FROM students
+						WHERE students.removed = false
+UNION ALL SELECT students.id, group_relations.parent, group_relations.level + 1,
+						GREATEST(COALESCE(student_placements.since, '-infinity'), COALESCE(students.joined_on, '-infinity')),
+						LEAST(COALESCE(student_placements.until, 'infinity'), COALESCE(students.left_on, 'infinity')) FROM students
+						INNER JOIN student_placements ON student_placements.student_id = students.id
+						INNER JOIN group_relations ON group_relations.child = student_placements.group_id
+						WHERE students.removed = false;
+
+CREATE OR REPLACE VIEW total_attendance AS SELECT DISTINCT ON (day, meal_id, student_targets.student_id) effective_attendance.value, effective_attendance.day, effective_attendance.meal_id, student_targets.student_id, effective_attendance.cause_id FROM student_targets
+						INNER JOIN effective_attendance ON effective_attendance.target = student_targets.target
+						WHERE effective_attendance.day BETWEEN student_targets.since AND student_targets.until
+ORDER BY day, meal_id, student_targets.student_id, value, level;
+
+CREATE OR REPLACE VIEW rooted_attendance AS SELECT bool_and(effective_attendance.value) AS present, effective_attendance.day, effective_attendance.meal_id, student_targets.student_id, root_target.target AS root FROM student_targets
+						INNER JOIN effective_attendance ON effective_attendance.target = student_targets.target
+						INNER JOIN student_targets AS root_target ON root_target.student_id = student_targets.student_id AND root_target.level >= student_targets.level
+						WHERE effective_attendance.day BETWEEN student_targets.since AND student_targets.until
+						AND effective_attendance.day BETWEEN root_target.since AND root_target.until
+						GROUP BY effective_attendance.day, effective_attendance.meal_id, student_targets.student_id, root_target.target;
@@ -0,0 +1,5 @@
+-- Add migration script here
+-- Placements outlive groups purged from the recycle bin, so the history of transferred students stays
+ALTER TABLE student_placements ALTER COLUMN group_id DROP NOT NULL;
+ALTER TABLE student_placements DROP CONSTRAINT student_placements_group_id_fkey;
+ALTER TABLE student_placements ADD CONSTRAINT student_placements_group_id_fkey FOREIGN KEY (group_id) REFERENCES groups(id) ON DELETE SET NULL;
@@ -0,0 +1,20 @@
+-- Add migration script here
+-- Final attendance compared with snapshots follows the placement valid on the snapshot day, like the snapshots themselves
+CREATE OR REPLACE VIEW meal_snapshot_changes AS
+WITH final AS (
+	SELECT meal_snapshots.catering_id, meal_snapshots.day, total_attendance.student_id, total_attendance.meal_id, total_attendance.value AS present FROM meal_snapshots
+	INNER JOIN caterings ON caterings.id = meal_snapshots.catering_id
+	INNER JOIN student_targets ON student_targets.target = caterings.group_id AND meal_snapshots.day BETWEEN student_targets.since AND student_targets.until
+	INNER JOIN total_attendance ON total_attendance.student_id = student_targets.student_id AND total_attendance.day = meal_snapshots.day
+	INNER JOIN catering_meals ON catering_meals.catering_id = caterings.id AND catering_meals.meal_id = total_attendance.meal_id
+)
+SELECT COALESCE(entries.catering_id, final.catering_id) AS catering_id,
+	COALESCE(entries.day, final.day) AS day,
+	COALESCE(entries.student_id, final.student_id) AS student_id,
+	COALESCE(entries.meal_id, final.meal_id) AS meal_id,
+	COALESCE(entries.present, false) AS snapshot,
+	COALESCE(final.present, false) AS final
+FROM meal_snapshot_entries AS entries
+FULL JOIN final ON final.catering_id = entries.catering_id AND final.day = entries.day
+	AND final.student_id = entries.student_id AND final.meal_id = entries.meal_id
+WHERE COALESCE(entries.present, false) <> COALESCE(final.present, false);
@@ -0,0 +1,292 @@
+use std::time::Duration;
+
+use chrono::{Local, TimeDelta};
+use dto::messages::ConnectorState;
+use sqlx::PgPool;
+use sqlx::postgres::PgListener;
+use uuid::Uuid;
+
+use crate::supervisor::report_state;
+use crate::transport::{
+    IncomingSms, OutgoingSms, SmsTransport, TransportError, TransportEvent, TransportStatus,
+};
+
+const MAX_SEND_ATTEMPTS: i32 = 5;
+const RETRY_BASE_DELAY: TimeDelta = TimeDelta::seconds(30);
+const RETRY_POLL_INTERVAL: Duration = Duration::from_secs(15);
+/// How often line status is refreshed even without changes, keeps `phones.last_seen` current
+const STATUS_POLL_INTERVAL: Duration = Duration::from_secs(60);
+
+/// Delay before the next attempt, doubling with every failed one
+fn retry_delay(attempts: i32) -> TimeDelta {
+    RETRY_BASE_DELAY * 2_i32.pow(attempts.clamp(1, MAX_SEND_ATTEMPTS) as u32 - 1)
+}
+
+/// Moves messages between the database and `transport` until the transport disconnects
+pub async fn run<T: SmsTransport>(pool: &PgPool, mut transport: T) -> Result<(), TransportError> {
+    let mut events = transport
+        .incoming()
+        .ok_or("Transport events are already consumed")?;
+
+    for status in transport.status().await? {
+        update_status(pool, status).await?;
+    }
+    log::info!("Updated info in db");
+
+    let mut listener = PgListener::connect_with(pool).await?;
+    listener.listen("sent").await?;
+
+    let messages = sqlx::query!("SELECT id FROM messages WHERE outgoing AND NOT processed")
+        .fetch_all(pool)
+        .await?;
+    for message in messages {
+        log::info!("Found message: {}", message.id);
+        send_message(pool, &transport, message.id).await?;
+    }
+    log::info!("Caught up to new events");
+    report_state(pool, ConnectorState::Running, None).await;
+
+    let mut retry_interval = tokio::time::interval(RETRY_POLL_INTERVAL);
+    let mut status_interval = tokio::time::interval(STATUS_POLL_INTERVAL);
+    loop {
+        tokio::select! {
+            event = events.recv() => {
+                match event {
+                    Some(TransportEvent::Disconnected(reason)) => return Err(reason.into()),
+                    Some(event) => on_transport_event(pool, event).await?,
+                    None => return Err("Transport closed event stream".into()),
+                }
+            }
+            notification = listener.recv() => {
+                let notification = notification?;
+                let payload = notification.payload();
+                if let Ok(message_id) = payload.try_into() {
+                    send_message(pool, &transport, message_id).await?;
+                } else {
+                    log::error!("Failed to parse message id: '{}'", payload);
+                }
+            }
+            _ = retry_interval.tick() => {
+                let due = sqlx::query!(
+                    "SELECT id FROM messages WHERE outgoing AND NOT processed
+                    AND (next_attempt IS NULL OR next_attempt <= $1)",
+                    Local::now().naive_local()
+                )
+                .fetch_all(pool)
+                .await?;
+                for message in due {
+                    log::info!("Retrying message: {}", message.id);
+                    send_message(pool, &transport, message.id).await?;
+                }
+            }
+            _ = status_interval.tick() => {
+                for status in transport.status().await? {
+                    update_status(pool, status).await?;
+                }
+            }
+        }
+    }
+}
+
+async fn send_message<T: SmsTransport>(
+    pool: &PgPool,
+    transport: &T,
+    id: Uuid,
+) -> Result<(), TransportError> {
+    let mut tr = pool.begin().await?;
+
+    let message = sqlx::query!(
+        "SELECT messages.phone, messages.content, messages.attempts, cause.via AS reply_via
+        FROM messages LEFT JOIN messages AS cause ON cause.id = messages.cause_id
+        WHERE NOT messages.processed AND messages.outgoing AND messages.id = $1
+        AND (messages.next_attempt IS NULL OR messages.next_attempt <= $2)
+        FOR UPDATE OF messages SKIP LOCKED",
+        id,
+        Local::now().naive_local()
+    )
+    .fetch_optional(&mut *tr)
+    .await?;
+
+    if let Some(message) = message {
+        let attempts = message.attempts + 1;
+        log::info!("Sending sms {}, attempt {}", id, attempts);
+
+        let sms = OutgoingSms {
+            id,
+            phone: message.phone,
+            content: message.content,
+            via: message.reply_via,
+        };
+
+        match transport.send(sms).await {
+            Ok(via) => {
+                log::info!("Sms sent via {:?}", via);
+                sqlx::query!(
+                    "UPDATE messages SET processed = true, status = 'sending', attempts = $2, last_error = NULL, via = $3 WHERE id = $1",
+                    id,
+                    attempts,
+                    via
+                )
+                .execute(&mut *tr)
+                .await?;
+                sqlx::query!(
+                    "UPDATE phones SET total_sent = total_sent + 1, last_seen = $2 WHERE number = $1",
+                    via,
+                    Local::now().naive_local()
+                )
+                .execute(&mut *tr)
+                .await?;
+            }
+            Err(e) if attempts >= MAX_SEND_ATTEMPTS => {
+                log::error!("Failed to send sms {}, giving up: {}", id, e);
+                sqlx::query!(
+                    "UPDATE messages SET processed = true, status = 'failed', attempts = $2, last_error = $3 WHERE id = $1",
+                    id,
+                    attempts,
+                    e.to_string()
+                )
+                .execute(&mut *tr)
+                .await?;
+            }
+            Err(e) => {
+                log::warn!("Failed to send sms {}, retrying later: {}", id, e);
+                sqlx::query!(
+                    "UPDATE messages SET status = 'queued', attempts = $2, last_error = $3, next_attempt = $4 WHERE id = $1",
+                    id,
+                    attempts,
+                    e.to_string(),
+                    Local::now().naive_local() + retry_delay(attempts)
+                )
+                .execute(&mut *tr)
+                .await?;
+            }
+        }
+    }
+
+    tr.commit().await?;
+    Ok(())
+}
+
+async fn on_transport_event(pool: &PgPool, event: TransportEvent) -> Result<(), TransportError> {
+    match event {
+        TransportEvent::Received(sms) => save_received(pool, sms).await?,
+        TransportEvent::Sent(id) => {
+            sqlx::query!(
+                "UPDATE messages SET sent = $2, status = 'sent' WHERE id = $1 AND status IN ('queued', 'sending')",
+                id,
+                Local::now().naive_local()
+            )
+            .execute(pool)
+            .await?;
+        }
+        TransportEvent::Delivered(id) => {
+            sqlx::query!("UPDATE messages SET status = 'delivered' WHERE id = $1", id)
+                .execute(pool)
+                .await?;
+        }
+        TransportEvent::DeliveryFailed(id, error) => {
+            sqlx::query!(
+                "UPDATE messages SET status = 'failed', last_error = $2 WHERE id = $1",
+                id,
+                error
+            )
+            .execute(pool)
+            .await?;
+        }
+        TransportEvent::StatusChanged(status) => update_status(pool, status).await?,
+        TransportEvent::Disconnected(_) => {}
+    }
+    Ok(())
+}
+
+async fn save_received(pool: &PgPool, sms: IncomingSms) -> Result<(), sqlx::Error> {
+    let db_message = sqlx::query!(
+        "SELECT * FROM messages WHERE sent = $1 AND phone = $2 AND content = $3",
+        sms.sent,
+        sms.phone,
+        sms.content
+    )
+    .fetch_optional(pool)
+    .await?;
+
+    if let Some(duplicate) = db_message {
+        log::info!(
+            "Message {} is duplicate of {}, ignoring",
+            sms.content,
+            duplicate.id
+        );
+    } else {
+        sqlx::query!(
+            "INSERT INTO messages (phone, content, sent, outgoing, status, via) VALUES ($1,$2,$3, false, 'delivered', $4)",
+            sms.phone,
+            sms.content,
+            sms.sent,
+            sms.via
+        )
+        .execute(pool)
+        .await?;
+        sqlx::query!(
+            "UPDATE phones SET total_received = total_received + 1, last_seen = $2 WHERE number = $1",
+            sms.via,
+            Local::now().naive_local()
+        )
+        .execute(pool)
+        .await?;
+    }
+    Ok(())
+}
+
+/// Stores status of a single line, dropping rows left over from a SIM previously inserted into the same modem.
+/// Every change of state or lock status is appended to `phone_state_history`
+async fn update_status(pool: &PgPool, status: TransportStatus) -> Result<(), sqlx::Error> {
+    let now = Local::now().naive_local();
+    let mut tr = pool.begin().await?;
+    sqlx::query!(
+        "DELETE FROM phones WHERE modem = $1 AND number != $2",
+        status.modem,
+        status.number
+    )
+    .execute(&mut *tr)
+    .await?;
+
+    let previous = sqlx::query!(
+        "SELECT state, locked FROM phones WHERE number = $1 FOR UPDATE",
+        status.number
+    )
+    .fetch_optional(&mut *tr)
+    .await?;
+
+    let changed = previous.is_none_or(|previous| {
+        previous.state != Some(status.state) || previous.locked != status.locked
+    });
+
+    if changed {
+        sqlx::query!(
+            "INSERT INTO phone_state_history (number, state, signal, locked, recorded) VALUES ($1,$2,$3,$4,$5)",
+            status.number,
+            status.state,
+            status.signal,
+            status.locked,
+            now
+        )
+        .execute(&mut *tr)
+        .await?;
+    }
+
+    sqlx::query!(
+        "INSERT INTO phones (number, signal, state, modem, locked, last_seen, state_since) VALUES ($1,$2,$3,$4,$5,$6,$6)
+        ON CONFLICT (number) DO UPDATE SET signal = $2, state = $3, modem = $4, locked = $5, last_seen = $6,
+        state_since = CASE WHEN $7 THEN $6 ELSE phones.state_since END",
+        status.number,
+        status.signal,
+        status.state,
+        status.modem,
+        status.locked,
+        now,
+        changed
+    )
+    .execute(&mut *tr)
+    .await?;
+    tr.commit().await?;
+    Ok(())
+}
@@ -0,0 +1,174 @@
+use std::time::Duration;
+
+use chrono::NaiveDateTime;
+use serde::{Deserialize, Serialize};
+use tokio::sync::mpsc::{self, UnboundedReceiver, UnboundedSender};
+use uuid::Uuid;
+
+use crate::transport::{
+    IncomingSms, OutgoingSms, SmsTransport, TransportError, TransportEvent, TransportStatus,
+    TransportTask,
+};
+
+const GATEWAY_POLL_INTERVAL: Duration = Duration::from_secs(5);
+
+#[derive(Serialize)]
+struct GatewayOutgoing<'a> {
+    id: Uuid,
+    phone: &'a str,
+    content: &'a str,
+    via: Option<&'a str>,
+}
+
+#[derive(Deserialize)]
+struct GatewaySent {
+    via: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct GatewayIncoming {
+    phone: String,
+    content: String,
+    sent: NaiveDateTime,
+    via: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct GatewayStatus {
+    modem: String,
+    number: String,
+    signal: i32,
+    state: i32,
+    #[serde(default)]
+    locked: bool,
+}
+
+/// Transport talking to an HTTP SMS gateway:
+/// `POST /send` accepts `{id, phone, content, via}` and returns `{via}`, `GET /inbox` returns received
+/// `[{phone, content, sent, via}]` since the previous call and `GET /status` returns
+/// `[{modem, number, signal, state, locked}]`
+pub struct HttpTransport {
+    client: reqwest::Client,
+    base_url: String,
+    token: Option<String>,
+    events: UnboundedSender<TransportEvent>,
+    incoming: Option<UnboundedReceiver<TransportEvent>>,
+    _inbox_poller: TransportTask,
+}
+
+impl HttpTransport {
+    pub fn new(base_url: String, token: Option<String>) -> Self {
+        let client = reqwest::Client::new();
+        let base_url = base_url.trim_end_matches('/').to_string();
+        let (events, incoming) = mpsc::unbounded_channel();
+
+        let inbox_poller = TransportTask::spawn({
+            let client = client.clone();
+            let inbox_url = format!("{}/inbox", base_url);
+            let token = token.clone();
+            let events = events.clone();
+            async move {
+                let mut interval = tokio::time::interval(GATEWAY_POLL_INTERVAL);
+                loop {
+                    interval.tick().await;
+                    match poll_inbox(&client, &inbox_url, token.as_deref()).await {
+                        Ok(messages) => {
+                            for message in messages {
+                                let _ = events.send(TransportEvent::Received(IncomingSms {
+                                    phone: message.phone,
+                                    content: message.content,
+                                    sent: message.sent,
+                                    via: message.via,
+                                }));
+                            }
+                        }
+                        Err(e) => log::warn!("Failed to poll sms gateway inbox: {}", e),
+                    }
+                }
+            }
+        });
+
+        HttpTransport {
+            client,
+            base_url,
+            token,
+            events,
+            incoming: Some(incoming),
+            _inbox_poller: inbox_poller,
+        }
+    }
+
+    fn request(&self, method: reqwest::Method, path: &str) -> reqwest::RequestBuilder {
+        authorized(
+            self.client
+                .request(method, format!("{}{}", self.base_url, path)),
+            self.token.as_deref(),
+        )
+    }
+}
+
+fn authorized(request: reqwest::RequestBuilder, token: Option<&str>) -> reqwest::RequestBuilder {
+    match token {
+        Some(token) => request.bearer_auth(token),
+        None => request,
+    }
+}
+
+async fn poll_inbox(
+    client: &reqwest::Client,
+    url: &str,
+    token: Option<&str>,
+) -> Result<Vec<GatewayIncoming>, reqwest::Error> {
+    authorized(client.get(url), token)
+        .send()
+        .await?
+        .error_for_status()?
+        .json()
+        .await
+}
+
+impl SmsTransport for HttpTransport {
+    async fn send(&self, message: OutgoingSms) -> Result<Option<String>, TransportError> {
+        let sent: GatewaySent = self
+            .request(reqwest::Method::POST, "/send")
+            .json(&GatewayOutgoing {
+                id: message.id,
+                phone: &message.phone,
+                content: &message.content,
+                via: message.via.as_deref(),
+            })
+            .send()
+            .await?
+            .error_for_status()?
+            .json()
+            .await?;
+
+        let _ = self.events.send(TransportEvent::Sent(message.id));
+        Ok(sent.via)
+    }
+
+    fn incoming(&mut self) -> Option<UnboundedReceiver<TransportEvent>> {
+        self.incoming.take()
+    }
+
+    async fn status(&self) -> Result<Vec<TransportStatus>, TransportError> {
+        let statuses: Vec<GatewayStatus> = self
+            .request(reqwest::Method::GET, "/status")
+            .send()
+            .await?
+            .error_for_status()?
+            .json()
+            .await?;
+
+        Ok(statuses
+            .into_iter()
+            .map(|status| TransportStatus {
+                modem: status.modem,
+                number: status.number,
+                signal: status.signal,
+                state: status.state,
+                locked: status.locked,
+            })
+            .collect())
+    }
+}
@@ -1,501 +1,175 @@
+pub mod connector;
+pub mod http_gateway;
+pub mod modem;
+pub mod modem_pool;
+pub mod spool;
+pub mod supervisor;
+pub mod tests;
+pub mod transport;
+
 use std::collections::HashMap;
 
-use chrono::{DateTime, Local, Utc};
-use futures::stream::StreamExt;
+use dto::messages::ConnectorState;
+use futures::StreamExt;
 use simple_logger::SimpleLogger;
 use sqlx::PgPool;
-use sqlx::postgres::PgListener;
-use tokio::sync::mpsc;
-use uuid::Uuid;
-use zbus::proxy;
 use zbus::{
-    Connection, Result,
-    fdo::ObjectManagerProxy,
-    zvariant::{OwnedObjectPath, Value},
+    Connection,
+    fdo::{InterfacesAddedStream, InterfacesRemovedStream, ObjectManagerProxy},
 };
 
-#[proxy(
-    default_service = "org.freedesktop.ModemManager1",
-    interface = "org.freedesktop.ModemManager1.Modem"
-)]
-trait Modem {
-    fn enable(&self, state: bool) -> Result<()>;
-
-    #[zbus(property)]
-    fn state(&self) -> Result<i32>;
-
-    #[zbus(property)]
-    fn unlock_required(&self) -> Result<u32>;
-
-    #[zbus(property)]
-    fn signal_quality(&self) -> Result<(u32, bool)>;
-
-    #[zbus(property)]
-    fn own_numbers(&self) -> Result<Vec<String>>;
-
-    #[zbus(property)]
-    fn sim(&self) -> Result<OwnedObjectPath>;
-
-    #[zbus(signal)]
-    fn state_signal(&self, old: i32, new: i32, reason: u32) -> Result<()>;
-}
-
-#[proxy(
-    default_service = "org.freedesktop.ModemManager1",
-    interface = "org.freedesktop.ModemManager1.Sms"
-)]
-trait Sms {
-    #[zbus(property)]
-    fn text(&self) -> Result<String>;
-
-    #[zbus(property)]
-    fn timestamp(&self) -> Result<String>;
-
-    #[zbus(property)]
-    fn number(&self) -> Result<String>;
-
-    #[zbus(property)]
-    fn state(&self) -> Result<u32>;
-
-    fn send(&self) -> Result<()>;
-}
-
-#[proxy(
-    default_service = "org.freedesktop.ModemManager1",
-    interface = "org.freedesktop.ModemManager1.Modem.Messaging"
-)]
-trait Messaging {
-    fn list(&self) -> Result<Vec<OwnedObjectPath>>;
-
-    #[zbus(signal)]
-    fn added(&self, path: OwnedObjectPath, received: bool) -> Result<()>;
-
-    fn create<'a>(&self, message: HashMap<&'static str, Value<'a>>) -> Result<OwnedObjectPath>;
-}
+use crate::{
+    http_gateway::HttpTransport,
+    modem::ModemProxy,
+    modem::ModemTransport,
+    modem_pool::ModemPool,
+    spool::SpoolTransport,
+    supervisor::{report_state, supervise},
+    transport::TransportError,
+};
 
-#[proxy(
-    default_service = "org.freedesktop.ModemManager1",
-    default_path = "/org/freedesktop/ModemManager1/SIM/0",
-    interface = "org.freedesktop.ModemManager1.Sim"
-)]
-trait Sim {
-    fn send_pin(&self, pin: String) -> Result<()>;
+/// Pins given as `MODEM_PINS="<imei>=<pin>,..."`, `MODEM_PIN` is used for modems not listed there
+fn modem_pins() -> (HashMap<String, String>, Option<String>) {
+    let pins = std::env::var("MODEM_PINS")
+        .unwrap_or_default()
+        .split(',')
+        .filter_map(|entry| entry.split_once('='))
+        .map(|(modem, pin)| (modem.trim().to_string(), pin.trim().to_string()))
+        .collect();
+    (pins, std::env::var("MODEM_PIN").ok())
 }
 
-enum ModemEvent {
-    StateChanged(i32, i32),
-    SignalQualityChanged(u32),
-    RequiresUnlock(bool),
-    MessageEnqueued(Uuid),
-    MessageAdded(OwnedObjectPath),
-}
+const MODEM_PATH_PREFIX: &str = "/org/freedesktop/ModemManager1/Modem/";
 
-async fn handle_modem(
-    pool: &PgPool,
+/// Connects to every modem currently exposed by ModemManager, `None` if there is none
+async fn connect_modems(
     connection: &Connection,
-    modem_path: OwnedObjectPath,
-    pin: &str,
-) -> Result<()> {
-    let modem_proxy = ModemProxy::builder(connection)
-        .path(modem_path.clone())?
-        .build()
-        .await?;
-
-    let messaging_proxy = MessagingProxy::builder(connection)
-        .path(modem_path.clone())?
-        .build()
-        .await?;
-
-    let own_numbers = modem_proxy.own_numbers().await?;
-    log::info!("Got the following numbers: {:?}", own_numbers);
-
-    let (signal_quality, recent) = modem_proxy.signal_quality().await?;
-    let state = modem_proxy.state().await?;
-
-    log::info!(
-        "Obtained signal and state info: {:?} {:?}",
-        signal_quality,
-        state
-    );
-
-    sqlx::query!(
-            "INSERT INTO phones (number, signal, state) VALUES ($1,$2,$3) ON CONFLICT (number) DO UPDATE SET number = $1, signal = $2",
-            "N/A",
-            signal_quality as i32,
-            state as i32
-        ).execute(pool)
-        .await
-        .expect("Failed to update phone in database");
-
-    log::info!("Updated info in db");
-
-    let (tx, mut rx) = mpsc::unbounded_channel::<ModemEvent>();
-
-    let mut is_locked = modem_proxy.unlock_required().await? == 2;
-    tx.send(ModemEvent::RequiresUnlock(is_locked))
-        .expect("Failed to send unlock data into channel");
-
-    let mut unlock_stream = modem_proxy.receive_unlock_required_changed().await;
-    let unlock_task = tokio::spawn({
-        let tx = tx.clone();
-        async move {
-            while let Some(unlock) = unlock_stream.next().await {
-                let unlock = unlock.get().await;
-                if let Ok(unlock) = unlock {
-                    tx.send(ModemEvent::RequiresUnlock(unlock == 2))
-                        .expect("Failed to send unlock info accros channel");
-                } else {
-                    log::error!("Failed to decode unlock property change");
-                }
-            }
-        }
-    });
-
-    let mut state_stream = modem_proxy.receive_state_signal().await?;
-    tx.send(ModemEvent::StateChanged(state, state))
-        .expect("Failed to send state into channel");
-
-    let state_task = tokio::spawn({
-        let tx = tx.clone();
-        async move {
-            while let Some(state) = state_stream.next().await {
-                let args: StateSignalArgs = state
-                    .args()
-                    .expect("Failed to deserialize state from modem manager");
-                tx.send(ModemEvent::StateChanged(args.old, args.new))
-                    .expect("Failed to send state change through channel");
-            }
-        }
-    });
-
-    let mut quality_stream = modem_proxy.receive_signal_quality_changed().await;
-
-    let quality_task = tokio::spawn({
-        let tx = tx.clone();
-        async move {
-            while let Some(quality) = quality_stream.next().await {
-                let val = quality
-                    .get()
-                    .await
-                    .expect("Failed to parse quality change notification");
-                tx.send(ModemEvent::SignalQualityChanged(val.0))
-                    .expect("Failed to send state change through channel");
-            }
-        }
-    });
-
-    let mut has_attempted_unlock = false;
-
-    let mut current_state = state;
-
-    let message_list_stream = messaging_proxy.receive_added().await?;
-    let mut message_stream = message_list_stream.map(|element| {
-        element
-            .args()
-            .expect("Failed to parse incoming message")
-            .path
-    });
-
-    let msg_task = tokio::spawn({
-        let tx = tx.clone();
-        async move {
-            while let Some(msg) = message_stream.next().await {
-                log::info!("Processing message");
-                tx.send(ModemEvent::MessageAdded(msg))
-                    .expect("Failed to send new message notification on the channel");
-            }
-        }
-    });
-
-    let messages_to_send = tokio::spawn({
-        let pool = pool.clone();
-        let tx = tx.clone();
-        async move {
-            let mut listener = PgListener::connect_with(&pool)
-                .await
-                .expect("Listener failed to connect to db");
-            listener
-                .listen("sent")
-                .await
-                .expect("Listener couldn't start listening to messages");
-            let messages = sqlx::query!("SELECT * FROM messages WHERE outgoing AND NOT processed")
-                .fetch_all(&pool)
-                .await
-                .expect("Failed to get messages from db");
-            for message in messages {
-                log::info!(
-                    "Found message: {} : {} to {}",
-                    message.id,
-                    message.content,
-                    message.phone
-                );
-                tx.send(ModemEvent::MessageEnqueued(message.id))
-                    .expect("Failed to enqueue outgoing message");
-            }
-            log::info!("Caught up to new events");
+    object_manager: &ObjectManagerProxy<'_>,
+) -> zbus::Result<Option<ModemPool>> {
+    let (pins, default_pin) = modem_pins();
+    let tree = object_manager.get_managed_objects().await?;
 
-            while let Ok(message) = listener.recv().await {
-                let message = message.payload();
-                if let Ok(message_id) = message.try_into() {
-                    tx.send(ModemEvent::MessageEnqueued(message_id))
-                        .expect("Failed to enqueue outgoing message");
-                } else {
-                    log::error!("Failed to parse message id: '{}'", message);
-                }
+    let mut modems = vec![];
+    for modem_path in tree.keys().filter(|m| m.starts_with(MODEM_PATH_PREFIX)) {
+        let modem = ModemProxy::builder(connection)
+            .path(modem_path.clone())?
+            .build()
+            .await?;
+        let identifier = modem.equipment_identifier().await?;
+        let pin = pins.get(&identifier).cloned().or(default_pin.clone());
+
+        match ModemTransport::connect(connection.clone(), modem_path.clone(), pin).await {
+            Ok(transport) => {
+                log::info!("Connected to modem {} at {}", identifier, modem_path);
+                modems.push(transport);
             }
+            Err(e) => log::error!("Failed to connect to modem {}: {}", identifier, e),
         }
-    });
-
-    while let Some(event) = rx.recv().await {
-        match event {
-            ModemEvent::StateChanged(old, new) => {
-                log::info!("Modem state changed from {} to {}", old, new);
-                match new {
-                    3 => {
-                        log::info!("Enabling modem");
-                        modem_proxy.enable(true).await?;
-                    }
-                    7 => {
-                        log::info!("Modem ready for work, getting message list");
-                        let message_list = messaging_proxy.list().await?;
-                        for message in message_list {
-                            tx.send(ModemEvent::MessageAdded(message))
-                                .expect("Failed to add messages to channel");
-                        }
-                    }
-                    state => {
-                        log::info!("Unknown state: {}, ignoring", state);
-                    }
-                }
-                current_state = new;
-            }
-            ModemEvent::SignalQualityChanged(signal) => {
-                log::info!("Updating phone signal info");
-                sqlx::query!(
-                    "UPDATE phones SET signal = $2 WHERE number = $1 ",
-                    "N/A",
-                    signal as i32
-                )
-                .execute(pool)
-                .await
-                .expect("Failed to update signal quality in db");
-            }
-            ModemEvent::RequiresUnlock(locked) => {
-                log::info!("Handling requires unlock");
-                if current_state == 2 {
-                    if locked {
-                        if has_attempted_unlock {
-                            log::warn!(
-                                "Phone status changed to locked, even though unlock took place, skipping unlock"
-                            );
-                        } else {
-                            has_attempted_unlock = true;
-                            let sim_path = modem_proxy.sim().await?;
-
-                            log::info!("Sending pin");
+    }
 
-                            let sim = SimProxy::builder(connection)
-                                .path(sim_path)?
-                                .build()
-                                .await?;
-                            sim.send_pin(pin.into()).await?;
+    Ok((!modems.is_empty()).then(|| ModemPool::new(modems)))
+}
 
-                            log::info!("Sent pind to unlock phone");
-                        }
-                    } else {
-                        if is_locked {
-                            log::info!("Phone unlocked");
-                        }
-                    }
+/// Resolves once a modem is plugged in or removed, or ModemManager stops reporting changes
+async fn modem_change(
+    mut added: InterfacesAddedStream,
+    mut removed: InterfacesRemovedStream,
+) -> zbus::Result<String> {
+    loop {
+        tokio::select! {
+            signal = added.next() => {
+                let Some(signal) = signal else {
+                    return Ok(String::from("ModemManager signal stream closed"));
+                };
+                let path = signal.args()?.object_path.to_string();
+                if path.starts_with(MODEM_PATH_PREFIX) {
+                    return Ok(format!("Modem {} added", path));
                 }
-                is_locked = locked;
             }
-            ModemEvent::MessageEnqueued(id) => {
-                log::info!("Enqueued message");
-                let mut tr = pool.begin().await.expect("Failed to start transaction");
-
-                let message = sqlx::query!("SELECT * FROM messages WHERE NOT processed AND outgoing AND id = $1 FOR UPDATE SKIP LOCKED", id).fetch_optional(&mut *tr).await.expect("Failed to fetch message from db");
-
-                if let Some(message) = message {
-                    let msg = [
-                        ("number", Value::Str(message.phone.into())),
-                        ("text", Value::Str(message.content.into())),
-                    ]
-                    .into_iter()
-                    .collect();
-
-                    let sms_path = messaging_proxy.create(msg).await?;
-                    let sms = SmsProxy::builder(connection)
-                        .path(sms_path)?
-                        .build()
-                        .await?;
-
-                    tokio::spawn({
-                        let sms = sms.clone();
-                        let pool = pool.clone();
-                        log::info!("starting waiting for message state change");
-                        async move {
-                            let mut state_stream = sms.receive_state_changed().await;
-                            while let Some(state) = state_stream.next().await {
-                                log::info!("state change detected for sms message");
-                                let state = state.get().await;
-                                log::info!("state: {:?}", state);
-                                if state == Ok(5) {
-                                    sqlx::query!(
-                                        "UPDATE messages SET sent = $2 WHERE id = $1",
-                                        id,
-                                        Local::now().naive_local()
-                                    )
-                                    .execute(&pool)
-                                    .await
-                                    .expect("Failed to set message delivery time");
-                                }
-                            }
-                        }
-                    });
-
-                    log::info!("Sending sms");
-                    sms.send().await?;
-                    log::info!("Sms sent");
-
-                    sqlx::query!("UPDATE messages SET processed = true WHERE id = $1", id)
-                        .execute(&mut *tr)
-                        .await
-                        .expect("Failed to mark message as processed");
+            signal = removed.next() => {
+                let Some(signal) = signal else {
+                    return Ok(String::from("ModemManager signal stream closed"));
+                };
+                let path = signal.args()?.object_path.to_string();
+                if path.starts_with(MODEM_PATH_PREFIX) {
+                    return Ok(format!("Modem {} removed", path));
                 }
-
-                tr.commit().await.expect("Failed to commit transaction");
-            }
-            ModemEvent::MessageAdded(path) => {
-                log::info!("Message added");
-                on_message_added(connection, pool, path)
-                    .await
-                    .expect("Failed to process added message");
             }
         }
     }
-    Ok(())
 }
 
-pub async fn on_message_added(
-    connection: &Connection,
-    pool: &PgPool,
-    sms_path: OwnedObjectPath,
-) -> Result<()> {
-    let sms_proxy = SmsProxy::builder(connection)
-        .path(sms_path)?
-        .build()
-        .await?;
+/// Single supervised run over all modems, finishes when the set of plugged in modems changes
+async fn run_modems(pool: &PgPool) -> Result<(), TransportError> {
+    let connection = Connection::system().await?;
+    log::info!("Connected to system bus");
 
-    let message_added = process_added_message(pool, &sms_proxy).await?;
+    let object_manager = ObjectManagerProxy::new(
+        &connection,
+        "org.freedesktop.ModemManager1",
+        "/org/freedesktop/ModemManager1",
+    )
+    .await?;
 
-    if !message_added {
-        let mut state_stream = sms_proxy.receive_state_changed().await;
-        tokio::spawn({
-            let pool = pool.clone();
-            async move {
-                while let Some(_) = state_stream.next().await {
-                    if let Ok(result) = process_added_message(&pool, &sms_proxy).await
-                        && result
-                    {
-                        break;
-                    }
+    // Subscribe before enumerating so that no modem plugged in meanwhile is missed
+    let change = modem_change(
+        object_manager.receive_interfaces_added().await?,
+        object_manager.receive_interfaces_removed().await?,
+    );
+    tokio::pin!(change);
+
+    match connect_modems(&connection, &object_manager).await? {
+        Some(modems) => {
+            tokio::select! {
+                result = connector::run(pool, modems) => result,
+                change = &mut change => {
+                    log::info!("{}, reconnecting modems", change?);
+                    Ok(())
                 }
             }
-        });
-    }
-
-    Ok(())
-}
-
-async fn process_added_message<'a>(pool: &PgPool, sms_proxy: &SmsProxy<'a>) -> Result<bool> {
-    let content = sms_proxy.text().await?;
-    let phone = sms_proxy.number().await?;
-    let state = sms_proxy.state().await?;
-
-    log::info!("Streaming state: {} {} {}", state, phone, content);
-
-    if state == 3 {
-        let sent = sms_proxy.timestamp().await?;
-        log::info!("Timestamp is: '{}'", sent);
-        let sent = DateTime::parse_from_str(&sent, "%Y-%m-%dT%H:%M:%S%#z")
-            .expect("Failed to parse timestamp")
-            .naive_local();
-
-        let db_message = sqlx::query!(
-            "SELECT * FROM messages WHERE sent = $1 AND phone = $2 AND content = $3",
-            sent,
-            phone,
-            content
-        )
-        .fetch_optional(pool)
-        .await
-        .expect("Failed to select message");
-
-        if let Some(duplicate) = db_message {
-            log::info!(
-                "Message {} is duplicate of {}, ignoring",
-                content,
-                duplicate.id
+        }
+        None => {
+            log::warn!(
+                "No modem exposed by ModemManager, verify that service is enabled and running and modem is plugged in"
             );
-        } else {
-            sqlx::query!(
-                "INSERT INTO messages (phone, content, sent, outgoing) VALUES ($1,$2,$3, false)",
-                phone,
-                content,
-                sent
-            )
-            .execute(pool)
-            .await
-            .expect("Failed to insert message");
+            report_state(pool, ConnectorState::WaitingForModem, None).await;
+            log::info!("{}, reconnecting modems", change.await?);
+            Ok(())
         }
-        Ok(true)
-    } else if state == 5 {
-        log::info!(
-            "Message is recognized as already sent to: '{}' content: '{}'",
-            phone,
-            content
-        );
-        Ok(true)
-    } else {
-        log::info!("Unknown sms state: {:?}", state);
-        Ok(false)
     }
 }
 
 #[tokio::main]
-async fn main() -> Result<()> {
+async fn main() {
     SimpleLogger::new().init().unwrap();
-    let connection = Connection::system().await?;
-    log::info!("Connected to system bus");
-
-    let modem_manager_interface = "org.freedesktop.ModemManager1";
     let db_url = std::env::var("DATABASE_URL").expect("No 'DATABASE_URL' specified");
-    let pin = std::env::var("MODEM_PIN").expect("No 'MODEM_PIN' specified");
-
-    let object_manager = ObjectManagerProxy::new(
-        &connection,
-        modem_manager_interface,
-        "/org/freedesktop/ModemManager1",
-    )
-    .await?;
-
-    let pool = PgPool::connect(&db_url)
-        .await
-        .expect("Failed to connect to postgres database");
-
-    log::info!("Connected to postgres db with url: {}", db_url);
-
-    let tree = object_manager.get_managed_objects().await?;
-
-    let modem_path = tree
-        .keys()
-        .find(|m| m.contains("/org/freedesktop/ModemManager1/Modem"))
-        .expect("Modem object not exposed by ModemManager, verify that service is enabled and running and modem is plugged in").clone();
-
-    handle_modem(&pool, &connection, modem_path, &pin)
-        .await
-        .expect("Modem handling failed");
-
-    Ok(())
+    let transport = std::env::var("SMS_TRANSPORT").unwrap_or(String::from("modem"));
+
+    // Connections are established on demand, so that the connector starts even if postgres is down
+    let pool = PgPool::connect_lazy(&db_url).expect("Invalid 'DATABASE_URL'");
+    supervisor::spawn_heartbeat(pool.clone());
+
+    match transport.as_str() {
+        "modem" => supervise(&pool, || run_modems(&pool)).await,
+        "http" => {
+            let url = std::env::var("SMS_GATEWAY_URL").expect("No 'SMS_GATEWAY_URL' specified");
+            let token = std::env::var("SMS_GATEWAY_TOKEN").ok();
+            supervise(&pool, || {
+                connector::run(&pool, HttpTransport::new(url.clone(), token.clone()))
+            })
+            .await
+        }
+        "spool" => {
+            let dir = std::env::var("SMS_SPOOL_DIR").expect("No 'SMS_SPOOL_DIR' specified");
+            supervise(&pool, || async {
+                let transport = SpoolTransport::open(dir.clone().into()).await?;
+                connector::run(&pool, transport).await
+            })
+            .await
+        }
+        other => panic!(
+            "Unknown SMS_TRANSPORT '{}', expected modem, http or spool",
+            other
+        ),
+    }
 }
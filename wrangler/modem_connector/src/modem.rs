@@ -0,0 +1,632 @@
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use chrono::{DateTime, Local};
+use futures::stream::StreamExt;
+use tokio::sync::mpsc::{self, UnboundedReceiver, UnboundedSender};
+use tokio::task::JoinSet;
+use uuid::Uuid;
+use zbus::proxy;
+use zbus::{
+    Connection, Result,
+    zvariant::{OwnedObjectPath, Value},
+};
+
+use crate::transport::{
+    IncomingSms, OutgoingSms, SmsTransport, TransportError, TransportEvent, TransportStatus,
+    TransportTask,
+};
+
+#[proxy(
+    default_service = "org.freedesktop.ModemManager1",
+    interface = "org.freedesktop.ModemManager1.Modem"
+)]
+pub trait Modem {
+    fn enable(&self, state: bool) -> Result<()>;
+
+    #[zbus(property)]
+    fn state(&self) -> Result<i32>;
+
+    #[zbus(property)]
+    fn unlock_required(&self) -> Result<u32>;
+
+    #[zbus(property)]
+    fn signal_quality(&self) -> Result<(u32, bool)>;
+
+    #[zbus(property)]
+    fn own_numbers(&self) -> Result<Vec<String>>;
+
+    #[zbus(property)]
+    fn equipment_identifier(&self) -> Result<String>;
+
+    #[zbus(property)]
+    fn sim(&self) -> Result<OwnedObjectPath>;
+
+    #[zbus(signal)]
+    fn state_signal(&self, old: i32, new: i32, reason: u32) -> Result<()>;
+}
+
+#[proxy(
+    default_service = "org.freedesktop.ModemManager1",
+    interface = "org.freedesktop.ModemManager1.Sms"
+)]
+trait Sms {
+    #[zbus(property)]
+    fn text(&self) -> Result<String>;
+
+    #[zbus(property)]
+    fn timestamp(&self) -> Result<String>;
+
+    #[zbus(property)]
+    fn number(&self) -> Result<String>;
+
+    #[zbus(property)]
+    fn state(&self) -> Result<u32>;
+
+    #[zbus(property)]
+    fn delivery_state(&self) -> Result<u32>;
+
+    fn send(&self) -> Result<()>;
+}
+
+#[proxy(
+    default_service = "org.freedesktop.ModemManager1",
+    interface = "org.freedesktop.ModemManager1.Modem.Messaging"
+)]
+trait Messaging {
+    fn list(&self) -> Result<Vec<OwnedObjectPath>>;
+
+    #[zbus(signal)]
+    fn added(&self, path: OwnedObjectPath, received: bool) -> Result<()>;
+
+    fn create<'a>(&self, message: HashMap<&'static str, Value<'a>>) -> Result<OwnedObjectPath>;
+}
+
+#[proxy(
+    default_service = "org.freedesktop.ModemManager1",
+    default_path = "/org/freedesktop/ModemManager1/SIM/0",
+    interface = "org.freedesktop.ModemManager1.Sim"
+)]
+trait Sim {
+    fn send_pin(&self, pin: String) -> Result<()>;
+}
+
+/// Sent messages are watched for state changes this long at most, reports may never arrive
+const MESSAGE_WATCH_TIMEOUT: Duration = Duration::from_secs(3 * 24 * 60 * 60);
+
+enum ModemEvent {
+    StateChanged(i32, i32),
+    SignalQualityChanged(u32),
+    RequiresUnlock(bool),
+    MessageAdded(OwnedObjectPath),
+}
+
+/// Maps MMSmsDeliveryState onto a delivery update, temporary errors are left pending
+fn into_delivery_event(id: Uuid, delivery_state: u32) -> Option<TransportEvent> {
+    match delivery_state {
+        0x00..=0x1f => Some(TransportEvent::Delivered(id)),
+        0x40..=0x7f => Some(TransportEvent::DeliveryFailed(
+            id,
+            format!("Delivery state: {:#x}", delivery_state),
+        )),
+        _ => None,
+    }
+}
+
+/// Last known state of the modem, shared between the transport and its handling task
+#[derive(Debug, Clone)]
+struct ModemLine {
+    identifier: String,
+    number: String,
+    signal: u32,
+    state: i32,
+    locked: bool,
+    disconnected: bool,
+}
+
+impl ModemLine {
+    fn status(&self) -> TransportStatus {
+        TransportStatus {
+            modem: self.identifier.clone(),
+            number: self.number.clone(),
+            signal: self.signal as i32,
+            state: self.state,
+            locked: self.locked,
+        }
+    }
+}
+
+/// First of the numbers reported by the SIM, the modem identifier if the SIM doesn't know its number
+async fn own_number(modem_proxy: &ModemProxy<'_>, identifier: &str) -> Result<String> {
+    let own_numbers = modem_proxy.own_numbers().await?;
+    log::info!(
+        "Modem {} has the following numbers: {:?}",
+        identifier,
+        own_numbers
+    );
+    Ok(own_numbers
+        .into_iter()
+        .find(|number| !number.is_empty())
+        .unwrap_or_else(|| identifier.to_string()))
+}
+
+/// Transport backed by a single ModemManager modem on the system D-Bus
+pub struct ModemTransport {
+    connection: Connection,
+    modem_proxy: ModemProxy<'static>,
+    messaging_proxy: MessagingProxy<'static>,
+    line: Arc<Mutex<ModemLine>>,
+    events: UnboundedSender<TransportEvent>,
+    incoming: Option<UnboundedReceiver<TransportEvent>>,
+    /// Watchers of sent messages, aborted along with the transport
+    message_watchers: Mutex<JoinSet<()>>,
+    _handler: TransportTask,
+}
+
+impl ModemTransport {
+    pub async fn connect(
+        connection: Connection,
+        modem_path: OwnedObjectPath,
+        pin: Option<String>,
+    ) -> Result<Self> {
+        let modem_proxy = ModemProxy::builder(&connection)
+            .path(modem_path.clone())?
+            .build()
+            .await?;
+
+        let messaging_proxy = MessagingProxy::builder(&connection)
+            .path(modem_path.clone())?
+            .build()
+            .await?;
+
+        let identifier = modem_proxy.equipment_identifier().await?;
+        let number = own_number(&modem_proxy, &identifier).await?;
+        let (signal, _) = modem_proxy.signal_quality().await?;
+        let line = Arc::new(Mutex::new(ModemLine {
+            identifier,
+            number,
+            signal,
+            state: modem_proxy.state().await?,
+            locked: modem_proxy.unlock_required().await? == 2,
+            disconnected: false,
+        }));
+
+        let (events, incoming) = mpsc::unbounded_channel();
+
+        let handler = TransportTask::spawn({
+            let connection = connection.clone();
+            let modem_proxy = modem_proxy.clone();
+            let messaging_proxy = messaging_proxy.clone();
+            let line = line.clone();
+            let events = events.clone();
+            async move {
+                if let Err(e) = handle_modem(
+                    &connection,
+                    &modem_proxy,
+                    &messaging_proxy,
+                    pin.as_deref(),
+                    &line,
+                    events.clone(),
+                )
+                .await
+                {
+                    log::error!("Modem handling failed: {}", e);
+                    line.lock().unwrap().disconnected = true;
+                    let _ = events.send(TransportEvent::Disconnected(e.to_string()));
+                }
+            }
+        });
+
+        Ok(ModemTransport {
+            connection,
+            modem_proxy,
+            messaging_proxy,
+            line,
+            events,
+            incoming: Some(incoming),
+            message_watchers: Mutex::new(JoinSet::new()),
+            _handler: handler,
+        })
+    }
+
+    /// Equipment identifier (IMEI) of the modem
+    pub fn identifier(&self) -> String {
+        self.line.lock().unwrap().identifier.clone()
+    }
+
+    /// Number of the SIM card inserted into the modem
+    pub fn number(&self) -> String {
+        self.line.lock().unwrap().number.clone()
+    }
+
+    /// Modem is still handled and registered in the network
+    pub fn is_healthy(&self) -> bool {
+        let line = self.line.lock().unwrap();
+        !line.disconnected && !line.locked && line.state >= 8
+    }
+}
+
+impl SmsTransport for ModemTransport {
+    async fn send(
+        &self,
+        message: OutgoingSms,
+    ) -> std::result::Result<Option<String>, TransportError> {
+        let id = message.id;
+        let msg = [
+            ("number", Value::Str(message.phone.into())),
+            ("text", Value::Str(message.content.into())),
+            ("delivery-report-request", Value::Bool(true)),
+        ]
+        .into_iter()
+        .collect();
+
+        let sms_path = self.messaging_proxy.create(msg).await?;
+        let sms = SmsProxy::builder(&self.connection)
+            .path(sms_path)?
+            .build()
+            .await?;
+
+        {
+            let mut watchers = self.message_watchers.lock().unwrap();
+            while watchers.try_join_next().is_some() {}
+
+            watchers.spawn({
+                let sms = sms.clone();
+                let events = self.events.clone();
+                log::info!("starting waiting for message state change");
+                async move {
+                    let mut state_stream = sms.receive_state_changed().await;
+                    let watch = async {
+                        while let Some(state) = state_stream.next().await {
+                            log::info!("state change detected for sms message");
+                            let state = state.get().await;
+                            log::info!("state: {:?}", state);
+                            if state == Ok(5) {
+                                let _ = events.send(TransportEvent::Sent(id));
+                                break;
+                            }
+                        }
+                    };
+                    if tokio::time::timeout(MESSAGE_WATCH_TIMEOUT, watch)
+                        .await
+                        .is_err()
+                    {
+                        log::warn!("Message {} wasn't reported as sent in time", id);
+                    }
+                }
+            });
+
+            watchers.spawn({
+                let sms = sms.clone();
+                let events = self.events.clone();
+                async move {
+                    let mut delivery_stream = sms.receive_delivery_state_changed().await;
+                    let watch = async {
+                        while let Some(delivery_state) = delivery_stream.next().await {
+                            let Ok(delivery_state) = delivery_state.get().await else {
+                                log::error!("Failed to decode delivery state of message {}", id);
+                                continue;
+                            };
+                            log::info!("Delivery state of message {}: {:#x}", id, delivery_state);
+                            if let Some(event) = into_delivery_event(id, delivery_state) {
+                                let _ = events.send(event);
+                                break;
+                            }
+                        }
+                    };
+                    if tokio::time::timeout(MESSAGE_WATCH_TIMEOUT, watch)
+                        .await
+                        .is_err()
+                    {
+                        log::warn!("No delivery report arrived for message {}", id);
+                    }
+                }
+            });
+        }
+
+        sms.send().await?;
+        Ok(Some(self.number()))
+    }
+
+    fn incoming(&mut self) -> Option<UnboundedReceiver<TransportEvent>> {
+        self.incoming.take()
+    }
+
+    async fn status(&self) -> std::result::Result<Vec<TransportStatus>, TransportError> {
+        let (signal_quality, _) = self.modem_proxy.signal_quality().await?;
+        let state = self.modem_proxy.state().await?;
+
+        let mut line = self.line.lock().unwrap();
+        line.signal = signal_quality;
+        line.state = state;
+        Ok(vec![line.status()])
+    }
+}
+
+async fn handle_modem(
+    connection: &Connection,
+    modem_proxy: &ModemProxy<'static>,
+    messaging_proxy: &MessagingProxy<'static>,
+    pin: Option<&str>,
+    line: &Mutex<ModemLine>,
+    events: UnboundedSender<TransportEvent>,
+) -> Result<()> {
+    let (signal_quality, _) = modem_proxy.signal_quality().await?;
+    let state = modem_proxy.state().await?;
+
+    log::info!(
+        "Obtained signal and state info: {:?} {:?}",
+        signal_quality,
+        state
+    );
+
+    let (tx, mut rx) = mpsc::unbounded_channel::<ModemEvent>();
+
+    let mut is_locked = modem_proxy.unlock_required().await? == 2;
+    let _ = tx.send(ModemEvent::RequiresUnlock(is_locked));
+
+    // Watchers are owned by the handler, so they are aborted together with it
+    let mut unlock_stream = modem_proxy.receive_unlock_required_changed().await;
+    let _unlock_watcher = TransportTask::spawn({
+        let tx = tx.clone();
+        async move {
+            while let Some(unlock) = unlock_stream.next().await {
+                let unlock = unlock.get().await;
+                if let Ok(unlock) = unlock {
+                    if tx.send(ModemEvent::RequiresUnlock(unlock == 2)).is_err() {
+                        break;
+                    }
+                } else {
+                    log::error!("Failed to decode unlock property change");
+                }
+            }
+        }
+    });
+
+    let mut state_stream = modem_proxy.receive_state_signal().await?;
+    let _ = tx.send(ModemEvent::StateChanged(state, state));
+
+    let _state_watcher = TransportTask::spawn({
+        let tx = tx.clone();
+        async move {
+            while let Some(state) = state_stream.next().await {
+                let Ok(args) = state.args() else {
+                    log::error!("Failed to deserialize state from modem manager");
+                    continue;
+                };
+                if tx
+                    .send(ModemEvent::StateChanged(args.old, args.new))
+                    .is_err()
+                {
+                    break;
+                }
+            }
+        }
+    });
+
+    let mut quality_stream = modem_proxy.receive_signal_quality_changed().await;
+
+    let _quality_watcher = TransportTask::spawn({
+        let tx = tx.clone();
+        async move {
+            while let Some(quality) = quality_stream.next().await {
+                let Ok((signal, _)) = quality.get().await else {
+                    log::error!("Failed to parse quality change notification");
+                    continue;
+                };
+                if tx.send(ModemEvent::SignalQualityChanged(signal)).is_err() {
+                    break;
+                }
+            }
+        }
+    });
+
+    let mut has_attempted_unlock = false;
+    let mut incoming_watchers = JoinSet::new();
+
+    let mut current_state = state;
+    let identifier = line.lock().unwrap().identifier.clone();
+
+    let mut message_stream = messaging_proxy.receive_added().await?;
+
+    let _message_watcher = TransportTask::spawn({
+        let tx = tx.clone();
+        async move {
+            while let Some(msg) = message_stream.next().await {
+                log::info!("Processing message");
+                let Ok(args) = msg.args() else {
+                    log::error!("Failed to parse incoming message");
+                    continue;
+                };
+                if tx.send(ModemEvent::MessageAdded(args.path)).is_err() {
+                    break;
+                }
+            }
+        }
+    });
+
+    while let Some(event) = rx.recv().await {
+        match event {
+            ModemEvent::StateChanged(old, new) => {
+                log::info!("Modem state changed from {} to {}", old, new);
+                match new {
+                    3 => {
+                        log::info!("Enabling modem");
+                        modem_proxy.enable(true).await?;
+                    }
+                    7 => {
+                        log::info!("Modem ready for work, getting message list");
+                        let message_list = messaging_proxy.list().await?;
+                        for message in message_list {
+                            let _ = tx.send(ModemEvent::MessageAdded(message));
+                        }
+                    }
+                    state => {
+                        log::info!("Unknown state: {}, ignoring", state);
+                    }
+                }
+                current_state = new;
+                // SIM number becomes readable only after unlocking
+                let number = own_number(modem_proxy, &identifier).await.ok();
+                let status = {
+                    let mut line = line.lock().unwrap();
+                    line.state = current_state;
+                    if let Some(number) = number {
+                        line.number = number;
+                    }
+                    line.status()
+                };
+                let _ = events.send(TransportEvent::StatusChanged(status));
+            }
+            ModemEvent::SignalQualityChanged(signal) => {
+                log::info!("Updating phone signal info");
+                let status = {
+                    let mut line = line.lock().unwrap();
+                    line.signal = signal;
+                    line.status()
+                };
+                let _ = events.send(TransportEvent::StatusChanged(status));
+            }
+            ModemEvent::RequiresUnlock(locked) => {
+                log::info!("Handling requires unlock");
+                if current_state == 2 {
+                    if locked {
+                        if let Some(pin) = pin.filter(|_| !has_attempted_unlock) {
+                            has_attempted_unlock = true;
+                            let sim_path = modem_proxy.sim().await?;
+
+                            log::info!("Sending pin to modem {}", identifier);
+
+                            let sim = SimProxy::builder(connection)
+                                .path(sim_path)?
+                                .build()
+                                .await?;
+                            sim.send_pin(pin.into()).await?;
+
+                            log::info!("Sent pind to unlock phone");
+                        } else if has_attempted_unlock {
+                            log::warn!(
+                                "Phone status changed to locked, even though unlock took place, skipping unlock"
+                            );
+                        } else {
+                            log::warn!(
+                                "Modem {} is locked and no pin was given for it",
+                                identifier
+                            );
+                        }
+                    } else {
+                        if is_locked {
+                            log::info!("Phone unlocked");
+                        }
+                    }
+                }
+                is_locked = locked;
+                let status = {
+                    let mut line = line.lock().unwrap();
+                    line.locked = locked;
+                    line.status()
+                };
+                let _ = events.send(TransportEvent::StatusChanged(status));
+            }
+            ModemEvent::MessageAdded(path) => {
+                log::info!("Message added");
+                let via = line.lock().unwrap().number.clone();
+                while incoming_watchers.try_join_next().is_some() {}
+                on_message_added(
+                    connection,
+                    path,
+                    via,
+                    events.clone(),
+                    &mut incoming_watchers,
+                )
+                .await?;
+            }
+        }
+    }
+    Ok(())
+}
+
+async fn on_message_added(
+    connection: &Connection,
+    sms_path: OwnedObjectPath,
+    via: String,
+    events: UnboundedSender<TransportEvent>,
+    watchers: &mut JoinSet<()>,
+) -> Result<()> {
+    let sms_proxy = SmsProxy::builder(connection)
+        .path(sms_path)?
+        .build()
+        .await?;
+
+    let message_added = process_added_message(&sms_proxy, &via, &events).await?;
+
+    if !message_added {
+        let mut state_stream = sms_proxy.receive_state_changed().await;
+        watchers.spawn(async move {
+            let watch = async {
+                while state_stream.next().await.is_some() {
+                    if let Ok(result) = process_added_message(&sms_proxy, &via, &events).await
+                        && result
+                    {
+                        break;
+                    }
+                }
+            };
+            if tokio::time::timeout(MESSAGE_WATCH_TIMEOUT, watch)
+                .await
+                .is_err()
+            {
+                log::warn!("Incoming message wasn't received completely in time");
+            }
+        });
+    }
+
+    Ok(())
+}
+
+async fn process_added_message<'a>(
+    sms_proxy: &SmsProxy<'a>,
+    via: &str,
+    events: &UnboundedSender<TransportEvent>,
+) -> Result<bool> {
+    let content = sms_proxy.text().await?;
+    let phone = sms_proxy.number().await?;
+    let state = sms_proxy.state().await?;
+
+    log::info!("Streaming state: {} {} {}", state, phone, content);
+
+    if state == 3 {
+        let sent = sms_proxy.timestamp().await?;
+        log::info!("Timestamp is: '{}'", sent);
+        let sent = match DateTime::parse_from_str(&sent, "%Y-%m-%dT%H:%M:%S%#z") {
+            Ok(sent) => sent.naive_local(),
+            Err(e) => {
+                log::warn!(
+                    "Failed to parse timestamp '{}': {}, using current time",
+                    sent,
+                    e
+                );
+                Local::now().naive_local()
+            }
+        };
+
+        let _ = events.send(TransportEvent::Received(IncomingSms {
+            phone,
+            content,
+            sent,
+            via: Some(via.to_string()),
+        }));
+        Ok(true)
+    } else if state == 5 {
+        log::info!(
+            "Message is recognized as already sent to: '{}' content: '{}'",
+            phone,
+            content
+        );
+        Ok(true)
+    } else {
+        log::info!("Unknown sms state: {:?}", state);
+        Ok(false)
+    }
+}
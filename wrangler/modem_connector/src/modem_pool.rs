@@ -0,0 +1,117 @@
+use std::sync::Arc;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use tokio::sync::mpsc::{self, UnboundedReceiver};
+
+use crate::modem::ModemTransport;
+use crate::transport::{
+    OutgoingSms, SmsTransport, TransportError, TransportEvent, TransportStatus, TransportTask,
+};
+
+/// Transport spreading outgoing messages over several modems.
+/// Replies are sent from the line the conversation arrived on, as long as it is healthy
+pub struct ModemPool {
+    modems: Vec<ModemTransport>,
+    next: AtomicUsize,
+    incoming: Option<UnboundedReceiver<TransportEvent>>,
+    _forwarders: Vec<TransportTask>,
+}
+
+impl ModemPool {
+    pub fn new(mut modems: Vec<ModemTransport>) -> Self {
+        let (events, incoming) = mpsc::unbounded_channel();
+        let connected = Arc::new(AtomicUsize::new(modems.len()));
+        let mut forwarders = vec![];
+
+        for modem in modems.iter_mut() {
+            let Some(mut modem_events) = modem.incoming() else {
+                continue;
+            };
+            let identifier = modem.identifier();
+            let events = events.clone();
+            let connected = connected.clone();
+            forwarders.push(TransportTask::spawn(async move {
+                while let Some(event) = modem_events.recv().await {
+                    if let TransportEvent::Disconnected(reason) = event {
+                        log::error!("Modem {} disconnected: {}", identifier, reason);
+                        if connected.fetch_sub(1, Ordering::SeqCst) == 1 {
+                            let _ = events.send(TransportEvent::Disconnected(String::from(
+                                "All modems disconnected",
+                            )));
+                        }
+                        break;
+                    }
+                    let _ = events.send(event);
+                }
+            }));
+        }
+
+        ModemPool {
+            modems,
+            next: AtomicUsize::new(0),
+            incoming: Some(incoming),
+            _forwarders: forwarders,
+        }
+    }
+
+    /// Healthy modems in the order they should be tried for `message`
+    fn candidates(&self, message: &OutgoingSms) -> Vec<&ModemTransport> {
+        let start = self.next.fetch_add(1, Ordering::Relaxed);
+        let count = self.modems.len();
+
+        let mut candidates: Vec<_> = (0..count)
+            .map(|i| &self.modems[(start + i) % count])
+            .filter(|modem| modem.is_healthy())
+            .collect();
+
+        if let Some(via) = &message.via
+            && let Some(position) = candidates.iter().position(|m| m.number() == *via)
+        {
+            let preferred = candidates.remove(position);
+            candidates.insert(0, preferred);
+        }
+        candidates
+    }
+}
+
+impl SmsTransport for ModemPool {
+    async fn send(&self, message: OutgoingSms) -> Result<Option<String>, TransportError> {
+        let mut last_error: Option<TransportError> = None;
+
+        for modem in self.candidates(&message) {
+            match modem.send(message.clone()).await {
+                Ok(via) => return Ok(via),
+                Err(e) => {
+                    log::warn!(
+                        "Modem {} failed to send message {}: {}",
+                        modem.identifier(),
+                        message.id,
+                        e
+                    );
+                    last_error = Some(e);
+                }
+            }
+        }
+
+        Err(last_error.unwrap_or_else(|| "No healthy modem available".into()))
+    }
+
+    fn incoming(&mut self) -> Option<UnboundedReceiver<TransportEvent>> {
+        self.incoming.take()
+    }
+
+    async fn status(&self) -> Result<Vec<TransportStatus>, TransportError> {
+        let mut statuses = Vec::with_capacity(self.modems.len());
+        for modem in &self.modems {
+            match modem.status().await {
+                Ok(status) => statuses.extend(status),
+                Err(e) => log::warn!(
+                    "Failed to read status of modem {}: {}",
+                    modem.identifier(),
+                    e
+                ),
+            }
+        }
+        Ok(statuses)
+    }
+}
@@ -0,0 +1,124 @@
+use std::{io, path::PathBuf, time::Duration};
+
+use chrono::Local;
+use serde::{Deserialize, Serialize};
+use tokio::{
+    fs,
+    sync::mpsc::{self, UnboundedReceiver, UnboundedSender},
+};
+
+use crate::transport::{
+    IncomingSms, OutgoingSms, SmsTransport, TransportError, TransportEvent, TransportStatus,
+    TransportTask,
+};
+
+const SPOOL_POLL_INTERVAL: Duration = Duration::from_secs(1);
+const SPOOL_NUMBER: &str = "spool";
+
+#[derive(Serialize, Deserialize)]
+struct SpoolMessage {
+    phone: String,
+    content: String,
+}
+
+/// Fake transport working on a spool directory, meant for CI and machines without a modem.
+/// Sent messages are written to `outgoing/<id>.json`, files dropped into `incoming/` are
+/// treated as received and removed afterwards
+pub struct SpoolTransport {
+    root: PathBuf,
+    events: UnboundedSender<TransportEvent>,
+    incoming: Option<UnboundedReceiver<TransportEvent>>,
+    _incoming_poller: TransportTask,
+}
+
+impl SpoolTransport {
+    pub async fn open(root: PathBuf) -> io::Result<Self> {
+        fs::create_dir_all(root.join("outgoing")).await?;
+        fs::create_dir_all(root.join("incoming")).await?;
+
+        let (events, incoming) = mpsc::unbounded_channel();
+
+        let incoming_poller = TransportTask::spawn({
+            let incoming_dir = root.join("incoming");
+            let events = events.clone();
+            async move {
+                let mut interval = tokio::time::interval(SPOOL_POLL_INTERVAL);
+                loop {
+                    interval.tick().await;
+                    if let Err(e) = poll_incoming(&incoming_dir, &events).await {
+                        log::error!("Failed to read spool directory: {}", e);
+                        let _ = events.send(TransportEvent::Disconnected(e.to_string()));
+                        break;
+                    }
+                }
+            }
+        });
+
+        Ok(SpoolTransport {
+            root,
+            events,
+            incoming: Some(incoming),
+            _incoming_poller: incoming_poller,
+        })
+    }
+}
+
+async fn poll_incoming(dir: &PathBuf, events: &UnboundedSender<TransportEvent>) -> io::Result<()> {
+    let mut entries = fs::read_dir(dir).await?;
+    while let Some(entry) = entries.next_entry().await? {
+        let path = entry.path();
+        if path.extension().is_none_or(|ext| ext != "json") {
+            continue;
+        }
+
+        match serde_json::from_slice::<SpoolMessage>(&fs::read(&path).await?) {
+            Ok(message) => {
+                log::info!("Picked up spooled message from {}", message.phone);
+                let _ = events.send(TransportEvent::Received(IncomingSms {
+                    phone: message.phone,
+                    content: message.content,
+                    sent: Local::now().naive_local(),
+                    via: Some(String::from(SPOOL_NUMBER)),
+                }));
+                fs::remove_file(&path).await?;
+            }
+            Err(e) => {
+                log::warn!("Rejecting malformed spool file {:?}: {}", path, e);
+                fs::rename(&path, path.with_extension("rejected")).await?;
+            }
+        }
+    }
+    Ok(())
+}
+
+impl SmsTransport for SpoolTransport {
+    async fn send(&self, message: OutgoingSms) -> Result<Option<String>, TransportError> {
+        let content = serde_json::to_vec_pretty(&SpoolMessage {
+            phone: message.phone,
+            content: message.content,
+        })?;
+
+        let outgoing = self.root.join("outgoing");
+        let partial = outgoing.join(format!("{}.partial", message.id));
+        fs::write(&partial, content).await?;
+        fs::rename(&partial, outgoing.join(format!("{}.json", message.id))).await?;
+
+        let _ = self.events.send(TransportEvent::Sent(message.id));
+        let _ = self.events.send(TransportEvent::Delivered(message.id));
+        Ok(Some(String::from(SPOOL_NUMBER)))
+    }
+
+    fn incoming(&mut self) -> Option<UnboundedReceiver<TransportEvent>> {
+        self.incoming.take()
+    }
+
+    async fn status(&self) -> Result<Vec<TransportStatus>, TransportError> {
+        Ok(vec![TransportStatus {
+            modem: String::from(SPOOL_NUMBER),
+            number: String::from(SPOOL_NUMBER),
+            signal: 100,
+            state: 8,
+            locked: false,
+        }])
+    }
+}
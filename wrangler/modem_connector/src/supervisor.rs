@@ -0,0 +1,83 @@
+use std::future::Future;
+use std::time::{Duration, Instant};
+
+use chrono::Local;
+use dto::messages::ConnectorState;
+use sqlx::PgPool;
+
+use crate::transport::TransportError;
+
+const RESTART_BASE_DELAY: Duration = Duration::from_secs(1);
+const RESTART_MAX_DELAY: Duration = Duration::from_secs(60);
+/// How often the connector confirms it is alive, web app treats a stale heartbeat as connector down
+const HEARTBEAT_INTERVAL: Duration = Duration::from_secs(30);
+
+/// Stores connector state for the web app, failures are only logged as the database may be the thing that is down
+pub async fn report_state(pool: &PgPool, state: ConnectorState, detail: Option<String>) {
+    let result = sqlx::query!(
+        "INSERT INTO connector_status (state, detail, since, heartbeat) VALUES ($1, $2, $3, $3)
+        ON CONFLICT (id) DO UPDATE SET state = $1, detail = $2, heartbeat = $3,
+        since = CASE WHEN connector_status.state = $1 THEN connector_status.since ELSE $3 END",
+        state.as_str(),
+        detail,
+        Local::now().naive_local()
+    )
+    .execute(pool)
+    .await;
+
+    if let Err(e) = result {
+        log::error!("Failed to report connector state {}: {}", state.as_str(), e);
+    }
+}
+
+/// Keeps `connector_status.heartbeat` fresh for as long as the process lives
+pub fn spawn_heartbeat(pool: PgPool) {
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(HEARTBEAT_INTERVAL);
+        loop {
+            interval.tick().await;
+            let result = sqlx::query!(
+                "UPDATE connector_status SET heartbeat = $1",
+                Local::now().naive_local()
+            )
+            .execute(&pool)
+            .await;
+            if let Err(e) = result {
+                log::warn!("Failed to update connector heartbeat: {}", e);
+            }
+        }
+    });
+}
+
+/// Runs `attempt` forever, restarting it with exponential backoff whenever it ends.
+/// Backoff is reset after a long enough run, so an attempt that keeps returning can't spin
+pub async fn supervise<F, Fut>(pool: &PgPool, mut attempt: F) -> !
+where
+    F: FnMut() -> Fut,
+    Fut: Future<Output = Result<(), TransportError>>,
+{
+    let mut delay = RESTART_BASE_DELAY;
+    report_state(pool, ConnectorState::Starting, None).await;
+
+    loop {
+        let started = Instant::now();
+        let result = attempt().await;
+        if started.elapsed() > RESTART_MAX_DELAY {
+            delay = RESTART_BASE_DELAY;
+        }
+
+        match result {
+            Ok(()) => {
+                log::info!("Connector finished, restarting in {:?}", delay);
+                tokio::time::sleep(delay).await;
+                delay = (delay * 2).min(RESTART_MAX_DELAY);
+            }
+            Err(e) => {
+                log::error!("Connector failed: {}, restarting in {:?}", e, delay);
+                report_state(pool, ConnectorState::Reconnecting, Some(e.to_string())).await;
+                tokio::time::sleep(delay).await;
+                delay = (delay * 2).min(RESTART_MAX_DELAY);
+            }
+        }
+    }
+}
@@ -0,0 +1,95 @@
+#[cfg(test)]
+mod test {
+    use std::sync::Arc;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::time::Duration;
+
+    use sqlx::postgres::PgPoolOptions;
+    use tokio::fs;
+
+    use crate::spool::SpoolTransport;
+    use crate::supervisor::supervise;
+    use crate::transport::{SmsTransport, TransportEvent, TransportTask};
+
+    #[tokio::test]
+    async fn transport_task_is_aborted_on_drop() {
+        let alive = Arc::new(());
+
+        // Every restart builds a new transport and drops the previous one
+        let mut task = None;
+        for _ in 0..3 {
+            let alive = alive.clone();
+            task = Some(TransportTask::spawn(async move {
+                let _alive = alive;
+                std::future::pending::<()>().await;
+            }));
+        }
+        tokio::time::sleep(Duration::from_millis(50)).await;
+
+        assert!(task.is_some());
+        assert_eq!(Arc::strong_count(&alive), 2);
+    }
+
+    #[tokio::test]
+    async fn spool_restart_leaves_single_poller() {
+        let root = std::env::temp_dir().join(format!(
+            "spool-{}-{}",
+            std::process::id(),
+            chrono::Local::now()
+                .timestamp_nanos_opt()
+                .unwrap_or_default()
+        ));
+
+        for _ in 0..2 {
+            drop(SpoolTransport::open(root.clone()).await.unwrap());
+        }
+
+        // Nothing may pick up the message while no transport is running
+        let message = root.join("incoming").join("message.json");
+        fs::write(&message, r#"{"phone": "+48123456789", "content": "Test"}"#)
+            .await
+            .unwrap();
+        tokio::time::sleep(Duration::from_millis(2500)).await;
+        assert!(fs::try_exists(&message).await.unwrap());
+
+        let mut transport = SpoolTransport::open(root.clone()).await.unwrap();
+        let mut incoming = transport.incoming().unwrap();
+        let event = tokio::time::timeout(Duration::from_secs(5), incoming.recv())
+            .await
+            .unwrap();
+        assert!(matches!(
+            event,
+            Some(TransportEvent::Received(sms)) if sms.phone == "+48123456789"
+        ));
+        assert!(!fs::try_exists(&message).await.unwrap());
+
+        drop(transport);
+        fs::remove_dir_all(&root).await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn supervisor_backs_off_after_clean_exit() {
+        // State reports fail fast, there is no database behind the pool
+        let pool = PgPoolOptions::new()
+            .acquire_timeout(Duration::from_millis(100))
+            .connect_lazy("postgres://localhost:1/wrangler")
+            .unwrap();
+        let attempts = Arc::new(AtomicUsize::new(0));
+
+        let counter = attempts.clone();
+        let _ = tokio::time::timeout(
+            Duration::from_millis(2500),
+            supervise(&pool, move || {
+                counter.fetch_add(1, Ordering::SeqCst);
+                async {
+                    tokio::task::yield_now().await;
+                    Ok(())
+                }
+            }),
+        )
+        .await;
+
+        let attempts = attempts.load(Ordering::SeqCst);
+        assert!((1..=3).contains(&attempts), "{} attempts", attempts);
+    }
+}
@@ -0,0 +1,82 @@
+use std::future::Future;
+
+use chrono::NaiveDateTime;
+use tokio::{sync::mpsc::UnboundedReceiver, task::JoinHandle};
+use uuid::Uuid;
+
+pub type TransportError = Box<dyn std::error::Error + Send + Sync>;
+
+#[derive(Debug, Clone)]
+pub struct OutgoingSms {
+    pub id: Uuid,
+    pub phone: String,
+    pub content: String,
+    /// Number of the line that should preferably send the message
+    pub via: Option<String>,
+}
+
+#[derive(Debug, Clone)]
+pub struct IncomingSms {
+    pub phone: String,
+    pub content: String,
+    pub sent: NaiveDateTime,
+    /// Number of the line the message arrived on, if known
+    pub via: Option<String>,
+}
+
+#[derive(Debug, Clone)]
+pub struct TransportStatus {
+    /// Stable identifier of the device behind the line, survives number changes
+    pub modem: String,
+    pub number: String,
+    pub signal: i32,
+    pub state: i32,
+    /// SIM waits for a PIN that wasn't accepted or given
+    pub locked: bool,
+}
+
+#[derive(Debug)]
+pub enum TransportEvent {
+    Received(IncomingSms),
+    Sent(Uuid),
+    Delivered(Uuid),
+    DeliveryFailed(Uuid, String),
+    StatusChanged(TransportStatus),
+    /// Transport can no longer operate, connector should stop
+    Disconnected(String),
+}
+
+/// Anything able to send and receive SMS on behalf of the connector
+pub trait SmsTransport: Send + Sync {
+    /// Hands message over for sending, further progress is reported through `incoming`.
+    /// Returns number of the line that took the message, if known
+    fn send(
+        &self,
+        message: OutgoingSms,
+    ) -> impl Future<Output = Result<Option<String>, TransportError>> + Send;
+
+    /// Stream of received messages and delivery updates, `None` once it was already taken
+    fn incoming(&mut self) -> Option<UnboundedReceiver<TransportEvent>>;
+
+    /// Status of every line handled by the transport
+    fn status(&self) -> impl Future<Output = Result<Vec<TransportStatus>, TransportError>> + Send;
+}
+
+/// Background task of a transport, aborted once the transport is dropped. Without it a restarted
+/// connector would leave the previous poller running, consuming messages nobody listens for
+pub struct TransportTask(JoinHandle<()>);
+
+impl TransportTask {
+    pub fn spawn<F>(future: F) -> Self
+    where
+        F: Future<Output = ()> + Send + 'static,
+    {
+        TransportTask(tokio::spawn(future))
+    }
+}
+
+impl Drop for TransportTask {
+    fn drop(&mut self) {
+        self.0.abort();
+    }
+}